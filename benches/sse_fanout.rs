@@ -0,0 +1,130 @@
+//! Fans events out to many subscribers of one room's event channel and
+//! compares sending the payload serialized once at broadcast with each
+//! subscriber serializing every event itself, as streams did before.
+//!
+//! Run with `cargo bench --bench sse_fanout -- [SUBSCRIBERS...]`; the default
+//! is 100, 300 and 500 subscribers.
+
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use raiku_simulator::models::{
+    event::{AppEvent, EVENT_SCHEMA_VERSION, EventBroadcaster, SharedEvent, versioned_payload},
+    slot::Slot,
+    types::Lamports,
+};
+
+/// Events broadcast per run, under the channel's capacity so no subscriber lags.
+const EVENTS: usize = 2_000;
+const RUNS: usize = 5;
+const DEFAULT_SUBSCRIBERS: [usize; 3] = [100, 300, 500];
+
+#[derive(Clone, Copy)]
+enum Mode {
+    /// Send the payload serialized at broadcast
+    Shared,
+    /// Serialize the event again in every subscriber
+    PerSubscriber,
+}
+
+impl Mode {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Shared => "shared payload",
+            Self::PerSubscriber => "per-subscriber",
+        }
+    }
+
+    fn payload_len(self, event: &SharedEvent) -> usize {
+        match self {
+            Self::Shared => event.payload.len(),
+            Self::PerSubscriber => versioned_payload(&event.event, EVENT_SCHEMA_VERSION).len(),
+        }
+    }
+}
+
+/// The mix a busy room broadcasts: bids, with a slot delta every tenth event.
+fn event(index: usize) -> AppEvent {
+    let slot_number = 1_000 + index as u64 / 10;
+    if index.is_multiple_of(10) {
+        return AppEvent::SlotsChanged {
+            slots: (slot_number..slot_number + 3)
+                .map(|slot| Slot::new(slot, Utc::now(), Lamports::from_sol(0.001)))
+                .collect(),
+            replaces_window: false,
+        };
+    }
+    AppEvent::JitBidSubmitted {
+        slot_number,
+        bidder: format!("{:08x}-bench-bidder", index),
+        amount: Lamports::from_sol(0.001 * index as f64),
+        request_id: None,
+    }
+}
+
+/// Time for every subscriber to receive and encode all `EVENTS` events.
+async fn run(mode: Mode, subscribers: usize) -> Duration {
+    let broadcaster = EventBroadcaster::new();
+    let subscriptions: Vec<_> = (0..subscribers).map(|_| broadcaster.subscribe()).collect();
+
+    let start = Instant::now();
+    let tasks: Vec<_> = subscriptions
+        .into_iter()
+        .map(|mut subscription| {
+            tokio::spawn(async move {
+                let mut bytes = 0;
+                for _ in 0..EVENTS {
+                    let event = subscription
+                        .recv()
+                        .await
+                        .expect("subscriber fell behind the channel");
+                    bytes += mode.payload_len(&event);
+                }
+                std::hint::black_box(bytes)
+            })
+        })
+        .collect();
+    for index in 0..EVENTS {
+        broadcaster.broadcast(event(index));
+    }
+    for task in tasks {
+        task.await.expect("subscriber task panicked");
+    }
+    start.elapsed()
+}
+
+#[tokio::main]
+async fn main() {
+    let counts: Vec<usize> = std::env::args()
+        .skip(1)
+        .filter_map(|arg| arg.parse().ok())
+        .collect();
+    let counts = if counts.is_empty() {
+        DEFAULT_SUBSCRIBERS.to_vec()
+    } else {
+        counts
+    };
+
+    println!(
+        "{} events per run, best of {} runs, {} worker threads",
+        EVENTS,
+        RUNS,
+        std::thread::available_parallelism().map_or(1, |threads| threads.get())
+    );
+    for subscribers in counts {
+        for mode in [Mode::PerSubscriber, Mode::Shared] {
+            let mut best = Duration::MAX;
+            for _ in 0..RUNS {
+                best = best.min(run(mode, subscribers).await);
+            }
+            let delivered = (subscribers * EVENTS) as f64;
+            println!(
+                "{:>4} subscribers, {:<14}: {:>8.1} ms, {:>6.0} ns per delivered event",
+                subscribers,
+                mode.name(),
+                best.as_secs_f64() * 1e3,
+                best.as_nanos() as f64 / delivered
+            );
+        }
+    }
+}
@@ -5,6 +5,7 @@ const API_BASE = process.env.REACT_APP_API_BASE || 'http://localhost:8080';
 
 const RaikuSimulator = () => {
   const [sessionId, setSessionId] = useState(null);
+  const [sessionToken, setSessionToken] = useState(null);
   const [currentSlot, setCurrentSlot] = useState(0);
   const [slots, setSlots] = useState([]);
   const [jitAuctions, setJitAuctions] = useState([]);
@@ -85,7 +86,7 @@ const RaikuSimulator = () => {
   const fetchPlayerStats = useCallback(async () => {
     if (!sessionId) return;
     try {
-      const response = await fetchWithCredentials(`${API_BASE}/game/player_stats?session_id=${sessionId}`);
+      const response = await fetchWithCredentials(`${API_BASE}/game/player_stats?session_id=${sessionToken}`);
       const responseData = await response.json();
       const data = responseData.data;
       
@@ -99,7 +100,7 @@ const RaikuSimulator = () => {
     } catch (error) {
       console.error('Failed to fetch player stats:', error);
     }
-  }, [sessionId, queueAchievements]);
+  }, [sessionId, sessionToken, queueAchievements]);
 
   const fetchLeaderboard = useCallback(async () => {
     try {
@@ -140,9 +141,9 @@ const RaikuSimulator = () => {
     try {
       let url;
       if (showAllTransactions) {
-        url = `${API_BASE}/transactions?show_all=true&session_id=${sessionId}&page=${page}&limit=20`;
+        url = `${API_BASE}/transactions?show_all=true&session_id=${sessionToken}&page=${page}&limit=20`;
       } else {
-        url = `${API_BASE}/transactions?session_id=${sessionId}&page=${page}&limit=20`;
+        url = `${API_BASE}/transactions?session_id=${sessionToken}&page=${page}&limit=20`;
       }
       
       const response = await fetchWithCredentials(url);
@@ -155,16 +156,25 @@ const RaikuSimulator = () => {
     } catch (error) {
       console.error('Failed to fetch transactions:', error);
     }
-  }, [sessionId, showAllTransactions]);
+  }, [sessionId, sessionToken, showAllTransactions]);
   
   const handleEvent = useCallback((event) => {
     switch (event.type) {
       case 'SlotAdvanced':
         setCurrentSlot(event.current_slot);
+        setSlots(prev => prev.filter(slot => slot.slot_number >= event.current_slot));
         break;
         
-      case 'SlotsUpdated':
-        setSlots(event.slots);
+      case 'SlotsChanged':
+        if (event.replaces_window) {
+          setSlots(event.slots);
+        } else {
+          setSlots(prev => {
+            const bySlot = new Map(prev.map(slot => [slot.slot_number, slot]));
+            event.slots.forEach(slot => bySlot.set(slot.slot_number, slot));
+            return [...bySlot.values()].sort((a, b) => a.slot_number - b.slot_number);
+          });
+        }
         break;
         
       case 'JitAuctionStarted':
@@ -176,6 +186,10 @@ const RaikuSimulator = () => {
         fetchAotAuctions();
         addNotification(`AOT auction started for slot ${event.slot_number}`, 'info');
         break;
+
+      case 'AotAuctionClosing':
+        addNotification(`AOT auction for slot ${event.slot_number} closes in ${event.seconds_left}s`, 'info');
+        break;
         
       case 'JitBidSubmitted':
         fetchJitAuctions();
@@ -220,17 +234,30 @@ const RaikuSimulator = () => {
         setCurrentSlot(event.current_slot);
         break;
         
+      case 'StreamResync':
+        setCurrentSlot(event.current_slot);
+        setSlots(event.slots);
+        setStats({
+          active_jit_auctions: event.active_jit_auctions,
+          active_aot_auctions: event.active_aot_auctions,
+          total_transactions: event.total_transactions
+        });
+        fetchJitAuctions();
+        fetchAotAuctions();
+        fetchTransactions();
+        break;
+        
       default:
         break;
     }
-  }, [sessionId, addNotification, fetchAotAuctions, fetchJitAuctions]);
+  }, [sessionId, addNotification, fetchAotAuctions, fetchJitAuctions, fetchTransactions]);
   
   const connectEventSource = useCallback(() => {
     if (eventSourceRef.current) {
       eventSourceRef.current.close();
     }
 
-    const eventSource = new EventSource(`${API_BASE}/events`);
+    const eventSource = new EventSource(`${API_BASE}/events?v=2`);
     eventSourceRef.current = eventSource;
 
     eventSource.onopen = () => {
@@ -277,6 +304,7 @@ const RaikuSimulator = () => {
       const responseData = await response.json();
       const data = responseData.data;
       setSessionId(data.session_id);
+      setSessionToken(data.session_token);
       
       if (data.status === 'validated') {
         addNotification('Session restored successfully!', 'success');
@@ -296,10 +324,10 @@ const RaikuSimulator = () => {
     try {
       const [statusRes, slotsRes, jitRes, aotRes, txRes] = await Promise.all([
         fetchWithCredentials(`${API_BASE}/marketplace/status`),
-        fetchWithCredentials(`${API_BASE}/marketplace/slots`),
+        fetchWithCredentials(`${API_BASE}/marketplace/slots/snapshot`),
         fetchWithCredentials(`${API_BASE}/auctions/jit`),
         fetchWithCredentials(`${API_BASE}/auctions/aot`),
-        fetchWithCredentials(`${API_BASE}/transactions?session_id=${sessionId}`)
+        fetchWithCredentials(`${API_BASE}/transactions?session_id=${sessionToken}`)
       ]);
 
       const status = await statusRes.json();
@@ -309,7 +337,7 @@ const RaikuSimulator = () => {
       const txData = await txRes.json();
 
       setCurrentSlot(status.current_slot);
-      setSlots(slotsData.slots || []);
+      setSlots(slotsData.data?.slots || []);
       setJitAuctions(jitData.auctions || []);
       setAotAuctions(aotData.auctions || []);
       setTransactions(txData.transactions || []);
@@ -319,7 +347,7 @@ const RaikuSimulator = () => {
     } catch (err) {
       console.error('Initial fetch error:', err);
     }
-  }, [sessionId]);
+  }, [sessionId, sessionToken]);
 
 
   useEffect(() => {
@@ -477,6 +505,7 @@ const RaikuSimulator = () => {
     if (status?.Included) return 'Included';
     if (status?.AuctionWon) return 'Auction Won';
     if (status?.Failed) return 'Failed';
+    if (status?.Expired) return 'Expired';
     return 'Pending';
   };
 
@@ -1292,13 +1321,13 @@ const RaikuSimulator = () => {
                             Min Bid: {auction.min_bid} SOL
                           </div>
                         </div>
-                        {auction.current_winner && (
+                        {auction.highest_bidder && (
                           <div style={{
                             color: '#75bd4f',
                             fontWeight: 'bold',
                             fontSize: isMobile ? '14px' : '16px'
                           }}>
-                            Winner: {auction.current_winner[0].slice(0, 8)}... ({auction.current_winner[1]} SOL)
+                            Winner: {auction.highest_bidder.slice(0, 8)}... ({auction.highest_bid} SOL)
                           </div>
                         )}
                       </div>
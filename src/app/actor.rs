@@ -0,0 +1,132 @@
+use std::{
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::Arc,
+};
+
+use futures_util::future::join_all;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{Instrument, info_span};
+
+use crate::error::AppError;
+
+/// Commands waiting for an actor before senders start to wait for room.
+const ACTOR_MAILBOX_CAPACITY: usize = 1024;
+
+type Command<T> = Box<dyn FnOnce(&mut T) + Send>;
+
+/// Owns a piece of core state on its own task. Callers send commands over a
+/// channel and await the reply, so commands run one at a time in arrival order
+/// and no caller ever holds a lock on the state.
+pub struct Actor<T> {
+    name: &'static str,
+    sender: mpsc::Sender<Command<T>>,
+}
+
+impl<T> Clone for Actor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Actor<T> {
+    /// Spawns the actor task. It runs until every handle is dropped.
+    pub fn spawn(name: &'static str, mut state: T) -> Self {
+        let (sender, mut receiver) = mpsc::channel::<Command<T>>(ACTOR_MAILBOX_CAPACITY);
+
+        tokio::spawn(async move {
+            while let Some(command) = receiver.recv().await {
+                // A panicking command fails its caller, not the actor
+                if catch_unwind(AssertUnwindSafe(|| command(&mut state))).is_err() {
+                    tracing::error!(alert = true, "Command panicked in the {} actor", name);
+                }
+            }
+        });
+
+        Self { name, sender }
+    }
+
+    /// Runs `command` on the actor's state and returns its result. Fails with
+    /// `AppError::Internal` if the command panicked or the actor has stopped.
+    pub async fn call<R, F>(&self, command: F) -> Result<R, AppError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut T) -> R + Send + 'static,
+    {
+        let (reply, response) = oneshot::channel();
+
+        async {
+            self.sender
+                .send(Box::new(move |state: &mut T| {
+                    let _ = reply.send(command(state));
+                }))
+                .await
+                .map_err(|_| anyhow::anyhow!("The {} actor has stopped", self.name))?;
+
+            // The reply is dropped unsent when the command panics
+            response.await.map_err(|_| {
+                anyhow::anyhow!("The {} actor failed to run a command", self.name).into()
+            })
+        }
+        .instrument(info_span!("actor.call", actor = self.name))
+        .await
+    }
+}
+
+/// State keyed by slot number, split over several actors so commands for
+/// unrelated slots run in parallel instead of queueing behind each other.
+pub struct Sharded<T> {
+    shards: Arc<[Actor<T>]>,
+}
+
+impl<T> Clone for Sharded<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shards: self.shards.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Sharded<T> {
+    /// Spawns `count` actors, building each one's state from its shard index.
+    pub fn spawn(name: &'static str, count: usize, mut init: impl FnMut(usize) -> T) -> Self {
+        Self {
+            shards: (0..count.max(1))
+                .map(|index| Actor::spawn(name, init(index)))
+                .collect(),
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Index of the shard that owns `slot_number`.
+    pub fn shard_index(&self, slot_number: u64) -> usize {
+        (slot_number % self.shards.len() as u64) as usize
+    }
+
+    /// The actor that owns `slot_number`.
+    pub fn shard(&self, slot_number: u64) -> &Actor<T> {
+        &self.shards[self.shard_index(slot_number)]
+    }
+
+    pub fn shards(&self) -> impl Iterator<Item = &Actor<T>> {
+        self.shards.iter()
+    }
+
+    /// Runs `command` on every shard concurrently, returning results in shard
+    /// order. Fails if any shard does.
+    pub async fn call_all<R, F>(&self, command: F) -> Result<Vec<R>, AppError>
+    where
+        R: Send + 'static,
+        F: Fn(&mut T) -> R + Clone + Send + 'static,
+    {
+        join_all(self.shards.iter().map(|shard| shard.call(command.clone())))
+            .await
+            .into_iter()
+            .collect()
+    }
+}
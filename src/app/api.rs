@@ -1,34 +1,79 @@
 use axum::{
     Router,
-    routing::{get, post},
+    extract::{Path, Request},
+    routing::{any, delete, get, patch, post},
 };
-use tower_http::cors::CorsLayer;
+use tokio_util::sync::CancellationToken;
+use tower_http::{cors::CorsLayer, trace::TraceLayer};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
-    app::state::AppState,
-    config::GlobalConfig,
-    middleware::rate_limiter::rate_limit_middleware,
+    app::{
+        room::{ROOM_HEADER, RoomManager},
+        state::AppState,
+    },
+    config::SharedConfig,
+    middleware::{
+        admin_auth::{require_admin, require_operator},
+        rate_limiter::{
+            RATE_LIMIT_LIMIT_HEADER, RATE_LIMIT_REMAINING_HEADER, RATE_LIMIT_RESET_HEADER,
+            rate_limit_middleware,
+        },
+        request_id::request_id_middleware,
+        request_log::request_log_middleware,
+    },
     routes::{
-        auction::{list_aot_auctions, list_jit_auctions},
-        event::sse_handler,
-        health::health_check,
-        session::create_or_validate_session,
-        slot::{get_slot, list_slots},
-        stats::{get_leaderboard, get_player_stats, marketplace_status},
+        admin::{
+            adjust_player_balance, ban_player, create_restriction, delete_restriction, kick_player,
+            ledger_audit, list_abuse_flags, list_bans, list_restrictions, reload_configuration,
+            reset_simulation, slot_reconciliation,
+        },
+        analytics::{demand_forecast, demand_heatmap, fee_stats, inclusion_latency, price_series},
+        auction::{
+            auction_history, estimate_auction_bid, get_aot_auction, get_jit_auction,
+            list_aot_auctions, list_jit_auctions,
+        },
+        chain::{forward_to_chain, list_chains},
+        docs::{get_json_schema, list_json_schemas},
+        event::{event_metrics, private_sse_handler, replay_events, room_sse_handler, sse_handler},
+        health::{health_check, liveness, readiness},
+        matchmaking::{get_matchmaking_ticket, join_matchmaking_queue, leave_matchmaking_queue},
+        room::{
+            close_own_room, close_room, configure_game_room, create_game_room, create_room_invite,
+            get_room, join_room, join_room_with_invite, kick_room_member, list_rooms, reset_room,
+        },
+        session::{
+            create_api_key, create_or_validate_session, create_recovery_code, list_active_sessions,
+            list_api_keys, logout, recover_session, refresh_session_id, revoke_session,
+            upgrade_session,
+        },
+        slot::{get_slot, list_slots, search_slots, slots_snapshot},
+        stats::{
+            delete_player, export_player, export_player_stats, get_global_leaderboard,
+            get_leaderboard, get_player_analytics, get_player_stats, marketplace_config,
+            marketplace_status, request_faucet,
+        },
         transaction::{
-            get_transaction, list_transactions, submit_aot_transaction, submit_jit_transaction,
+            cancel_transaction, export_transaction_history, get_transaction, list_transactions,
+            retry_transaction, simulate_transaction, submit_aot_transaction,
+            submit_bundle_transaction, submit_jit_transaction,
         },
+        wallet::{create_wallet_challenge, verify_wallet},
     },
-    utils::rate_limiter::RateLimiter,
+    services::idempotency::{IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER},
+    utils::{rate_limiter::RateLimiter, request_id::REQUEST_ID_HEADER},
 };
 
 #[derive(Clone)]
 pub struct AppContext {
+    /// The global room's state; handlers wrapped in `RoomScoped` see the
+    /// state of the room the request applies to instead
     pub state: AppState,
-    pub config: GlobalConfig,
+    pub rooms: RoomManager,
+    pub config: SharedConfig,
     pub rate_limiter: RateLimiter,
+    pub shutdown: CancellationToken,
 }
 
 #[derive(OpenApi)]
@@ -36,29 +81,150 @@ pub struct AppContext {
     info(title = "Raiku Simulator Backend API", version = "1.0.0"),
     paths(
         crate::routes::health::health_check,
+        crate::routes::health::liveness,
+        crate::routes::health::readiness,
         crate::routes::event::sse_handler,
+        crate::routes::event::room_sse_handler,
+        crate::routes::event::private_sse_handler,
+        crate::routes::event::replay_events,
+        crate::routes::event::event_metrics,
         crate::routes::session::create_or_validate_session,
+        crate::routes::session::refresh_session_id,
+        crate::routes::session::upgrade_session,
+        crate::routes::session::create_recovery_code,
+        crate::routes::session::recover_session,
+        crate::routes::session::create_api_key,
+        crate::routes::session::list_api_keys,
+        crate::routes::session::logout,
+        crate::routes::session::list_active_sessions,
+        crate::routes::session::revoke_session,
+        crate::routes::wallet::create_wallet_challenge,
+        crate::routes::wallet::verify_wallet,
+        crate::routes::room::create_game_room,
+        crate::routes::room::list_rooms,
+        crate::routes::room::get_room,
+        crate::routes::room::join_room,
+        crate::routes::room::join_room_with_invite,
+        crate::routes::room::create_room_invite,
+        crate::routes::room::kick_room_member,
+        crate::routes::room::reset_room,
+        crate::routes::room::configure_game_room,
+        crate::routes::room::close_own_room,
+        crate::routes::chain::list_chains,
+        crate::routes::matchmaking::join_matchmaking_queue,
+        crate::routes::matchmaking::get_matchmaking_ticket,
+        crate::routes::matchmaking::leave_matchmaking_queue,
         crate::routes::slot::list_slots,
+        crate::routes::slot::slots_snapshot,
+        crate::routes::slot::search_slots,
         crate::routes::slot::get_slot,
         crate::routes::stats::get_player_stats,
+        crate::routes::stats::get_player_analytics,
+        crate::routes::stats::export_player_stats,
+        crate::routes::stats::export_player,
+        crate::routes::stats::delete_player,
         crate::routes::stats::get_leaderboard,
+        crate::routes::stats::request_faucet,
+        crate::routes::stats::get_global_leaderboard,
         crate::routes::stats::marketplace_status,
+        crate::routes::stats::marketplace_config,
         crate::routes::auction::list_aot_auctions,
         crate::routes::auction::list_jit_auctions,
+        crate::routes::auction::get_jit_auction,
+        crate::routes::auction::get_aot_auction,
+        crate::routes::auction::auction_history,
+        crate::routes::auction::estimate_auction_bid,
+        crate::routes::analytics::price_series,
+        crate::routes::analytics::fee_stats,
+        crate::routes::analytics::demand_heatmap,
+        crate::routes::analytics::demand_forecast,
+        crate::routes::analytics::inclusion_latency,
         crate::routes::transaction::submit_aot_transaction,
         crate::routes::transaction::submit_jit_transaction,
+        crate::routes::transaction::submit_bundle_transaction,
+        crate::routes::transaction::simulate_transaction,
         crate::routes::transaction::list_transactions,
+        crate::routes::transaction::export_transaction_history,
         crate::routes::transaction::get_transaction,
+        crate::routes::transaction::cancel_transaction,
+        crate::routes::transaction::retry_transaction,
+        crate::routes::admin::reload_configuration,
+        crate::routes::admin::reset_simulation,
+        crate::routes::admin::adjust_player_balance,
+        crate::routes::admin::kick_player,
+        crate::routes::admin::ban_player,
+        crate::routes::admin::list_bans,
+        crate::routes::admin::list_abuse_flags,
+        crate::routes::admin::ledger_audit,
+        crate::routes::admin::slot_reconciliation,
+        crate::routes::admin::create_restriction,
+        crate::routes::admin::list_restrictions,
+        crate::routes::admin::delete_restriction,
+        crate::routes::room::close_room,
+        crate::routes::docs::list_json_schemas,
+        crate::routes::docs::get_json_schema,
     ),
-    components(schemas(crate::models::responses::ApiResponse,),)
+    components(schemas(
+        crate::models::responses::ErrorResponse,
+        crate::models::responses::ErrorCode,
+        crate::models::event::AppEvent,
+        crate::models::requests::RetryBidRequest,
+        crate::models::requests::CreateApiKeyRequest,
+        crate::models::requests::BalanceAdjustmentRequest,
+        crate::models::requests::WalletChallengeRequest,
+        crate::models::requests::WalletVerifyRequest,
+        crate::models::requests::RecoverSessionRequest,
+        crate::models::requests::UpgradeSessionRequest,
+        crate::models::requests::UpgradeMethod,
+        crate::models::requests::BanRequest,
+        crate::models::requests::AuctionRestrictionRequest,
+        crate::models::requests::CreateRoomRequest,
+        crate::models::requests::CreateInviteRequest,
+        crate::models::requests::JoinRoomRequest,
+        crate::models::requests::RoomConfigRequest,
+        crate::models::requests::JoinQueueRequest,
+        crate::models::requests::FaucetRequest,
+        crate::models::transaction::TransactionStatusKind,
+        crate::models::requests::ExportFormat,
+        crate::models::slot::SlotStateKind,
+        crate::managers::analytics::PriceResolution,
+    ),)
 )]
-struct ApiDoc;
+pub struct ApiDoc;
+
+/// Operator actions are reachable with either token; destructive ones need the admin token.
+fn admin_router(context: AppContext) -> Router<AppContext> {
+    let operator_routes = Router::new()
+        .route("/config/reload", post(reload_configuration))
+        .route("/players/{session_id}/kick", post(kick_player))
+        .route("/bans", get(list_bans))
+        .route("/abuse-flags", get(list_abuse_flags))
+        .route("/ledger", get(ledger_audit))
+        .route("/reconciliation", get(slot_reconciliation))
+        .route("/restrictions", get(list_restrictions))
+        .route_layer(axum::middleware::from_fn_with_state(
+            context.clone(),
+            require_operator,
+        ));
+
+    let admin_routes = Router::new()
+        .route("/reset", post(reset_simulation))
+        .route("/players/{session_id}/balance", post(adjust_player_balance))
+        .route("/players/{session_id}/ban", post(ban_player))
+        .route("/rooms/{room_id}", delete(close_room))
+        .route("/restrictions", post(create_restriction))
+        .route("/restrictions/{restriction_id}", delete(delete_restriction))
+        .route_layer(axum::middleware::from_fn_with_state(context, require_admin));
+
+    operator_routes.merge(admin_routes)
+}
 
 pub fn create_api_router(context: AppContext) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(
             context
                 .config
+                .current()
                 .server
                 .cors_allowed_origins
                 .iter()
@@ -68,33 +234,137 @@ pub fn create_api_router(context: AppContext) -> Router {
         .allow_methods([
             axum::http::Method::GET,
             axum::http::Method::POST,
+            axum::http::Method::PATCH,
+            axum::http::Method::DELETE,
             axum::http::Method::OPTIONS,
         ])
         .allow_headers([
             axum::http::header::CONTENT_TYPE,
             axum::http::header::COOKIE,
+            axum::http::header::AUTHORIZATION,
             axum::http::header::CACHE_CONTROL,
+            axum::http::HeaderName::from_static(REQUEST_ID_HEADER),
+            axum::http::HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+            axum::http::HeaderName::from_static(ROOM_HEADER),
+        ])
+        .expose_headers([
+            axum::http::HeaderName::from_static(REQUEST_ID_HEADER),
+            axum::http::HeaderName::from_static(RATE_LIMIT_LIMIT_HEADER),
+            axum::http::HeaderName::from_static(RATE_LIMIT_REMAINING_HEADER),
+            axum::http::HeaderName::from_static(RATE_LIMIT_RESET_HEADER),
+            axum::http::header::RETRY_AFTER,
+            axum::http::header::CONTENT_DISPOSITION,
+            axum::http::HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
         ])
         .allow_credentials(true);
 
-    Router::new()
-        .route("/sessions", post(create_or_validate_session))
+    let routes: Router = Router::new()
+        .route("/sessions", post(create_or_validate_session).delete(logout))
+        .route("/sessions/refresh", post(refresh_session_id))
+        .route("/sessions/upgrade", post(upgrade_session))
+        .route("/sessions/recovery-code", post(create_recovery_code))
+        .route("/sessions/recover", post(recover_session))
+        .route("/sessions/devices", get(list_active_sessions))
+        .route("/sessions/devices/{device_id}", delete(revoke_session))
+        // Earlier names of the device routes
+        .route("/sessions/active", get(list_active_sessions))
+        .route("/sessions/active/{device_id}", delete(revoke_session))
+        .route(
+            "/sessions/api-keys",
+            post(create_api_key).get(list_api_keys),
+        )
+        .route("/sessions/wallet/challenge", post(create_wallet_challenge))
+        .route("/sessions/wallet/verify", post(verify_wallet))
+        .route("/rooms", post(create_game_room).get(list_rooms))
+        .route("/rooms/{room_id}", get(get_room).delete(close_own_room))
+        .route("/rooms/join", post(join_room_with_invite))
+        .route("/rooms/{room_id}/join", post(join_room))
+        .route("/rooms/{room_id}/invites", post(create_room_invite))
+        .route(
+            "/rooms/{room_id}/members/{session_id}",
+            delete(kick_room_member),
+        )
+        .route("/rooms/{room_id}/reset", post(reset_room))
+        .route("/rooms/{room_id}/config", patch(configure_game_room))
+        .route("/rooms/{room_id}/events", get(room_sse_handler))
+        .route(
+            "/matchmaking/queue",
+            post(join_matchmaking_queue)
+                .get(get_matchmaking_ticket)
+                .delete(leave_matchmaking_queue),
+        )
+        .route("/chains", get(list_chains))
         .route("/events", get(sse_handler))
+        .route("/events/me", get(private_sse_handler))
+        .route("/events/replay", get(replay_events))
+        .route("/events/metrics", get(event_metrics))
         .route("/marketplace/status", get(marketplace_status))
+        .route("/marketplace/config", get(marketplace_config))
         .route("/marketplace/slots", get(list_slots))
+        .route("/marketplace/slots/snapshot", get(slots_snapshot))
+        .route("/marketplace/slots/search", get(search_slots))
         .route("/marketplace/slots/{slot_number}", get(get_slot))
         .route("/auctions/jit", get(list_jit_auctions))
         .route("/auctions/aot", get(list_aot_auctions))
+        .route("/auctions/jit/{slot_number}", get(get_jit_auction))
+        .route("/auctions/aot/{slot_number}", get(get_aot_auction))
+        .route("/auctions/history", get(auction_history))
+        .route("/auctions/estimate", get(estimate_auction_bid))
+        .route("/analytics/prices", get(price_series))
+        .route("/analytics/fees", get(fee_stats))
+        .route("/analytics/heatmap", get(demand_heatmap))
+        .route("/analytics/forecast", get(demand_forecast))
+        .route("/analytics/latency", get(inclusion_latency))
         .route("/transactions/jit", post(submit_jit_transaction))
         .route("/transactions/aot", post(submit_aot_transaction))
+        .route("/transactions/bundle", post(submit_bundle_transaction))
+        .route("/transactions/simulate", post(simulate_transaction))
         .route("/transactions", get(list_transactions))
+        .route("/transactions/export", get(export_transaction_history))
         .route("/transactions/{transaction_id}", get(get_transaction))
+        .route(
+            "/transactions/{transaction_id}/cancel",
+            post(cancel_transaction),
+        )
+        .route(
+            "/transactions/{transaction_id}/retry",
+            post(retry_transaction),
+        )
         .route("/health", get(health_check))
+        .route("/health/live", get(liveness))
+        .route("/health/ready", get(readiness))
         .route("/game/player_stats", get(get_player_stats))
+        .route("/game/analytics", get(get_player_analytics))
+        .route("/game/stats/export", get(export_player_stats))
+        .route("/game/export", get(export_player))
+        .route("/game/me", delete(delete_player))
         .route("/game/leaderboard", get(get_leaderboard))
+        .route("/game/faucet", post(request_faucet))
+        .route("/game/leaderboard/global", get(get_global_leaderboard))
+        .nest("/admin", admin_router(context.clone()))
+        .route("/docs/schemas", get(list_json_schemas))
+        .route("/docs/schemas/{name}", get(get_json_schema))
         .merge(SwaggerUi::new("/swagger-ui").url("/docs/openapi.json", ApiDoc::openapi()))
+        .with_state(context.clone());
+
+    // Every endpoint is also served per chain, by forwarding to the routes above
+    let (chain_routes, rooms) = (routes.clone(), context.rooms.clone());
+    routes
+        .route(
+            "/chains/{chain_id}/{*path}",
+            any(
+                move |Path((chain_id, path)): Path<(String, String)>, req: Request| {
+                    forward_to_chain(chain_routes.clone(), rooms.clone(), chain_id, path, req)
+                },
+            ),
+        )
         .layer(axum::middleware::from_fn(rate_limit_middleware))
         .layer(axum::Extension(context.rate_limiter.clone()))
+        .layer(axum::middleware::from_fn_with_state(
+            context.state.sessions.clone(),
+            request_log_middleware,
+        ))
+        .layer(axum::middleware::from_fn(request_id_middleware))
         .layer(cors)
-        .with_state(context)
+        .layer(TraceLayer::new_for_http())
 }
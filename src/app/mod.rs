@@ -1,2 +1,4 @@
+pub mod actor;
 pub mod api;
+pub mod room;
 pub mod state;
@@ -0,0 +1,511 @@
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+
+use anyhow::{Result, anyhow};
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use chrono::{DateTime, Utc};
+use dashmap::{DashMap, DashSet};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+use crate::{
+    app::{api::AppContext, state::AppState},
+    config::{ChainConfig, MarketplaceConfig},
+    error::AppError,
+    managers::matchmaking::MatchmakingQueue,
+    models::types::Lamports,
+    services::session::get_session_from_cookie,
+};
+
+/// Room every session starts in, backed by the server's original state.
+pub const GLOBAL_ROOM_ID: &str = "global";
+/// Header naming the room a request applies to.
+pub const ROOM_HEADER: &str = "x-room-id";
+/// Most rooms that may exist at once, including the global room. Each runs its
+/// own slot clock.
+pub const MAX_ROOMS: usize = 50;
+/// Characters invite codes are drawn from, without look-alikes such as 0/O and 1/I.
+const INVITE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const INVITE_CODE_LENGTH: usize = 8;
+
+/// An isolated marketplace with its own slots, auctions, players and ledger.
+pub struct Room {
+    pub id: String,
+    pub name: String,
+    /// Session that created the room; `None` for the global room. Follows the
+    /// creator's session when it is refreshed.
+    pub created_by: RwLock<Option<String>>,
+    pub created_at: DateTime<Utc>,
+    /// Private rooms only take bids from sessions admitted by invite
+    pub private: bool,
+    /// Sessions admitted to a private room, by invite or as its creator
+    pub admitted: DashSet<String>,
+    /// Set for rooms that simulate a configured chain; their parameters are
+    /// fixed by the config and they cannot be closed
+    pub chain: Option<ChainConfig>,
+    /// Settings the creator changed from the config
+    pub overrides: RwLock<RoomOverrides>,
+    /// When bidding opens and the slot clock starts, for matchmade rooms
+    pub starts_at: Option<DateTime<Utc>>,
+    pub state: AppState,
+    /// Stops the room's background tasks
+    pub shutdown: CancellationToken,
+    /// Stops the room's slot clock alone, so it can be restarted at a new pace
+    pub clock: Mutex<CancellationToken>,
+}
+
+/// Marketplace settings a room's creator set for the room.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoomOverrides {
+    /// Also the pace of the room's slot clock
+    pub slot_duration_ms: Option<i64>,
+    pub base_fee_sol: Option<f64>,
+}
+
+/// A room's effective slot timing and base fee.
+#[derive(Debug, Clone, Copy)]
+pub struct RoomTiming {
+    pub slot_duration_ms: i64,
+    pub advance_slot_interval_ms: u64,
+    pub base_fee_sol: f64,
+}
+
+impl Room {
+    /// Builds a room around `state`, with its own slot clock token.
+    pub fn new(
+        id: String,
+        name: String,
+        created_by: Option<String>,
+        private: bool,
+        chain: Option<ChainConfig>,
+        state: AppState,
+        shutdown: CancellationToken,
+    ) -> Self {
+        Self {
+            id,
+            name,
+            admitted: DashSet::from_iter(created_by.clone()),
+            created_by: RwLock::new(created_by),
+            created_at: Utc::now(),
+            private,
+            chain,
+            overrides: RwLock::new(RoomOverrides::default()),
+            starts_at: None,
+            state,
+            clock: Mutex::new(shutdown.child_token()),
+            shutdown,
+        }
+    }
+
+    /// Whether the session may bid in the room now.
+    pub fn check_bid_access(&self, session_id: &str) -> Result<(), AppError> {
+        if !self.admits(session_id) {
+            return Err(AppError::NotRoomMember {
+                room_id: self.id.clone(),
+            });
+        }
+        match self.starts_at {
+            Some(starts_at) if starts_at > Utc::now() => Err(AppError::MatchNotStarted {
+                room_id: self.id.clone(),
+                starts_at,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// The room's slot timing and base fee: a chain's own, else the creator's
+    /// overrides, else the config's.
+    pub fn timing(&self, config: &MarketplaceConfig) -> RoomTiming {
+        if let Some(chain) = &self.chain {
+            return RoomTiming {
+                slot_duration_ms: chain.slot_duration_ms,
+                advance_slot_interval_ms: chain.advance_slot_interval_ms,
+                base_fee_sol: chain.base_fee_sol,
+            };
+        }
+
+        let overrides = self.overrides();
+        RoomTiming {
+            slot_duration_ms: overrides
+                .slot_duration_ms
+                .unwrap_or(config.slot_duration_ms),
+            advance_slot_interval_ms: overrides
+                .slot_duration_ms
+                .map_or(config.advance_slot_interval_ms, |duration| duration as u64),
+            base_fee_sol: overrides.base_fee_sol.unwrap_or(config.base_fee_sol),
+        }
+    }
+
+    pub fn creator(&self) -> Option<String> {
+        self.created_by
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn overrides(&self) -> RoomOverrides {
+        *self
+            .overrides
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set_overrides(&self, overrides: RoomOverrides) {
+        *self
+            .overrides
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = overrides;
+    }
+
+    /// Stops the current slot clock and returns the token for its replacement.
+    pub fn replace_clock(&self) -> CancellationToken {
+        let mut clock = self
+            .clock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        clock.cancel();
+        *clock = self.shutdown.child_token();
+        clock.clone()
+    }
+
+    /// Whether the session may join the room and bid in it.
+    pub fn admits(&self, session_id: &str) -> bool {
+        !self.private || self.admitted.contains(session_id)
+    }
+}
+
+/// A code that admits whoever redeems it to a room, a limited number of times.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RoomInvite {
+    pub code: String,
+    pub room_id: String,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Every room by ID, and the room each session has joined. Sessions that never
+/// joined one play in the global room.
+#[derive(Clone)]
+pub struct RoomManager {
+    global: Arc<Room>,
+    /// Rooms other than the global one
+    rooms: Arc<DashMap<String, Arc<Room>>>,
+    members: Arc<DashMap<String, String>>,
+    /// Invites by code
+    invites: Arc<DashMap<String, RoomInvite>>,
+    matchmaking: Arc<Mutex<MatchmakingQueue>>,
+}
+
+impl RoomManager {
+    pub fn new(global: AppState, shutdown: CancellationToken) -> Self {
+        Self {
+            global: Arc::new(Room::new(
+                GLOBAL_ROOM_ID.to_string(),
+                "Global".to_string(),
+                None,
+                false,
+                None,
+                global,
+                shutdown,
+            )),
+            rooms: Arc::new(DashMap::new()),
+            members: Arc::new(DashMap::new()),
+            invites: Arc::new(DashMap::new()),
+            matchmaking: Arc::new(Mutex::new(MatchmakingQueue::new())),
+        }
+    }
+
+    pub fn get(&self, room_id: &str) -> Option<Arc<Room>> {
+        if room_id == GLOBAL_ROOM_ID {
+            return Some(self.global());
+        }
+        self.rooms.get(room_id).map(|room| room.clone())
+    }
+
+    pub fn global(&self) -> Arc<Room> {
+        self.global.clone()
+    }
+
+    /// The room simulating the configured chain, if there is one.
+    pub fn chain(&self, chain_id: &str) -> Option<Arc<Room>> {
+        self.rooms
+            .get(chain_id)
+            .filter(|room| room.chain.is_some())
+            .map(|room| room.clone())
+    }
+
+    /// Rooms simulating configured chains, in the order they were opened.
+    pub fn chains(&self) -> Vec<Arc<Room>> {
+        self.list()
+            .into_iter()
+            .filter(|room| room.chain.is_some())
+            .collect()
+    }
+
+    /// Every room, the global room first and the rest oldest first.
+    pub fn list(&self) -> Vec<Arc<Room>> {
+        let mut rooms: Vec<Arc<Room>> = self.rooms.iter().map(|room| room.clone()).collect();
+        rooms.sort_by_key(|room| room.created_at);
+        rooms.insert(0, self.global());
+        rooms
+    }
+
+    pub fn insert(&self, room: Room) -> Result<Arc<Room>> {
+        if self.rooms.len() + 1 >= MAX_ROOMS {
+            return Err(anyhow!("At most {} rooms may exist at once", MAX_ROOMS));
+        }
+        let room = Arc::new(room);
+        self.rooms.insert(room.id.clone(), room.clone());
+        Ok(room)
+    }
+
+    /// Takes a room out of play, sending its members back to the global room;
+    /// the caller stops its tasks. The global room and chains cannot be removed.
+    pub fn remove(&self, room_id: &str) -> Option<Arc<Room>> {
+        let (_, room) = self
+            .rooms
+            .remove_if(room_id, |_, room| room.chain.is_none())?;
+        self.members.retain(|_, joined| joined != room_id);
+        self.invites.retain(|_, invite| invite.room_id != room_id);
+        Some(room)
+    }
+
+    pub fn create_invite(
+        &self,
+        room_id: &str,
+        max_uses: u32,
+        expires_at: DateTime<Utc>,
+    ) -> RoomInvite {
+        // Drop spent invites so codes don't pile up
+        let now = Utc::now();
+        self.invites
+            .retain(|_, invite| invite.expires_at > now && invite.uses < invite.max_uses);
+
+        let mut rng = rand::rng();
+        let code = loop {
+            let code: String = (0..INVITE_CODE_LENGTH)
+                .map(|_| INVITE_ALPHABET[rng.random_range(0..INVITE_ALPHABET.len())] as char)
+                .collect();
+            if !self.invites.contains_key(&code) {
+                break code;
+            }
+        };
+
+        let invite = RoomInvite {
+            code: code.clone(),
+            room_id: room_id.to_string(),
+            max_uses,
+            uses: 0,
+            created_at: now,
+            expires_at,
+        };
+        self.invites.insert(code, invite.clone());
+        invite
+    }
+
+    /// Uses up one redemption of the invite, admits the session to its room
+    /// and joins it there.
+    pub fn redeem_invite(&self, code: &str, session_id: &str) -> Result<Arc<Room>, AppError> {
+        let code = code.trim().to_uppercase();
+        let room_id = {
+            let mut invite = self
+                .invites
+                .get_mut(&code)
+                .ok_or_else(|| AppError::InvalidInvite("Invite code not found".to_string()))?;
+            if invite.expires_at <= Utc::now() {
+                return Err(AppError::InvalidInvite(
+                    "Invite code has expired".to_string(),
+                ));
+            }
+            if invite.uses >= invite.max_uses {
+                return Err(AppError::InvalidInvite(
+                    "Invite code has been used up".to_string(),
+                ));
+            }
+            invite.uses += 1;
+            invite.room_id.clone()
+        };
+
+        let room = self
+            .get(&room_id)
+            .ok_or(AppError::RoomNotFound { room_id })?;
+        room.admitted.insert(session_id.to_string());
+        self.join(session_id, &room.id);
+        Ok(room)
+    }
+
+    pub fn join(&self, session_id: &str, room_id: &str) {
+        if room_id == GLOBAL_ROOM_ID {
+            self.members.remove(session_id);
+        } else {
+            self.members
+                .insert(session_id.to_string(), room_id.to_string());
+        }
+    }
+
+    /// Sends the session back to the global room if it joined this one, and
+    /// withdraws its admission to it. Returns whether it was in the room.
+    pub fn remove_member(&self, session_id: &str, room_id: &str) -> bool {
+        let joined = self
+            .members
+            .remove_if(session_id, |_, joined| joined == room_id)
+            .is_some();
+        let admitted = self
+            .get(room_id)
+            .is_some_and(|room| room.admitted.remove(session_id).is_some());
+        joined || admitted
+    }
+
+    /// Carries a session's room membership, admissions, rooms it created and
+    /// place in the matchmaking queue over to its new ID.
+    pub fn rename_member(&self, from: &str, to: &str) {
+        if let Some((_, room_id)) = self.members.remove(from) {
+            self.members.insert(to.to_string(), room_id);
+        }
+        for room in self.list() {
+            if room.admitted.remove(from).is_some() {
+                room.admitted.insert(to.to_string());
+            }
+            let mut created_by = room
+                .created_by
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if created_by.as_deref() == Some(from) {
+                *created_by = Some(to.to_string());
+            }
+        }
+        self.matchmaking().rename(from, to);
+    }
+
+    /// The room the session plays in, the global room unless it joined another.
+    pub fn room_of(&self, session_id: &str) -> Arc<Room> {
+        self.members
+            .get(session_id)
+            .and_then(|room_id| self.get(&room_id))
+            .unwrap_or_else(|| self.global())
+    }
+
+    /// Ends a session everywhere: the player is removed from every room and
+    /// their open bids refunded. Returns the total refunded.
+    pub async fn end_session(&self, session_id: &str, reason: &str) -> Result<Lamports, String> {
+        let mut refund = self.global.state.end_session(session_id, reason).await?;
+        refund += self
+            .remove_from_rooms(session_id, reason)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(refund)
+    }
+
+    /// Removes the player from every room other than the global one, for
+    /// sessions that were already ended there.
+    pub async fn remove_from_rooms(
+        &self,
+        session_id: &str,
+        reason: &str,
+    ) -> Result<Lamports, AppError> {
+        self.members.remove(session_id);
+        self.matchmaking().leave(session_id);
+        let rooms: Vec<Arc<Room>> = self.rooms.iter().map(|room| room.clone()).collect();
+        let mut refund = Lamports::ZERO;
+        for room in rooms {
+            refund += room.state.remove_player(session_id, reason).await?;
+        }
+        Ok(refund)
+    }
+
+    pub fn matchmaking(&self) -> MutexGuard<'_, MatchmakingQueue> {
+        self.matchmaking
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn member_count(&self, room_id: &str) -> usize {
+        if room_id == GLOBAL_ROOM_ID {
+            return 0;
+        }
+        self.members
+            .iter()
+            .filter(|member| member.value() == room_id)
+            .count()
+    }
+
+    pub fn has_members(&self) -> bool {
+        !self.members.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+struct RoomQuery {
+    room: Option<String>,
+}
+
+/// The app context with its state swapped for the room the request applies
+/// to: the `X-Room-Id` header, else the `room` query parameter, else the room
+/// the caller's session joined, else the global room.
+pub struct RoomScoped(pub AppContext);
+
+impl FromRequestParts<AppContext> for RoomScoped {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        context: &AppContext,
+    ) -> Result<Self, Self::Rejection> {
+        let CurrentRoom(room) = CurrentRoom::from_request_parts(parts, context).await?;
+        Ok(Self(context.scoped_to(&room)))
+    }
+}
+
+impl AppContext {
+    /// This context with its state swapped for the room's.
+    pub fn scoped_to(&self, room: &Room) -> Self {
+        let mut scoped = self.clone();
+        scoped.state = room.state.clone();
+        scoped
+    }
+}
+
+/// The room a request applies to, resolved like [`RoomScoped`].
+pub struct CurrentRoom(pub Arc<Room>);
+
+impl FromRequestParts<AppContext> for CurrentRoom {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        context: &AppContext,
+    ) -> Result<Self, Self::Rejection> {
+        let requested = parts
+            .headers
+            .get(ROOM_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .or_else(|| {
+                Query::<RoomQuery>::try_from_uri(&parts.uri)
+                    .ok()
+                    .and_then(|query| query.0.room)
+            });
+
+        let room = match requested {
+            Some(room_id) => context
+                .rooms
+                .get(&room_id)
+                .ok_or(AppError::RoomNotFound { room_id })?,
+            None if context.rooms.has_members() => {
+                match get_session_from_cookie(&parts.headers, None, &context.state.sessions).await {
+                    Ok(session_id) => context.rooms.room_of(&session_id),
+                    Err(_) => context.rooms.global(),
+                }
+            }
+            None => context.rooms.global(),
+        };
+
+        Ok(Self(room))
+    }
+}
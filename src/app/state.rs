@@ -1,155 +1,677 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, net::IpAddr, sync::Arc};
 
-use tokio::sync::RwLock;
+use chrono::Utc;
+use futures_util::future::join_all;
+use tokio::sync::{OwnedMutexGuard, RwLock};
+use tracing::{Instrument, info_span};
 
 use crate::{
-    managers::{auction::AuctionManager, game::GameManager, session::SessionManager},
+    JIT_PREMIUM_MULTIPLIER, MAX_BALANCE_ADJUSTMENT, MAX_PLAYER_BALANCE, SLOT_SHARD_COUNT,
+    app::actor::{Actor, Sharded},
+    config::{AbuseConfig, CookieConfig},
+    error::AppError,
+    managers::{
+        analytics::{DemandHeatmap, FeeStats, LatencyStats, PriceCollector, SettlementOutcome},
+        auction::{AuctionHistory, AuctionManager},
+        escrow::{ESCROW_RETENTION_SLOTS, EscrowManager, EscrowRecord},
+        game::GameManager,
+        health::HealthMonitor,
+        idempotency::IdempotencyManager,
+        invariants::InvariantChecker,
+        moderation::{ModerationManager, SELF_OUTBID_MAX_INCREMENT},
+        nonce::NonceManager,
+        restriction::{RestrictionManager, RestrictionSummary},
+        session::SessionManager,
+        transaction::{TransactionFilter, TransactionStore},
+    },
     models::{
+        admin::BanRecord,
         event::{AppEvent, EventBroadcaster},
-        marketplace::{MarketplaceStats, SlotMarketplace},
+        marketplace::{MarketplaceStats, SlotMarketplace, SlotShard, SlotWindow},
         metrics::Leaderboard,
-        player::PlayerStats,
-        transaction::Transaction,
+        player::{GameMode, PlayerStats, session_display_name},
+        recovery::hash_recovery_code,
+        slot::Slot,
+        transaction::{Transaction, TransactionStatus},
+        types::{Lamports, TransactionType},
     },
+    utils::{auction::resolved_auction, request_id::current_request_id, validation::FieldError},
 };
 
+/// Resolved AOT auction as `(slot, winner, winning_bid, losing_bids)`.
+pub type ResolvedAotAuction = (u64, String, Lamports, Vec<(String, Lamports)>);
+
+/// A bid that entered its auction but has not been announced yet.
+pub struct EnteredBid {
+    pub auction_type: TransactionType,
+    pub slot_number: u64,
+    pub bidder: String,
+    pub amount: Lamports,
+    /// Who led the auction before the bid
+    previous_leader: Option<(String, Lamports)>,
+}
+
 #[derive(Clone)]
 pub struct AppState {
-    pub marketplace: Arc<RwLock<SlotMarketplace>>,
-    pub auctions: Arc<RwLock<AuctionManager>>,
-    pub transactions: Arc<RwLock<HashMap<String, Transaction>>>,
-    pub session_transactions: Arc<RwLock<HashMap<String, Vec<String>>>>,
+    pub marketplace: Actor<SlotMarketplace>,
+    pub slots: Sharded<SlotShard>,
+    pub auctions: Sharded<AuctionManager>,
+    pub auction_history: Actor<AuctionHistory>,
+    pub prices: Actor<PriceCollector>,
+    pub fees: Actor<FeeStats>,
+    pub demand: Actor<DemandHeatmap>,
+    pub latency: Actor<LatencyStats>,
+    /// Escrow records live in the shard of the slot they bid on
+    pub escrow: Sharded<EscrowManager>,
+    /// Held shared while funds move between balances and escrow, and
+    /// exclusively by audits and resets, so neither sees funds in flight
+    pub ledger: Arc<RwLock<()>>,
+    /// Allowlists for slot ranges; kept across resets like bans
+    pub restrictions: Actor<RestrictionManager>,
+    /// Slot states seen by the last invariant check, in debug builds
+    pub invariants: Actor<InvariantChecker>,
+    pub transactions: TransactionStore,
     pub sessions: SessionManager,
     pub events: EventBroadcaster,
-    pub game: Arc<RwLock<GameManager>>,
+    pub game: Actor<GameManager>,
+    pub health: HealthMonitor,
+    pub moderation: ModerationManager,
+    pub idempotency: IdempotencyManager,
+    pub nonces: NonceManager,
 }
 
 impl AppState {
-    pub fn new(slot_duration_ms: i64) -> Self {
+    pub fn new(
+        slot_duration_ms: i64,
+        base_fee: Lamports,
+        slot_window: SlotWindow,
+        abuse_config: AbuseConfig,
+        cookie: CookieConfig,
+    ) -> Self {
+        Self::with_shared(
+            SlotMarketplace::new(slot_duration_ms, base_fee, slot_window),
+            GameManager::new(),
+            SessionManager::new(cookie),
+            ModerationManager::new(abuse_config),
+            IdempotencyManager::new(),
+            NonceManager::new(),
+        )
+    }
+
+    /// A fresh marketplace, game and ledger for a room. Sessions, bans,
+    /// idempotency keys and bid nonces are shared with `self`, so a player is the same person
+    /// in every room.
+    pub fn new_room(
+        &self,
+        slot_duration_ms: i64,
+        base_fee: Lamports,
+        slot_window: SlotWindow,
+        mode: GameMode,
+    ) -> Self {
+        Self::with_shared(
+            SlotMarketplace::new(slot_duration_ms, base_fee, slot_window),
+            GameManager::with_mode(mode),
+            self.sessions.clone(),
+            self.moderation.clone(),
+            self.idempotency.clone(),
+            self.nonces.clone(),
+        )
+    }
+
+    fn with_shared(
+        marketplace: SlotMarketplace,
+        game: GameManager,
+        sessions: SessionManager,
+        moderation: ModerationManager,
+        idempotency: IdempotencyManager,
+        nonces: NonceManager,
+    ) -> Self {
+        let mut window = shard_slots(marketplace.window(), SLOT_SHARD_COUNT);
+
         Self {
-            marketplace: Arc::new(RwLock::new(SlotMarketplace::new(slot_duration_ms))),
-            auctions: Arc::new(RwLock::new(AuctionManager::new())),
-            transactions: Arc::new(RwLock::new(HashMap::new())),
-            session_transactions: Arc::new(RwLock::new(HashMap::new())),
-            sessions: SessionManager::new(),
+            slots: Sharded::spawn("slots", SLOT_SHARD_COUNT, |index| {
+                SlotShard::new(std::mem::take(&mut window[index]))
+            }),
+            marketplace: Actor::spawn("marketplace", marketplace),
+            auctions: Sharded::spawn("auctions", SLOT_SHARD_COUNT, |_| AuctionManager::new()),
+            auction_history: Actor::spawn("auction_history", AuctionHistory::new()),
+            prices: Actor::spawn("prices", PriceCollector::new()),
+            fees: Actor::spawn("fees", FeeStats::new()),
+            demand: Actor::spawn("demand", DemandHeatmap::new()),
+            latency: Actor::spawn("latency", LatencyStats::new()),
+            escrow: Sharded::spawn("escrow", SLOT_SHARD_COUNT, |_| EscrowManager::new()),
+            ledger: Arc::new(RwLock::new(())),
+            restrictions: Actor::spawn("restrictions", RestrictionManager::new()),
+            invariants: Actor::spawn("invariants", InvariantChecker::new()),
+            transactions: TransactionStore::new(),
+            sessions,
             events: EventBroadcaster::new(),
-            game: Arc::new(RwLock::new(GameManager::new())),
+            game: Actor::spawn("game", game),
+            health: HealthMonitor::new(),
+            moderation,
+            idempotency,
+            nonces,
         }
     }
 
-    pub async fn start_jit_auction(&self, slot_number: u64, base_fee: f64) -> anyhow::Result<()> {
-        {
-            let mut auctions = self.auctions.write().await;
-            auctions.start_jit_auction(slot_number, base_fee)?;
-        }
+    pub async fn start_jit_auction(
+        &self,
+        slot_number: u64,
+        base_fee: Lamports,
+    ) -> anyhow::Result<()> {
+        self.auctions
+            .shard(slot_number)
+            .call(move |auctions| auctions.start_jit_auction(slot_number, base_fee))
+            .await??;
 
         self.events.broadcast(AppEvent::JitAuctionStarted {
             slot_number,
-            min_bid: base_fee * 1.05,
+            min_bid: base_fee.scale(JIT_PREMIUM_MULTIPLIER),
+            request_id: current_request_id(),
         });
 
         Ok(())
     }
 
+    /// Opens the slot's AOT auction for `duration_seconds`, or until the slot
+    /// is expected to arrive if that is sooner.
     pub async fn start_aot_auction(
         &self,
         slot_number: u64,
-        base_fee: f64,
+        base_fee: Lamports,
         duration_seconds: i64,
+        closing_warnings: Vec<u64>,
     ) -> anyhow::Result<()> {
-        let ends_at = chrono::Utc::now() + chrono::Duration::seconds(duration_seconds);
+        let slot_arrives_at = self
+            .slots
+            .shard(slot_number)
+            .call(move |shard| shard.get(slot_number).map(|slot| slot.estimated_time))
+            .await?;
 
-        {
-            let mut auctions = self.auctions.write().await;
-            auctions.start_aot_auction(slot_number, base_fee, duration_seconds)?;
-        }
+        let ends_at = self
+            .auctions
+            .shard(slot_number)
+            .call(move |auctions| {
+                auctions.start_aot_auction(
+                    slot_number,
+                    base_fee,
+                    duration_seconds,
+                    slot_arrives_at,
+                    &closing_warnings,
+                )
+            })
+            .await??;
 
         self.events.broadcast(AppEvent::AotAuctionStarted {
             slot_number,
             min_bid: base_fee,
             ends_at,
+            request_id: current_request_id(),
         });
 
         Ok(())
     }
 
+    /// Enters the bid in the slot's JIT auction. Nothing is announced until
+    /// `announce_bid`, so a rejected bundle can still withdraw it unseen.
+    #[tracing::instrument(skip(self, bidder_id), fields(amount = %amount))]
     pub async fn submit_jit_bid(
         &self,
         slot_number: u64,
         bidder_id: String,
-        amount: f64,
-    ) -> anyhow::Result<()> {
-        {
-            let mut auctions = self.auctions.write().await;
-            auctions.submit_jit_bid(slot_number, bidder_id.clone(), amount)?;
-        }
+        amount: Lamports,
+    ) -> Result<EnteredBid, AppError> {
+        let bidder = bidder_id.clone();
+        let previous_leader = self
+            .auctions
+            .shard(slot_number)
+            .call(move |auctions| {
+                let previous_leader = auctions
+                    .jit_auctions
+                    .get(&slot_number)
+                    .and_then(|auction| auction.resolve());
+                auctions
+                    .submit_jit_bid(slot_number, bidder, amount)
+                    .map(|_| previous_leader)
+            })
+            .await??;
 
-        self.events.broadcast(AppEvent::JitBidSubmitted {
+        Ok(EnteredBid {
+            auction_type: TransactionType::Jit,
             slot_number,
             bidder: bidder_id,
             amount,
-        });
-
-        Ok(())
+            previous_leader,
+        })
     }
 
+    /// Enters the bid in the slot's AOT auction. Nothing is announced until
+    /// `announce_bid`, so a rejected bundle can still withdraw it unseen.
+    #[tracing::instrument(skip(self, bidder_id), fields(amount = %amount))]
     pub async fn submit_aot_bid(
         &self,
         slot_number: u64,
         bidder_id: String,
-        amount: f64,
-    ) -> anyhow::Result<()> {
-        {
-            let mut auctions = self.auctions.write().await;
-            auctions.submit_aot_bid(slot_number, bidder_id.clone(), amount)?;
-        }
+        amount: Lamports,
+    ) -> Result<EnteredBid, AppError> {
+        let bidder = bidder_id.clone();
+        let previous_leader = self
+            .auctions
+            .shard(slot_number)
+            .call(move |auctions| {
+                let previous_leader = auctions
+                    .aot_auctions
+                    .get(&slot_number)
+                    .and_then(|auction| auction.resolve());
+                auctions
+                    .submit_aot_bid(slot_number, bidder, amount)
+                    .map(|_| previous_leader)
+            })
+            .await??;
 
-        self.events.broadcast(AppEvent::AotBidSubmitted {
+        Ok(EnteredBid {
+            auction_type: TransactionType::Aot,
             slot_number,
             bidder: bidder_id,
             amount,
+            previous_leader,
+        })
+    }
+
+    /// Broadcasts a bid that entered its auction, tells the leader it passed
+    /// and feeds the abuse detector.
+    pub async fn announce_bid(&self, entered: EnteredBid) {
+        let EnteredBid {
+            auction_type,
+            slot_number,
+            bidder,
+            amount,
+            previous_leader,
+        } = entered;
+
+        self.notify_outbid(
+            auction_type,
+            slot_number,
+            &bidder,
+            amount,
+            previous_leader.as_ref(),
+        );
+        self.track_self_outbid(&bidder, amount, previous_leader)
+            .await;
+
+        let request_id = current_request_id();
+        self.events.broadcast(match auction_type {
+            TransactionType::Jit => AppEvent::JitBidSubmitted {
+                slot_number,
+                bidder,
+                amount,
+                request_id,
+            },
+            TransactionType::Aot => AppEvent::AotBidSubmitted {
+                slot_number,
+                bidder,
+                amount,
+                request_id,
+            },
         });
+    }
 
-        Ok(())
+    /// Tells the previous leader on their private stream that they were
+    /// passed, so they can counter-bid before the auction closes.
+    fn notify_outbid(
+        &self,
+        auction_type: TransactionType,
+        slot_number: u64,
+        bidder_id: &str,
+        amount: Lamports,
+        previous_leader: Option<&(String, Lamports)>,
+    ) {
+        if let Some((leader, leading_amount)) = previous_leader {
+            if leader != bidder_id && amount > *leading_amount {
+                self.events.send_private(
+                    leader,
+                    AppEvent::Outbid {
+                        slot_number,
+                        auction_type,
+                        your_bid: *leading_amount,
+                        new_bid: amount,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Feeds the abuse detector when a bidder raises their own leading bid by a
+    /// minimum increment, which only burns escrow and floods the event stream.
+    async fn track_self_outbid(
+        &self,
+        bidder_id: &str,
+        amount: Lamports,
+        previous_leader: Option<(String, Lamports)>,
+    ) {
+        if let Some((leader, leading_amount)) = previous_leader {
+            if leader == bidder_id && amount <= leading_amount + SELF_OUTBID_MAX_INCREMENT {
+                self.moderation.record_self_outbid(bidder_id).await;
+            }
+        }
+    }
+
+    /// Waits for the player's other bids to finish. Hold the guard across a whole
+    /// bid submission or eviction; it is taken before any other lock.
+    pub async fn lock_player_bids(
+        &self,
+        session_id: &str,
+    ) -> Result<OwnedMutexGuard<()>, AppError> {
+        loop {
+            let (guard, current) = self.wait_for_bid_lock(session_id).await?;
+            // The player was removed while we waited, so lock whoever holds the ID now
+            if current {
+                return Ok(guard);
+            }
+        }
+    }
+
+    /// Takes the player's bid lock to place a bid. Refuses once the player was
+    /// removed, renamed or banned while the bid waited behind an eviction.
+    pub async fn lock_bidder(&self, session_id: &str) -> Result<OwnedMutexGuard<()>, AppError> {
+        let (guard, current) = self.wait_for_bid_lock(session_id).await?;
+        if !current {
+            return Err(AppError::Unauthorized);
+        }
+        if self.moderation.is_session_banned(session_id).await {
+            return Err(AppError::Banned(
+                "This player is banned from bidding".to_string(),
+            ));
+        }
+        Ok(guard)
+    }
+
+    /// Locks the session's bid lock and reports whether it is still the
+    /// session's once acquired.
+    async fn wait_for_bid_lock(
+        &self,
+        session_id: &str,
+    ) -> Result<(OwnedMutexGuard<()>, bool), AppError> {
+        let session = session_id.to_string();
+        let lock = self.game.call(move |game| game.bid_lock(&session)).await?;
+        let guard = lock.clone().lock_owned().await;
+
+        let session = session_id.to_string();
+        let current = self
+            .game
+            .call(move |game| game.is_current_bid_lock(&session, &lock))
+            .await?;
+        Ok((guard, current))
+    }
+
+    /// Moves the transaction's priority fee from the sender's balance into escrow
+    /// and records the bid on their stats.
+    pub async fn hold_escrow(&self, transaction: &Transaction) -> Result<(), AppError> {
+        // Audits wait until the funds land in escrow, so they never see them in
+        // neither place
+        let _ledger = self
+            .ledger
+            .read()
+            .instrument(info_span!("lock.acquire", lock = "ledger"))
+            .await;
+
+        let (transaction_id, sender, auction_type, slot, amount) = (
+            transaction.id.clone(),
+            transaction.sender.clone(),
+            transaction.transaction_type(),
+            transaction.target_slot(),
+            transaction.priority_fee,
+        );
+        self.escrow
+            .shard(slot)
+            .call(move |escrow| escrow.hold(transaction_id, sender, auction_type, slot, amount))
+            .await??;
+
+        let sender = transaction.sender.clone();
+        let deducted = self
+            .game
+            .call(move |game| game.fund_bid(sender, amount, slot))
+            .await
+            .and_then(|deducted| deducted);
+
+        if deducted.is_err() {
+            let transaction_id = transaction.id.clone();
+            self.escrow
+                .shard(slot)
+                .call(move |escrow| escrow.cancel(&transaction_id))
+                .await?;
+        }
+        deducted
+    }
+
+    /// Reverses `hold_escrow` for a bid that failed before it was placed, restoring
+    /// the sender's balance and bid stats. Returns the amount returned, or `None`
+    /// if the escrow was not held.
+    pub async fn rollback_escrow(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Option<Lamports>, AppError> {
+        let _ledger = self
+            .ledger
+            .read()
+            .instrument(info_span!("lock.acquire", lock = "ledger"))
+            .await;
+
+        let transaction_id = transaction.id.clone();
+        let cancelled = self
+            .escrow
+            .shard(transaction.target_slot())
+            .call(move |escrow| {
+                let record = escrow.cancel(&transaction_id)?;
+                let other_bids_for_slot =
+                    escrow.has_bid_for_slot(&record.bidder, record.slot_number);
+                Some((record, other_bids_for_slot))
+            })
+            .await?;
+        let Some((record, other_bids_for_slot)) = cancelled else {
+            return Ok(None);
+        };
+
+        let (bidder, amount, slot) = (record.bidder, record.amount, record.slot_number);
+        self.game
+            .call(move |game| game.revert_bid(&bidder, amount, slot, other_bids_for_slot))
+            .await?;
+
+        Ok(Some(amount))
+    }
+
+    /// Pays out a transaction's escrow once it executes. Returns the amount, or
+    /// `None` if the escrow was already settled.
+    pub async fn release_escrow(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Option<Lamports>, AppError> {
+        let transaction_id = transaction.id.clone();
+        self.escrow
+            .shard(transaction.target_slot())
+            .call(move |escrow| escrow.release(&transaction_id).map(|record| record.amount))
+            .await
     }
 
-    pub async fn resolve_jit_auction(&self, slot_number: u64) -> Option<(String, f64)> {
-        let result = {
-            let mut auctions = self.auctions.write().await;
-            auctions.resolve_jit(slot_number)
+    /// Returns a transaction's escrow to its bidder. Returns the amount credited, or
+    /// `None` if the escrow was already settled, so repeated refunds are harmless.
+    pub async fn refund_escrow(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Option<Lamports>, AppError> {
+        let _ledger = self
+            .ledger
+            .read()
+            .instrument(info_span!("lock.acquire", lock = "ledger"))
+            .await;
+
+        let transaction_id = transaction.id.clone();
+        let Some(record) = self
+            .escrow
+            .shard(transaction.target_slot())
+            .call(move |escrow| escrow.refund(&transaction_id))
+            .await?
+        else {
+            return Ok(None);
         };
+        let (bidder, amount) = (record.bidder.clone(), record.amount);
+        self.fees
+            .call(move |fees| fees.record_refund(amount))
+            .await?;
+
+        let refunded = self
+            .game
+            .call(move |game| game.credit_refund(&bidder, amount))
+            .await?;
+
+        if !refunded {
+            tracing::warn!(
+                "Could not credit player {} with {} SOL of escrow for transaction {}, forfeiting it",
+                record.bidder.chars().take(8).collect::<String>(),
+                record.amount,
+                transaction.id.chars().take(8).collect::<String>()
+            );
+        }
+
+        Ok(Some(record.amount))
+    }
+
+    /// Escrow records of the given transactions that are still retained,
+    /// keyed by transaction ID.
+    pub async fn escrow_records(
+        &self,
+        transactions: &[Transaction],
+    ) -> Result<HashMap<String, EscrowRecord>, AppError> {
+        let mut shard_ids = vec![Vec::new(); self.escrow.shard_count()];
+        for transaction in transactions {
+            shard_ids[self.escrow.shard_index(transaction.target_slot())]
+                .push(transaction.id.clone());
+        }
 
-        if let Some((winner, winning_bid)) = &result {
-            self.events.broadcast(AppEvent::JitAuctionResolved {
+        let records = join_all(self.escrow.shards().zip(shard_ids).map(|(shard, ids)| {
+            shard.call(move |escrow| {
+                ids.iter()
+                    .filter_map(|id| escrow.get(id).cloned())
+                    .collect::<Vec<_>>()
+            })
+        }))
+        .await;
+
+        let mut by_transaction = HashMap::new();
+        for record in records
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+        {
+            by_transaction.insert(record.transaction_id.clone(), record);
+        }
+        Ok(by_transaction)
+    }
+
+    /// Every escrow record for the slot, of either auction type and any status.
+    pub async fn escrow_records_for_slot(
+        &self,
+        slot_number: u64,
+    ) -> Result<Vec<EscrowRecord>, AppError> {
+        self.escrow
+            .shard(slot_number)
+            .call(move |escrow| escrow.records_for_slot(slot_number))
+            .await
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub async fn resolve_jit_auction(
+        &self,
+        slot_number: u64,
+    ) -> Result<Option<(String, Lamports)>, AppError> {
+        let result = self
+            .auctions
+            .shard(slot_number)
+            .call(move |auctions| auctions.resolve_jit(slot_number))
+            .await?;
+
+        match &result {
+            Some((winner, winning_bid)) => self.events.broadcast(AppEvent::JitAuctionResolved {
                 slot_number,
                 winner: winner.clone(),
                 winning_bid: *winning_bid,
-            });
+            }),
+            None => self
+                .events
+                .forget_auction(TransactionType::Jit, slot_number),
         }
 
-        result
+        Ok(result)
+    }
+
+    /// Adds a resolved auction to the history once its losers have been settled,
+    /// reading the bid count and refunds from the slot's escrow.
+    pub async fn record_auction_history(
+        &self,
+        slot_number: u64,
+        auction_type: TransactionType,
+        winner: String,
+        clearing_price: Lamports,
+    ) -> Result<(), AppError> {
+        let records = self.escrow_records_for_slot(slot_number).await?;
+        let auction = resolved_auction(
+            &records,
+            slot_number,
+            auction_type,
+            winner,
+            clearing_price,
+            |transaction_id| {
+                self.transactions
+                    .get(transaction_id)
+                    .map(|transaction| transaction.created_slot)
+            },
+        );
+
+        let base_fee = self
+            .slots
+            .shard(slot_number)
+            .call(move |shard| shard.get(slot_number).map(|slot| slot.base_fee))
+            .await?
+            .unwrap_or_default();
+
+        self.prices
+            .call(move |prices| prices.record_clearing_price(auction_type, clearing_price))
+            .await?;
+        self.fees
+            .call(move |fees| fees.record_winning_bid(auction_type, clearing_price, base_fee))
+            .await?;
+        self.auction_history
+            .call(move |history| history.record(auction))
+            .await
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn resolve_ready_aot_auctions(
         &self,
         current_slot: u64,
-    ) -> Vec<(u64, String, f64, Vec<(String, f64)>)> {
-        let results = {
-            let mut auctions = self.auctions.write().await;
-            let mut resolved = Vec::new();
-
-            let ready_slots: Vec<u64> = auctions
-                .aot_auctions
-                .iter()
-                .filter(|(_, auction)| auction.should_resolve(current_slot))
-                .map(|(slot, _)| *slot)
-                .collect();
-
-            for slot in ready_slots {
-                if let Some(auction) = auctions.aot_auctions.remove(&slot) {
-                    if let Some((winner, winning_bid)) = auction.resolve() {
+    ) -> Result<Vec<ResolvedAotAuction>, AppError> {
+        let shards = self
+            .auctions
+            .call_all(move |auctions| {
+                let ready_slots: Vec<u64> = auctions
+                    .aot_auctions
+                    .iter()
+                    .filter(|(_, auction)| auction.should_resolve(current_slot))
+                    .map(|(slot, _)| *slot)
+                    .collect();
+
+                let mut resolved = Vec::new();
+                let mut unsold = Vec::new();
+                for slot in ready_slots {
+                    if let Some(auction) = auctions.aot_auctions.remove(&slot) {
+                        let Some((winner, winning_bid)) = auction.resolve() else {
+                            unsold.push(slot);
+                            continue;
+                        };
+
                         // Get losers with their bid amounts for refunds
-                        let losers_with_bids: Vec<(String, f64)> = auction
+                        let losers_with_bids: Vec<(String, Lamports)> = auction
                             .bids
                             .iter()
                             .filter(|(bidder, _, _)| bidder != &winner)
@@ -159,10 +681,20 @@ impl AppState {
                         resolved.push((slot, winner, winning_bid, losers_with_bids));
                     }
                 }
-            }
 
-            resolved
-        };
+                (resolved, unsold)
+            })
+            .await?;
+
+        let mut results: Vec<ResolvedAotAuction> = Vec::new();
+        for (resolved, unsold) in shards {
+            results.extend(resolved);
+            for slot_number in unsold {
+                self.events
+                    .forget_auction(TransactionType::Aot, slot_number);
+            }
+        }
+        results.sort_by_key(|(slot, ..)| *slot);
 
         for (slot_number, winner, winning_bid, _) in &results {
             self.events.broadcast(AppEvent::AotAuctionResolved {
@@ -172,42 +704,43 @@ impl AppState {
             });
         }
 
-        results
+        Ok(results)
+    }
+
+    /// Broadcasts `AotAuctionClosing` for each AOT auction that just passed
+    /// one of its closing warning thresholds.
+    pub async fn announce_closing_aot_auctions(&self) -> Result<(), AppError> {
+        let mut closing: Vec<(u64, u64)> = self
+            .auctions
+            .call_all(|auctions| auctions.take_closing_warnings())
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+        closing.sort_unstable();
+
+        for (slot_number, seconds_left) in closing {
+            self.events.broadcast(AppEvent::AotAuctionClosing {
+                slot_number,
+                seconds_left,
+            });
+        }
+
+        Ok(())
     }
 
     /// Adds a new transaction to global and session-specific stores.
     /// Also broadcasts a `TransactionUpdated` event.
     pub async fn add_transaction(&self, session_id: String, transaction: Transaction) {
-        let transaction_id = transaction.id.clone();
-
-        self.transactions
-            .write()
-            .await
-            .insert(transaction_id.clone(), transaction.clone());
-
-        self.session_transactions
-            .write()
-            .await
-            .entry(session_id)
-            .or_insert_with(Vec::new)
-            .push(transaction_id);
+        self.transactions.insert(session_id, transaction.clone());
 
-        self.events
-            .broadcast(AppEvent::TransactionUpdated { transaction });
+        self.events.broadcast(AppEvent::TransactionUpdated {
+            transaction: Box::new(transaction),
+        });
     }
 
     pub async fn get_session_transactions(&self, session_id: &str) -> Vec<Transaction> {
-        let session_transactions = self.session_transactions.read().await;
-        let transaction_ids = session_transactions
-            .get(session_id)
-            .cloned()
-            .unwrap_or_default();
-
-        let transactions = self.transactions.read().await;
-        transaction_ids
-            .iter()
-            .filter_map(|id| transactions.get(id).cloned())
-            .collect()
+        self.transactions.session_transactions(session_id)
     }
 
     pub async fn get_session_transactions_paginated(
@@ -216,119 +749,761 @@ impl AppState {
         offset: u32,
         limit: u32,
     ) -> Vec<Transaction> {
-        let session_transactions = self.session_transactions.read().await;
-        let transaction_ids = session_transactions
-            .get(session_id)
-            .cloned()
-            .unwrap_or_default();
-
-        let transactions = self.transactions.read().await;
-
-        transaction_ids
-            .iter()
-            .skip(offset as usize)
-            .take(limit as usize)
-            .filter_map(|id| transactions.get(id).cloned())
-            .collect()
+        self.transactions
+            .session_page(session_id, offset as usize, limit as usize)
     }
 
     pub async fn get_session_transaction_count(&self, session_id: &str) -> u32 {
-        let session_transactions = self.session_transactions.read().await;
-        session_transactions
-            .get(session_id)
-            .map(|ids| ids.len() as u32)
-            .unwrap_or(0)
+        self.transactions.session_count(session_id) as u32
     }
 
     pub async fn get_transaction_by_id(&self, transaction_id: &str) -> Option<Transaction> {
-        self.transactions.read().await.get(transaction_id).cloned()
+        self.transactions.get(transaction_id)
     }
 
+    /// A page of transactions matching `filter`, newest first, with the total match count.
     pub async fn get_all_transactions_paginated(
         &self,
+        filter: TransactionFilter,
         offset: u32,
         limit: u32,
-    ) -> Vec<Transaction> {
-        let transactions = self.transactions.read().await;
+    ) -> (Vec<Transaction>, u32) {
+        let (transactions, total) = self
+            .transactions
+            .page(filter, offset as usize, limit as usize);
+        (transactions, total as u32)
+    }
+
+    pub async fn update_transaction_by_id(
+        &self,
+        transaction_id: &str,
+        transaction: Transaction,
+    ) -> Result<(), AppError> {
+        let previous = self
+            .transactions
+            .update(transaction_id, transaction.clone());
 
-        let mut all_transactions: Vec<Transaction> = transactions.values().cloned().collect();
-        all_transactions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        // Latency is measured once, when a pending transaction first settles
+        let outcome = match transaction.status {
+            TransactionStatus::Included { .. } | TransactionStatus::AuctionWon { .. } => {
+                Some(SettlementOutcome::Included)
+            }
+            TransactionStatus::Failed { .. } => Some(SettlementOutcome::Failed),
+            _ => None,
+        };
+        let was_pending =
+            previous.is_some_and(|previous| matches!(previous.status, TransactionStatus::Pending));
+        if let Some(outcome) = outcome.filter(|_| was_pending) {
+            let settled_at = transaction.included_at.unwrap_or_else(Utc::now);
+            let latency_ms = (settled_at - transaction.created_at)
+                .num_milliseconds()
+                .max(0);
+            let (transaction_type, priority_fee) =
+                (transaction.transaction_type(), transaction.priority_fee);
+            self.latency
+                .call(move |latency| {
+                    latency.record(transaction_type, priority_fee, outcome, latency_ms as u64)
+                })
+                .await?;
+        }
+
+        self.events.broadcast(AppEvent::TransactionUpdated {
+            transaction: Box::new(transaction),
+        });
+        Ok(())
+    }
 
-        all_transactions
+    pub async fn get_current_slot(&self) -> Result<u64, AppError> {
+        self.marketplace
+            .call(|marketplace| marketplace.current_slot)
+            .await
+    }
+
+    pub async fn advance_slot(&self) -> Result<u64, AppError> {
+        let (next_slot, (current_slot, listing_end)) = self
+            .marketplace
+            .call(|marketplace| (marketplace.advance_slot(), marketplace.listing_range()))
+            .await?;
+
+        self.slots.call_all(|shard| shard.expire_stale()).await?;
+        let prune_before = current_slot.saturating_sub(ESCROW_RETENTION_SLOTS);
+        self.escrow
+            .call_all(move |escrow| escrow.prune_settled(prune_before))
+            .await?;
+        let base_fee = next_slot.base_fee;
+        self.prices
+            .call(move |prices| prices.record_base_fee(base_fee))
+            .await?;
+        self.demand.call(|demand| demand.record_slot()).await?;
+        self.slots
+            .shard(next_slot.slot_number)
+            .call(move |shard| shard.insert(next_slot))
+            .await?;
+
+        self.events
+            .broadcast(AppEvent::SlotAdvanced { current_slot });
+
+        let mut slots: Vec<Slot> = self
+            .slots
+            .call_all(move |shard| shard.take_changes(current_slot, listing_end))
+            .await?
             .into_iter()
-            .skip(offset as usize)
-            .take(limit as usize)
-            .collect()
+            .flatten()
+            .collect();
+        if !slots.is_empty() {
+            slots.sort_by_key(|slot| slot.slot_number);
+            self.events.broadcast(AppEvent::SlotsChanged {
+                slots,
+                replaces_window: false,
+            });
+        }
+
+        self.broadcast_stats().await?;
+        Ok(current_slot)
     }
 
-    pub async fn get_global_transaction_count(&self) -> u32 {
-        self.transactions.read().await.len() as u32
+    /// The current slot and the listed slots from it onwards.
+    pub async fn get_upcoming_slots(&self) -> Result<(u64, Vec<Slot>), AppError> {
+        let (current_slot, listing_end) = self.get_listing_range().await?;
+        Ok((
+            current_slot,
+            self.get_slots(current_slot, listing_end).await?,
+        ))
     }
 
-    pub async fn update_transaction_by_id(&self, transaction_id: &str, transaction: Transaction) {
-        self.transactions
-            .write()
+    /// The current slot and the end of the listed slots, exclusive.
+    pub async fn get_listing_range(&self) -> Result<(u64, u64), AppError> {
+        self.marketplace
+            .call(|marketplace| marketplace.listing_range())
             .await
-            .insert(transaction_id.to_string(), transaction.clone());
+    }
 
-        self.events
-            .broadcast(AppEvent::TransactionUpdated { transaction });
+    /// Slots numbered from `from` up to, but not including, `to`, in order.
+    pub async fn get_slots(&self, from: u64, to: u64) -> Result<Vec<Slot>, AppError> {
+        let mut slots: Vec<Slot> = self
+            .slots
+            .call_all(move |shard| shard.range(from, to))
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+        slots.sort_by_key(|slot| slot.slot_number);
+        Ok(slots)
     }
 
-    pub async fn get_current_slot(&self) -> u64 {
-        self.marketplace.read().await.current_slot
+    /// Snapshot of the marketplace for a subscriber that missed `missed_events`.
+    pub async fn stream_resync(&self, missed_events: u64) -> Result<AppEvent, AppError> {
+        let stats = self.get_marketplace_stats().await?;
+        let (_, slots) = self.get_upcoming_slots().await?;
+
+        Ok(AppEvent::StreamResync {
+            missed_events,
+            current_slot: stats.current_slot,
+            slots,
+            active_jit_auctions: stats.active_jit_auctions,
+            active_aot_auctions: stats.active_aot_auctions,
+            total_transactions: stats.total_transactions,
+        })
     }
 
-    pub async fn advance_slot(&self) -> u64 {
-        let current_slot = {
-            let mut marketplace = self.marketplace.write().await;
-            marketplace.advance_slot();
-            marketplace.current_slot
-        };
+    pub async fn anchor_to_slot(&self, slot_number: u64) -> Result<(), AppError> {
+        let window = self
+            .marketplace
+            .call(move |marketplace| marketplace.anchor_to_slot(slot_number))
+            .await?;
+        self.replace_slots(window).await?;
 
-        self.events
-            .broadcast(AppEvent::SlotAdvanced { current_slot });
+        self.events.broadcast(AppEvent::SlotAdvanced {
+            current_slot: slot_number,
+        });
+        self.broadcast_window().await?;
+        self.broadcast_stats().await
+    }
 
-        let slots: Vec<_> = {
-            let marketplace = self.marketplace.read().await;
-            marketplace
-                .slots
-                .iter()
-                .filter(|(slot_num, _)| {
-                    **slot_num >= current_slot && **slot_num < current_slot + 50
-                })
-                .map(|(_, slot)| slot.clone())
-                .collect()
+    /// Sends clients the whole visible window in place of their copy.
+    async fn broadcast_window(&self) -> Result<(), AppError> {
+        self.events.broadcast(AppEvent::SlotsChanged {
+            slots: self.get_upcoming_slots().await?.1,
+            replaces_window: true,
+        });
+        Ok(())
+    }
+
+    /// Swaps every slot for `window`, one shard at a time.
+    async fn replace_slots(&self, window: Vec<Slot>) -> Result<(), AppError> {
+        let shard_windows = shard_slots(window, self.slots.shard_count());
+
+        join_all(
+            self.slots
+                .shards()
+                .zip(shard_windows)
+                .map(|(shard, slots)| shard.call(move |shard| *shard = SlotShard::new(slots))),
+        )
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+        // The new slots start over, which is not a regression
+        self.invariants
+            .call(|invariants| *invariants = InvariantChecker::new())
+            .await
+    }
+
+    /// Clears all auctions, transactions and player stats, keeping sessions and
+    /// the current slot so connected clients carry on from a clean marketplace.
+    pub async fn reset_simulation(&self) -> Result<(), AppError> {
+        let window = self
+            .marketplace
+            .call(|marketplace| marketplace.window())
+            .await?;
+        self.replace_slots(window).await?;
+
+        self.auctions
+            .call_all(|auctions| *auctions = AuctionManager::new())
+            .await?;
+        self.auction_history
+            .call(|history| *history = AuctionHistory::new())
+            .await?;
+        self.prices
+            .call(|prices| *prices = PriceCollector::new())
+            .await?;
+        self.fees.call(|fees| *fees = FeeStats::new()).await?;
+        self.demand
+            .call(|demand| *demand = DemandHeatmap::new())
+            .await?;
+        self.latency
+            .call(|latency| *latency = LatencyStats::new())
+            .await?;
+        {
+            // Escrow and balances are swapped together so audits never see half a reset
+            let _ledger = self.ledger.write().await;
+            self.transactions.clear();
+            self.escrow
+                .call_all(|escrow| *escrow = EscrowManager::new())
+                .await?;
+            self.game
+                .call(|game| *game = GameManager::with_mode(game.mode))
+                .await?;
+        }
+        // Recorded bid responses refer to transactions that no longer exist
+        self.idempotency.clear().await;
+
+        self.broadcast_window().await?;
+        self.broadcast_stats().await
+    }
+
+    /// Links a verified wallet to the session. Restoring a saved profile is refused
+    /// while either side has pending bids, since their escrow belongs to the old stats.
+    pub async fn link_wallet(&self, session_id: &str, wallet: &str) -> Result<PlayerStats, String> {
+        let (session, wallet_key) = (session_id.to_string(), wallet.to_string());
+        let (other_session, restoring) = self
+            .game
+            .call(move |game| {
+                let other_session = game
+                    .session_for_wallet(&wallet_key)
+                    .filter(|other| *other != session);
+                let restoring =
+                    other_session.is_some() || game.wallet_profiles.contains_key(&wallet_key);
+                (other_session, restoring)
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // No bid may land between the pending check and the profile swap; locks
+        // are taken in a fixed order so two sessions linking each other cannot deadlock
+        let mut sessions: Vec<&str> = std::iter::once(session_id)
+            .chain(other_session.as_deref())
+            .collect();
+        sessions.sort();
+        let mut bid_guards = Vec::new();
+        for sid in sessions {
+            bid_guards.push(
+                self.lock_player_bids(sid)
+                    .await
+                    .map_err(|e| e.to_string())?,
+            );
+        }
+
+        if restoring {
+            for sid in std::iter::once(session_id).chain(other_session.as_deref()) {
+                if self.has_pending_bids(sid).await {
+                    return Err(
+                        "Wait for pending bids to settle before linking this wallet".to_string()
+                    );
+                }
+            }
+        }
+
+        let (session, wallet_key) = (session_id.to_string(), wallet.to_string());
+        let stats = self
+            .game
+            .call(move |game| game.link_wallet(&session, &wallet_key).cloned())
+            .await
+            .map_err(|e| e.to_string())??;
+
+        // Move the previous session's transaction history along with its profile
+        if let Some(other_session) = other_session {
+            self.transactions.move_session(&other_session, session_id);
+        }
+
+        Ok(stats)
+    }
+
+    /// Attaches the profile and transaction history a recovery code was issued
+    /// for to the session, under the new code `next_code`. Refused while either
+    /// side has pending bids, like linking a wallet.
+    pub async fn recover_profile(
+        &self,
+        session_id: &str,
+        code: &str,
+        next_code: &str,
+    ) -> Result<PlayerStats, AppError> {
+        let code_hash = hash_recovery_code(code);
+        let (session, hash) = (session_id.to_string(), code_hash.clone());
+        let other_session = self
+            .game
+            .call(move |game| {
+                game.session_for_recovery_code(&hash)
+                    .filter(|other| *other != session)
+            })
+            .await?;
+
+        let mut sessions: Vec<&str> = std::iter::once(session_id)
+            .chain(other_session.as_deref())
+            .collect();
+        sessions.sort();
+        let mut bid_guards = Vec::new();
+        for sid in sessions {
+            bid_guards.push(self.lock_player_bids(sid).await?);
+        }
+        for sid in std::iter::once(session_id).chain(other_session.as_deref()) {
+            if self.has_pending_bids(sid).await {
+                return Err(AppError::BidsPending);
+            }
+        }
+
+        let (session, next_hash) = (session_id.to_string(), hash_recovery_code(next_code));
+        let (stats, previous_session) = self
+            .game
+            .call(move |game| {
+                game.recover_profile(&session, &code_hash, next_hash)
+                    .map(|(stats, previous)| (stats.clone(), previous))
+            })
+            .await?
+            .map_err(AppError::InvalidRecoveryCode)?;
+
+        if previous_session != session_id {
+            self.transactions
+                .rename_session(&previous_session, session_id);
+        }
+
+        Ok(stats)
+    }
+
+    pub async fn has_pending_bids(&self, session_id: &str) -> bool {
+        self.get_session_transactions(session_id)
+            .await
+            .iter()
+            .any(|transaction| matches!(transaction.status, TransactionStatus::Pending))
+    }
+
+    /// Moves a player's stats, transactions, escrow records and allowlist
+    /// entries to a new session ID. The caller holds the player's bid lock and
+    /// has checked that no bid is pending, since auction entries stay with the
+    /// old ID.
+    pub async fn rename_player(&self, from: &str, to: &str) -> Result<(), AppError> {
+        let (from_id, to_id) = (from.to_string(), to.to_string());
+        self.game
+            .call(move |game| game.rename_player(&from_id, &to_id))
+            .await?;
+        self.transactions.rename_session(from, to);
+        self.rename_bidder(from, to).await
+    }
+
+    /// Moves the escrow records and allowlist entries of bidder `from` to `to`.
+    async fn rename_bidder(&self, from: &str, to: &str) -> Result<(), AppError> {
+        let (from_id, to_id) = (from.to_string(), to.to_string());
+        self.escrow
+            .call_all(move |escrow| escrow.rename_bidder(&from_id, &to_id))
+            .await?;
+        let (from_id, to_id) = (from.to_string(), to.to_string());
+        self.restrictions
+            .call(move |restrictions| restrictions.rename_session(&from_id, &to_id))
+            .await
+    }
+
+    /// Ends the least recently active sessions of `wallet` beyond `max_sessions`,
+    /// never `keep_session`. Returns the device IDs of the ended sessions.
+    pub async fn enforce_session_limit(
+        &self,
+        wallet: &str,
+        keep_session: &str,
+        max_sessions: usize,
+    ) -> Vec<String> {
+        let others: Vec<_> = self
+            .sessions
+            .wallet_sessions(wallet)
+            .await
+            .into_iter()
+            .filter(|session| session.id != keep_session)
+            .collect();
+
+        let mut ended = Vec::new();
+        for session in others.into_iter().skip(max_sessions.saturating_sub(1)) {
+            if self
+                .end_session(&session.id, "Signed out by a newer session")
+                .await
+                .is_ok()
+            {
+                ended.push(session.device_id);
+            }
+        }
+
+        ended
+    }
+
+    /// Withdraws a player's bids from open auctions and refunds the escrow of
+    /// their pending transactions. Returns the amount refunded. The caller
+    /// holds the player's bid lock, so no bid lands behind the eviction.
+    async fn evict_player_bids(
+        &self,
+        session_id: &str,
+        reason: &str,
+    ) -> Result<Lamports, AppError> {
+        let bidder = session_id.to_string();
+        self.auctions
+            .call_all(move |auctions| auctions.remove_bidder(&bidder))
+            .await?;
+
+        let mut refund = Lamports::ZERO;
+        for mut transaction in self.get_session_transactions(session_id).await {
+            if matches!(transaction.status, TransactionStatus::Pending) {
+                transaction.mark_failed(format!(
+                    "{}. Refunding {} SOL",
+                    reason, transaction.priority_fee
+                ));
+
+                self.update_transaction_by_id(&transaction.id, transaction.clone())
+                    .await?;
+                refund += self.refund_escrow(&transaction).await?.unwrap_or_default();
+            }
+        }
+
+        Ok(refund)
+    }
+
+    /// Ends a session after refunding its open bids with `reason`. The profile is
+    /// dropped like an expired session's, so wallet-linked players can sign back in.
+    pub async fn end_session(&self, session_id: &str, reason: &str) -> Result<Lamports, String> {
+        if !self.sessions.invalidate_session(session_id).await {
+            return Err("Session not found".to_string());
+        }
+
+        self.remove_player(session_id, reason)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Refunds a player's open bids with `reason` and drops their profile,
+    /// leaving the session itself alone. Returns the amount refunded.
+    pub async fn remove_player(
+        &self,
+        session_id: &str,
+        reason: &str,
+    ) -> Result<Lamports, AppError> {
+        let bid_guard = self.lock_player_bids(session_id).await?;
+        let refund = self.evict_player_bids(session_id, reason).await?;
+        let session = session_id.to_string();
+        self.game
+            .call(move |game| game.cleanup_players(&[session]))
+            .await?;
+        drop(bid_guard);
+        self.broadcast_stats().await?;
+
+        Ok(refund)
+    }
+
+    /// Refunds a player's open bids and erases their profile, keeping nothing
+    /// for a wallet or recovery code. Their transactions and bid records stay
+    /// under `alias`. Returns the amount refunded and the number of
+    /// transactions anonymized.
+    pub async fn delete_player(
+        &self,
+        session_id: &str,
+        alias: &str,
+        reason: &str,
+    ) -> Result<(Lamports, usize), AppError> {
+        let bid_guard = self.lock_player_bids(session_id).await?;
+        let refund = self.evict_player_bids(session_id, reason).await?;
+        let session = session_id.to_string();
+        self.game
+            .call(move |game| game.delete_player(&session))
+            .await?;
+        let anonymized = self.transactions.anonymize_session(session_id, alias);
+        self.rename_bidder(session_id, alias).await?;
+        drop(bid_guard);
+        self.broadcast_stats().await?;
+
+        Ok((refund, anonymized))
+    }
+
+    /// Bans a player: ends the session, refunds open bids, freezes their stats and
+    /// blocks further bids from their wallet and last known IP. A session that
+    /// never bid has no stats to freeze and none are created for it.
+    pub async fn ban_player(
+        &self,
+        session_id: &str,
+        reason: Option<String>,
+    ) -> Result<(BanRecord, Lamports), String> {
+        let ip = self.sessions.get_client_ip(session_id).await;
+        if !self.sessions.invalidate_session(session_id).await {
+            return Err("Session not found".to_string());
+        }
+
+        let bid_guard = self
+            .lock_player_bids(session_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        let refund = self
+            .evict_player_bids(session_id, "Player was banned")
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let session = session_id.to_string();
+        let wallet = self
+            .game
+            .call(move |game| {
+                let stats = game.player_stats.get_mut(&session)?;
+                stats.banned = true;
+                let wallet = stats.wallet.clone();
+                game.invalidate_leaderboard();
+                wallet
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let record = BanRecord {
+            session_id: session_id.to_string(),
+            wallet,
+            ip,
+            reason,
+            banned_at: chrono::Utc::now(),
         };
+        self.moderation.ban(record.clone()).await;
+        drop(bid_guard);
+        self.broadcast_stats().await.map_err(|e| e.to_string())?;
 
-        self.events.broadcast(AppEvent::SlotsUpdated { slots });
-        self.broadcast_stats().await;
-        current_slot
+        Ok((record, refund))
+    }
+
+    /// The allowlist shown on the auction for `slot`, if bidding is restricted.
+    pub async fn restriction_summary(
+        &self,
+        slot: u64,
+    ) -> Result<Option<RestrictionSummary>, AppError> {
+        self.restrictions
+            .call(move |restrictions| restrictions.for_slot(slot).map(RestrictionSummary::from))
+            .await
     }
 
-    pub async fn get_player_stats_by_id(&self, session_id: &str) -> Option<PlayerStats> {
-        self.game.read().await.player_stats.get(session_id).cloned()
+    /// The allowlist summary of each of `slots` that is restricted.
+    pub async fn restriction_summaries(
+        &self,
+        slots: Vec<u64>,
+    ) -> Result<HashMap<u64, RestrictionSummary>, AppError> {
+        self.restrictions
+            .call(move |restrictions| {
+                slots
+                    .into_iter()
+                    .filter_map(|slot| {
+                        restrictions
+                            .for_slot(slot)
+                            .map(|restriction| (slot, RestrictionSummary::from(restriction)))
+                    })
+                    .collect()
+            })
+            .await
     }
 
-    pub async fn get_leaderboard(&self) -> Leaderboard {
-        self.game.read().await.generate_leaderboard()
+    /// Rejects a bid on `slot` from a session left off the slot's allowlist.
+    pub async fn check_restriction(&self, session_id: &str, slot: u64) -> Result<(), AppError> {
+        let session = session_id.to_string();
+        self.restrictions
+            .call(move |restrictions| restrictions.check(&session, slot))
+            .await?
     }
 
-    pub async fn get_marketplace_stats(&self) -> MarketplaceStats {
-        let marketplace = self.marketplace.read().await;
-        let auctions = self.auctions.read().await;
+    /// The session's stats. Spectators get a blank profile that is never stored,
+    /// so watching adds no balance to the ledger and no player to the leaderboard.
+    pub async fn player_stats(&self, session_id: String) -> Result<PlayerStats, AppError> {
+        let spectator = self.sessions.is_spectator(&session_id).await;
+        self.game
+            .call(move |game| match game.player_stats.get(&session_id) {
+                Some(stats) => stats.clone(),
+                None if spectator => PlayerStats::new(session_id),
+                None => game.get_or_create_player(session_id).clone(),
+            })
+            .await
+    }
 
-        MarketplaceStats {
-            current_slot: marketplace.current_slot,
-            total_slots: marketplace.slots.len(),
-            active_jit_auctions: auctions.jit_auctions.len(),
-            active_aot_auctions: auctions.aot_auctions.len(),
-            total_transactions: self.transactions.read().await.len(),
+    /// Returns why a bid from this session and IP must be rejected, if it must.
+    /// Bids placed in-process have no IP to check.
+    pub async fn check_bid_allowed(
+        &self,
+        session_id: &str,
+        ip: Option<IpAddr>,
+    ) -> Result<(), AppError> {
+        if self.sessions.is_spectator(session_id).await {
+            return Err(AppError::SpectatorReadOnly);
+        }
+
+        let session = session_id.to_string();
+        let wallet = self
+            .game
+            .call(move |game| {
+                game.player_stats
+                    .get(&session)
+                    .and_then(|stats| stats.wallet.clone())
+            })
+            .await?;
+
+        let banned = self.moderation.is_session_banned(session_id).await
+            || match &ip {
+                Some(ip) => self.moderation.is_ip_banned(ip).await,
+                None => false,
+            }
+            || match &wallet {
+                Some(wallet) => self.moderation.is_wallet_banned(wallet).await,
+                None => false,
+            };
+
+        if banned {
+            Err(AppError::Banned(
+                "This player is banned from bidding".to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Credits or debits a player's balance outside of any auction, returning the
+    /// new balance. One adjustment moves at most `MAX_BALANCE_ADJUSTMENT` and a
+    /// credit may not lift the balance past `MAX_PLAYER_BALANCE`.
+    pub async fn adjust_player_balance(
+        &self,
+        session_id: &str,
+        amount: Lamports,
+        credit: bool,
+    ) -> Result<Lamports, AppError> {
+        if amount > MAX_BALANCE_ADJUSTMENT {
+            return Err(AppError::Validation(vec![FieldError::new(
+                "amount",
+                format!("must not exceed {} SOL", MAX_BALANCE_ADJUSTMENT),
+            )]));
         }
+
+        let session = session_id.to_string();
+        self.game
+            .call(move |game| {
+                let stats = game.get_or_create_player(session);
+
+                stats.balance = if credit {
+                    stats
+                        .balance
+                        .checked_add(amount)
+                        .filter(|balance| *balance <= MAX_PLAYER_BALANCE)
+                        .ok_or(AppError::BalanceLimitExceeded {
+                            limit: MAX_PLAYER_BALANCE,
+                        })?
+                } else {
+                    stats
+                        .balance
+                        .checked_sub(amount)
+                        .ok_or(AppError::InsufficientBalance {
+                            available: stats.balance,
+                            required: amount,
+                        })?
+                };
+                let balance = stats.balance;
+
+                if credit {
+                    game.total_issued += amount;
+                } else {
+                    game.total_retired += amount;
+                }
+
+                Ok(balance)
+            })
+            .await?
+    }
+
+    pub async fn game_mode(&self) -> Result<GameMode, AppError> {
+        self.game.call(|game| game.mode).await
+    }
+
+    pub async fn get_player_stats_by_id(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<PlayerStats>, AppError> {
+        let session = session_id.to_string();
+        self.game
+            .call(move |game| game.player_stats.get(&session).cloned())
+            .await
+    }
+
+    /// Display names for the given sessions, falling back to a name derived from
+    /// the session ID for players that no longer exist.
+    pub async fn display_names(
+        &self,
+        session_ids: Vec<String>,
+    ) -> Result<HashMap<String, String>, AppError> {
+        self.game
+            .call(move |game| {
+                session_ids
+                    .into_iter()
+                    .map(|session_id| {
+                        let name = match game.player_stats.get(&session_id) {
+                            Some(stats) => stats.display_name(),
+                            None => session_display_name(&session_id),
+                        };
+                        (session_id, name)
+                    })
+                    .collect()
+            })
+            .await
+    }
+
+    pub async fn get_leaderboard(&self) -> Result<Leaderboard, AppError> {
+        self.game.call(|game| game.leaderboard()).await
     }
 
-    pub async fn broadcast_stats(&self) {
-        let stats = self.get_marketplace_stats().await;
+    pub async fn get_marketplace_stats(&self) -> Result<MarketplaceStats, AppError> {
+        let current_slot = self.get_current_slot().await?;
+        let total_slots = self
+            .slots
+            .call_all(|shard| shard.len())
+            .await?
+            .into_iter()
+            .sum();
+        let (active_jit_auctions, active_aot_auctions) = self
+            .auctions
+            .call_all(|auctions| (auctions.jit_auctions.len(), auctions.aot_auctions.len()))
+            .await?
+            .into_iter()
+            .fold((0, 0), |(jit, aot), (shard_jit, shard_aot)| {
+                (jit + shard_jit, aot + shard_aot)
+            });
+
+        Ok(MarketplaceStats {
+            current_slot,
+            total_slots,
+            active_jit_auctions,
+            active_aot_auctions,
+            total_transactions: self.transactions.len(),
+        })
+    }
+
+    pub async fn broadcast_stats(&self) -> Result<(), AppError> {
+        let stats = self.get_marketplace_stats().await?;
 
         self.events.broadcast(AppEvent::MarketplaceStats {
             current_slot: stats.current_slot,
@@ -336,5 +1511,15 @@ impl AppState {
             active_aot_auctions: stats.active_aot_auctions,
             total_transactions: stats.total_transactions,
         });
+        Ok(())
+    }
+}
+
+/// Groups `slots` by the shard that owns them.
+fn shard_slots(slots: Vec<Slot>, shard_count: usize) -> Vec<Vec<Slot>> {
+    let mut shards = vec![Vec::new(); shard_count];
+    for slot in slots {
+        shards[(slot.slot_number % shard_count as u64) as usize].push(slot);
     }
+    shards
 }
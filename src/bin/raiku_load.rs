@@ -0,0 +1,108 @@
+//! Puts a running simulator under load with many concurrent sessions bidding
+//! and holding event streams, and reports request latency percentiles.
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use raiku_simulator::services::load::{LoadSettings, run_load};
+
+const USAGE: &str = "\
+Usage: raiku-load [OPTIONS]
+
+Options:
+  --url <URL>             Simulator to load [default: http://localhost:8080]
+  --sessions <N>          Concurrent sessions [default: 200]
+  --duration <SECS>       How long sessions keep bidding once all have
+                          started [default: 30]
+  --bid-interval-ms <MS>  Average pause between one session's bids
+                          [default: 500]
+  --streams <N>           Sessions that also hold an event stream open
+                          [default: all of them]
+  --ramp-up <SECS>        Spread session starts over this long [default: 5]
+  --seed <N>              Seed for bid timing and amounts, to repeat a run
+  --output <PATH>         Also write the report to PATH as JSON
+  -h, --help              Print this help
+
+The instance's rate limits apply per client IP; raise them (e.g.
+RATE_LIMIT_WRITE_PER_SECOND) to measure more than the limiter.";
+
+const DEFAULT_URL: &str = "http://localhost:8080";
+const DEFAULT_SESSIONS: usize = 200;
+const DEFAULT_DURATION_SECS: u64 = 30;
+const DEFAULT_BID_INTERVAL_MS: u64 = 500;
+const DEFAULT_RAMP_UP_SECS: u64 = 5;
+
+struct Args {
+    settings: LoadSettings,
+    output: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let Some(args) = parse_args(std::env::args().skip(1))? else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+
+    println!(
+        "Starting {} sessions against {}",
+        args.settings.sessions, args.settings.base_url
+    );
+    let report = run_load(args.settings).await?;
+    print!("{}", report);
+
+    if let Some(path) = args.output {
+        fs::write(&path, serde_json::to_string_pretty(&report)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+        println!("Report written to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// `None` when help was asked for.
+fn parse_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<Option<Args>> {
+    let mut base_url = DEFAULT_URL.to_string();
+    let mut sessions = DEFAULT_SESSIONS;
+    let mut duration_secs = DEFAULT_DURATION_SECS;
+    let mut bid_interval_ms = DEFAULT_BID_INTERVAL_MS;
+    let mut streams = None;
+    let mut ramp_up_secs = DEFAULT_RAMP_UP_SECS;
+    let mut seed = None;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        }
+
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("{} needs a value\n\n{}", arg, USAGE))
+        };
+        let invalid = |e: std::num::ParseIntError| anyhow::anyhow!("Invalid {}: {}", arg, e);
+        match arg.as_str() {
+            "--url" => base_url = value()?,
+            "--sessions" => sessions = value()?.parse().map_err(invalid)?,
+            "--duration" => duration_secs = value()?.parse().map_err(invalid)?,
+            "--bid-interval-ms" => bid_interval_ms = value()?.parse().map_err(invalid)?,
+            "--streams" => streams = Some(value()?.parse().map_err(invalid)?),
+            "--ramp-up" => ramp_up_secs = value()?.parse().map_err(invalid)?,
+            "--seed" => seed = Some(value()?.parse().map_err(invalid)?),
+            "--output" => output = Some(PathBuf::from(value()?)),
+            _ => anyhow::bail!("Unknown argument {}\n\n{}", arg, USAGE),
+        }
+    }
+
+    Ok(Some(Args {
+        settings: LoadSettings {
+            base_url,
+            sessions,
+            duration: Duration::from_secs(duration_secs),
+            bid_interval: Duration::from_millis(bid_interval_ms),
+            streams: streams.unwrap_or(sessions),
+            ramp_up: Duration::from_secs(ramp_up_secs),
+            seed,
+        },
+        output,
+    }))
+}
@@ -0,0 +1,104 @@
+//! Runs the marketplace headless, with bots instead of players, and reports
+//! how the auctions played out. Marketplace and auction settings come from the
+//! same config file and environment variables as the server.
+
+use std::{fs, path::PathBuf};
+
+use raiku_simulator::config::GlobalConfig;
+use raiku_simulator::services::simulation::{BotPopulation, SimulationSettings, run_simulation};
+
+const USAGE: &str = "\
+Usage: raiku-sim [OPTIONS]
+
+Options:
+  --slots <N>          Slots to simulate [default: 1000]
+  --bots <POPULATIONS> Comma-separated strategy=count pairs; strategies are
+                       passive, aggressive, planner and random
+                       [default: passive=5,aggressive=3,planner=3,random=5]
+  --seed <N>           Seed for the bots' decisions, to repeat a run
+  --output <PATH>      Also write the report to PATH, as CSV if it ends in
+                       .csv and as JSON otherwise
+  -h, --help           Print this help";
+
+const DEFAULT_SLOTS: u64 = 1000;
+const DEFAULT_BOTS: &str = "passive=5,aggressive=3,planner=3,random=5";
+
+struct Args {
+    settings: SimulationSettings,
+    output: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let Some(args) = parse_args(std::env::args().skip(1))? else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+
+    let config = GlobalConfig::load()?;
+    let report = run_simulation(&config, args.settings).await?;
+    print!("{}", report);
+
+    if let Some(path) = args.output {
+        let contents = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => report.strategies_csv(),
+            _ => serde_json::to_string_pretty(&report)?,
+        };
+        fs::write(&path, contents)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+        println!("Report written to {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// `None` when help was asked for.
+fn parse_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<Option<Args>> {
+    let mut slots = DEFAULT_SLOTS;
+    let mut bots = DEFAULT_BOTS.to_string();
+    let mut seed = None;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        }
+
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| anyhow::anyhow!("{} needs a value\n\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--slots" => {
+                slots = value()?
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("Invalid --slots: {}", e))?;
+            }
+            "--bots" => bots = value()?,
+            "--seed" => {
+                seed = Some(
+                    value()?
+                        .parse()
+                        .map_err(|e| anyhow::anyhow!("Invalid --seed: {}", e))?,
+                );
+            }
+            "--output" => output = Some(PathBuf::from(value()?)),
+            _ => anyhow::bail!("Unknown argument {}\n\n{}", arg, USAGE),
+        }
+    }
+
+    let populations = bots
+        .split(',')
+        .filter(|population| !population.trim().is_empty())
+        .map(str::parse)
+        .collect::<anyhow::Result<Vec<BotPopulation>>>()?;
+
+    Ok(Some(Args {
+        settings: SimulationSettings {
+            slots,
+            populations,
+            seed,
+        },
+        output,
+    }))
+}
@@ -0,0 +1,280 @@
+//! Typed HTTP client for the simulator's API, for bots and other Rust
+//! consumers. Requests and responses use the server's own model types, so the
+//! two cannot drift apart.
+
+use futures_util::{Stream, stream};
+use reqwest::{Method, RequestBuilder, Response, header};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{
+    app::room::ROOM_HEADER,
+    config::DEFAULT_SESSION_COOKIE_NAME,
+    models::{
+        event::{AppEvent, EVENT_SCHEMA_VERSION},
+        player::PlayerStats,
+        requests::{AotBidRequest, BundleBidRequest, JitBidRequest, SimulateBidRequest},
+        responses::{ApiResponse, BidReceipt, BidSimulation, BundleReceipt, SessionView},
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Request failed with {status}: {message}")]
+    Api {
+        status: u16,
+        /// Machine-readable reason, e.g. `bid_too_low`
+        error_code: Option<String>,
+        message: String,
+    },
+
+    #[error("No session; call create_session first or use an API key")]
+    NoSession,
+
+    #[error("Invalid response or event: {0}")]
+    Decode(#[from] serde_json::Error),
+}
+
+/// A client for one simulator, optionally scoped to a room or chain.
+#[derive(Clone)]
+pub struct RaikuClient {
+    http: reqwest::Client,
+    base_url: String,
+    session_token: Option<String>,
+    cookie_name: String,
+    api_key: Option<String>,
+    room_id: Option<String>,
+}
+
+impl RaikuClient {
+    /// `base_url` is the server's root, e.g. `http://localhost:8080`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            session_token: None,
+            cookie_name: DEFAULT_SESSION_COOKIE_NAME.to_string(),
+            api_key: None,
+            room_id: None,
+        }
+    }
+
+    /// Authenticates with an API key instead of a session cookie.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Resumes an existing session from the `session_token` it was issued.
+    pub fn with_session(mut self, session_token: impl Into<String>) -> Self {
+        self.session_token = Some(session_token.into());
+        self
+    }
+
+    /// Sends the session token in a cookie with this name, for servers that
+    /// set `server.cookie.name`.
+    pub fn with_cookie_name(mut self, cookie_name: impl Into<String>) -> Self {
+        self.cookie_name = cookie_name.into();
+        self
+    }
+
+    /// Sends every request to the room or chain with this ID.
+    pub fn in_room(mut self, room_id: impl Into<String>) -> Self {
+        self.room_id = Some(room_id.into());
+        self
+    }
+
+    pub fn session_token(&self) -> Option<&str> {
+        self.session_token.as_deref()
+    }
+
+    /// Starts a new session and uses it for later requests.
+    pub async fn create_session(&mut self) -> Result<SessionView, ClientError> {
+        self.start_session(false).await
+    }
+
+    /// Starts a read-only session that can watch but not bid.
+    pub async fn create_spectator_session(&mut self) -> Result<SessionView, ClientError> {
+        self.start_session(true).await
+    }
+
+    async fn start_session(&mut self, spectator: bool) -> Result<SessionView, ClientError> {
+        self.session_token = None;
+        let path = format!("/sessions?spectator={}", spectator);
+        let session: SessionView = send(self.request(Method::POST, &path)).await?;
+        self.session_token = Some(session.session_token.clone());
+        Ok(session)
+    }
+
+    pub async fn submit_jit_bid(&self, bid: &JitBidRequest) -> Result<BidReceipt, ClientError> {
+        self.post("/transactions/jit", bid).await
+    }
+
+    pub async fn submit_aot_bid(&self, bid: &AotBidRequest) -> Result<BidReceipt, ClientError> {
+        self.post("/transactions/aot", bid).await
+    }
+
+    /// Places every bid in the bundle, or none of them.
+    pub async fn submit_bundle(
+        &self,
+        bundle: &BundleBidRequest,
+    ) -> Result<BundleReceipt, ClientError> {
+        self.post("/transactions/bundle", bundle).await
+    }
+
+    /// Checks a bid without placing it.
+    pub async fn simulate_bid(
+        &self,
+        bid: &SimulateBidRequest,
+    ) -> Result<BidSimulation, ClientError> {
+        self.post("/transactions/simulate", bid).await
+    }
+
+    pub async fn player_stats(&self) -> Result<PlayerStats, ClientError> {
+        self.ensure_authenticated()?;
+        send(self.request(Method::GET, "/game/player_stats")).await
+    }
+
+    /// Subscribes to the event stream of the client's room. Events arrive in
+    /// the order the server sends them, in the schema version this client was
+    /// built against; the stream ends when the server closes the connection.
+    pub async fn events(
+        &self,
+    ) -> Result<impl Stream<Item = Result<AppEvent, ClientError>>, ClientError> {
+        self.event_stream(&format!("/events?v={}", EVENT_SCHEMA_VERSION))
+            .await
+    }
+
+    /// Subscribes to the events concerning the client's own session: its
+    /// transactions, the auctions it bids in and its achievements.
+    pub async fn my_events(
+        &self,
+    ) -> Result<impl Stream<Item = Result<AppEvent, ClientError>>, ClientError> {
+        self.ensure_authenticated()?;
+        self.event_stream(&format!("/events/me?v={}", EVENT_SCHEMA_VERSION))
+            .await
+    }
+
+    async fn event_stream(
+        &self,
+        path: &str,
+    ) -> Result<impl Stream<Item = Result<AppEvent, ClientError>>, ClientError> {
+        let response = check_status(self.request(Method::GET, path).send().await?).await?;
+
+        Ok(stream::unfold(
+            (response, SseBuffer::default()),
+            |(mut response, mut buffer)| async move {
+                loop {
+                    if let Some(data) = buffer.next_data() {
+                        let event = serde_json::from_str(&data).map_err(ClientError::from);
+                        return Some((event, (response, buffer)));
+                    }
+                    match response.chunk().await {
+                        Ok(Some(chunk)) => buffer.push(&chunk),
+                        Ok(None) => return None,
+                        Err(e) => return Some((Err(e.into()), (response, buffer))),
+                    }
+                }
+            },
+        ))
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, ClientError> {
+        self.ensure_authenticated()?;
+        send(self.request(Method::POST, path).json(body)).await
+    }
+
+    fn ensure_authenticated(&self) -> Result<(), ClientError> {
+        if self.session_token.is_none() && self.api_key.is_none() {
+            return Err(ClientError::NoSession);
+        }
+        Ok(())
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut request = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        } else if let Some(session_token) = &self.session_token {
+            request = request.header(
+                header::COOKIE,
+                format!("{}={}", self.cookie_name, session_token),
+            );
+        }
+        if let Some(room_id) = &self.room_id {
+            request = request.header(ROOM_HEADER, room_id);
+        }
+        request
+    }
+}
+
+async fn send<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, ClientError> {
+    let response = check_status(request.send().await?).await?;
+    let body: ApiResponse<T> = serde_json::from_slice(&response.bytes().await?)?;
+    let message = body.message().to_string();
+    body.into_data().ok_or(ClientError::Api {
+        status: 200,
+        error_code: None,
+        message,
+    })
+}
+
+/// Turns error responses into `ClientError::Api` with the server's reason.
+async fn check_status(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let bytes = response.bytes().await?;
+    let (error_code, message) = match serde_json::from_slice::<ApiResponse>(&bytes) {
+        Ok(body) => (
+            body.error_code().map(str::to_string),
+            body.message().to_string(),
+        ),
+        Err(_) => (None, String::from_utf8_lossy(&bytes).into_owned()),
+    };
+    Err(ClientError::Api {
+        status: status.as_u16(),
+        error_code,
+        message,
+    })
+}
+
+/// Reassembles server-sent events from response chunks, which may split an
+/// event or a character anywhere.
+#[derive(Default)]
+struct SseBuffer {
+    pending: Vec<u8>,
+}
+
+impl SseBuffer {
+    fn push(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk);
+    }
+
+    /// The data of the next complete event, skipping keep-alive comments.
+    fn next_data(&mut self) -> Option<String> {
+        while let Some(end) = self.pending.windows(2).position(|window| window == b"\n\n") {
+            let block: Vec<u8> = self.pending.drain(..end + 2).collect();
+            let block = String::from_utf8_lossy(&block);
+            let data: Vec<&str> = block
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+            if !data.is_empty() {
+                return Some(data.join("\n"));
+            }
+        }
+        None
+    }
+}
@@ -1,74 +1,857 @@
-use std::env;
+use std::{
+    collections::HashMap,
+    env,
+    path::Path,
+    sync::{Arc, RwLock},
+};
 
-use dotenvy::dotenv;
+use anyhow::{anyhow, bail};
+use axum::http::HeaderValue;
+use figment::{
+    Figment, Metadata, Profile, Provider,
+    providers::{Format, Serialized, Toml},
+    value::{Dict, Map, Value},
+};
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    MAX_PAYLOAD_BYTES_PER_SLOT, MAX_TRANSACTION_DATA_BYTES,
+    app::room::{GLOBAL_ROOM_ID, MAX_ROOMS},
+};
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct GlobalConfig {
     pub server: ServerConfig,
     pub marketplace: MarketplaceConfig,
     pub auction: AuctionConfig,
+    pub session: SessionConfig,
+    pub rate_limit: RateLimitConfig,
+    pub abuse: AbuseConfig,
+    pub admin: AdminConfig,
+    pub telemetry: TelemetryConfig,
+    pub events: EventsConfig,
+    /// Extra simulated chains served under `/chains/{id}`, each with its own
+    /// marketplace and slot clock
+    #[serde(default)]
+    pub chains: Vec<ChainConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u32,
     pub cors_allowed_origins: Vec<String>,
+    pub cookie: CookieConfig,
+}
+
+/// Session cookie name used unless `server.cookie.name` says otherwise
+pub const DEFAULT_SESSION_COOKIE_NAME: &str = "raiku_session";
+
+/// Attributes of the session cookie. Browsers reject `SameSite=None` without
+/// `Secure`, and never send `Secure` cookies over plain HTTP, so local
+/// development over HTTP needs `same_site = "lax"` and `secure = false`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CookieConfig {
+    pub name: String,
+    pub same_site: SameSite,
+    pub secure: bool,
+    /// Unset for a cookie sent only to the host that set it
+    pub domain: Option<String>,
+    pub max_age_secs: u64,
+    /// Key the cookie's session ID is signed with. Unset for a random key
+    /// per process, which only works with a single instance.
+    pub secret: Option<String>,
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_SESSION_COOKIE_NAME.to_string(),
+            same_site: SameSite::None,
+            secure: true,
+            domain: None,
+            max_age_secs: 86400,
+            secret: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct MarketplaceConfig {
     pub slot_duration_ms: i64,
     pub base_fee_sol: f64,
     pub advance_slot_interval_ms: u64,
+    pub rpc_sync_url: Option<String>,
+    pub rpc_poll_interval_ms: u64,
+    pub transaction_ttl_slots: u64,
+    /// Slots kept ahead of the current one
+    pub slot_lookahead: u64,
+    /// Slots from the current one that are listed to clients and sent changes for
+    pub listing_window_slots: u64,
+    /// How far past the current slot an AOT bid may target
+    pub aot_max_lookahead_slots: u64,
+    /// Largest `data` payload a bid may carry, in bytes
+    pub max_payload_bytes: usize,
+    /// Reject bids whose `data` is not standard base64
+    pub require_base64_payload: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    /// Path segment the chain is served under, also its room ID
+    pub id: String,
+    pub name: String,
+    pub slot_duration_ms: i64,
+    pub advance_slot_interval_ms: u64,
+    pub base_fee_sol: f64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventsConfig {
+    /// How long an SSE subscriber's events are batched and coalesced before
+    /// being sent; 0 sends every event as it happens
+    pub flush_interval_ms: u64,
+    /// NATS server to publish every event to; needs the `nats` feature
+    pub nats_url: Option<String>,
+    /// Redis server to publish every event to; needs the `redis` feature
+    pub redis_url: Option<String>,
+    /// NATS subject or Redis channel the events are published on
+    pub bus_subject: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct AuctionConfig {
     pub aot_default_duration_sec: i64,
+    /// Seconds before an AOT auction ends at which `AotAuctionClosing` is broadcast
+    pub aot_closing_warnings_secs: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionConfig {
+    /// Concurrent sessions allowed per wallet; signing in beyond it ends the least recently active
+    pub max_concurrent_per_identity: usize,
+    /// How long bid responses are kept for replay under their Idempotency-Key
+    pub idempotency_key_ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub read: RateLimitProfile,
+    pub write: RateLimitProfile,
+    pub auth: RateLimitProfile,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitProfile {
+    pub requests_per_second: u32,
+}
+
+/// Groups of routes that share a rate limit bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    /// Side-effect free requests
+    Read,
+    /// Bids and other state-changing requests
+    Write,
+    /// Session, API key, wallet and admin endpoints
+    Auth,
+}
+
+impl RouteClass {
+    pub const ALL: [RouteClass; 3] = [RouteClass::Read, RouteClass::Write, RouteClass::Auth];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteClass::Read => "read",
+            RouteClass::Write => "write",
+            RouteClass::Auth => "auth",
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn profile(&self, class: RouteClass) -> &RateLimitProfile {
+        match class {
+            RouteClass::Read => &self.read,
+            RouteClass::Write => &self.write,
+            RouteClass::Auth => &self.auth,
+        }
+    }
+}
+
+/// Thresholds for flagging sessions that spam minimum-increment bids over their own.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AbuseConfig {
+    /// Self-outbids allowed per rolling minute before a session is flagged
+    pub self_outbid_limit_per_minute: u32,
+    /// Cooldown for a first flag; it doubles with each repeat offence
+    pub cooldown_secs: u64,
+}
+
+/// Tokens for the `/admin` API. The API is disabled when neither is set.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdminConfig {
+    pub admin_token: Option<String>,
+    pub operator_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    pub log_format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+impl MarketplaceConfig {
+    /// Expected interval between slot-advancement task iterations.
+    pub fn tick_interval_ms(&self) -> u64 {
+        if self.rpc_sync_url.is_some() {
+            self.rpc_poll_interval_ms
+        } else {
+            self.advance_slot_interval_ms
+        }
+    }
+}
+
+/// Settings that only take effect at startup; reloading them logs a warning instead.
+pub const RESTART_REQUIRED_SETTINGS: &[&str] = &[
+    "server",
+    "marketplace.advance_slot_interval_ms",
+    "marketplace.rpc_sync_url",
+    "marketplace.rpc_poll_interval_ms",
+    "marketplace.slot_lookahead",
+    "telemetry",
+    "events.nats_url",
+    "events.redis_url",
+    "events.bus_subject",
+    "chains",
+];
+
+/// How an environment variable's raw string is turned into a config value.
+#[derive(Clone, Copy)]
+enum EnvValue {
+    /// Kept verbatim
+    Text,
+    /// Lowercased, for enum keywords
+    Keyword,
+    /// Parsed as a number, boolean or string
+    Parsed,
+    /// Comma-separated list
+    List,
+    /// Comma-separated list, each item parsed like `Parsed`
+    ParsedList,
+}
+
+/// Environment variables and the config keys they override. Later entries win,
+/// so `PORT` takes precedence over `SERVER_PORT` and `RATE_LIMIT_READ_PER_SECOND`
+/// over `RATE_LIMIT_PER_SECOND`.
+const ENV_OVERRIDES: &[(&str, &str, EnvValue)] = &[
+    ("SERVER_HOST", "server.host", EnvValue::Text),
+    ("SERVER_PORT", "server.port", EnvValue::Parsed),
+    ("PORT", "server.port", EnvValue::Parsed),
+    (
+        "CORS_ORIGINS",
+        "server.cors_allowed_origins",
+        EnvValue::List,
+    ),
+    ("SESSION_COOKIE_NAME", "server.cookie.name", EnvValue::Text),
+    (
+        "SESSION_COOKIE_SAME_SITE",
+        "server.cookie.same_site",
+        EnvValue::Keyword,
+    ),
+    (
+        "SESSION_COOKIE_SECURE",
+        "server.cookie.secure",
+        EnvValue::Parsed,
+    ),
+    (
+        "SESSION_COOKIE_DOMAIN",
+        "server.cookie.domain",
+        EnvValue::Text,
+    ),
+    (
+        "SESSION_COOKIE_MAX_AGE_SECS",
+        "server.cookie.max_age_secs",
+        EnvValue::Parsed,
+    ),
+    ("SESSION_SECRET", "server.cookie.secret", EnvValue::Text),
+    (
+        "SLOT_DURATION_MS",
+        "marketplace.slot_duration_ms",
+        EnvValue::Parsed,
+    ),
+    ("BASE_FEE_SOL", "marketplace.base_fee_sol", EnvValue::Parsed),
+    (
+        "ADVANCE_SLOT_INTERVAL_MS",
+        "marketplace.advance_slot_interval_ms",
+        EnvValue::Parsed,
+    ),
+    ("SOLANA_RPC_URL", "marketplace.rpc_sync_url", EnvValue::Text),
+    (
+        "SOLANA_RPC_POLL_INTERVAL_MS",
+        "marketplace.rpc_poll_interval_ms",
+        EnvValue::Parsed,
+    ),
+    (
+        "TRANSACTION_TTL_SLOTS",
+        "marketplace.transaction_ttl_slots",
+        EnvValue::Parsed,
+    ),
+    (
+        "SLOT_LOOKAHEAD",
+        "marketplace.slot_lookahead",
+        EnvValue::Parsed,
+    ),
+    (
+        "SLOT_LISTING_WINDOW",
+        "marketplace.listing_window_slots",
+        EnvValue::Parsed,
+    ),
+    (
+        "AOT_MAX_LOOKAHEAD_SLOTS",
+        "marketplace.aot_max_lookahead_slots",
+        EnvValue::Parsed,
+    ),
+    (
+        "MAX_PAYLOAD_BYTES",
+        "marketplace.max_payload_bytes",
+        EnvValue::Parsed,
+    ),
+    (
+        "REQUIRE_BASE64_PAYLOAD",
+        "marketplace.require_base64_payload",
+        EnvValue::Parsed,
+    ),
+    (
+        "AOT_DURATION_SEC",
+        "auction.aot_default_duration_sec",
+        EnvValue::Parsed,
+    ),
+    (
+        "AOT_CLOSING_WARNINGS_SECS",
+        "auction.aot_closing_warnings_secs",
+        EnvValue::ParsedList,
+    ),
+    (
+        "MAX_SESSIONS_PER_IDENTITY",
+        "session.max_concurrent_per_identity",
+        EnvValue::Parsed,
+    ),
+    (
+        "IDEMPOTENCY_KEY_TTL_SECS",
+        "session.idempotency_key_ttl_secs",
+        EnvValue::Parsed,
+    ),
+    (
+        "RATE_LIMIT_PER_SECOND",
+        "rate_limit.read.requests_per_second",
+        EnvValue::Parsed,
+    ),
+    (
+        "RATE_LIMIT_READ_PER_SECOND",
+        "rate_limit.read.requests_per_second",
+        EnvValue::Parsed,
+    ),
+    (
+        "RATE_LIMIT_WRITE_PER_SECOND",
+        "rate_limit.write.requests_per_second",
+        EnvValue::Parsed,
+    ),
+    (
+        "RATE_LIMIT_AUTH_PER_SECOND",
+        "rate_limit.auth.requests_per_second",
+        EnvValue::Parsed,
+    ),
+    (
+        "ABUSE_SELF_OUTBID_LIMIT",
+        "abuse.self_outbid_limit_per_minute",
+        EnvValue::Parsed,
+    ),
+    (
+        "ABUSE_COOLDOWN_SECS",
+        "abuse.cooldown_secs",
+        EnvValue::Parsed,
+    ),
+    ("ADMIN_TOKEN", "admin.admin_token", EnvValue::Text),
+    ("OPERATOR_TOKEN", "admin.operator_token", EnvValue::Text),
+    (
+        "OTEL_EXPORTER_OTLP_ENDPOINT",
+        "telemetry.otlp_endpoint",
+        EnvValue::Text,
+    ),
+    (
+        "OTEL_SERVICE_NAME",
+        "telemetry.service_name",
+        EnvValue::Text,
+    ),
+    ("LOG_FORMAT", "telemetry.log_format", EnvValue::Keyword),
+    (
+        "SSE_FLUSH_INTERVAL_MS",
+        "events.flush_interval_ms",
+        EnvValue::Parsed,
+    ),
+    ("EVENT_BUS_NATS_URL", "events.nats_url", EnvValue::Text),
+    ("EVENT_BUS_REDIS_URL", "events.redis_url", EnvValue::Text),
+    ("EVENT_BUS_SUBJECT", "events.bus_subject", EnvValue::Text),
+];
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+const MIN_ADMIN_TOKEN_LEN: usize = 16;
+const MIN_SESSION_SECRET_LEN: usize = 32;
+const MAX_EVENT_FLUSH_INTERVAL_MS: u64 = 5000;
+/// Highest rate limit whose one-minute window still fits in a `u32`
+const MAX_REQUESTS_PER_SECOND: u32 = u32::MAX / 60;
+
+/// A single environment variable override, named in extraction errors.
+struct EnvOverride {
+    var: &'static str,
+    key: &'static str,
+    value: Value,
+}
+
+impl Provider for EnvOverride {
+    fn metadata(&self) -> Metadata {
+        Metadata::named(format!("`{}` environment variable", self.var))
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
+        Serialized::default(self.key, &self.value).data()
+    }
 }
 
 impl GlobalConfig {
-    pub fn from_env() -> anyhow::Result<Self> {
-        dotenv().ok();
+    /// Loads configuration from built-in defaults, then `config.toml` (or the file
+    /// named by `CONFIG_FILE`), then environment variables, with later layers winning.
+    ///
+    /// Environment variables are read from the process first and `.env` second. The
+    /// `.env` file is re-read on every call (rather than loaded into the process
+    /// environment) so that a reload picks up edits to it.
+    pub fn load() -> anyhow::Result<Self> {
+        let dotenv: HashMap<String, String> = dotenvy::dotenv_iter()
+            .map(|iter| iter.filter_map(Result::ok).collect())
+            .unwrap_or_default();
+
+        Self::from_lookup(|key| env::var(key).ok().or_else(|| dotenv.get(key).cloned()))
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let mut figment = Figment::from(Serialized::defaults(GlobalConfig::default()));
+
+        match lookup("CONFIG_FILE").filter(|path| !path.trim().is_empty()) {
+            Some(path) => {
+                if !Path::new(&path).is_file() {
+                    bail!("Config file {} set by CONFIG_FILE does not exist", path);
+                }
+                figment = figment.merge(Toml::file_exact(path));
+            }
+            // The default file is optional
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => {
+                figment = figment.merge(Toml::file_exact(DEFAULT_CONFIG_FILE));
+            }
+            None => {}
+        }
+
+        for &(var, key, kind) in ENV_OVERRIDES {
+            let Some(raw) = lookup(var).filter(|raw| !raw.trim().is_empty()) else {
+                continue;
+            };
+
+            let value = match kind {
+                EnvValue::Text => Value::from(raw),
+                EnvValue::Keyword => Value::from(raw.trim().to_lowercase()),
+                EnvValue::Parsed => raw.trim().parse().unwrap_or_else(|_| Value::from(raw)),
+                EnvValue::List => Value::from(
+                    raw.split(',')
+                        .map(|item| item.trim().to_string())
+                        .collect::<Vec<_>>(),
+                ),
+                EnvValue::ParsedList => Value::from(
+                    raw.split(',')
+                        .map(|item| {
+                            let item = item.trim();
+                            item.parse().unwrap_or_else(|_| Value::from(item))
+                        })
+                        .collect::<Vec<Value>>(),
+                ),
+            };
+
+            figment = figment.merge(EnvOverride { var, key, value });
+        }
+
+        let mut config: GlobalConfig = figment
+            .extract()
+            .map_err(|e| anyhow!("Invalid configuration: {}", e))?;
+
+        // An empty string in the file means "unset", same as for env vars
+        config.marketplace.rpc_sync_url = config
+            .marketplace
+            .rpc_sync_url
+            .filter(|url| !url.trim().is_empty());
+        config.server.cookie.domain = config
+            .server
+            .cookie
+            .domain
+            .filter(|domain| !domain.trim().is_empty());
+        config.server.cookie.secret = config
+            .server
+            .cookie
+            .secret
+            .filter(|secret| !secret.is_empty());
+        config.admin.admin_token = config.admin.admin_token.filter(|token| !token.is_empty());
+        config.admin.operator_token = config
+            .admin
+            .operator_token
+            .filter(|token| !token.is_empty());
+        config.telemetry.otlp_endpoint = config
+            .telemetry
+            .otlp_endpoint
+            .filter(|endpoint| !endpoint.trim().is_empty());
+        config.events.nats_url = config.events.nats_url.filter(|url| !url.trim().is_empty());
+        config.events.redis_url = config.events.redis_url.filter(|url| !url.trim().is_empty());
+
+        config.validate()?;
+        Ok(config)
+    }
 
-        Ok(GlobalConfig {
+    /// Rejects values that would otherwise panic or misbehave at runtime.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut errors = Vec::new();
+
+        if self.server.port == 0 || self.server.port > u16::MAX as u32 {
+            errors.push(format!(
+                "server.port must be 1-65535, got {}",
+                self.server.port
+            ));
+        }
+        for origin in &self.server.cors_allowed_origins {
+            if HeaderValue::from_str(origin).is_err() {
+                errors.push(format!(
+                    "server.cors_allowed_origins contains invalid origin {:?}",
+                    origin
+                ));
+            }
+        }
+        let cookie = &self.server.cookie;
+        // A cookie name is an RFC 6265 token
+        if cookie.name.is_empty()
+            || !cookie
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+        {
+            errors.push(format!(
+                "server.cookie.name must be a non-empty cookie token, got {:?}",
+                cookie.name
+            ));
+        }
+        if let Some(domain) = &cookie.domain {
+            if domain.contains([';', ',', ' ']) || HeaderValue::from_str(domain).is_err() {
+                errors.push(format!("server.cookie.domain is invalid: {:?}", domain));
+            }
+        }
+        if cookie.same_site == SameSite::None && !cookie.secure {
+            errors.push(
+                "server.cookie.same_site = \"none\" requires server.cookie.secure = true"
+                    .to_string(),
+            );
+        }
+        if cookie.max_age_secs == 0 {
+            errors.push("server.cookie.max_age_secs must be positive".to_string());
+        }
+        if cookie
+            .secret
+            .as_ref()
+            .is_some_and(|secret| secret.len() < MIN_SESSION_SECRET_LEN)
+        {
+            errors.push(format!(
+                "server.cookie.secret must be at least {} characters",
+                MIN_SESSION_SECRET_LEN
+            ));
+        }
+        if self.marketplace.slot_duration_ms <= 0 {
+            errors.push("marketplace.slot_duration_ms must be positive".to_string());
+        }
+        if !(self.marketplace.base_fee_sol.is_finite() && self.marketplace.base_fee_sol > 0.0) {
+            errors.push("marketplace.base_fee_sol must be a positive number".to_string());
+        }
+        if self.marketplace.advance_slot_interval_ms == 0 {
+            errors.push("marketplace.advance_slot_interval_ms must be positive".to_string());
+        }
+        if self.marketplace.rpc_poll_interval_ms == 0 {
+            errors.push("marketplace.rpc_poll_interval_ms must be positive".to_string());
+        }
+        if self.marketplace.transaction_ttl_slots == 0 {
+            errors.push("marketplace.transaction_ttl_slots must be positive".to_string());
+        }
+        let lookahead = self.marketplace.slot_lookahead;
+        if lookahead == 0 {
+            errors.push("marketplace.slot_lookahead must be positive".to_string());
+        }
+        if self.marketplace.listing_window_slots == 0
+            || self.marketplace.listing_window_slots > lookahead + 1
+        {
+            errors.push(format!(
+                "marketplace.listing_window_slots must be 1-{} (slot_lookahead + 1)",
+                lookahead + 1
+            ));
+        }
+        if self.marketplace.aot_max_lookahead_slots == 0
+            || self.marketplace.aot_max_lookahead_slots > lookahead
+        {
+            errors.push(format!(
+                "marketplace.aot_max_lookahead_slots must be 1-{} (slot_lookahead)",
+                lookahead
+            ));
+        }
+        if self.marketplace.max_payload_bytes == 0
+            || self.marketplace.max_payload_bytes > MAX_PAYLOAD_BYTES_PER_SLOT
+        {
+            errors.push(format!(
+                "marketplace.max_payload_bytes must be 1-{} (the per-slot payload limit)",
+                MAX_PAYLOAD_BYTES_PER_SLOT
+            ));
+        }
+        if self.auction.aot_default_duration_sec <= 0 {
+            errors.push("auction.aot_default_duration_sec must be positive".to_string());
+        }
+        if self.auction.aot_closing_warnings_secs.contains(&0) {
+            errors.push("auction.aot_closing_warnings_secs must be positive".to_string());
+        }
+        if self.session.max_concurrent_per_identity == 0 {
+            errors.push("session.max_concurrent_per_identity must be positive".to_string());
+        }
+        if self.session.idempotency_key_ttl_secs == 0 {
+            errors.push("session.idempotency_key_ttl_secs must be positive".to_string());
+        }
+        for class in RouteClass::ALL {
+            let requests_per_second = self.rate_limit.profile(class).requests_per_second;
+            if requests_per_second == 0 || requests_per_second > MAX_REQUESTS_PER_SECOND {
+                errors.push(format!(
+                    "rate_limit.{}.requests_per_second must be 1-{}",
+                    class.as_str(),
+                    MAX_REQUESTS_PER_SECOND
+                ));
+            }
+        }
+        if self.abuse.self_outbid_limit_per_minute == 0 {
+            errors.push("abuse.self_outbid_limit_per_minute must be positive".to_string());
+        }
+        if self.abuse.cooldown_secs == 0 {
+            errors.push("abuse.cooldown_secs must be positive".to_string());
+        }
+
+        if self.events.flush_interval_ms > MAX_EVENT_FLUSH_INTERVAL_MS {
+            errors.push(format!(
+                "events.flush_interval_ms must be at most {}",
+                MAX_EVENT_FLUSH_INTERVAL_MS
+            ));
+        }
+        if self.events.nats_url.is_some() && !cfg!(feature = "nats") {
+            errors.push("events.nats_url needs a build with the `nats` feature".to_string());
+        }
+        if self.events.redis_url.is_some() && !cfg!(feature = "redis") {
+            errors.push("events.redis_url needs a build with the `redis` feature".to_string());
+        }
+        if (self.events.nats_url.is_some() || self.events.redis_url.is_some())
+            && self.events.bus_subject.trim().is_empty()
+        {
+            errors.push("events.bus_subject must not be empty".to_string());
+        }
+
+        for (index, chain) in self.chains.iter().enumerate() {
+            if chain.id.is_empty()
+                || chain.id == GLOBAL_ROOM_ID
+                || !chain
+                    .id
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            {
+                errors.push(format!(
+                    "chains[{}].id must be lowercase letters, digits and dashes, and not {:?}, got {:?}",
+                    index, GLOBAL_ROOM_ID, chain.id
+                ));
+            }
+            if self.chains[..index]
+                .iter()
+                .any(|other| other.id == chain.id)
+            {
+                errors.push(format!("chains[{}].id {:?} is used twice", index, chain.id));
+            }
+            if chain.slot_duration_ms <= 0 {
+                errors.push(format!(
+                    "chains[{}].slot_duration_ms must be positive",
+                    index
+                ));
+            }
+            if chain.advance_slot_interval_ms == 0 {
+                errors.push(format!(
+                    "chains[{}].advance_slot_interval_ms must be positive",
+                    index
+                ));
+            }
+            if !(chain.base_fee_sol.is_finite() && chain.base_fee_sol > 0.0) {
+                errors.push(format!(
+                    "chains[{}].base_fee_sol must be a positive number",
+                    index
+                ));
+            }
+        }
+        if self.chains.len() >= MAX_ROOMS {
+            errors.push(format!(
+                "at most {} chains may be configured",
+                MAX_ROOMS - 1
+            ));
+        }
+
+        for (name, token) in [
+            ("admin.admin_token", &self.admin.admin_token),
+            ("admin.operator_token", &self.admin.operator_token),
+        ] {
+            if token
+                .as_ref()
+                .is_some_and(|token| token.len() < MIN_ADMIN_TOKEN_LEN)
+            {
+                errors.push(format!(
+                    "{} must be at least {} characters",
+                    name, MIN_ADMIN_TOKEN_LEN
+                ));
+            }
+        }
+
+        if !errors.is_empty() {
+            bail!("Invalid configuration: {}", errors.join("; "));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for GlobalConfig {
+    fn default() -> Self {
+        Self {
             server: ServerConfig {
-                host: env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
-                port: env::var("PORT")
-                    .or_else(|_| env::var("SERVER_PORT"))
-                    .unwrap_or_else(|_| "8080".to_string())
-                    .parse()
-                    .unwrap_or(8080),
-                cors_allowed_origins: env::var("CORS_ORIGINS")
-                    .unwrap_or_else(|_| "http://localhost:3000".to_string())
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect(),
+                host: "0.0.0.0".to_string(),
+                port: 8080,
+                cors_allowed_origins: vec!["http://localhost:3000".to_string()],
+                cookie: CookieConfig::default(),
             },
-
             marketplace: MarketplaceConfig {
-                slot_duration_ms: env::var("SLOT_DURATION_MS")
-                    .unwrap_or_else(|_| "400".to_string())
-                    .parse()
-                    .unwrap_or(400),
-                base_fee_sol: env::var("BASE_FEE_SOL")
-                    .unwrap_or_else(|_| "0.001".to_string())
-                    .parse()
-                    .unwrap_or(0.001),
-                advance_slot_interval_ms: env::var("ADVANCE_SLOT_INTERVAL_MS")
-                    .unwrap_or_else(|_| "400".to_string())
-                    .parse()
-                    .unwrap_or(400),
+                slot_duration_ms: 400,
+                base_fee_sol: 0.001,
+                advance_slot_interval_ms: 400,
+                rpc_sync_url: None,
+                rpc_poll_interval_ms: 400,
+                transaction_ttl_slots: 150,
+                slot_lookahead: 100,
+                listing_window_slots: 50,
+                aot_max_lookahead_slots: 100,
+                max_payload_bytes: MAX_TRANSACTION_DATA_BYTES,
+                require_base64_payload: false,
             },
-
             auction: AuctionConfig {
-                aot_default_duration_sec: env::var("AOT_DURATION_SEC")
-                    .unwrap_or_else(|_| "35".to_string())
-                    .parse()
-                    .unwrap_or(35),
+                aot_default_duration_sec: 35,
+                aot_closing_warnings_secs: vec![30, 10, 5],
+            },
+            session: SessionConfig {
+                max_concurrent_per_identity: 5,
+                idempotency_key_ttl_secs: 86400,
+            },
+            rate_limit: RateLimitConfig {
+                read: RateLimitProfile {
+                    requests_per_second: 100,
+                },
+                write: RateLimitProfile {
+                    requests_per_second: 10,
+                },
+                auth: RateLimitProfile {
+                    requests_per_second: 2,
+                },
             },
-        })
+            abuse: AbuseConfig {
+                self_outbid_limit_per_minute: 100,
+                cooldown_secs: 60,
+            },
+            admin: AdminConfig {
+                admin_token: None,
+                operator_token: None,
+            },
+            telemetry: TelemetryConfig {
+                otlp_endpoint: None,
+                service_name: "raiku-simulator".to_string(),
+                log_format: LogFormat::Pretty,
+            },
+            events: EventsConfig {
+                flush_interval_ms: 250,
+                nats_url: None,
+                redis_url: None,
+                bus_subject: "raiku.events".to_string(),
+            },
+            chains: Vec::new(),
+        }
+    }
+}
+
+/// Shared handle to the active configuration, swapped atomically on reload.
+#[derive(Clone)]
+pub struct SharedConfig(Arc<RwLock<Arc<GlobalConfig>>>);
+
+impl SharedConfig {
+    pub fn new(config: GlobalConfig) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(config))))
+    }
+
+    pub fn current(&self) -> Arc<GlobalConfig> {
+        self.0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Replaces the active configuration and returns the previous one.
+    pub fn replace(&self, config: GlobalConfig) -> Arc<GlobalConfig> {
+        let mut current = self
+            .0
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        std::mem::replace(&mut *current, Arc::new(config))
     }
 }
@@ -0,0 +1,273 @@
+//! The simulator as a library: everything `main.rs` wires together, behind
+//! one type that other crates can drive slot by slot or serve over HTTP.
+
+use axum::Router;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    app::{
+        api::{AppContext, create_api_router},
+        room::RoomManager,
+        state::AppState,
+    },
+    config::{GlobalConfig, SharedConfig},
+    error::AppError,
+    models::{
+        marketplace::{MarketplaceStats, SlotWindow},
+        metrics::Leaderboard,
+        requests::{AotBidRequest, JitBidRequest},
+        slot::Slot,
+        transaction::Transaction,
+        types::Lamports,
+    },
+    services::{
+        bid::{place_aot_bid, place_jit_bid},
+        config::run_config_reload_on_sighup,
+        event_bus::start_event_buses,
+        ledger::run_ledger_audit,
+        room::open_chains,
+        session::run_session_cleanup,
+        slot::{process_slot_tick, run_local_slot_clock, run_rpc_slot_sync},
+        supervisor::supervise,
+    },
+    utils::{
+        rate_limiter::RateLimiter,
+        rpc::SolanaRpcClient,
+        validation::{FieldError, Validate},
+    },
+};
+
+/// A bid for `SimulationEngine::submit_bid`.
+pub enum Bid {
+    Jit(JitBidRequest),
+    Aot(AotBidRequest),
+}
+
+/// The global room's marketplace at one point in time.
+#[derive(Debug, Serialize)]
+pub struct EngineSnapshot {
+    pub current_slot: u64,
+    pub upcoming_slots: Vec<Slot>,
+    pub stats: MarketplaceStats,
+    pub leaderboard: Leaderboard,
+}
+
+/// Background tasks started by `SimulationEngine::start`.
+pub struct EngineTasks {
+    tasks: Vec<(&'static str, JoinHandle<()>)>,
+}
+
+impl EngineTasks {
+    /// Waits for every task to stop; call after cancelling the engine's
+    /// shutdown token.
+    pub async fn join(self) {
+        for (name, task) in self.tasks {
+            if let Err(e) = task.await {
+                tracing::error!("Background {} task failed during shutdown: {}", name, e);
+            }
+        }
+    }
+}
+
+/// One simulator: the global room, any configured chains, and the shared
+/// config, rate limiter and shutdown token the API serves them with.
+///
+/// Slots only advance when `tick` is called, unless `start` has spawned the
+/// slot clock.
+#[derive(Clone)]
+pub struct SimulationEngine {
+    context: AppContext,
+}
+
+impl SimulationEngine {
+    /// Builds the engine from `config`. Must be called inside a Tokio runtime,
+    /// since each room runs its state on actor tasks.
+    pub fn new(config: GlobalConfig) -> anyhow::Result<Self> {
+        let state = AppState::new(
+            config.marketplace.slot_duration_ms,
+            Lamports::from_sol(config.marketplace.base_fee_sol),
+            SlotWindow {
+                lookahead: config.marketplace.slot_lookahead,
+                listing: config.marketplace.listing_window_slots,
+            },
+            config.abuse.clone(),
+            config.server.cookie.clone(),
+        );
+        let shutdown = CancellationToken::new();
+        let rooms = RoomManager::new(state.clone(), shutdown.clone());
+        open_chains(&rooms, &config, &shutdown)?;
+
+        Ok(Self {
+            context: AppContext {
+                state,
+                rooms,
+                rate_limiter: RateLimiter::new(&config.rate_limit),
+                config: SharedConfig::new(config),
+                shutdown,
+            },
+        })
+    }
+
+    pub fn context(&self) -> &AppContext {
+        &self.context
+    }
+
+    /// The global room's state.
+    pub fn state(&self) -> &AppState {
+        &self.context.state
+    }
+
+    /// Cancelling this token stops the background tasks and any server
+    /// running the engine's router with it as its shutdown signal.
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.context.shutdown.clone()
+    }
+
+    /// The full HTTP API, served by `main.rs` for the standalone simulator.
+    pub fn router(&self) -> Router {
+        create_api_router(self.context.clone())
+    }
+
+    /// Spawns the slot clock (or Solana RPC sync), session cleanup, ledger
+    /// audit and config reload tasks, each restarted if it panics, plus a
+    /// publisher for each configured event broker.
+    pub fn start(&self) -> EngineTasks {
+        let config = self.context.config.current();
+        let state = &self.context.state;
+        let shutdown = &self.context.shutdown;
+
+        let slot_task = match config.marketplace.rpc_sync_url.clone() {
+            Some(url) => {
+                tracing::info!("Syncing slot progression with Solana RPC at {}", url);
+                let client = SolanaRpcClient::new(url);
+                let poll_interval_ms = config.marketplace.rpc_poll_interval_ms;
+                let (task_state, task_shutdown) = (state.clone(), shutdown.clone());
+
+                tokio::spawn(supervise(
+                    "slot",
+                    state.health.clone(),
+                    shutdown.clone(),
+                    move || {
+                        run_rpc_slot_sync(
+                            task_state.clone(),
+                            client.clone(),
+                            poll_interval_ms,
+                            task_shutdown.clone(),
+                        )
+                    },
+                ))
+            }
+            None => {
+                let interval_ms = config.marketplace.advance_slot_interval_ms;
+                let (task_state, task_shutdown) = (state.clone(), shutdown.clone());
+
+                tokio::spawn(supervise(
+                    "slot",
+                    state.health.clone(),
+                    shutdown.clone(),
+                    move || {
+                        run_local_slot_clock(task_state.clone(), interval_ms, task_shutdown.clone())
+                    },
+                ))
+            }
+        };
+
+        // Expired sessions are cleaned up in every room
+        let (task_rooms, task_shutdown) = (self.context.rooms.clone(), shutdown.clone());
+        let session_task = tokio::spawn(supervise(
+            "session cleanup",
+            state.health.clone(),
+            shutdown.clone(),
+            move || run_session_cleanup(task_rooms.clone(), task_shutdown.clone()),
+        ));
+
+        // Checks that no SOL is created or lost
+        let (task_state, task_shutdown) = (state.clone(), shutdown.clone());
+        let ledger_task = tokio::spawn(supervise(
+            "ledger audit",
+            state.health.clone(),
+            shutdown.clone(),
+            move || run_ledger_audit(task_state.clone(), task_shutdown.clone()),
+        ));
+
+        let reload_task = tokio::spawn(run_config_reload_on_sighup(
+            self.context.rooms.clone(),
+            self.context.config.clone(),
+            self.context.rate_limiter.clone(),
+            shutdown.clone(),
+        ));
+
+        let mut tasks = vec![
+            ("slot", slot_task),
+            ("session cleanup", session_task),
+            ("ledger audit", ledger_task),
+            ("config reload", reload_task),
+        ];
+        // Only the global room's events go to external brokers
+        tasks.extend(start_event_buses(&config.events, &state.events, shutdown));
+
+        EngineTasks { tasks }
+    }
+
+    /// Starts a player session in the global room and returns its ID.
+    pub async fn create_player(&self) -> String {
+        self.context
+            .state
+            .sessions
+            .create_session(None, None, false)
+            .await
+            .id
+    }
+
+    /// Advances the global room one slot, resolving its due auctions, and
+    /// returns the slot that was resolved.
+    pub async fn tick(&self) -> Result<u64, AppError> {
+        process_slot_tick(&self.context.state).await
+    }
+
+    /// Validates and places a bid for the session, with the same checks the
+    /// API runs apart from IP bans.
+    pub async fn submit_bid(&self, session_id: &str, bid: Bid) -> Result<Transaction, AppError> {
+        let state = &self.context.state;
+        if !state.sessions.validate_session(session_id).await {
+            return Err(AppError::Unauthorized);
+        }
+        state.check_bid_allowed(session_id, None).await?;
+
+        let config = self.context.config.current();
+        let session_id = session_id.to_string();
+        match bid {
+            Bid::Jit(req) => {
+                check_fields(&req)?;
+                place_jit_bid(state, &config, session_id, req).await
+            }
+            Bid::Aot(req) => {
+                check_fields(&req)?;
+                place_aot_bid(state, &config, session_id, req).await
+            }
+        }
+    }
+
+    pub async fn snapshot(&self) -> Result<EngineSnapshot, AppError> {
+        let state = &self.context.state;
+        let (current_slot, upcoming_slots) = state.get_upcoming_slots().await?;
+
+        Ok(EngineSnapshot {
+            current_slot,
+            upcoming_slots,
+            stats: state.get_marketplace_stats().await?,
+            leaderboard: state.get_leaderboard().await?,
+        })
+    }
+}
+
+fn check_fields(req: &impl Validate) -> Result<(), AppError> {
+    let errors: Vec<FieldError> = req.validate();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Validation(errors))
+    }
+}
@@ -0,0 +1,352 @@
+#[cfg(feature = "server")]
+use axum::{
+    Json,
+    http::header::RETRY_AFTER,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use http::StatusCode;
+use serde_json::{Map, Value, json};
+
+#[cfg(feature = "server")]
+use crate::models::responses::ApiResponse;
+use crate::{
+    models::{responses::ErrorCode, types::Lamports},
+    utils::validation::FieldError,
+};
+
+/// Errors surfaced to API clients. Each variant maps to an HTTP status and a
+/// stable `error_code`, and carries the details a client needs to react.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("Session ID is missing or invalid")]
+    Unauthorized,
+
+    #[error("Session token signature is invalid")]
+    InvalidSessionToken,
+
+    #[error("{0}")]
+    Banned(String),
+
+    #[error("Spectator sessions are read-only and cannot bid")]
+    SpectatorReadOnly,
+
+    #[error("Bidding paused for {retry_after_secs} seconds after bid spam was detected: {reason}")]
+    BidCooldown {
+        retry_after_secs: u64,
+        reason: String,
+    },
+
+    #[error("Invalid slot number {slot}, current slot is {current_slot}")]
+    InvalidSlot { slot: u64, current_slot: u64 },
+
+    #[error("Slot {slot} is too far ahead, AOT bids may target up to slot {max_slot}")]
+    SlotBeyondWindow { slot: u64, max_slot: u64 },
+
+    #[error("{0}")]
+    InvalidDependency(String),
+
+    #[error("Payload of {size} bytes exceeds the limit of {max_bytes} bytes")]
+    PayloadTooLarge { size: usize, max_bytes: usize },
+
+    #[error("Request validation failed: {}", .0.iter().map(|e| e.field.as_str()).collect::<Vec<_>>().join(", "))]
+    Validation(Vec<FieldError>),
+
+    #[error("Insufficient balance. Have: {available}, Need: {required}")]
+    InsufficientBalance {
+        available: Lamports,
+        required: Lamports,
+    },
+
+    #[error("Balance may not exceed {limit} SOL")]
+    BalanceLimitExceeded { limit: Lamports },
+
+    #[error("Bid too low, minimum {minimum} SOL")]
+    BidTooLow { minimum: Lamports },
+
+    #[error("Bid must exceed current highest bid of {current:.4} SOL")]
+    BidNotHigher { current: Lamports },
+
+    #[error("AOT auction for slot {slot} has ended. Closed at: {}", closed_at.format("%H:%M:%S UTC"))]
+    AuctionClosed { slot: u64, closed_at: DateTime<Utc> },
+
+    #[error("No auction exists for slot {slot}")]
+    AuctionNotFound { slot: u64 },
+
+    #[error("Bidding on slot {slot} is restricted to an allowlist")]
+    AuctionRestricted { slot: u64, restriction_id: String },
+
+    #[error("A request with this Idempotency-Key is still being processed")]
+    IdempotencyKeyInUse,
+
+    #[error("This Idempotency-Key was already used for a different request")]
+    IdempotencyKeyReused,
+
+    #[error("Nonce {nonce} must be above the last nonce used, {last_nonce}")]
+    StaleNonce { nonce: u64, last_nonce: u64 },
+
+    #[error("Transaction not found")]
+    TransactionNotFound,
+
+    #[error("{0}")]
+    TransactionNotCancellable(String),
+
+    #[error("{0}")]
+    TransactionNotRetryable(String),
+
+    #[error("Room {room_id} not found")]
+    RoomNotFound { room_id: String },
+
+    #[error("Session {session_id} is not in room {room_id}")]
+    RoomMemberNotFound { room_id: String, session_id: String },
+
+    #[error("The match in room {room_id} starts at {starts_at}")]
+    MatchNotStarted {
+        room_id: String,
+        starts_at: DateTime<Utc>,
+    },
+
+    #[error("The faucet is only available in sandbox rooms")]
+    SandboxOnly,
+
+    #[error("Already waiting for a match")]
+    AlreadyQueued,
+
+    #[error("Not waiting for a match")]
+    NotQueued,
+
+    #[error("Chain {chain_id} not found")]
+    ChainNotFound { chain_id: String },
+
+    #[error("At most {max_rooms} rooms may exist at once")]
+    RoomLimitReached { max_rooms: usize },
+
+    #[error("Room {room_id} is private, join it with an invite code first")]
+    NotRoomMember { room_id: String },
+
+    #[error("Only the creator of room {room_id} can invite players")]
+    NotRoomCreator { room_id: String },
+
+    #[error("{0}")]
+    InvalidInvite(String),
+
+    #[error("{0}")]
+    InvalidRecoveryCode(String),
+
+    #[error("Wait for pending bids to settle first")]
+    BidsPending,
+
+    #[error("Link a wallet with POST /sessions/wallet/verify first")]
+    WalletNotLinked,
+
+    #[error("No schema named {name}")]
+    SchemaNotFound { name: String },
+
+    #[error("Event schema version {version} is not supported, request {min} to {latest}")]
+    UnsupportedSchemaVersion { version: u32, min: u32, latest: u32 },
+
+    /// A bid in a bundle was rejected, so none of the bundle was placed
+    #[error("Bundle leg {leg} rejected: {source}")]
+    BundleLegRejected { leg: usize, source: Box<AppError> },
+
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl AppError {
+    /// Attributes the error to leg `leg` of a bundle.
+    pub fn in_bundle_leg(self, leg: usize) -> Self {
+        Self::BundleLegRejected {
+            leg,
+            source: Box::new(self),
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BundleLegRejected { source, .. } => source.status(),
+            Self::Unauthorized | Self::InvalidSessionToken => StatusCode::UNAUTHORIZED,
+            Self::Banned(_)
+            | Self::SpectatorReadOnly
+            | Self::AuctionRestricted { .. }
+            | Self::NotRoomMember { .. }
+            | Self::NotRoomCreator { .. }
+            | Self::SandboxOnly => StatusCode::FORBIDDEN,
+            Self::BidCooldown { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::InsufficientBalance { .. } => StatusCode::PAYMENT_REQUIRED,
+            Self::AuctionNotFound { .. }
+            | Self::TransactionNotFound
+            | Self::RoomNotFound { .. }
+            | Self::RoomMemberNotFound { .. }
+            | Self::ChainNotFound { .. }
+            | Self::SchemaNotFound { .. }
+            | Self::NotQueued => StatusCode::NOT_FOUND,
+            Self::IdempotencyKeyInUse
+            | Self::StaleNonce { .. }
+            | Self::RoomLimitReached { .. }
+            | Self::MatchNotStarted { .. }
+            | Self::AlreadyQueued
+            | Self::BidsPending
+            | Self::TransactionNotCancellable(_)
+            | Self::TransactionNotRetryable(_) => StatusCode::CONFLICT,
+            Self::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Validation(_) | Self::IdempotencyKeyReused => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidSlot { .. }
+            | Self::SlotBeyondWindow { .. }
+            | Self::InvalidDependency(_)
+            | Self::BalanceLimitExceeded { .. }
+            | Self::BidTooLow { .. }
+            | Self::BidNotHigher { .. }
+            | Self::AuctionClosed { .. }
+            | Self::InvalidInvite(_)
+            | Self::InvalidRecoveryCode(_)
+            | Self::WalletNotLinked
+            | Self::UnsupportedSchemaVersion { .. } => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.error_code().as_str()
+    }
+
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            Self::Unauthorized => ErrorCode::Unauthorized,
+            Self::InvalidSessionToken => ErrorCode::InvalidSessionToken,
+            Self::Banned(_) => ErrorCode::PlayerBanned,
+            Self::SpectatorReadOnly => ErrorCode::SpectatorReadOnly,
+            Self::BidCooldown { .. } => ErrorCode::BidCooldown,
+            Self::InvalidSlot { .. } => ErrorCode::InvalidSlot,
+            Self::SlotBeyondWindow { .. } => ErrorCode::SlotBeyondWindow,
+            Self::InvalidDependency(_) => ErrorCode::InvalidDependency,
+            Self::PayloadTooLarge { .. } => ErrorCode::PayloadTooLarge,
+            Self::Validation(_) => ErrorCode::ValidationFailed,
+            Self::InsufficientBalance { .. } => ErrorCode::InsufficientBalance,
+            Self::BalanceLimitExceeded { .. } => ErrorCode::BalanceLimitExceeded,
+            Self::BidTooLow { .. } => ErrorCode::BidTooLow,
+            Self::BidNotHigher { .. } => ErrorCode::BidNotHigher,
+            Self::AuctionClosed { .. } => ErrorCode::AuctionClosed,
+            Self::AuctionNotFound { .. } => ErrorCode::AuctionNotFound,
+            Self::AuctionRestricted { .. } => ErrorCode::AuctionRestricted,
+            Self::IdempotencyKeyInUse => ErrorCode::IdempotencyKeyInUse,
+            Self::IdempotencyKeyReused => ErrorCode::IdempotencyKeyReused,
+            Self::StaleNonce { .. } => ErrorCode::StaleNonce,
+            Self::TransactionNotFound => ErrorCode::TransactionNotFound,
+            Self::TransactionNotCancellable(_) => ErrorCode::TransactionNotCancellable,
+            Self::TransactionNotRetryable(_) => ErrorCode::TransactionNotRetryable,
+            Self::RoomNotFound { .. } => ErrorCode::RoomNotFound,
+            Self::RoomMemberNotFound { .. } => ErrorCode::RoomMemberNotFound,
+            Self::MatchNotStarted { .. } => ErrorCode::MatchNotStarted,
+            Self::SandboxOnly => ErrorCode::SandboxOnly,
+            Self::AlreadyQueued => ErrorCode::AlreadyQueued,
+            Self::NotQueued => ErrorCode::NotQueued,
+            Self::ChainNotFound { .. } => ErrorCode::ChainNotFound,
+            Self::RoomLimitReached { .. } => ErrorCode::RoomLimitReached,
+            Self::NotRoomMember { .. } => ErrorCode::NotRoomMember,
+            Self::NotRoomCreator { .. } => ErrorCode::NotRoomCreator,
+            Self::InvalidInvite(_) => ErrorCode::InvalidInvite,
+            Self::InvalidRecoveryCode(_) => ErrorCode::InvalidRecoveryCode,
+            Self::BidsPending => ErrorCode::BidsPending,
+            Self::WalletNotLinked => ErrorCode::WalletNotLinked,
+            Self::SchemaNotFound { .. } => ErrorCode::SchemaNotFound,
+            Self::UnsupportedSchemaVersion { .. } => ErrorCode::UnsupportedSchemaVersion,
+            Self::BundleLegRejected { source, .. } => source.error_code(),
+            Self::Internal(_) => ErrorCode::InternalError,
+        }
+    }
+
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            Self::BidCooldown {
+                retry_after_secs, ..
+            } => Some(json!({ "retry_after_secs": retry_after_secs })),
+            Self::InvalidSlot { slot, current_slot } => {
+                Some(json!({ "slot_number": slot, "current_slot": current_slot }))
+            }
+            Self::SlotBeyondWindow { slot, max_slot } => {
+                Some(json!({ "slot_number": slot, "max_slot": max_slot }))
+            }
+            Self::PayloadTooLarge { size, max_bytes } => {
+                Some(json!({ "size": size, "max_bytes": max_bytes }))
+            }
+            Self::Validation(errors) => Some(json!({ "errors": errors })),
+            Self::StaleNonce { nonce, last_nonce } => {
+                Some(json!({ "nonce": nonce, "last_nonce": last_nonce }))
+            }
+            Self::InsufficientBalance {
+                available,
+                required,
+            } => Some(json!({ "available": available, "required": required })),
+            Self::BalanceLimitExceeded { limit } => Some(json!({ "max_balance": limit })),
+            Self::BidTooLow { minimum } => Some(json!({ "minimum_bid": minimum })),
+            Self::BidNotHigher { current } => Some(json!({ "current_highest_bid": current })),
+            Self::AuctionClosed { slot, closed_at } => {
+                Some(json!({ "slot_number": slot, "closed_at": closed_at }))
+            }
+            Self::AuctionNotFound { slot } => Some(json!({ "slot_number": slot })),
+            Self::AuctionRestricted {
+                slot,
+                restriction_id,
+            } => Some(json!({ "slot_number": slot, "restriction_id": restriction_id })),
+            Self::RoomNotFound { room_id }
+            | Self::NotRoomMember { room_id }
+            | Self::NotRoomCreator { room_id } => Some(json!({ "room_id": room_id })),
+            Self::RoomMemberNotFound {
+                room_id,
+                session_id,
+            } => Some(json!({ "room_id": room_id, "session_id": session_id })),
+            Self::MatchNotStarted { room_id, starts_at } => {
+                Some(json!({ "room_id": room_id, "starts_at": starts_at }))
+            }
+            Self::ChainNotFound { chain_id } => Some(json!({ "chain_id": chain_id })),
+            Self::SchemaNotFound { name } => Some(json!({ "name": name })),
+            Self::UnsupportedSchemaVersion {
+                version,
+                min,
+                latest,
+            } => Some(json!({ "version": version, "min_version": min, "latest_version": latest })),
+            Self::RoomLimitReached { max_rooms } => Some(json!({ "max_rooms": max_rooms })),
+            Self::BundleLegRejected { leg, source } => {
+                let mut details = match source.details() {
+                    Some(Value::Object(details)) => details,
+                    _ => Map::new(),
+                };
+                details.insert("leg".to_string(), json!(leg));
+                Some(Value::Object(details))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        match &self {
+            Self::Internal(e) => tracing::error!("Request failed: {:#}", e),
+            Self::BundleLegRejected { source, .. } if matches!(**source, Self::Internal(_)) => {
+                tracing::error!("Request failed: {:#}", self)
+            }
+            _ => {}
+        }
+
+        let mut body = ApiResponse::failure(self.to_string(), status.as_u16() as u32)
+            .with_error_code(self.code());
+        if let Some(details) = self.details() {
+            body = body.with_data(details);
+        }
+
+        let mut response = (status, Json(body)).into_response();
+        if let Self::BidCooldown {
+            retry_after_secs, ..
+        } = &self
+        {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, retry_after_secs.to_string().parse().unwrap());
+        }
+        response
+    }
+}
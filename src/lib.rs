@@ -1,13 +1,52 @@
+use crate::models::types::Lamports;
+
+#[cfg(feature = "server")]
 pub mod app;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "server")]
 pub mod config;
+#[cfg(feature = "server")]
+pub mod engine;
+pub mod error;
 pub mod managers;
+#[cfg(feature = "server")]
 pub mod middleware;
 pub mod models;
+pub mod practice;
+#[cfg(feature = "server")]
 pub mod routes;
+pub mod schema;
+#[cfg(feature = "server")]
 pub mod services;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-pub const INITIAL_PLAYER_BALANCE: f64 = 100_000.0;
+pub const INITIAL_PLAYER_BALANCE: Lamports = Lamports::from_whole_sol(100_000);
 pub const MAX_COMPUTE_UNITS_PER_SLOT: u64 = 48_000_000;
-pub const MIN_AOT_BID_INCREMENT: f64 = 0.001;
+pub const MAX_BID_AMOUNT: Lamports = Lamports::from_whole_sol(1_000_000);
+/// Largest credit or debit a single admin or faucet adjustment may make
+pub const MAX_BALANCE_ADJUSTMENT: Lamports = MAX_BID_AMOUNT;
+/// Balance an adjustment may not raise a player past
+pub const MAX_PLAYER_BALANCE: Lamports = Lamports::from_whole_sol(10_000_000);
+/// Default for `marketplace.max_payload_bytes`, a Solana packet's payload
+pub const MAX_TRANSACTION_DATA_BYTES: usize = 1232;
+/// Payload bytes the transactions executed in one slot may carry between them
+pub const MAX_PAYLOAD_BYTES_PER_SLOT: usize = 65_536;
+/// Longest memo a bid may carry, in characters
+pub const MAX_MEMO_LENGTH: usize = 256;
+pub const MAX_TAGS_PER_TRANSACTION: usize = 8;
+pub const MAX_TAG_LENGTH: usize = 32;
+pub const MIN_AOT_BID_INCREMENT: Lamports = Lamports(1_000_000);
 pub const JIT_PREMIUM_MULTIPLIER: f64 = 1.05;
+/// Most slots a single `/marketplace/slots` range query may cover
+pub const MAX_SLOT_QUERY_RANGE: u64 = 500;
+/// Fastest time compression `/events/replay` allows
+pub const MAX_REPLAY_SPEED: f64 = 1000.0;
+/// Most events one `/events/replay` request plays back
+pub const MAX_REPLAY_EVENTS: usize = 1000;
+/// Actors that slots and their auctions are spread over by slot number
+pub const SLOT_SHARD_COUNT: usize = 16;
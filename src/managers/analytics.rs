@@ -0,0 +1,641 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::types::{Lamports, TransactionType};
+
+/// Ten-second buckets kept, one hour's worth.
+const TEN_SECOND_BUCKETS: usize = 360;
+/// One-minute buckets kept, one day's worth.
+const ONE_MINUTE_BUCKETS: usize = 1440;
+/// Longest trailing window fee statistics cover, in minutes.
+pub const MAX_FEE_WINDOW_MINUTES: u32 = 1440;
+/// Histogram buckets per power of two, so percentiles are within about 3%.
+const HISTOGRAM_SUB_BITS: u32 = 4;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub enum PriceResolution {
+    #[serde(rename = "10s")]
+    TenSeconds,
+    #[serde(rename = "1m")]
+    OneMinute,
+}
+
+impl PriceResolution {
+    pub fn bucket_secs(self) -> i64 {
+        match self {
+            Self::TenSeconds => 10,
+            Self::OneMinute => 60,
+        }
+    }
+}
+
+/// Prices seen within one bucket.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct PriceSummary {
+    pub count: u64,
+    pub min: Option<Lamports>,
+    pub max: Option<Lamports>,
+    pub average: Option<Lamports>,
+    #[serde(skip)]
+    total: u128,
+}
+
+impl PriceSummary {
+    fn add(&mut self, price: Lamports) {
+        self.count += 1;
+        self.total += u128::from(price.0);
+        self.min = Some(self.min.map_or(price, |min| min.min(price)));
+        self.max = Some(self.max.map_or(price, |max| max.max(price)));
+        self.average = Some(Lamports((self.total / u128::from(self.count)) as u64));
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct PriceBucket {
+    pub start: DateTime<Utc>,
+    /// Base fees of slots created in the bucket
+    pub base_fee: PriceSummary,
+    /// Winning JIT bids resolved in the bucket
+    pub jit: PriceSummary,
+    /// Winning AOT bids resolved in the bucket
+    pub aot: PriceSummary,
+}
+
+enum PriceSample {
+    BaseFee(Lamports),
+    Clearing(TransactionType, Lamports),
+}
+
+/// Fixed-size ring of buckets; buckets with no samples are not stored.
+struct PriceSeries {
+    resolution: PriceResolution,
+    capacity: usize,
+    buckets: VecDeque<PriceBucket>,
+}
+
+impl PriceSeries {
+    fn new(resolution: PriceResolution, capacity: usize) -> Self {
+        Self {
+            resolution,
+            capacity,
+            buckets: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn record(&mut self, at: DateTime<Utc>, sample: &PriceSample) {
+        let bucket_secs = self.resolution.bucket_secs();
+        let start_secs = at.timestamp() - at.timestamp().rem_euclid(bucket_secs);
+        let start = Utc.timestamp_opt(start_secs, 0).single().unwrap_or(at);
+
+        if self
+            .buckets
+            .back()
+            .is_none_or(|bucket| bucket.start < start)
+        {
+            if self.buckets.len() == self.capacity {
+                self.buckets.pop_front();
+            }
+            self.buckets.push_back(PriceBucket {
+                start,
+                base_fee: PriceSummary::default(),
+                jit: PriceSummary::default(),
+                aot: PriceSummary::default(),
+            });
+        }
+        let Some(bucket) = self.buckets.back_mut() else {
+            return;
+        };
+
+        match sample {
+            PriceSample::BaseFee(fee) => bucket.base_fee.add(*fee),
+            PriceSample::Clearing(TransactionType::Jit, price) => bucket.jit.add(*price),
+            PriceSample::Clearing(TransactionType::Aot, price) => bucket.aot.add(*price),
+        }
+    }
+}
+
+/// Time-bucketed base fees and clearing prices at each supported resolution.
+pub struct PriceCollector {
+    ten_seconds: PriceSeries,
+    one_minute: PriceSeries,
+}
+
+impl Default for PriceCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PriceCollector {
+    pub fn new() -> Self {
+        Self {
+            ten_seconds: PriceSeries::new(PriceResolution::TenSeconds, TEN_SECOND_BUCKETS),
+            one_minute: PriceSeries::new(PriceResolution::OneMinute, ONE_MINUTE_BUCKETS),
+        }
+    }
+
+    pub fn record_base_fee(&mut self, fee: Lamports) {
+        self.record(PriceSample::BaseFee(fee));
+    }
+
+    pub fn record_clearing_price(&mut self, auction_type: TransactionType, price: Lamports) {
+        self.record(PriceSample::Clearing(auction_type, price));
+    }
+
+    /// Buckets at `resolution`, oldest first.
+    pub fn series(&self, resolution: PriceResolution) -> Vec<PriceBucket> {
+        let series = match resolution {
+            PriceResolution::TenSeconds => &self.ten_seconds,
+            PriceResolution::OneMinute => &self.one_minute,
+        };
+        series.buckets.iter().cloned().collect()
+    }
+
+    fn record(&mut self, sample: PriceSample) {
+        let now = Utc::now();
+        self.ten_seconds.record(now, &sample);
+        self.one_minute.record(now, &sample);
+    }
+}
+
+/// Sparse log-linear histogram, so windows merge without revisiting samples.
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+    count: u64,
+    buckets: BTreeMap<u32, u64>,
+}
+
+impl Histogram {
+    fn add(&mut self, value: u64) {
+        self.count += 1;
+        *self.buckets.entry(histogram_key(value)).or_default() += 1;
+    }
+
+    fn merge(&mut self, other: &Histogram) {
+        self.count += other.count;
+        for (key, count) in &other.buckets {
+            *self.buckets.entry(*key).or_default() += count;
+        }
+    }
+
+    fn percentile(&self, percent: u64) -> Option<u64> {
+        let rank = (percent * self.count).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (key, count) in &self.buckets {
+            seen += count;
+            if seen >= rank {
+                return Some(histogram_value(*key));
+            }
+        }
+        None
+    }
+}
+
+/// Winning bids of one auction type in one minute.
+#[derive(Clone, Debug, Default)]
+struct WinningBids {
+    bids: Histogram,
+    overbid_total: u128,
+    overbid_ratio_total: f64,
+}
+
+impl WinningBids {
+    fn add(&mut self, price: Lamports, base_fee: Lamports) {
+        self.bids.add(price.0);
+        self.overbid_total += u128::from(price.saturating_sub(base_fee).0);
+        if !base_fee.is_zero() {
+            self.overbid_ratio_total += price.0 as f64 / base_fee.0 as f64;
+        }
+    }
+
+    fn merge(&mut self, other: &WinningBids) {
+        self.bids.merge(&other.bids);
+        self.overbid_total += other.overbid_total;
+        self.overbid_ratio_total += other.overbid_ratio_total;
+    }
+
+    fn summary(&self) -> WinningBidStats {
+        let count = self.bids.count;
+        let average = |total: f64| (count > 0).then(|| total / count as f64);
+        let percentile = |percent| self.bids.percentile(percent).map(Lamports);
+        WinningBidStats {
+            count,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            average_overbid: average(self.overbid_total as f64).map(|total| Lamports(total as u64)),
+            average_overbid_ratio: average(self.overbid_ratio_total),
+        }
+    }
+}
+
+/// Values below `2^HISTOGRAM_SUB_BITS` are exact; larger ones share a key with
+/// the values in the same sixteenth of their power of two.
+fn histogram_key(value: u64) -> u32 {
+    let exponent = 63 - value.max(1).leading_zeros();
+    if exponent < HISTOGRAM_SUB_BITS {
+        return value as u32;
+    }
+    let sub_bucket =
+        (value >> (exponent - HISTOGRAM_SUB_BITS)) as u32 & ((1 << HISTOGRAM_SUB_BITS) - 1);
+    ((exponent - HISTOGRAM_SUB_BITS + 1) << HISTOGRAM_SUB_BITS) | sub_bucket
+}
+
+/// The midpoint of the values sharing `key`.
+fn histogram_value(key: u32) -> u64 {
+    let shift = key >> HISTOGRAM_SUB_BITS;
+    if shift == 0 {
+        return u64::from(key);
+    }
+    let shift = shift - 1;
+    let sub_bucket = u64::from(key & ((1 << HISTOGRAM_SUB_BITS) - 1));
+    let lower = ((1 << HISTOGRAM_SUB_BITS) | sub_bucket) << shift;
+    lower + (1 << shift) / 2
+}
+
+#[derive(Clone, Debug, Default)]
+struct MinuteFees {
+    minute: i64,
+    jit: WinningBids,
+    aot: WinningBids,
+    refunded: Lamports,
+    refunds: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct WinningBidStats {
+    pub count: u64,
+    pub p50: Option<Lamports>,
+    pub p90: Option<Lamports>,
+    pub p99: Option<Lamports>,
+    /// Average amount the winning bid exceeded the slot's base fee by
+    pub average_overbid: Option<Lamports>,
+    /// Average winning bid as a multiple of the slot's base fee
+    pub average_overbid_ratio: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct FeeWindow {
+    pub window_minutes: u32,
+    pub jit: WinningBidStats,
+    pub aot: WinningBidStats,
+    /// Escrow returned to bidders, from lost auctions, expiry and evictions
+    pub refund_volume: Lamports,
+    pub refund_count: u64,
+}
+
+/// Winning bid and refund statistics in one-minute buckets over the last day,
+/// updated as auctions resolve and escrow is refunded.
+#[derive(Default)]
+pub struct FeeStats {
+    minutes: VecDeque<MinuteFees>,
+}
+
+impl FeeStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_winning_bid(
+        &mut self,
+        auction_type: TransactionType,
+        price: Lamports,
+        base_fee: Lamports,
+    ) {
+        let minute = self.current_minute();
+        match auction_type {
+            TransactionType::Jit => minute.jit.add(price, base_fee),
+            TransactionType::Aot => minute.aot.add(price, base_fee),
+        }
+    }
+
+    pub fn record_refund(&mut self, amount: Lamports) {
+        let minute = self.current_minute();
+        minute.refunded += amount;
+        minute.refunds += 1;
+    }
+
+    /// Statistics over the trailing `window_minutes`, including the current minute.
+    pub fn window(&self, window_minutes: u32) -> FeeWindow {
+        let since = Utc::now().timestamp() / 60 - i64::from(window_minutes) + 1;
+        let mut jit = WinningBids::default();
+        let mut aot = WinningBids::default();
+        let mut refund_volume = Lamports::ZERO;
+        let mut refund_count = 0;
+
+        for minute in self
+            .minutes
+            .iter()
+            .rev()
+            .take_while(|minute| minute.minute >= since)
+        {
+            jit.merge(&minute.jit);
+            aot.merge(&minute.aot);
+            refund_volume += minute.refunded;
+            refund_count += minute.refunds;
+        }
+
+        FeeWindow {
+            window_minutes,
+            jit: jit.summary(),
+            aot: aot.summary(),
+            refund_volume,
+            refund_count,
+        }
+    }
+
+    fn current_minute(&mut self) -> &mut MinuteFees {
+        let minute = Utc::now().timestamp() / 60;
+        if self.minutes.back().is_none_or(|last| last.minute < minute) {
+            if self.minutes.len() == MAX_FEE_WINDOW_MINUTES as usize {
+                self.minutes.pop_front();
+            }
+            self.minutes.push_back(MinuteFees {
+                minute,
+                ..MinuteFees::default()
+            });
+        }
+        let last = self.minutes.len() - 1;
+        &mut self.minutes[last]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct DemandCell {
+    bids: u64,
+    volume: u128,
+}
+
+impl DemandCell {
+    fn merge(&mut self, other: &DemandCell) {
+        self.bids += other.bids;
+        self.volume += other.volume;
+    }
+
+    fn view(&self) -> DemandStats {
+        DemandStats {
+            bids: self.bids,
+            average_price: (self.bids > 0)
+                .then(|| Lamports((self.volume / u128::from(self.bids)) as u64)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct DemandStats {
+    pub bids: u64,
+    pub average_price: Option<Lamports>,
+}
+
+/// Bids placed between `min_distance` and `max_distance` slots ahead of the
+/// current slot, inclusive.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct DistanceDemand {
+    pub min_distance: u64,
+    pub max_distance: u64,
+    pub jit: DemandStats,
+    pub aot: DemandStats,
+    /// Fraction of all bids that fall in this range
+    pub share: f64,
+}
+
+/// Bid counts and volume by how many slots ahead the bid's slot was when it was
+/// placed, since the simulation started.
+#[derive(Default)]
+pub struct DemandHeatmap {
+    by_distance: BTreeMap<u64, (DemandCell, DemandCell)>,
+    total_bids: u64,
+    /// Slot ticks seen; each tick moves exactly one slot to every distance
+    slots_observed: u64,
+}
+
+impl DemandHeatmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_bid(&mut self, auction_type: TransactionType, distance: u64, amount: Lamports) {
+        let (jit, aot) = self.by_distance.entry(distance.max(1)).or_default();
+        let cell = match auction_type {
+            TransactionType::Jit => jit,
+            TransactionType::Aot => aot,
+        };
+        cell.bids += 1;
+        cell.volume += u128::from(amount.0);
+        self.total_bids += 1;
+    }
+
+    pub fn record_slot(&mut self) {
+        self.slots_observed += 1;
+    }
+
+    pub fn total_bids(&self) -> u64 {
+        self.total_bids
+    }
+
+    /// Bids of `auction_type` a single slot has historically drawn while it was
+    /// between `min_distance` and `max_distance` slots ahead, or `None` before
+    /// the first slot tick.
+    pub fn expected_bids(
+        &self,
+        auction_type: TransactionType,
+        min_distance: u64,
+        max_distance: u64,
+    ) -> Option<f64> {
+        if self.slots_observed == 0 || min_distance > max_distance {
+            return (self.slots_observed > 0).then_some(0.0);
+        }
+        let bids: u64 = self
+            .by_distance
+            .range(min_distance..=max_distance)
+            .map(|(_, (jit, aot))| match auction_type {
+                TransactionType::Jit => jit.bids,
+                TransactionType::Aot => aot.bids,
+            })
+            .sum();
+        Some(bids as f64 / self.slots_observed as f64)
+    }
+
+    /// Demand grouped into ranges of `bucket_size` distances, nearest first.
+    /// Ranges without bids are omitted.
+    pub fn buckets(&self, bucket_size: u64) -> Vec<DistanceDemand> {
+        let bucket_size = bucket_size.max(1);
+        let mut buckets: BTreeMap<u64, (DemandCell, DemandCell)> = BTreeMap::new();
+        for (distance, (jit, aot)) in &self.by_distance {
+            let bucket = buckets.entry((distance - 1) / bucket_size).or_default();
+            bucket.0.merge(jit);
+            bucket.1.merge(aot);
+        }
+
+        buckets
+            .into_iter()
+            .map(|(index, (jit, aot))| DistanceDemand {
+                min_distance: index * bucket_size + 1,
+                max_distance: (index + 1) * bucket_size,
+                share: (jit.bids + aot.bids) as f64 / self.total_bids.max(1) as f64,
+                jit: jit.view(),
+                aot: aot.view(),
+            })
+            .collect()
+    }
+}
+
+/// Bid size ranges latency is grouped by, in lamports; the last is open-ended.
+const LATENCY_BID_RANGES: [(Lamports, Option<Lamports>); 5] = [
+    (Lamports::ZERO, Some(Lamports(1_000_000))),
+    (Lamports(1_000_000), Some(Lamports(10_000_000))),
+    (Lamports(10_000_000), Some(Lamports(100_000_000))),
+    (Lamports(100_000_000), Some(Lamports::from_whole_sol(1))),
+    (Lamports::from_whole_sol(1), None),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettlementOutcome {
+    Included,
+    Failed,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub average_ms: Option<u64>,
+    pub p50_ms: Option<u64>,
+    pub p90_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+}
+
+/// Time from submission to inclusion or failure.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct OutcomeLatency {
+    pub included: LatencySummary,
+    pub failed: LatencySummary,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct TypeLatency {
+    pub jit: OutcomeLatency,
+    pub aot: OutcomeLatency,
+}
+
+/// Latency of bids from `min_bid` up to, but excluding, `max_bid`.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct BidSizeLatency {
+    pub min_bid: Lamports,
+    /// `None` for the open-ended range
+    pub max_bid: Option<Lamports>,
+    #[serde(flatten)]
+    pub latency: TypeLatency,
+}
+
+#[derive(Clone, Debug, Default)]
+struct LatencyCell {
+    latencies: Histogram,
+    total_ms: u128,
+}
+
+impl LatencyCell {
+    fn merge(&mut self, other: &LatencyCell) {
+        self.latencies.merge(&other.latencies);
+        self.total_ms += other.total_ms;
+    }
+
+    fn summary(&self) -> LatencySummary {
+        let count = self.latencies.count;
+        LatencySummary {
+            count,
+            average_ms: (count > 0).then(|| (self.total_ms / u128::from(count)) as u64),
+            p50_ms: self.latencies.percentile(50),
+            p90_ms: self.latencies.percentile(90),
+            p99_ms: self.latencies.percentile(99),
+        }
+    }
+}
+
+/// Included and failed latency for each auction type, in that order.
+#[derive(Clone, Debug, Default)]
+struct LatencyCells([LatencyCell; 4]);
+
+impl LatencyCells {
+    fn index(auction_type: TransactionType, outcome: SettlementOutcome) -> usize {
+        let type_offset = match auction_type {
+            TransactionType::Jit => 0,
+            TransactionType::Aot => 2,
+        };
+        match outcome {
+            SettlementOutcome::Included => type_offset,
+            SettlementOutcome::Failed => type_offset + 1,
+        }
+    }
+
+    fn merge(&mut self, other: &LatencyCells) {
+        for (cell, other) in self.0.iter_mut().zip(&other.0) {
+            cell.merge(other);
+        }
+    }
+
+    fn view(&self) -> TypeLatency {
+        let [jit_included, jit_failed, aot_included, aot_failed] = &self.0;
+        TypeLatency {
+            jit: OutcomeLatency {
+                included: jit_included.summary(),
+                failed: jit_failed.summary(),
+            },
+            aot: OutcomeLatency {
+                included: aot_included.summary(),
+                failed: aot_failed.summary(),
+            },
+        }
+    }
+}
+
+/// Submission-to-settlement latency by auction type and bid size, since the
+/// simulation started.
+#[derive(Default)]
+pub struct LatencyStats {
+    by_bid_size: [LatencyCells; LATENCY_BID_RANGES.len()],
+}
+
+impl LatencyStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(
+        &mut self,
+        auction_type: TransactionType,
+        bid: Lamports,
+        outcome: SettlementOutcome,
+        latency_ms: u64,
+    ) {
+        let range = LATENCY_BID_RANGES
+            .iter()
+            .position(|(_, max_bid)| max_bid.is_none_or(|max_bid| bid < max_bid))
+            .unwrap_or(LATENCY_BID_RANGES.len() - 1);
+        let cell = &mut self.by_bid_size[range].0[LatencyCells::index(auction_type, outcome)];
+        cell.latencies.add(latency_ms);
+        cell.total_ms += u128::from(latency_ms);
+    }
+
+    pub fn by_type(&self) -> TypeLatency {
+        let mut total = LatencyCells::default();
+        for cells in &self.by_bid_size {
+            total.merge(cells);
+        }
+        total.view()
+    }
+
+    pub fn by_bid_size(&self) -> Vec<BidSizeLatency> {
+        LATENCY_BID_RANGES
+            .iter()
+            .zip(&self.by_bid_size)
+            .map(|((min_bid, max_bid), cells)| BidSizeLatency {
+                min_bid: *min_bid,
+                max_bid: *max_bid,
+                latency: cells.view(),
+            })
+            .collect()
+    }
+}
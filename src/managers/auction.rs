@@ -1,8 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
 
-use crate::models::auction::{AotAuction, JitAuction};
+use crate::{
+    error::AppError,
+    models::{
+        auction::{AotAuction, JitAuction, ResolvedAuction},
+        types::Lamports,
+    },
+};
+
+/// Resolved auctions kept in the history before the oldest slots are dropped.
+const AUCTION_HISTORY_CAPACITY: usize = 1000;
 
 #[derive(Clone, Debug, Default)]
 pub struct AuctionManager {
@@ -18,7 +28,7 @@ impl AuctionManager {
         }
     }
 
-    pub fn start_jit_auction(&mut self, slot_number: u64, base_fee: f64) -> Result<()> {
+    pub fn start_jit_auction(&mut self, slot_number: u64, base_fee: Lamports) -> Result<()> {
         if self.jit_auctions.contains_key(&slot_number) {
             return Err(anyhow!(
                 "JIT auction already exists for slot {}",
@@ -35,28 +45,31 @@ impl AuctionManager {
         &mut self,
         slot_number: u64,
         bidder_id: String,
-        amount: f64,
-    ) -> Result<()> {
+        amount: Lamports,
+    ) -> Result<(), AppError> {
         let auction = self
             .jit_auctions
             .get_mut(&slot_number)
-            .ok_or_else(|| anyhow!("No JIT auction exists for slot {}", slot_number))?;
+            .ok_or(AppError::AuctionNotFound { slot: slot_number })?;
 
         auction.submit_bid(bidder_id, amount)
     }
 
-    pub fn resolve_jit(&mut self, slot_number: u64) -> Option<(String, f64)> {
+    pub fn resolve_jit(&mut self, slot_number: u64) -> Option<(String, Lamports)> {
         self.jit_auctions
             .remove(&slot_number)
             .and_then(|a| a.resolve())
     }
 
+    /// Opens the slot's AOT auction, returning when it closes.
     pub fn start_aot_auction(
         &mut self,
         slot_number: u64,
-        base_fee: f64,
+        base_fee: Lamports,
         duration_seconds: i64,
-    ) -> Result<()> {
+        slot_arrives_at: Option<DateTime<Utc>>,
+        closing_warnings: &[u64],
+    ) -> Result<DateTime<Utc>> {
         if self.aot_auctions.contains_key(&slot_number) {
             return Err(anyhow!(
                 "AOT auction already exists for slot {}",
@@ -64,26 +77,36 @@ impl AuctionManager {
             ));
         }
 
-        let auction = AotAuction::new(slot_number, base_fee, duration_seconds);
+        let auction = AotAuction::new(
+            slot_number,
+            base_fee,
+            duration_seconds,
+            slot_arrives_at,
+            closing_warnings,
+        );
+        let ends_at = auction.ends_at;
         self.aot_auctions.insert(slot_number, auction);
-        Ok(())
+        Ok(ends_at)
     }
 
     pub fn submit_aot_bid(
         &mut self,
         slot_number: u64,
         bidder_id: String,
-        amount: f64,
-    ) -> Result<()> {
+        amount: Lamports,
+    ) -> Result<(), AppError> {
         let auction = self
             .aot_auctions
             .get_mut(&slot_number)
-            .ok_or_else(|| anyhow!("No AOT auction exists for slot {}", slot_number))?;
+            .ok_or(AppError::AuctionNotFound { slot: slot_number })?;
 
         auction.submit_bid(bidder_id, amount)
     }
 
-    pub fn resolve_ready_aot(&mut self, current_slot: u64) -> Vec<(u64, String, f64, Vec<String>)> {
+    pub fn resolve_ready_aot(
+        &mut self,
+        current_slot: u64,
+    ) -> Vec<(u64, String, Lamports, Vec<String>)> {
         let mut resolved = Vec::new();
 
         let ready_slots: Vec<u64> = self
@@ -105,6 +128,19 @@ impl AuctionManager {
         resolved
     }
 
+    /// Active AOT auctions that just passed a closing warning threshold, with
+    /// the seconds they have left.
+    pub fn take_closing_warnings(&mut self) -> Vec<(u64, u64)> {
+        self.aot_auctions
+            .values_mut()
+            .filter_map(|auction| {
+                auction
+                    .take_closing_warning()
+                    .map(|seconds_left| (auction.slot_number, seconds_left))
+            })
+            .collect()
+    }
+
     pub fn get_active_jit_auctions(&self) -> Vec<&JitAuction> {
         self.jit_auctions.values().collect()
     }
@@ -112,4 +148,126 @@ impl AuctionManager {
     pub fn get_active_aot_auctions(&self) -> Vec<&AotAuction> {
         self.aot_auctions.values().collect()
     }
+
+    /// Checks that `bidder_id` may withdraw a bid from the slot's JIT auction.
+    /// Only the leading bid is kept, so there is nothing to remove for anyone
+    /// else, and the leader can't withdraw since the bids it beat are gone.
+    pub fn withdraw_jit_bid(&self, slot_number: u64, bidder_id: &str) -> Result<(), AppError> {
+        let auction = self
+            .jit_auctions
+            .get(&slot_number)
+            .ok_or_else(|| already_resolved(slot_number))?;
+
+        if auction
+            .current_highest_bidder
+            .as_ref()
+            .is_some_and(|(bidder, _)| bidder == bidder_id)
+        {
+            return Err(AppError::TransactionNotCancellable(format!(
+                "You lead the JIT auction for slot {}, so your bids can't be withdrawn",
+                slot_number
+            )));
+        }
+        Ok(())
+    }
+
+    /// Takes one of `bidder_id`'s bids of `amount` out of the slot's AOT
+    /// auction while it is still open.
+    pub fn withdraw_aot_bid(
+        &mut self,
+        slot_number: u64,
+        bidder_id: &str,
+        amount: Lamports,
+    ) -> Result<(), AppError> {
+        let auction = self
+            .aot_auctions
+            .get_mut(&slot_number)
+            .ok_or_else(|| already_resolved(slot_number))?;
+        if auction.has_ended() {
+            return Err(AppError::AuctionClosed {
+                slot: slot_number,
+                closed_at: auction.ends_at,
+            });
+        }
+
+        if let Some(index) = auction
+            .bids
+            .iter()
+            .position(|(bidder, bid, _)| bidder == bidder_id && *bid == amount)
+        {
+            auction.bids.remove(index);
+        }
+        Ok(())
+    }
+
+    /// Drops every bid placed by `bidder_id` from open auctions. A JIT auction led
+    /// by the bidder loses its leader, since earlier bids are not retained.
+    pub fn remove_bidder(&mut self, bidder_id: &str) {
+        for auction in self.jit_auctions.values_mut() {
+            if auction
+                .current_highest_bidder
+                .as_ref()
+                .is_some_and(|(bidder, _)| bidder == bidder_id)
+            {
+                auction.current_highest_bidder = None;
+            }
+        }
+
+        for auction in self.aot_auctions.values_mut() {
+            auction.bids.retain(|(bidder, _, _)| bidder != bidder_id);
+        }
+    }
+}
+
+fn already_resolved(slot_number: u64) -> AppError {
+    AppError::TransactionNotCancellable(format!(
+        "The auction for slot {} has already resolved",
+        slot_number
+    ))
+}
+
+/// Bounded record of resolved auctions by slot, for post-game analysis.
+#[derive(Clone, Debug, Default)]
+pub struct AuctionHistory {
+    by_slot: BTreeMap<u64, Vec<ResolvedAuction>>,
+    len: usize,
+}
+
+impl AuctionHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, auction: ResolvedAuction) {
+        self.by_slot
+            .entry(auction.slot_number)
+            .or_default()
+            .push(auction);
+        self.len += 1;
+
+        while self.len > AUCTION_HISTORY_CAPACITY {
+            let Some((_, dropped)) = self.by_slot.pop_first() else {
+                break;
+            };
+            self.len -= dropped.len();
+        }
+    }
+
+    /// Every resolved auction, latest slot first.
+    pub fn iter(&self) -> impl Iterator<Item = &ResolvedAuction> {
+        self.by_slot.values().rev().flatten()
+    }
+
+    /// Resolved auctions for slots in `slot_min..=slot_max`, latest slot first.
+    pub fn range(&self, slot_min: u64, slot_max: u64) -> Vec<ResolvedAuction> {
+        if slot_min > slot_max {
+            return Vec::new();
+        }
+
+        self.by_slot
+            .range(slot_min..=slot_max)
+            .rev()
+            .flat_map(|(_, auctions)| auctions.iter().cloned())
+            .collect()
+    }
 }
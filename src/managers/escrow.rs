@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::models::{
+    transaction::TransactionStatus,
+    types::{Lamports, TransactionType},
+};
+
+/// Slots a settled record is kept for after its slot, for reconciliation and
+/// player analytics. Held records are kept until they settle.
+pub const ESCROW_RETENTION_SLOTS: u64 = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscrowStatus {
+    /// Funds are locked behind an open bid
+    Held,
+    /// The bid executed and its funds were paid out
+    Released,
+    /// The funds went back to the bidder
+    Refunded,
+}
+
+impl EscrowStatus {
+    /// How the escrow of a transaction in `status` settled, for bids whose
+    /// record has already been pruned.
+    pub fn settled_as(status: &TransactionStatus) -> Self {
+        match status {
+            TransactionStatus::Pending => Self::Held,
+            TransactionStatus::Included { .. } | TransactionStatus::AuctionWon { .. } => {
+                Self::Released
+            }
+            _ => Self::Refunded,
+        }
+    }
+}
+
+/// Funds a bidder locked for one transaction in one auction.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct EscrowRecord {
+    pub transaction_id: String,
+    pub bidder: String,
+    pub auction_type: TransactionType,
+    pub slot_number: u64,
+    pub amount: Lamports,
+    pub status: EscrowStatus,
+    pub created_at: DateTime<Utc>,
+    pub settled_at: Option<DateTime<Utc>>,
+}
+
+/// Single source of truth for bid funds. Each record settles exactly once, so
+/// releasing or refunding the same transaction twice is a no-op. Settled
+/// records are pruned `ESCROW_RETENTION_SLOTS` after their slot; the funds
+/// they released stay counted in `total_released`.
+#[derive(Clone, Debug, Default)]
+pub struct EscrowManager {
+    records: HashMap<String, EscrowRecord>,
+    /// Released by records that were pruned since
+    pruned_released: Lamports,
+}
+
+impl EscrowManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn hold(
+        &mut self,
+        transaction_id: String,
+        bidder: String,
+        auction_type: TransactionType,
+        slot_number: u64,
+        amount: Lamports,
+    ) -> Result<()> {
+        if self.records.contains_key(&transaction_id) {
+            return Err(anyhow!(
+                "Escrow already exists for transaction {}",
+                transaction_id
+            ));
+        }
+
+        self.records.insert(
+            transaction_id.clone(),
+            EscrowRecord {
+                transaction_id,
+                bidder,
+                auction_type,
+                slot_number,
+                amount,
+                status: EscrowStatus::Held,
+                created_at: Utc::now(),
+                settled_at: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Pays out a held escrow. Returns `None` if it was already settled or never held.
+    pub fn release(&mut self, transaction_id: &str) -> Option<EscrowRecord> {
+        self.settle(transaction_id, EscrowStatus::Released)
+    }
+
+    /// Marks a held escrow as refunded and returns it so the caller can credit the
+    /// bidder. Returns `None` if it was already settled or never held.
+    pub fn refund(&mut self, transaction_id: &str) -> Option<EscrowRecord> {
+        self.settle(transaction_id, EscrowStatus::Refunded)
+    }
+
+    /// Drops a held escrow whose bid was never placed, as if it had not been held.
+    pub fn cancel(&mut self, transaction_id: &str) -> Option<EscrowRecord> {
+        match self.records.get(transaction_id) {
+            Some(record) if record.status == EscrowStatus::Held => {
+                self.records.remove(transaction_id)
+            }
+            _ => None,
+        }
+    }
+
+    fn settle(&mut self, transaction_id: &str, status: EscrowStatus) -> Option<EscrowRecord> {
+        let record = self
+            .records
+            .get_mut(transaction_id)
+            .filter(|record| record.status == EscrowStatus::Held)?;
+
+        record.status = status;
+        record.settled_at = Some(Utc::now());
+        Some(record.clone())
+    }
+
+    /// Drops settled records for slots before `before_slot`. Returns how many
+    /// were dropped.
+    pub fn prune_settled(&mut self, before_slot: u64) -> usize {
+        let before = self.records.len();
+        let mut pruned_released = Lamports::ZERO;
+        self.records.retain(|_, record| {
+            let keep = record.status == EscrowStatus::Held || record.slot_number >= before_slot;
+            if !keep && record.status == EscrowStatus::Released {
+                pruned_released += record.amount;
+            }
+            keep
+        });
+        self.pruned_released += pruned_released;
+        before - self.records.len()
+    }
+
+    /// Moves every record of bidder `from`, settled or not, to `to`.
+    pub fn rename_bidder(&mut self, from: &str, to: &str) {
+        for record in self.records.values_mut() {
+            if record.bidder == from {
+                record.bidder = to.to_string();
+            }
+        }
+    }
+
+    pub fn get(&self, transaction_id: &str) -> Option<&EscrowRecord> {
+        self.records.get(transaction_id)
+    }
+
+    /// Bidders other than `winner` with funds still held in the auction.
+    pub fn held_bidders(
+        &self,
+        auction_type: &TransactionType,
+        slot_number: u64,
+        winner: &str,
+    ) -> Vec<String> {
+        let mut bidders: Vec<String> = self
+            .held()
+            .filter(|record| {
+                record.auction_type == *auction_type
+                    && record.slot_number == slot_number
+                    && record.bidder != winner
+            })
+            .map(|record| record.bidder.clone())
+            .collect();
+
+        bidders.sort();
+        bidders.dedup();
+        bidders
+    }
+
+    /// Every escrow record for the slot, of either auction type and any status.
+    pub fn records_for_slot(&self, slot_number: u64) -> Vec<EscrowRecord> {
+        self.records
+            .values()
+            .filter(|record| record.slot_number == slot_number)
+            .cloned()
+            .collect()
+    }
+
+    /// Whether the bidder has escrow of any status for the slot.
+    pub fn has_bid_for_slot(&self, bidder: &str, slot_number: u64) -> bool {
+        self.records
+            .values()
+            .any(|record| record.bidder == bidder && record.slot_number == slot_number)
+    }
+
+    pub fn total_held(&self) -> Lamports {
+        self.held().map(|record| record.amount).sum()
+    }
+
+    /// Funds paid out for executed bids, which leave circulation as fees,
+    /// including those of pruned records.
+    pub fn total_released(&self) -> Lamports {
+        self.pruned_released
+            + self
+                .records
+                .values()
+                .filter(|record| record.status == EscrowStatus::Released)
+                .map(|record| record.amount)
+                .sum::<Lamports>()
+    }
+
+    fn held(&self) -> impl Iterator<Item = &EscrowRecord> {
+        self.records
+            .values()
+            .filter(|record| record.status == EscrowStatus::Held)
+    }
+}
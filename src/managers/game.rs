@@ -1,97 +1,372 @@
 use std::collections::HashMap;
+#[cfg(feature = "server")]
+use std::sync::Arc;
 
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use rand::Rng;
-
-use crate::models::{
-    metrics::{Achievement, AchievementType, Leaderboard, LeaderboardEntry},
-    player::PlayerStats,
-    types::TransactionType,
+#[cfg(feature = "server")]
+use tokio::sync::Mutex;
+
+use crate::{
+    error::AppError,
+    models::{
+        metrics::{Achievement, AchievementType, Leaderboard, LeaderboardEntry},
+        player::{GameMode, PlayerStats},
+        types::{Lamports, TransactionType},
+    },
 };
 
+const LEADERBOARD_SIZE: usize = 10;
+/// How long a leaderboard is served before balance changes are picked up
+const LEADERBOARD_TTL_MS: i64 = 2_000;
+
+/// Each board's ranks as of the last time it was built, keyed by session.
+#[derive(Default)]
+struct LeaderboardRanks {
+    wins: HashMap<String, u32>,
+    balance: HashMap<String, u32>,
+    winrate: HashMap<String, u32>,
+}
+
+#[derive(Default)]
 pub struct GameManager {
+    /// Applied to every profile created here
+    pub mode: GameMode,
     pub player_stats: HashMap<String, PlayerStats>,
+    /// Wallet-linked profiles whose session expired, keyed by wallet public key
+    pub wallet_profiles: HashMap<String, PlayerStats>,
+    /// Profiles with a recovery code whose session expired, keyed by code hash
+    pub recovery_profiles: HashMap<String, PlayerStats>,
+    /// SOL put into circulation by starting balances and admin credits
+    pub total_issued: Lamports,
+    /// SOL taken out of circulation by admin debits and discarded profiles
+    pub total_retired: Lamports,
+    /// Serializes each player's bids so one bid's balance check, escrow and
+    /// auction entry never interleave with another's
+    #[cfg(feature = "server")]
+    bid_locks: HashMap<String, Arc<Mutex<()>>>,
+    leaderboard: Option<Leaderboard>,
+    leaderboard_ranks: LeaderboardRanks,
 }
 
 impl GameManager {
     pub fn new() -> Self {
+        Self::with_mode(GameMode::default())
+    }
+
+    pub fn with_mode(mode: GameMode) -> Self {
         Self {
+            mode,
             player_stats: HashMap::new(),
+            wallet_profiles: HashMap::new(),
+            recovery_profiles: HashMap::new(),
+            total_issued: Lamports::ZERO,
+            total_retired: Lamports::ZERO,
+            #[cfg(feature = "server")]
+            bid_locks: HashMap::new(),
+            leaderboard: None,
+            leaderboard_ranks: LeaderboardRanks::default(),
         }
     }
 
+    #[cfg(feature = "server")]
+    pub fn bid_lock(&mut self, session_id: &str) -> Arc<Mutex<()>> {
+        self.bid_locks
+            .entry(session_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Whether `lock` is still the session's bid lock. It stops being so once
+    /// the player is removed or renamed.
+    #[cfg(feature = "server")]
+    pub fn is_current_bid_lock(&self, session_id: &str, lock: &Arc<Mutex<()>>) -> bool {
+        self.bid_locks
+            .get(session_id)
+            .is_some_and(|current| Arc::ptr_eq(current, lock))
+    }
+
     pub fn get_or_create_player(&mut self, session_id: String) -> &mut PlayerStats {
+        let (total_issued, mode) = (&mut self.total_issued, self.mode);
         self.player_stats
             .entry(session_id.clone())
-            .or_insert_with(|| PlayerStats::new(session_id))
+            .or_insert_with(|| {
+                let mut stats = PlayerStats::new(session_id);
+                stats.mode = mode;
+                *total_issued += stats.balance;
+                stats
+            })
+    }
+
+    /// Returns refunded escrow to the player, or retires it if the player is
+    /// gone or the credit would overflow their balance. Returns whether the
+    /// player was credited.
+    pub fn credit_refund(&mut self, session_id: &str, amount: Lamports) -> bool {
+        let credited = self
+            .player_stats
+            .get_mut(session_id)
+            .is_some_and(|stats| stats.increment_balance(amount).is_ok());
+        if !credited {
+            self.total_retired += amount;
+        }
+        credited
+    }
+
+    /// Takes a bid's priority fee from the bidder's balance and counts the bid
+    /// on their stats, creating the player if needed.
+    pub fn fund_bid(
+        &mut self,
+        session_id: String,
+        amount: Lamports,
+        slot_number: u64,
+    ) -> Result<(), AppError> {
+        let stats = self.get_or_create_player(session_id);
+        stats.deduct_balance(amount)?;
+        stats.track_bid(slot_number);
+        Ok(())
+    }
+
+    /// Undoes `fund_bid` for a bid that was never placed, or retires the fee
+    /// if the player is gone.
+    pub fn revert_bid(
+        &mut self,
+        session_id: &str,
+        amount: Lamports,
+        slot_number: u64,
+        other_bids_for_slot: bool,
+    ) {
+        match self.player_stats.get_mut(session_id) {
+            Some(stats) => stats.revert_bid(amount, slot_number, other_bids_for_slot),
+            None => self.total_retired += amount,
+        }
+    }
+
+    /// Sum of every balance still held by a player or a saved wallet profile.
+    pub fn total_balances(&self) -> Lamports {
+        self.player_stats
+            .values()
+            .chain(self.wallet_profiles.values())
+            .chain(self.recovery_profiles.values())
+            .map(|stats| stats.balance)
+            .sum()
     }
 
     pub fn cleanup_players(&mut self, session_ids: &[String]) {
+        self.invalidate_leaderboard();
         for session_id in session_ids {
-            self.player_stats.remove(session_id);
+            #[cfg(feature = "server")]
+            self.bid_locks.remove(session_id);
+            if let Some(stats) = self.player_stats.remove(session_id) {
+                match (stats.wallet.clone(), stats.recovery_code_hash.clone()) {
+                    (Some(wallet), _) => {
+                        self.wallet_profiles.insert(wallet, stats);
+                    }
+                    (None, Some(code_hash)) => {
+                        self.recovery_profiles.insert(code_hash, stats);
+                    }
+                    (None, None) => self.total_retired += stats.balance,
+                }
+            }
+        }
+    }
+
+    /// Session currently holding the profile with this recovery code, if any.
+    pub fn session_for_recovery_code(&self, code_hash: &str) -> Option<String> {
+        self.player_stats
+            .values()
+            .find(|stats| stats.recovery_code_hash.as_deref() == Some(code_hash))
+            .map(|stats| stats.session_id.clone())
+    }
+
+    /// Sets the code that recovers the session's profile, replacing any earlier one.
+    pub fn set_recovery_code(&mut self, session_id: &str, code_hash: String) {
+        self.get_or_create_player(session_id.to_string())
+            .recovery_code_hash = Some(code_hash);
+    }
+
+    /// Moves the profile recovered by `code_hash`, from another session or an
+    /// expired one, to `session_id` in place of its current stats, under the
+    /// new code `next_code_hash`. Returns the profile and the session it
+    /// belonged to.
+    pub fn recover_profile(
+        &mut self,
+        session_id: &str,
+        code_hash: &str,
+        next_code_hash: String,
+    ) -> Result<(&PlayerStats, String), String> {
+        let recovered = match self.session_for_recovery_code(code_hash) {
+            Some(other_session) => self.player_stats.remove(&other_session),
+            None => self.recovery_profiles.remove(code_hash),
+        };
+        let Some(mut stats) = recovered else {
+            return Err("Recovery code not found".to_string());
+        };
+
+        self.invalidate_leaderboard();
+        let previous_session = std::mem::replace(&mut stats.session_id, session_id.to_string());
+        stats.recovery_code_hash = Some(next_code_hash);
+        if previous_session != session_id {
+            // The session's own stats are discarded in favour of the recovered profile
+            if let Some(replaced) = self.player_stats.remove(session_id) {
+                self.total_retired += replaced.balance;
+            }
+        }
+        self.player_stats.insert(session_id.to_string(), stats);
+
+        Ok((&self.player_stats[session_id], previous_session))
+    }
+
+    /// Moves a player's profile and leaderboard ranks to a new session ID.
+    pub fn rename_player(&mut self, from: &str, to: &str) {
+        #[cfg(feature = "server")]
+        self.bid_locks.remove(from);
+        if let Some(mut stats) = self.player_stats.remove(from) {
+            stats.session_id = to.to_string();
+            self.player_stats.insert(to.to_string(), stats);
         }
+        for ranks in [
+            &mut self.leaderboard_ranks.wins,
+            &mut self.leaderboard_ranks.balance,
+            &mut self.leaderboard_ranks.winrate,
+        ] {
+            if let Some(rank) = ranks.remove(from) {
+                ranks.insert(to.to_string(), rank);
+            }
+        }
+        self.invalidate_leaderboard();
+    }
+
+    /// Drops a player's profile for good, without keeping it for a linked
+    /// wallet or recovery code.
+    pub fn delete_player(&mut self, session_id: &str) {
+        #[cfg(feature = "server")]
+        self.bid_locks.remove(session_id);
+        if let Some(stats) = self.player_stats.remove(session_id) {
+            self.total_retired += stats.balance;
+        }
+        for ranks in [
+            &mut self.leaderboard_ranks.wins,
+            &mut self.leaderboard_ranks.balance,
+            &mut self.leaderboard_ranks.winrate,
+        ] {
+            ranks.remove(session_id);
+        }
+        self.invalidate_leaderboard();
+    }
+
+    /// Session currently holding the profile linked to `wallet`, if any.
+    pub fn session_for_wallet(&self, wallet: &str) -> Option<String> {
+        self.player_stats
+            .values()
+            .find(|stats| stats.wallet.as_deref() == Some(wallet))
+            .map(|stats| stats.session_id.clone())
+    }
+
+    /// Links `wallet` to `session_id`. If the wallet already has a profile, from another
+    /// session or an expired one, that profile replaces the session's current stats.
+    pub fn link_wallet(&mut self, session_id: &str, wallet: &str) -> Result<&PlayerStats, String> {
+        let linked = self
+            .player_stats
+            .get(session_id)
+            .and_then(|stats| stats.wallet.as_deref());
+        if let Some(linked) = linked.filter(|linked| *linked != wallet) {
+            return Err(format!("Session is already linked to wallet {}", linked));
+        }
+
+        let existing = match self.session_for_wallet(wallet) {
+            Some(other_session) if other_session == session_id => None,
+            Some(other_session) => self.player_stats.remove(&other_session),
+            None => self.wallet_profiles.remove(wallet),
+        };
+
+        self.invalidate_leaderboard();
+        match existing {
+            Some(mut stats) => {
+                stats.session_id = session_id.to_string();
+                // The session's own stats are discarded in favour of the wallet's profile
+                if let Some(replaced) = self.player_stats.insert(session_id.to_string(), stats) {
+                    self.total_retired += replaced.balance;
+                }
+            }
+            None => {
+                self.get_or_create_player(session_id.to_string()).wallet = Some(wallet.to_string());
+            }
+        }
+
+        Ok(&self.player_stats[session_id])
     }
 
-    pub fn generate_leaderboard(&self) -> Leaderboard {
-        let mut by_wins: Vec<_> = self.player_stats.values().collect();
+    /// The current leaderboard. It is rebuilt when a win, loss, ban or departure
+    /// invalidates it, and otherwise at most every `LEADERBOARD_TTL_MS`, which is
+    /// how long balance changes take to show up.
+    pub fn leaderboard(&mut self) -> Leaderboard {
+        let fresh = self.leaderboard.as_ref().filter(|leaderboard| {
+            Utc::now() - leaderboard.last_updated < Duration::milliseconds(LEADERBOARD_TTL_MS)
+        });
+        if let Some(leaderboard) = fresh {
+            return leaderboard.clone();
+        }
+
+        let leaderboard = self.generate_leaderboard();
+        self.leaderboard = Some(leaderboard.clone());
+        leaderboard
+    }
+
+    /// Makes the next `leaderboard` call rebuild it.
+    pub fn invalidate_leaderboard(&mut self) {
+        self.leaderboard = None;
+    }
+
+    fn generate_leaderboard(&mut self) -> Leaderboard {
+        // Ties are broken by session so rank changes reflect real moves
+        let mut by_wins: Vec<_> = self
+            .player_stats
+            .values()
+            .filter(|p| p.is_ranked())
+            .collect();
         by_wins.sort_by(|a, b| {
             b.total_auctions_won
-                .partial_cmp(&a.total_auctions_won)
-                .unwrap()
+                .cmp(&a.total_auctions_won)
+                .then_with(|| a.session_id.cmp(&b.session_id))
         });
 
-        let mut by_balance: Vec<_> = self.player_stats.values().collect();
-        by_balance.sort_by(|a, b| b.balance.partial_cmp(&a.balance).unwrap());
+        let mut by_balance: Vec<_> = self
+            .player_stats
+            .values()
+            .filter(|p| p.is_ranked())
+            .collect();
+        by_balance.sort_by(|a, b| {
+            b.balance
+                .cmp(&a.balance)
+                .then_with(|| a.session_id.cmp(&b.session_id))
+        });
 
         let mut by_winrate: Vec<_> = self
             .player_stats
             .values()
-            .filter(|p| p.total_auctions_participated >= 5)
+            .filter(|p| p.is_ranked() && p.total_auctions_participated >= 5)
             .collect();
-        by_winrate.sort_by(|a, b| b.win_rate().partial_cmp(&a.win_rate()).unwrap());
+        by_winrate.sort_by(|a, b| {
+            b.win_rate()
+                .total_cmp(&a.win_rate())
+                .then_with(|| a.session_id.cmp(&b.session_id))
+        });
 
+        let ranks = &mut self.leaderboard_ranks;
         Leaderboard {
-            top_by_wins: by_wins
-                .iter()
-                .take(10)
-                .enumerate()
-                .map(|(i, p)| LeaderboardEntry {
-                    session_id: p.session_id.clone(),
-                    display_name: format!("Player {}", &p.session_id[..6]),
-                    rank: (i + 1) as u32,
-                    level: p.level,
-                })
-                .collect(),
-
-            top_by_balance: by_balance
-                .iter()
-                .take(10)
-                .enumerate()
-                .map(|(i, p)| LeaderboardEntry {
-                    session_id: p.session_id.clone(),
-                    display_name: format!("Player {}", &p.session_id[..6]),
-                    rank: (i + 1) as u32,
-                    level: p.level,
-                })
-                .collect(),
-
-            top_by_winrate: by_winrate
-                .iter()
-                .take(10)
-                .enumerate()
-                .map(|(i, p)| LeaderboardEntry {
-                    session_id: p.session_id.clone(),
-                    display_name: format!("Player {}", &p.session_id[..6]),
-                    rank: (i + 1) as u32,
-                    level: p.level,
-                })
-                .collect(),
-
+            top_by_wins: rank_entries(&by_wins, &mut ranks.wins),
+            top_by_balance: rank_entries(&by_balance, &mut ranks.balance),
+            top_by_winrate: rank_entries(&by_winrate, &mut ranks.winrate),
             last_updated: Utc::now(),
         }
     }
 
-    pub fn process_auction_win(&mut self, session_id: &str, transaction_type: TransactionType) {
+    /// Returns the achievements the win unlocked.
+    pub fn process_auction_win(
+        &mut self,
+        session_id: &str,
+        transaction_type: TransactionType,
+    ) -> Vec<Achievement> {
         if let Some(stats) = self.player_stats.get_mut(session_id) {
             stats.total_auctions_won += 1;
             stats.current_streak += 1;
@@ -107,18 +382,23 @@ impl GameManager {
 
             stats.add_xp(rand::rng().random_range(5..20));
 
-            self.check_achievements(session_id);
+            self.invalidate_leaderboard();
+            return self.check_achievements(session_id);
         }
+        Vec::new()
     }
 
-    pub fn process_auction_loss(&mut self, session_id: &str) {
+    /// Returns the achievements the loss unlocked.
+    pub fn process_auction_loss(&mut self, session_id: &str) -> Vec<Achievement> {
         if let Some(stats) = self.player_stats.get_mut(session_id) {
             stats.current_streak = 0;
-            self.check_achievements(session_id);
+            self.invalidate_leaderboard();
+            return self.check_achievements(session_id);
         }
+        Vec::new()
     }
 
-    fn check_achievements(&mut self, session_id: &str) {
+    fn check_achievements(&mut self, session_id: &str) -> Vec<Achievement> {
         if let Some(stats) = self.player_stats.get_mut(session_id) {
             let mut new_achievements = Vec::new();
 
@@ -176,7 +456,7 @@ impl GameManager {
                 new_achievements.push(Achievement::beginner());
             }
 
-            if stats.total_sol_spent >= 10.0
+            if stats.total_sol_spent >= Lamports::from_whole_sol(10)
                 && !stats
                     .achievements
                     .iter()
@@ -212,7 +492,7 @@ impl GameManager {
                 new_achievements.push(Achievement::diversified());
             }
 
-            if stats.total_sol_spent >= 50.0
+            if stats.total_sol_spent >= Lamports::from_whole_sol(50)
                 && !stats
                     .achievements
                     .iter()
@@ -257,7 +537,7 @@ impl GameManager {
                 new_achievements.push(Achievement::champion());
             }
 
-            if stats.total_sol_spent >= 100.0
+            if stats.total_sol_spent >= Lamports::from_whole_sol(100)
                 && !stats
                     .achievements
                     .iter()
@@ -302,10 +582,43 @@ impl GameManager {
                 new_achievements.push(Achievement::perfect_record());
             }
 
-            for achievement in new_achievements {
+            for achievement in &new_achievements {
                 stats.add_xp(achievement.reward_xp);
-                stats.achievements.push(achievement);
+                stats.achievements.push(achievement.clone());
             }
+            return new_achievements;
         }
+        Vec::new()
     }
 }
+
+/// Ranks the top players of one board, comparing each rank with the one they
+/// held when the board was last built, and remembers the new ranks.
+fn rank_entries(
+    players: &[&PlayerStats],
+    ranks: &mut HashMap<String, u32>,
+) -> Vec<LeaderboardEntry> {
+    let entries: Vec<LeaderboardEntry> = players
+        .iter()
+        .take(LEADERBOARD_SIZE)
+        .enumerate()
+        .map(|(i, p)| {
+            let rank = (i + 1) as u32;
+            LeaderboardEntry {
+                session_id: p.session_id.clone(),
+                display_name: p.display_name(),
+                rank,
+                rank_change: ranks
+                    .get(&p.session_id)
+                    .map(|previous| *previous as i32 - rank as i32),
+                level: p.level,
+            }
+        })
+        .collect();
+
+    *ranks = entries
+        .iter()
+        .map(|entry| (entry.session_id.clone(), entry.rank))
+        .collect();
+    entries
+}
@@ -0,0 +1,77 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicI64, Ordering},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use dashmap::DashMap;
+
+use crate::models::responses::TaskFailure;
+
+/// How long readiness reports degraded after a background task panics.
+const DEGRADED_WINDOW_SECS: i64 = 60;
+
+/// Tracks liveness signals from background tasks for the readiness probe.
+#[derive(Clone, Default)]
+pub struct HealthMonitor {
+    last_tick_ms: Arc<AtomicI64>,
+    task_failures: Arc<DashMap<&'static str, TaskFailure>>,
+}
+
+impl HealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_tick(&self) {
+        self.last_tick_ms
+            .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn last_tick(&self) -> Option<DateTime<Utc>> {
+        match self.last_tick_ms.load(Ordering::Relaxed) {
+            0 => None,
+            millis => DateTime::from_timestamp_millis(millis),
+        }
+    }
+
+    pub fn record_task_panic(&self, task: &'static str, message: String) {
+        let now = Utc::now();
+        self.task_failures
+            .entry(task)
+            .and_modify(|failure| {
+                failure.restarts += 1;
+                failure.last_panic_at = now;
+                failure.last_panic = message.clone();
+            })
+            .or_insert(TaskFailure {
+                restarts: 1,
+                last_panic_at: now,
+                last_panic: message,
+            });
+    }
+
+    pub fn task_restarts(&self, task: &str) -> u32 {
+        self.task_failures
+            .get(task)
+            .map(|failure| failure.restarts)
+            .unwrap_or(0)
+    }
+
+    pub fn task_failures(&self) -> Vec<(&'static str, TaskFailure)> {
+        self.task_failures
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect()
+    }
+
+    /// Tasks that panicked recently enough to keep the server degraded.
+    pub fn degraded_tasks(&self) -> Vec<&'static str> {
+        let cutoff = Utc::now() - Duration::seconds(DEGRADED_WINDOW_SECS);
+        self.task_failures
+            .iter()
+            .filter(|entry| entry.value().last_panic_at > cutoff)
+            .map(|entry| *entry.key())
+            .collect()
+    }
+}
@@ -0,0 +1,156 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{body::Bytes, http::StatusCode};
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::RwLock;
+
+/// A response recorded for an idempotency key, replayed verbatim on retries.
+#[derive(Clone, Debug)]
+pub struct StoredResponse {
+    pub status: StatusCode,
+    pub body: Bytes,
+}
+
+/// SHA-256 of the request a key was first used with.
+pub type RequestFingerprint = [u8; 32];
+
+#[derive(Clone, Debug)]
+enum IdempotencyEntry {
+    InFlight {
+        fingerprint: RequestFingerprint,
+        expires_at: DateTime<Utc>,
+    },
+    Completed {
+        fingerprint: RequestFingerprint,
+        response: StoredResponse,
+        expires_at: DateTime<Utc>,
+    },
+}
+
+impl IdempotencyEntry {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        match self {
+            Self::InFlight { expires_at, .. } | Self::Completed { expires_at, .. } => {
+                *expires_at <= now
+            }
+        }
+    }
+
+    fn fingerprint(&self) -> &RequestFingerprint {
+        match self {
+            Self::InFlight { fingerprint, .. } | Self::Completed { fingerprint, .. } => fingerprint,
+        }
+    }
+}
+
+pub enum IdempotencyClaim {
+    /// The key is new; the caller must `complete` or `release` it.
+    Claimed,
+    /// A request with the same key is still being processed.
+    InFlight,
+    Replay(StoredResponse),
+    /// The key was first used with a different request body.
+    Mismatch,
+}
+
+/// Responses of keyed requests, scoped per session so keys from different
+/// clients never collide.
+#[derive(Clone, Default)]
+pub struct IdempotencyManager {
+    entries: Arc<RwLock<HashMap<(String, String), IdempotencyEntry>>>,
+}
+
+impl IdempotencyManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claims `key` for a request with `fingerprint`. `ttl` bounds how long
+    /// the claim blocks retries if it is never completed or released.
+    pub async fn claim(
+        &self,
+        session_id: &str,
+        key: &str,
+        fingerprint: RequestFingerprint,
+        ttl: Duration,
+    ) -> IdempotencyClaim {
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let entry_key = (session_id.to_string(), key.to_string());
+
+        match entries.get(&entry_key) {
+            Some(entry) if !entry.is_expired(now) => match entry {
+                _ if *entry.fingerprint() != fingerprint => IdempotencyClaim::Mismatch,
+                IdempotencyEntry::InFlight { .. } => IdempotencyClaim::InFlight,
+                IdempotencyEntry::Completed { response, .. } => {
+                    IdempotencyClaim::Replay(response.clone())
+                }
+            },
+            _ => {
+                entries.insert(
+                    entry_key,
+                    IdempotencyEntry::InFlight {
+                        fingerprint,
+                        expires_at: now + ttl,
+                    },
+                );
+                IdempotencyClaim::Claimed
+            }
+        }
+    }
+
+    pub async fn complete(
+        &self,
+        session_id: &str,
+        key: &str,
+        fingerprint: RequestFingerprint,
+        response: StoredResponse,
+        ttl: Duration,
+    ) {
+        self.entries.write().await.insert(
+            (session_id.to_string(), key.to_string()),
+            IdempotencyEntry::Completed {
+                fingerprint,
+                response,
+                expires_at: Utc::now() + ttl,
+            },
+        );
+    }
+
+    /// Forgets a claimed key so the request can be retried.
+    pub async fn release(&self, session_id: &str, key: &str) {
+        self.entries
+            .write()
+            .await
+            .remove(&(session_id.to_string(), key.to_string()));
+    }
+
+    /// Like `release`, for callers that cannot await, such as a `Drop` impl.
+    pub fn release_detached(&self, session_id: &str, key: &str) {
+        let entry_key = (session_id.to_string(), key.to_string());
+        match self.entries.try_write() {
+            Ok(mut entries) => {
+                entries.remove(&entry_key);
+            }
+            Err(_) => {
+                let entries = self.entries.clone();
+                tokio::spawn(async move {
+                    entries.write().await.remove(&entry_key);
+                });
+            }
+        }
+    }
+
+    pub async fn clear(&self) {
+        self.entries.write().await.clear();
+    }
+
+    /// Drops expired keys and returns how many were removed.
+    pub async fn cleanup_expired(&self) -> usize {
+        let now = Utc::now();
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|_, entry| !entry.is_expired(now));
+        before - entries.len()
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::models::slot::{Slot, SlotStateKind};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Invariant {
+    /// A slot's state moved back, e.g. from reserved to an open auction
+    SlotStateRegressed,
+    /// Balances, escrow, burned fees and retired SOL no longer add up to
+    /// what was issued, so escrow paid out more than it held or lost funds
+    LedgerImbalance,
+    /// An auction was resolved for a winner with no won or included
+    /// transaction for its slot
+    WinnerWithoutTransaction,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InvariantViolation {
+    pub invariant: Invariant,
+    pub slot_number: Option<u64>,
+    pub detail: String,
+}
+
+/// How far a slot has progressed. A slot may move between JIT and AOT
+/// auctions, and back to available once the last bid in its auction is
+/// withdrawn, but never back from a reservation.
+fn progress(kind: SlotStateKind) -> u8 {
+    match kind {
+        SlotStateKind::Available | SlotStateKind::JitAuction | SlotStateKind::AotAuction => 0,
+        SlotStateKind::Reserved => 1,
+        SlotStateKind::Filled | SlotStateKind::Expired => 2,
+    }
+}
+
+/// The state each slot in the window was last seen in, to catch slots that
+/// move backwards between ticks.
+#[derive(Default)]
+pub struct InvariantChecker {
+    slot_states: HashMap<u64, SlotStateKind>,
+}
+
+impl InvariantChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the state of every slot in the window, returning a violation
+    /// for each slot whose state went back since it was last seen. Slots that
+    /// left the window are forgotten.
+    pub fn observe_slots(&mut self, slots: &[Slot]) -> Vec<InvariantViolation> {
+        let mut violations = Vec::new();
+        let mut seen = HashMap::with_capacity(slots.len());
+
+        for slot in slots {
+            let kind = slot.state.kind();
+            if let Some(&previous) = self.slot_states.get(&slot.slot_number) {
+                if progress(kind) < progress(previous) {
+                    violations.push(InvariantViolation {
+                        invariant: Invariant::SlotStateRegressed,
+                        slot_number: Some(slot.slot_number),
+                        detail: format!(
+                            "Slot {} went from {:?} back to {:?}",
+                            slot.slot_number, previous, kind
+                        ),
+                    });
+                }
+            }
+            seen.insert(slot.slot_number, kind);
+        }
+
+        self.slot_states = seen;
+        violations
+    }
+}
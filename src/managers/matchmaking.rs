@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct QueuedPlayer {
+    pub session_id: String,
+    /// Players in the match this session is waiting for
+    pub match_size: usize,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// Players waiting for a competitive match, matched in arrival order with
+/// others who asked for the same match size.
+#[derive(Default)]
+pub struct MatchmakingQueue {
+    waiting: Vec<QueuedPlayer>,
+}
+
+impl MatchmakingQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<&QueuedPlayer> {
+        self.waiting
+            .iter()
+            .find(|player| player.session_id == session_id)
+    }
+
+    /// The session's 1-based place among players waiting for the same match
+    /// size, and how many are waiting for it.
+    pub fn position(&self, session_id: &str) -> Option<(usize, usize)> {
+        let player = self.get(session_id)?;
+        let same_size: Vec<&QueuedPlayer> = self
+            .waiting
+            .iter()
+            .filter(|waiting| waiting.match_size == player.match_size)
+            .collect();
+        let position = same_size
+            .iter()
+            .position(|waiting| waiting.session_id == session_id)?;
+        Some((position + 1, same_size.len()))
+    }
+
+    /// Queues the player. Once enough players wait for the match size, they
+    /// are taken off the queue and returned, oldest first.
+    pub fn join(&mut self, player: QueuedPlayer) -> Option<Vec<QueuedPlayer>> {
+        let match_size = player.match_size;
+        self.waiting.push(player);
+
+        let ready = self
+            .waiting
+            .iter()
+            .filter(|waiting| waiting.match_size == match_size)
+            .count();
+        if ready < match_size {
+            return None;
+        }
+
+        let (matched, waiting) = std::mem::take(&mut self.waiting)
+            .into_iter()
+            .partition(|waiting| waiting.match_size == match_size);
+        self.waiting = waiting;
+        Some(matched)
+    }
+
+    /// Puts players back at the front of the queue, e.g. when their match
+    /// could not be started.
+    pub fn restore(&mut self, players: Vec<QueuedPlayer>) {
+        self.waiting.splice(0..0, players);
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) {
+        for player in &mut self.waiting {
+            if player.session_id == from {
+                player.session_id = to.to_string();
+            }
+        }
+    }
+
+    pub fn leave(&mut self, session_id: &str) -> Option<QueuedPlayer> {
+        let index = self
+            .waiting
+            .iter()
+            .position(|player| player.session_id == session_id)?;
+        Some(self.waiting.remove(index))
+    }
+}
@@ -1,3 +1,19 @@
+pub mod analytics;
 pub mod auction;
+pub mod escrow;
 pub mod game;
+#[cfg(feature = "server")]
+pub mod health;
+#[cfg(feature = "server")]
+pub mod idempotency;
+pub mod invariants;
+pub mod matchmaking;
+#[cfg(feature = "server")]
+pub mod moderation;
+#[cfg(feature = "server")]
+pub mod nonce;
+pub mod restriction;
+#[cfg(feature = "server")]
 pub mod session;
+#[cfg(feature = "server")]
+pub mod transaction;
@@ -0,0 +1,169 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    net::IpAddr,
+    sync::Arc,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::RwLock;
+
+use crate::{
+    MIN_AOT_BID_INCREMENT,
+    config::AbuseConfig,
+    models::{
+        admin::{AbuseFlag, BanRecord},
+        types::Lamports,
+    },
+};
+
+/// Raising your own leading bid by no more than this counts as a self-outbid.
+pub const SELF_OUTBID_MAX_INCREMENT: Lamports = MIN_AOT_BID_INCREMENT;
+
+/// Escalating cooldowns stop doubling at one hour.
+const MAX_ABUSE_COOLDOWN_SECS: u64 = 3600;
+
+/// Bans keyed by session, with the banned wallets and IPs indexed for bid checks,
+/// plus bid-spam flags and the recent self-outbids they are detected from.
+#[derive(Clone)]
+pub struct ModerationManager {
+    bans: Arc<RwLock<HashMap<String, BanRecord>>>,
+    banned_wallets: Arc<RwLock<HashSet<String>>>,
+    banned_ips: Arc<RwLock<HashSet<IpAddr>>>,
+    abuse_config: Arc<RwLock<AbuseConfig>>,
+    self_outbids: Arc<RwLock<HashMap<String, VecDeque<DateTime<Utc>>>>>,
+    abuse_flags: Arc<RwLock<HashMap<String, AbuseFlag>>>,
+}
+
+impl ModerationManager {
+    pub fn new(abuse_config: AbuseConfig) -> Self {
+        Self {
+            bans: Arc::new(RwLock::new(HashMap::new())),
+            banned_wallets: Arc::new(RwLock::new(HashSet::new())),
+            banned_ips: Arc::new(RwLock::new(HashSet::new())),
+            abuse_config: Arc::new(RwLock::new(abuse_config)),
+            self_outbids: Arc::new(RwLock::new(HashMap::new())),
+            abuse_flags: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub async fn apply_config(&self, abuse_config: AbuseConfig) {
+        *self.abuse_config.write().await = abuse_config;
+    }
+
+    pub async fn ban(&self, record: BanRecord) {
+        if let Some(wallet) = &record.wallet {
+            self.banned_wallets.write().await.insert(wallet.clone());
+        }
+        if let Some(ip) = record.ip {
+            self.banned_ips.write().await.insert(ip);
+        }
+
+        self.bans
+            .write()
+            .await
+            .insert(record.session_id.clone(), record);
+    }
+
+    pub async fn is_session_banned(&self, session_id: &str) -> bool {
+        self.bans.read().await.contains_key(session_id)
+    }
+
+    pub async fn is_wallet_banned(&self, wallet: &str) -> bool {
+        self.banned_wallets.read().await.contains(wallet)
+    }
+
+    pub async fn is_ip_banned(&self, ip: &IpAddr) -> bool {
+        self.banned_ips.read().await.contains(ip)
+    }
+
+    pub async fn list_bans(&self) -> Vec<BanRecord> {
+        let mut bans: Vec<BanRecord> = self.bans.read().await.values().cloned().collect();
+        bans.sort_by_key(|ban| ban.banned_at);
+        bans
+    }
+
+    /// Records a bid that raised the session's own leading bid by a minimum increment.
+    /// Returns the new flag when this pushes the session over the per-minute limit.
+    pub async fn record_self_outbid(&self, session_id: &str) -> Option<AbuseFlag> {
+        let config = self.abuse_config.read().await.clone();
+        let now = Utc::now();
+
+        {
+            let mut self_outbids = self.self_outbids.write().await;
+            let recent = self_outbids.entry(session_id.to_string()).or_default();
+
+            recent.push_back(now);
+            while recent
+                .front()
+                .is_some_and(|at| now - *at > Duration::minutes(1))
+            {
+                recent.pop_front();
+            }
+
+            if recent.len() < config.self_outbid_limit_per_minute as usize {
+                return None;
+            }
+            recent.clear();
+        }
+
+        let mut flags = self.abuse_flags.write().await;
+        let strikes = flags.get(session_id).map_or(0, |flag| flag.strikes) + 1;
+        let cooldown_secs = config
+            .cooldown_secs
+            .saturating_mul(1 << (strikes - 1).min(16))
+            .min(MAX_ABUSE_COOLDOWN_SECS);
+
+        let flag = AbuseFlag {
+            session_id: session_id.to_string(),
+            reason: format!(
+                "{} minimum-increment self-outbids within a minute",
+                config.self_outbid_limit_per_minute
+            ),
+            strikes,
+            flagged_at: now,
+            cooldown_until: now + Duration::seconds(cooldown_secs as i64),
+        };
+
+        tracing::warn!(
+            alert = true,
+            strikes,
+            cooldown_secs,
+            "Flagged session {} for bid spam",
+            session_id.chars().take(8).collect::<String>()
+        );
+
+        flags.insert(session_id.to_string(), flag.clone());
+        Some(flag)
+    }
+
+    /// The session's flag while its cooldown is still running.
+    pub async fn active_cooldown(&self, session_id: &str) -> Option<AbuseFlag> {
+        self.abuse_flags
+            .read()
+            .await
+            .get(session_id)
+            .filter(|flag| flag.is_cooling_down())
+            .cloned()
+    }
+
+    /// Keeps a session's bid-spam history and cooldown when its ID changes.
+    pub async fn rename_session(&self, from: &str, to: &str) {
+        let mut self_outbids = self.self_outbids.write().await;
+        if let Some(recent) = self_outbids.remove(from) {
+            self_outbids.insert(to.to_string(), recent);
+        }
+        drop(self_outbids);
+
+        let mut flags = self.abuse_flags.write().await;
+        if let Some(mut flag) = flags.remove(from) {
+            flag.session_id = to.to_string();
+            flags.insert(to.to_string(), flag);
+        }
+    }
+
+    pub async fn list_abuse_flags(&self) -> Vec<AbuseFlag> {
+        let mut flags: Vec<AbuseFlag> = self.abuse_flags.read().await.values().cloned().collect();
+        flags.sort_by_key(|flag| flag.flagged_at);
+        flags
+    }
+}
@@ -0,0 +1,63 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::RwLock;
+
+/// The highest bid nonce each session has used. Nonces only move forward, so
+/// a retried or reordered request carrying an old nonce is refused.
+#[derive(Clone, Default)]
+pub struct NonceManager {
+    last: Arc<RwLock<HashMap<String, u64>>>,
+}
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes `nonce` for the session if it is above every nonce used so far,
+    /// returning the one it replaced. Otherwise returns the last nonce used.
+    pub async fn claim(&self, session_id: &str, nonce: u64) -> Result<Option<u64>, u64> {
+        let mut last = self.last.write().await;
+        match last.get(session_id) {
+            Some(&previous) if nonce <= previous => Err(previous),
+            previous => {
+                let previous = previous.copied();
+                last.insert(session_id.to_string(), nonce);
+                Ok(previous)
+            }
+        }
+    }
+
+    /// Gives back a nonce whose bid was rejected, so it can be sent again.
+    /// A later nonce claimed in the meantime is kept.
+    pub async fn release(&self, session_id: &str, nonce: u64, previous: Option<u64>) {
+        let mut last = self.last.write().await;
+        if last.get(session_id) != Some(&nonce) {
+            return;
+        }
+        match previous {
+            Some(previous) => last.insert(session_id.to_string(), previous),
+            None => last.remove(session_id),
+        };
+    }
+
+    pub async fn last_nonce(&self, session_id: &str) -> Option<u64> {
+        self.last.read().await.get(session_id).copied()
+    }
+
+    /// Carries a session's last nonce over to its new ID, so the sequence
+    /// keeps rising across a token rotation.
+    pub async fn rename(&self, from: &str, to: &str) {
+        let mut last = self.last.write().await;
+        if let Some(nonce) = last.remove(from) {
+            last.insert(to.to_string(), nonce);
+        }
+    }
+
+    pub async fn forget(&self, session_ids: &[String]) {
+        let mut last = self.last.write().await;
+        for session_id in session_ids {
+            last.remove(session_id);
+        }
+    }
+}
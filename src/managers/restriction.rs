@@ -0,0 +1,135 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::error::AppError;
+
+/// Limits who may bid on the auctions of a slot range, e.g. for tournament
+/// finals. A session may bid if it is listed directly or on any team.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct AuctionRestriction {
+    pub id: String,
+    pub from_slot: u64,
+    /// Inclusive
+    pub to_slot: u64,
+    pub label: Option<String>,
+    pub session_ids: BTreeSet<String>,
+    /// Team name to its members' session IDs
+    pub teams: BTreeMap<String, BTreeSet<String>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuctionRestriction {
+    pub fn covers(&self, slot: u64) -> bool {
+        (self.from_slot..=self.to_slot).contains(&slot)
+    }
+
+    pub fn allows(&self, session_id: &str) -> bool {
+        self.session_ids.contains(session_id)
+            || self
+                .teams
+                .values()
+                .any(|members| members.contains(session_id))
+    }
+}
+
+/// What bidders see of a restriction in auction listings; the allowlist
+/// itself stays private.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct RestrictionSummary {
+    pub restriction_id: String,
+    pub label: Option<String>,
+    pub teams: Vec<String>,
+    /// Sessions allowed to bid, directly or through a team
+    pub allowed_sessions: usize,
+}
+
+impl From<&AuctionRestriction> for RestrictionSummary {
+    fn from(restriction: &AuctionRestriction) -> Self {
+        let allowed: BTreeSet<&String> = restriction
+            .session_ids
+            .iter()
+            .chain(restriction.teams.values().flatten())
+            .collect();
+        Self {
+            restriction_id: restriction.id.clone(),
+            label: restriction.label.clone(),
+            teams: restriction.teams.keys().cloned().collect(),
+            allowed_sessions: allowed.len(),
+        }
+    }
+}
+
+/// Allowlists over non-overlapping slot ranges.
+#[derive(Default)]
+pub struct RestrictionManager {
+    restrictions: Vec<AuctionRestriction>,
+}
+
+impl RestrictionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The restriction whose range overlaps `from_slot..=to_slot`, if any.
+    pub fn overlapping(&self, from_slot: u64, to_slot: u64) -> Option<&AuctionRestriction> {
+        self.restrictions.iter().find(|restriction| {
+            restriction.from_slot <= to_slot && from_slot <= restriction.to_slot
+        })
+    }
+
+    /// Adds a restriction, dropping those for slots before `current_slot`.
+    pub fn add(&mut self, restriction: AuctionRestriction, current_slot: u64) {
+        self.restrictions
+            .retain(|existing| existing.to_slot >= current_slot);
+        self.restrictions.push(restriction);
+        self.restrictions
+            .sort_by_key(|restriction| restriction.from_slot);
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<AuctionRestriction> {
+        let index = self
+            .restrictions
+            .iter()
+            .position(|restriction| restriction.id == id)?;
+        Some(self.restrictions.remove(index))
+    }
+
+    pub fn for_slot(&self, slot: u64) -> Option<&AuctionRestriction> {
+        self.restrictions
+            .iter()
+            .find(|restriction| restriction.covers(slot))
+    }
+
+    /// Rejects a bid on `slot` from a session left off the slot's allowlist.
+    pub fn check(&self, session_id: &str, slot: u64) -> Result<(), AppError> {
+        match self.for_slot(slot) {
+            Some(restriction) if !restriction.allows(session_id) => {
+                Err(AppError::AuctionRestricted {
+                    slot,
+                    restriction_id: restriction.id.clone(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Carries a session's place on allowlists over to its new ID.
+    pub fn rename_session(&mut self, from: &str, to: &str) {
+        for restriction in &mut self.restrictions {
+            for members in
+                std::iter::once(&mut restriction.session_ids).chain(restriction.teams.values_mut())
+            {
+                if members.remove(from) {
+                    members.insert(to.to_string());
+                }
+            }
+        }
+    }
+
+    pub fn list(&self) -> &[AuctionRestriction] {
+        &self.restrictions
+    }
+}
@@ -1,24 +1,96 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, net::IpAddr, sync::Arc};
 
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
 use tokio::sync::RwLock;
 
-use crate::models::session::Session;
+use crate::{
+    config::CookieConfig,
+    models::{
+        api_key::{ApiKey, hash_api_key},
+        session::Session,
+        wallet::WalletChallenge,
+    },
+};
+
+type HmacSha256 = Hmac<Sha256>;
 
 #[derive(Clone)]
 pub struct SessionManager {
     sessions: Arc<RwLock<HashMap<String, Session>>>,
+    /// API keys indexed by the hash of their plaintext
+    api_keys: Arc<RwLock<HashMap<String, ApiKey>>>,
+    /// Outstanding Sign-in-with-Solana challenges, one per session
+    wallet_challenges: Arc<RwLock<HashMap<String, WalletChallenge>>>,
+    /// Last IP address each session made a request from
+    client_ips: Arc<RwLock<HashMap<String, IpAddr>>>,
+    /// Session cookie attributes, fixed at startup
+    cookie: Arc<CookieConfig>,
+    /// Signs session tokens with the configured secret or a random key
+    signer: HmacSha256,
 }
 
 impl SessionManager {
-    pub fn new() -> Self {
+    pub fn new(cookie: CookieConfig) -> Self {
+        let key = match &cookie.secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => rand::rng().random::<[u8; 32]>().to_vec(),
+        };
+        let signer = HmacSha256::new_from_slice(&key).expect("HMAC accepts keys of any length");
+
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            api_keys: Arc::new(RwLock::new(HashMap::new())),
+            wallet_challenges: Arc::new(RwLock::new(HashMap::new())),
+            client_ips: Arc::new(RwLock::new(HashMap::new())),
+            cookie: Arc::new(cookie),
+            signer,
         }
     }
 
-    pub async fn create_session(&self) -> Session {
+    pub fn cookie(&self) -> &CookieConfig {
+        &self.cookie
+    }
+
+    /// `session_id` followed by its signature, as stored in the cookie.
+    pub fn session_token(&self, session_id: &str) -> String {
+        format!("{}.{}", session_id, self.signature(session_id))
+    }
+
+    /// The session ID a token was issued for, or `None` if its signature
+    /// doesn't match. Signatures are compared in constant time.
+    pub fn verify_session_token(&self, token: &str) -> Option<String> {
+        let (session_id, signature) = token.rsplit_once('.')?;
+        let expected = self.signature(session_id);
+
+        let matches = signature.len() == expected.len()
+            && signature
+                .bytes()
+                .zip(expected.bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0;
+        matches.then(|| session_id.to_string())
+    }
+
+    fn signature(&self, session_id: &str) -> String {
+        let mut mac = self.signer.clone();
+        mac.update(session_id.as_bytes());
+        format!("{:x}", mac.finalize().into_bytes())
+    }
+
+    pub async fn create_session(
+        &self,
+        user_agent: Option<String>,
+        created_ip: Option<IpAddr>,
+        spectator: bool,
+    ) -> Session {
         let session_id = uuid::Uuid::new_v4().to_string();
-        let session = Session::new(session_id);
+        let session = Session {
+            created_ip,
+            spectator,
+            ..Session::new(session_id, user_agent)
+        };
 
         self.sessions
             .write()
@@ -47,6 +119,14 @@ impl SessionManager {
         self.get_session(session_id).await.is_some()
     }
 
+    pub async fn is_spectator(&self, session_id: &str) -> bool {
+        self.sessions
+            .read()
+            .await
+            .get(session_id)
+            .is_some_and(|session| session.spectator)
+    }
+
     pub async fn cleanup_expired_sessions(&self) -> Vec<String> {
         let mut sessions = self.sessions.write().await;
         let mut removed = Vec::new();
@@ -60,9 +140,187 @@ impl SessionManager {
             }
         });
 
+        drop(sessions);
+        self.remove_session_credentials(&removed).await;
+
         removed
     }
 
+    /// Marks the session as signed in with `wallet`, grouping it with that wallet's
+    /// other sessions.
+    pub async fn bind_wallet(&self, session_id: &str, wallet: &str) {
+        if let Some(session) = self.sessions.write().await.get_mut(session_id) {
+            session.wallet = Some(wallet.to_string());
+        }
+    }
+
+    /// Live sessions signed in with `wallet`, most recently active first.
+    pub async fn wallet_sessions(&self, wallet: &str) -> Vec<Session> {
+        let mut sessions: Vec<Session> = self
+            .sessions
+            .read()
+            .await
+            .values()
+            .filter(|session| session.wallet.as_deref() == Some(wallet) && !session.is_expired())
+            .cloned()
+            .collect();
+
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_active));
+        sessions
+    }
+
+    pub async fn find_by_device(&self, device_id: &str) -> Option<Session> {
+        self.sessions
+            .read()
+            .await
+            .values()
+            .find(|session| session.device_id == device_id && !session.is_expired())
+            .cloned()
+    }
+
+    /// Moves a live session to `new_id` along with its pending wallet challenge
+    /// and last IP; the old ID stops working at once. API keys are moved
+    /// separately by `move_api_keys`, once the session's data has followed.
+    pub async fn rotate_session(&self, session_id: &str, new_id: String) -> Option<Session> {
+        let session = {
+            let mut sessions = self.sessions.write().await;
+            let mut session = sessions
+                .remove(session_id)
+                .filter(|session| !session.is_expired())?;
+            session.id = new_id;
+            session.extend();
+            sessions.insert(session.id.clone(), session.clone());
+            session
+        };
+
+        let mut wallet_challenges = self.wallet_challenges.write().await;
+        if let Some(challenge) = wallet_challenges.remove(session_id) {
+            wallet_challenges.insert(session.id.clone(), challenge);
+        }
+        drop(wallet_challenges);
+
+        let mut client_ips = self.client_ips.write().await;
+        if let Some(ip) = client_ips.remove(session_id) {
+            client_ips.insert(session.id.clone(), ip);
+        }
+
+        Some(session)
+    }
+
+    pub async fn move_api_keys(&self, from: &str, to: &str) {
+        for api_key in self.api_keys.write().await.values_mut() {
+            if api_key.session_id == from {
+                api_key.session_id = to.to_string();
+            }
+        }
+    }
+
+    /// Ends a session immediately, revoking its API keys and pending wallet challenge.
+    pub async fn invalidate_session(&self, session_id: &str) -> bool {
+        let existed = self.sessions.write().await.remove(session_id).is_some();
+        self.remove_session_credentials(&[session_id.to_string()])
+            .await;
+        existed
+    }
+
+    async fn remove_session_credentials(&self, session_ids: &[String]) {
+        if session_ids.is_empty() {
+            return;
+        }
+
+        self.api_keys
+            .write()
+            .await
+            .retain(|_, api_key| !session_ids.contains(&api_key.session_id));
+        self.wallet_challenges
+            .write()
+            .await
+            .retain(|session_id, _| !session_ids.contains(session_id));
+        self.client_ips
+            .write()
+            .await
+            .retain(|session_id, _| !session_ids.contains(session_id));
+    }
+
+    pub async fn record_client_ip(&self, session_id: &str, ip: IpAddr) {
+        self.client_ips
+            .write()
+            .await
+            .insert(session_id.to_string(), ip);
+    }
+
+    pub async fn get_client_ip(&self, session_id: &str) -> Option<IpAddr> {
+        self.client_ips.read().await.get(session_id).copied()
+    }
+
+    /// Issues a new API key for `session_id` and returns its plaintext with the stored key.
+    pub async fn create_api_key(&self, session_id: &str, name: Option<String>) -> (String, ApiKey) {
+        let (plaintext, api_key) = ApiKey::generate(session_id.to_string(), name);
+
+        self.api_keys
+            .write()
+            .await
+            .insert(api_key.key_hash.clone(), api_key.clone());
+        (plaintext, api_key)
+    }
+
+    /// Resolves an API key to its session, recording the use. Keys of expired
+    /// sessions are rejected.
+    pub async fn authenticate_api_key(&self, key: &str) -> Option<String> {
+        let key_hash = hash_api_key(key);
+
+        let session_id = {
+            let mut api_keys = self.api_keys.write().await;
+            let api_key = api_keys.get_mut(&key_hash)?;
+            api_key.record_use();
+            api_key.session_id.clone()
+        };
+
+        if self.validate_session(&session_id).await {
+            Some(session_id)
+        } else {
+            None
+        }
+    }
+
+    /// Issues a sign-in challenge for `public_key`, replacing any earlier one for the session.
+    pub async fn create_wallet_challenge(
+        &self,
+        session_id: &str,
+        public_key: String,
+    ) -> WalletChallenge {
+        let challenge = WalletChallenge::new(public_key);
+
+        self.wallet_challenges
+            .write()
+            .await
+            .insert(session_id.to_string(), challenge.clone());
+        challenge
+    }
+
+    /// Removes and returns the session's challenge, so each one can be answered once.
+    pub async fn take_wallet_challenge(&self, session_id: &str) -> Option<WalletChallenge> {
+        self.wallet_challenges
+            .write()
+            .await
+            .remove(session_id)
+            .filter(|challenge| !challenge.is_expired())
+    }
+
+    pub async fn list_api_keys(&self, session_id: &str) -> Vec<ApiKey> {
+        let mut api_keys: Vec<ApiKey> = self
+            .api_keys
+            .read()
+            .await
+            .values()
+            .filter(|api_key| api_key.session_id == session_id)
+            .cloned()
+            .collect();
+
+        api_keys.sort_by_key(|api_key| api_key.created_at);
+        api_keys
+    }
+
     pub async fn get_session_count(&self) -> usize {
         self.sessions.read().await.len()
     }
@@ -0,0 +1,424 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, RwLock, RwLockWriteGuard},
+};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+
+use crate::{
+    SLOT_SHARD_COUNT,
+    models::{
+        transaction::{Transaction, TransactionStatusKind},
+        types::TransactionType,
+    },
+};
+
+/// Orders transactions by creation time, ties broken by ID.
+type IndexKey = (DateTime<Utc>, String);
+
+/// Narrows a transaction query; unset fields match everything.
+#[derive(Clone, Debug, Default)]
+pub struct TransactionFilter {
+    pub status: Option<TransactionStatusKind>,
+    pub transaction_type: Option<TransactionType>,
+    /// Lowest target slot to include
+    pub slot_min: Option<u64>,
+    /// Highest target slot to include
+    pub slot_max: Option<u64>,
+    /// Only transactions carrying this exact tag
+    pub tag: Option<String>,
+    /// Only transactions whose memo contains this text, ignoring case
+    pub memo: Option<String>,
+}
+
+impl TransactionFilter {
+    /// Matches transactions targeting exactly `slot_number`.
+    pub fn slot(slot_number: u64) -> Self {
+        Self {
+            slot_min: Some(slot_number),
+            slot_max: Some(slot_number),
+            ..Self::default()
+        }
+    }
+
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        let slot = transaction.target_slot();
+        self.status
+            .is_none_or(|status| transaction.status.kind() == status)
+            && self
+                .transaction_type
+                .is_none_or(|transaction_type| transaction.transaction_type() == transaction_type)
+            && self.slot_min.is_none_or(|min| slot >= min)
+            && self.slot_max.is_none_or(|max| slot <= max)
+            && self
+                .tag
+                .as_ref()
+                .is_none_or(|tag| transaction.tags.contains(tag))
+            && self.memo.as_ref().is_none_or(|text| {
+                transaction
+                    .memo
+                    .as_ref()
+                    .is_some_and(|memo| memo.to_lowercase().contains(&text.to_lowercase()))
+            })
+    }
+
+    pub fn matches_all(&self) -> bool {
+        self.status.is_none()
+            && self.transaction_type.is_none()
+            && !self.has_slot_range()
+            && !self.has_labels()
+    }
+
+    /// Tags and memos aren't indexed, so these always need checking.
+    fn has_labels(&self) -> bool {
+        self.tag.is_some() || self.memo.is_some()
+    }
+
+    fn has_slot_range(&self) -> bool {
+        self.slot_min.is_some() || self.slot_max.is_some()
+    }
+
+    /// Index shards that can hold a match: those of the slots in a narrow
+    /// slot range, otherwise all of them.
+    fn shards(&self) -> Vec<usize> {
+        let min = self.slot_min.unwrap_or(0);
+        let max = self.slot_max.unwrap_or(u64::MAX);
+        if min > max {
+            return Vec::new();
+        }
+        match max - min {
+            span if span < SLOT_SHARD_COUNT as u64 => {
+                let mut shards: Vec<usize> = (min..=max).map(shard_index).collect();
+                shards.sort_unstable();
+                shards.dedup();
+                shards
+            }
+            _ => (0..SLOT_SHARD_COUNT).collect(),
+        }
+    }
+}
+
+/// Index entries a query has to scan, newest last.
+enum Candidates<'a> {
+    Indexed(&'a BTreeSet<IndexKey>),
+    /// Entries merged from several slots of the slot index
+    Merged(BTreeSet<&'a IndexKey>),
+}
+
+impl Candidates<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Indexed(keys) => keys.len(),
+            Self::Merged(keys) => keys.len(),
+        }
+    }
+
+    fn newest_first(&self) -> Box<dyn Iterator<Item = &IndexKey> + '_> {
+        match self {
+            Self::Indexed(keys) => Box::new(keys.iter().rev()),
+            Self::Merged(keys) => Box::new(keys.iter().rev().copied()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TransactionIndex {
+    by_created: BTreeSet<IndexKey>,
+    by_status: HashMap<TransactionStatusKind, BTreeSet<IndexKey>>,
+    by_slot: BTreeMap<u64, BTreeSet<IndexKey>>,
+}
+
+impl TransactionIndex {
+    fn add(&mut self, transaction: &Transaction) {
+        let key = index_key(transaction);
+        self.by_status
+            .entry(transaction.status.kind())
+            .or_default()
+            .insert(key.clone());
+        self.by_slot
+            .entry(transaction.target_slot())
+            .or_default()
+            .insert(key.clone());
+        self.by_created.insert(key);
+    }
+
+    fn remove(&mut self, transaction: &Transaction) {
+        let key = index_key(transaction);
+        self.by_created.remove(&key);
+        if let Some(keys) = self.by_status.get_mut(&transaction.status.kind()) {
+            keys.remove(&key);
+        }
+        if let Some(keys) = self.by_slot.get_mut(&transaction.target_slot()) {
+            keys.remove(&key);
+            if keys.is_empty() {
+                self.by_slot.remove(&transaction.target_slot());
+            }
+        }
+    }
+
+    /// The narrowest index covering `filter`, and whether its entries still
+    /// need checking against the filter's other fields.
+    fn keys_for(&self, filter: &TransactionFilter) -> (Option<Candidates<'_>>, bool) {
+        if filter.has_slot_range() {
+            let min = filter.slot_min.unwrap_or(0);
+            let max = filter.slot_max.unwrap_or(u64::MAX);
+            let needs_check =
+                filter.status.is_some() || filter.transaction_type.is_some() || filter.has_labels();
+            if min > max {
+                return (None, false);
+            }
+            if min == max {
+                return (self.by_slot.get(&min).map(Candidates::Indexed), needs_check);
+            }
+
+            let keys: BTreeSet<&IndexKey> = self
+                .by_slot
+                .range(min..=max)
+                .flat_map(|(_, keys)| keys.iter())
+                .collect();
+            return (Some(Candidates::Merged(keys)), needs_check);
+        }
+
+        let needs_check = filter.transaction_type.is_some() || filter.has_labels();
+        match filter.status {
+            Some(status) => (
+                self.by_status.get(&status).map(Candidates::Indexed),
+                needs_check,
+            ),
+            None => (Some(Candidates::Indexed(&self.by_created)), needs_check),
+        }
+    }
+}
+
+fn index_key(transaction: &Transaction) -> IndexKey {
+    (transaction.created_at, transaction.id.clone())
+}
+
+fn shard_index(slot: u64) -> usize {
+    (slot % SLOT_SHARD_COUNT as u64) as usize
+}
+
+/// Every transaction by ID, plus an append-only index of each session's
+/// transaction IDs in submission order. Both maps are sharded, so adding or
+/// updating a transaction never waits on readers paging through another session.
+/// Ordered indexes by creation time, status and target slot turn filtered,
+/// paginated queries into range scans; they are split by target slot like the
+/// slot actors, so writes for different slots take different locks.
+#[derive(Clone)]
+pub struct TransactionStore {
+    transactions: Arc<DashMap<String, Transaction>>,
+    session_index: Arc<DashMap<String, Vec<String>>>,
+    index: Arc<[RwLock<TransactionIndex>]>,
+}
+
+impl Default for TransactionStore {
+    fn default() -> Self {
+        Self {
+            transactions: Arc::default(),
+            session_index: Arc::default(),
+            index: (0..SLOT_SHARD_COUNT).map(|_| RwLock::default()).collect(),
+        }
+    }
+}
+
+impl TransactionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn write_index(&self, slot: u64) -> RwLockWriteGuard<'_, TransactionIndex> {
+        self.index[shard_index(slot)]
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn insert(&self, session_id: String, transaction: Transaction) {
+        let transaction_id = transaction.id.clone();
+        {
+            let mut index = self.write_index(transaction.target_slot());
+            index.add(&transaction);
+            self.transactions
+                .insert(transaction_id.clone(), transaction);
+        }
+        self.session_index
+            .entry(session_id)
+            .or_default()
+            .push(transaction_id);
+    }
+
+    /// Stores a new version of an existing transaction, returning the version it replaced.
+    pub fn update(&self, transaction_id: &str, transaction: Transaction) -> Option<Transaction> {
+        // Held across the swap so concurrent updates re-index in the order they
+        // land. A transaction's target slot never changes, so neither does its shard.
+        let mut index = self.write_index(transaction.target_slot());
+        let previous = self
+            .transactions
+            .insert(transaction_id.to_string(), transaction.clone());
+
+        if let Some(previous) = &previous {
+            index.remove(previous);
+        }
+        index.add(&transaction);
+        previous
+    }
+
+    pub fn get(&self, transaction_id: &str) -> Option<Transaction> {
+        self.transactions
+            .get(transaction_id)
+            .map(|transaction| transaction.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Transactions matching `filter`, newest first, from `offset`, along with
+    /// how many match in total. Only index keys are copied under the index
+    /// locks, and only the page's transactions are cloned.
+    pub fn page(
+        &self,
+        filter: TransactionFilter,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<Transaction>, usize) {
+        let wanted = offset.saturating_add(limit);
+        let mut keys: Vec<IndexKey> = Vec::new();
+        let mut total = 0;
+        let mut needs_check = false;
+
+        for shard in filter.shards() {
+            let index = self.index[shard].read().unwrap_or_else(|e| e.into_inner());
+            let (candidates, check) = index.keys_for(&filter);
+            let Some(candidates) = candidates else {
+                continue;
+            };
+            needs_check = check;
+            // Unchecked candidates all match, so no shard can add more than the
+            // page's newest `wanted`
+            let take = if check { usize::MAX } else { wanted };
+            if !check {
+                total += candidates.len();
+            }
+            keys.extend(candidates.newest_first().take(take).cloned());
+        }
+        keys.sort_unstable_by(|a, b| b.cmp(a));
+
+        if !needs_check {
+            let page = keys
+                .iter()
+                .skip(offset)
+                .take(limit)
+                .filter_map(|(_, id)| self.get(id))
+                .collect();
+            return (page, total);
+        }
+
+        let mut page_ids = Vec::new();
+        for (_, id) in &keys {
+            let matches = self
+                .transactions
+                .get(id)
+                .is_some_and(|transaction| filter.matches(&transaction));
+            if matches {
+                if total >= offset && total < wanted {
+                    page_ids.push(id);
+                }
+                total += 1;
+            }
+        }
+        (
+            page_ids.into_iter().filter_map(|id| self.get(id)).collect(),
+            total,
+        )
+    }
+
+    /// Every transaction matching `filter`, newest first.
+    pub fn filtered(&self, filter: TransactionFilter) -> Vec<Transaction> {
+        self.page(filter, 0, usize::MAX).0
+    }
+
+    /// The session's transactions from `offset`, oldest first. Only the page's
+    /// IDs are copied out of the index.
+    pub fn session_page(&self, session_id: &str, offset: usize, limit: usize) -> Vec<Transaction> {
+        let page: Vec<String> = match self.session_index.get(session_id) {
+            Some(ids) => ids.iter().skip(offset).take(limit).cloned().collect(),
+            None => return Vec::new(),
+        };
+
+        page.iter().filter_map(|id| self.get(id)).collect()
+    }
+
+    pub fn session_transactions(&self, session_id: &str) -> Vec<Transaction> {
+        self.session_page(session_id, 0, usize::MAX)
+    }
+
+    pub fn session_count(&self, session_id: &str) -> usize {
+        self.session_index
+            .get(session_id)
+            .map(|ids| ids.len())
+            .unwrap_or(0)
+    }
+
+    /// Moves `from`'s transaction history to the end of `to`'s.
+    pub fn move_session(&self, from: &str, to: &str) {
+        if let Some((_, ids)) = self.session_index.remove(from) {
+            self.session_index
+                .entry(to.to_string())
+                .or_default()
+                .extend(ids);
+        }
+    }
+
+    /// Moves a session's transactions to a new session ID, as their sender too.
+    pub fn rename_session(&self, from: &str, to: &str) {
+        let Some((_, ids)) = self.session_index.remove(from) else {
+            return;
+        };
+        for id in &ids {
+            if let Some(mut transaction) = self.transactions.get_mut(id) {
+                if transaction.sender == from {
+                    transaction.sender = to.to_string();
+                }
+            }
+        }
+        self.session_index
+            .entry(to.to_string())
+            .or_default()
+            .extend(ids);
+    }
+
+    /// Detaches a session's transactions from it: they stay in the store, and
+    /// in market totals, but their sender becomes `alias` and the session's
+    /// history is forgotten. Returns how many were anonymized.
+    pub fn anonymize_session(&self, session_id: &str, alias: &str) -> usize {
+        let Some((_, ids)) = self.session_index.remove(session_id) else {
+            return 0;
+        };
+        for id in &ids {
+            if let Some(mut transaction) = self.transactions.get_mut(id) {
+                if transaction.sender == session_id {
+                    transaction.sender = alias.to_string();
+                }
+            }
+        }
+        ids.len()
+    }
+
+    pub fn clear(&self) {
+        let mut shards: Vec<_> = self
+            .index
+            .iter()
+            .map(|index| index.write().unwrap_or_else(|e| e.into_inner()))
+            .collect();
+        for index in &mut shards {
+            **index = TransactionIndex::default();
+        }
+        self.transactions.clear();
+        self.session_index.clear();
+    }
+}
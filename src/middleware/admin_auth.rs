@@ -0,0 +1,104 @@
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+use crate::{app::api::AppContext, models::admin::AdminRole, models::responses::ApiResponse};
+
+pub const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+pub async fn require_operator(
+    State(context): State<AppContext>,
+    req: Request,
+    next: Next,
+) -> Response {
+    authorize(context, req, next, AdminRole::Operator).await
+}
+
+pub async fn require_admin(
+    State(context): State<AppContext>,
+    req: Request,
+    next: Next,
+) -> Response {
+    authorize(context, req, next, AdminRole::Admin).await
+}
+
+/// Resolves the caller's role from the `x-admin-token` header and rejects the
+/// request unless it is at least `required`. The granted role is added as a
+/// request extension for handlers to log.
+async fn authorize(
+    context: AppContext,
+    mut req: Request,
+    next: Next,
+    required: AdminRole,
+) -> Response {
+    let config = context.config.current();
+    let admin = &config.admin;
+
+    if admin.admin_token.is_none() && admin.operator_token.is_none() {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::failure("Admin API is disabled", 403)),
+        )
+            .into_response();
+    }
+
+    let Some(token) = req
+        .headers()
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|h| h.to_str().ok())
+    else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ApiResponse::failure("Admin token is missing", 401)),
+        )
+            .into_response();
+    };
+
+    let role = if token_matches(token, admin.admin_token.as_deref()) {
+        AdminRole::Admin
+    } else if token_matches(token, admin.operator_token.as_deref()) {
+        AdminRole::Operator
+    } else {
+        tracing::warn!("Rejected admin request with an invalid token");
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ApiResponse::failure("Admin token is invalid", 401)),
+        )
+            .into_response();
+    };
+
+    if role < required {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::failure(
+                format!("This action requires the {:?} role", required),
+                403,
+            )),
+        )
+            .into_response();
+    }
+
+    req.extensions_mut().insert(role);
+    next.run(req).await
+}
+
+/// Compares token digests in constant time so response timing doesn't leak the token.
+fn token_matches(presented: &str, expected: Option<&str>) -> bool {
+    let Some(expected) = expected else {
+        return false;
+    };
+
+    let presented = Sha256::digest(presented.as_bytes());
+    let expected = Sha256::digest(expected.as_bytes());
+
+    presented
+        .iter()
+        .zip(expected.iter())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
@@ -1 +1,4 @@
+pub mod admin_auth;
 pub mod rate_limiter;
+pub mod request_id;
+pub mod request_log;
@@ -1,12 +1,20 @@
 use axum::{
+    Json,
     extract::ConnectInfo,
-    http::{Request, StatusCode},
+    http::{HeaderMap, HeaderValue, Request, StatusCode, header::RETRY_AFTER},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use std::net::SocketAddr;
 
-use crate::utils::rate_limiter::RateLimiter;
+use crate::{
+    models::responses::ApiResponse,
+    utils::rate_limiter::{RateLimitStatus, RateLimiter},
+};
+
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "x-ratelimit-limit";
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "x-ratelimit-remaining";
+pub const RATE_LIMIT_RESET_HEADER: &str = "x-ratelimit-reset";
 
 pub async fn rate_limit_middleware(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -19,10 +27,40 @@ pub async fn rate_limit_middleware(
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let client_key = rate_limiter.get_client_key(&addr);
+    let class = RateLimiter::classify(req.method(), req.uri().path());
+    let status = rate_limiter.check_rate_limit(class, &client_key);
+
+    // Round up so clients never retry before the window has actually reset
+    let reset_secs =
+        status.reset_after.as_secs() + u64::from(status.reset_after.subsec_nanos() > 0);
 
-    if !rate_limiter.check_rate_limit(&client_key) {
-        return Err(StatusCode::TOO_MANY_REQUESTS);
-    }
+    let mut response = if status.allowed {
+        next.run(req).await
+    } else {
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ApiResponse::failure(
+                format!("Rate limit exceeded. Retry in {} seconds", reset_secs),
+                429,
+            )),
+        )
+            .into_response();
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from(reset_secs));
+        response
+    };
+
+    insert_rate_limit_headers(response.headers_mut(), &status, reset_secs);
+
+    Ok(response)
+}
 
-    Ok(next.run(req).await)
+fn insert_rate_limit_headers(headers: &mut HeaderMap, status: &RateLimitStatus, reset_secs: u64) {
+    headers.insert(RATE_LIMIT_LIMIT_HEADER, HeaderValue::from(status.limit));
+    headers.insert(
+        RATE_LIMIT_REMAINING_HEADER,
+        HeaderValue::from(status.remaining),
+    );
+    headers.insert(RATE_LIMIT_RESET_HEADER, HeaderValue::from(reset_secs));
 }
@@ -0,0 +1,34 @@
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::utils::request_id::{REQUEST_ID_HEADER, is_valid_request_id, scope_request_id};
+
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+/// Honors an incoming `x-request-id` or generates one, exposes it to handlers and
+/// echoes it back on the response.
+pub async fn request_id_middleware(mut req: Request<axum::body::Body>, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let mut response = scope_request_id(request_id.clone(), next.run(req)).await;
+
+    if let Ok(header_value) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, header_value);
+    }
+
+    response
+}
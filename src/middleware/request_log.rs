@@ -0,0 +1,62 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, State},
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use tracing::{Instrument, info_span};
+
+use crate::{
+    managers::session::SessionManager, middleware::request_id::RequestId,
+    services::session::session_token_from_cookie,
+};
+
+/// Wraps each request in a span carrying a request ID and truncated session ID,
+/// and emits one structured completion record with route, status and latency.
+pub async fn request_log_middleware(
+    State(sessions): State<SessionManager>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    let started_at = Instant::now();
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(|request_id| request_id.0.clone())
+        .unwrap_or_default();
+
+    let session_id = session_token_from_cookie(req.headers(), &sessions.cookie().name)
+        .and_then(|token| sessions.verify_session_token(&token))
+        .map(|sid| sid.chars().take(8).collect::<String>())
+        .unwrap_or_default();
+
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+
+    let method = req.method().to_string();
+
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        session_id = %session_id,
+        method = %method,
+        route = %route,
+    );
+
+    let response = next.run(req).instrument(span.clone()).await;
+
+    span.in_scope(|| {
+        tracing::info!(
+            status = response.status().as_u16(),
+            latency_ms = started_at.elapsed().as_secs_f64() * 1000.0,
+            "request completed"
+        );
+    });
+
+    response
+}
@@ -0,0 +1,45 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Privilege level of an admin API caller. Admins can do everything operators can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRole {
+    Operator,
+    Admin,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct BanRecord {
+    pub session_id: String,
+    pub wallet: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub ip: Option<IpAddr>,
+    pub reason: Option<String>,
+    pub banned_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct AbuseFlag {
+    pub session_id: String,
+    pub reason: String,
+    /// Number of times the session has been flagged
+    pub strikes: u32,
+    pub flagged_at: DateTime<Utc>,
+    pub cooldown_until: DateTime<Utc>,
+}
+
+impl AbuseFlag {
+    pub fn is_cooling_down(&self) -> bool {
+        Utc::now() < self.cooldown_until
+    }
+
+    /// Whole seconds left in the cooldown, rounded up.
+    pub fn retry_after_secs(&self) -> u64 {
+        let remaining_ms = (self.cooldown_until - Utc::now()).num_milliseconds().max(0) as u64;
+        remaining_ms.div_ceil(1000)
+    }
+}
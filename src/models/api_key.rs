@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+const API_KEY_PREFIX: &str = "rk_";
+
+/// A bearer credential bound to a session. Only the hash of the key is stored;
+/// the plaintext is returned once, when the key is issued.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub session_id: String,
+    pub name: Option<String>,
+    /// First characters of the key, so users can tell their keys apart
+    pub prefix: String,
+    #[serde(skip_serializing)]
+    #[schema(ignore)]
+    pub key_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub usage_count: u64,
+}
+
+impl ApiKey {
+    /// Generates a new key for `session_id`, returning the plaintext alongside it.
+    pub fn generate(session_id: String, name: Option<String>) -> (String, Self) {
+        let secret: [u8; 32] = rand::rng().random();
+        let plaintext = format!(
+            "{}{}",
+            API_KEY_PREFIX,
+            secret
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<String>()
+        );
+
+        let api_key = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id,
+            name,
+            prefix: plaintext.chars().take(API_KEY_PREFIX.len() + 8).collect(),
+            key_hash: hash_api_key(&plaintext),
+            created_at: Utc::now(),
+            last_used_at: None,
+            usage_count: 0,
+        };
+
+        (plaintext, api_key)
+    }
+
+    pub fn record_use(&mut self) {
+        self.last_used_at = Some(Utc::now());
+        self.usage_count += 1;
+    }
+}
+
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
@@ -1,39 +1,62 @@
-use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-use crate::{JIT_PREMIUM_MULTIPLIER, MIN_AOT_BID_INCREMENT, models::types::TransactionType};
+use crate::{
+    JIT_PREMIUM_MULTIPLIER, MIN_AOT_BID_INCREMENT,
+    error::AppError,
+    models::types::{Lamports, TransactionType},
+};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Bid {
     pub bidder_id: String,
-    pub amount: f64,
+    pub amount: Lamports,
     pub slot_number: u64,
     pub timestamp: DateTime<Utc>,
     pub bid_type: TransactionType,
 }
 
+/// Outcome of an auction after it resolved and its losers were settled.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct ResolvedAuction {
+    pub slot_number: u64,
+    pub auction_type: TransactionType,
+    pub winner: String,
+    /// Winning bid, which is what the winner paid
+    pub clearing_price: Lamports,
+    pub bid_count: usize,
+    /// Slots between the winning bid and the slot it won
+    pub lead_slots: u64,
+    /// Bidders whose escrow was refunded, each listed once
+    pub losers: Vec<String>,
+    pub refunded: Lamports,
+    pub resolved_at: DateTime<Utc>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct JitAuction {
     pub slot_number: u64,
-    pub min_bid: f64,
-    pub current_highest_bidder: Option<(String, f64)>,
+    pub min_bid: Lamports,
+    pub current_highest_bidder: Option<(String, Lamports)>,
     pub created_at: DateTime<Utc>,
 }
 
 impl JitAuction {
-    pub fn new(slot_number: u64, base_fee: f64) -> Self {
+    pub fn new(slot_number: u64, base_fee: Lamports) -> Self {
         Self {
             slot_number,
-            min_bid: base_fee * JIT_PREMIUM_MULTIPLIER,
+            min_bid: base_fee.scale(JIT_PREMIUM_MULTIPLIER),
             current_highest_bidder: None,
             created_at: Utc::now(),
         }
     }
 
-    pub fn submit_bid(&mut self, bidder_id: String, amount: f64) -> Result<()> {
+    pub fn submit_bid(&mut self, bidder_id: String, amount: Lamports) -> Result<(), AppError> {
         if amount < self.min_bid {
-            return Err(anyhow!("Bid too low for JIT auction",));
+            return Err(AppError::BidTooLow {
+                minimum: self.min_bid,
+            });
         }
 
         // Check against current highest bidder
@@ -47,16 +70,23 @@ impl JitAuction {
                     self.current_highest_bidder = Some((bidder_id, amount));
                     Ok(())
                 } else {
-                    Err(anyhow!(
-                        "Bid must exceed current highest bid of {:.4} SOL",
-                        current_amount,
-                    ))
+                    Err(AppError::BidNotHigher {
+                        current: *current_amount,
+                    })
                 }
             }
         }
     }
 
-    pub fn resolve(&self) -> Option<(String, f64)> {
+    /// Smallest bid that would take the lead.
+    pub fn min_next_bid(&self) -> Lamports {
+        match &self.current_highest_bidder {
+            Some((_, amount)) => (*amount + Lamports(1)).max(self.min_bid),
+            None => self.min_bid,
+        }
+    }
+
+    pub fn resolve(&self) -> Option<(String, Lamports)> {
         self.current_highest_bidder.clone()
     }
 }
@@ -64,35 +94,64 @@ impl JitAuction {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AotAuction {
     pub slot_number: u64,
-    pub min_bid: f64,
-    pub bids: Vec<(String, f64, DateTime<Utc>)>,
+    pub min_bid: Lamports,
+    pub bids: Vec<(String, Lamports, DateTime<Utc>)>,
     pub ends_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
+    /// Seconds-left thresholds not announced yet, largest first
+    #[serde(default)]
+    pub closing_warnings: Vec<u64>,
 }
 
 impl AotAuction {
-    pub fn new(slot_number: u64, base_fee: f64, duration_seconds: i64) -> Self {
+    /// Opens bidding for `duration_seconds`, or until the slot is expected to
+    /// arrive if that is sooner, since the auction resolves when its slot does.
+    pub fn new(
+        slot_number: u64,
+        base_fee: Lamports,
+        duration_seconds: i64,
+        slot_arrives_at: Option<DateTime<Utc>>,
+        closing_warnings: &[u64],
+    ) -> Self {
+        let created_at = Utc::now();
+        let mut ends_at = created_at + chrono::Duration::seconds(duration_seconds);
+        if let Some(slot_arrives_at) = slot_arrives_at {
+            ends_at = ends_at.min(slot_arrives_at);
+        }
+        let open_ms = (ends_at - created_at).num_milliseconds();
+
+        // Thresholds the auction starts inside of would fire on the first tick
+        let mut closing_warnings: Vec<u64> = closing_warnings
+            .iter()
+            .copied()
+            .filter(|&seconds| (seconds as i64) * 1000 < open_ms)
+            .collect();
+        closing_warnings.sort_unstable_by(|a, b| b.cmp(a));
+        closing_warnings.dedup();
+
         Self {
             slot_number,
             min_bid: base_fee,
             bids: Vec::new(),
-            ends_at: Utc::now() + chrono::Duration::seconds(duration_seconds),
-            created_at: Utc::now(),
+            ends_at,
+            created_at,
+            closing_warnings,
         }
     }
 
-    pub fn submit_bid(&mut self, bidder_id: String, amount: f64) -> Result<()> {
+    pub fn submit_bid(&mut self, bidder_id: String, amount: Lamports) -> Result<(), AppError> {
         if self.has_ended() {
-            return Err(anyhow!(
-                "AOT auction for slot {} has ended. Closed at: {}",
-                self.slot_number,
-                self.ends_at.format("%H:%M:%S UTC")
-            ));
+            return Err(AppError::AuctionClosed {
+                slot: self.slot_number,
+                closed_at: self.ends_at,
+            });
         }
 
         let min_required = self.get_min_next_bid();
         if amount < min_required {
-            return Err(anyhow!("Bid too low for AOT auction",));
+            return Err(AppError::BidTooLow {
+                minimum: min_required,
+            });
         }
 
         // Note: users can bid multiple times
@@ -100,28 +159,53 @@ impl AotAuction {
         Ok(())
     }
 
-    pub fn get_min_next_bid(&self) -> f64 {
+    pub fn get_min_next_bid(&self) -> Lamports {
         match self.get_highest_bid() {
-            Some((_, amount, _)) => amount + MIN_AOT_BID_INCREMENT,
+            Some((_, amount, _)) => *amount + MIN_AOT_BID_INCREMENT,
             None => self.min_bid,
         }
     }
 
-    pub fn get_highest_bid(&self) -> Option<&(String, f64, DateTime<Utc>)> {
-        self.bids
-            .iter()
-            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+    pub fn get_highest_bid(&self) -> Option<&(String, Lamports, DateTime<Utc>)> {
+        self.bids.iter().max_by_key(|(_, amount, _)| *amount)
     }
 
     pub fn has_ended(&self) -> bool {
         Utc::now() > self.ends_at
     }
 
+    /// Bids from highest to lowest, earlier bids first among equal amounts.
+    pub fn bid_ladder(&self) -> Vec<&(String, Lamports, DateTime<Utc>)> {
+        let mut ladder: Vec<_> = self.bids.iter().collect();
+        ladder.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.cmp(&b.2)));
+        ladder
+    }
+
+    /// Seconds left, rounded up, once the auction has passed another of its
+    /// warning thresholds. Thresholds passed between two calls are announced once.
+    pub fn take_closing_warning(&mut self) -> Option<u64> {
+        let remaining_ms = (self.ends_at - Utc::now()).num_milliseconds();
+        if remaining_ms <= 0 {
+            return None;
+        }
+
+        let passed = self
+            .closing_warnings
+            .iter()
+            .take_while(|&&seconds| seconds as i64 * 1000 >= remaining_ms)
+            .count();
+        if passed == 0 {
+            return None;
+        }
+        self.closing_warnings.drain(..passed);
+        Some((remaining_ms as u64).div_ceil(1000))
+    }
+
     pub fn should_resolve(&self, current_slot: u64) -> bool {
         self.has_ended() || self.slot_number <= current_slot
     }
 
-    pub fn resolve(&self) -> Option<(String, f64)> {
+    pub fn resolve(&self) -> Option<(String, Lamports)> {
         self.get_highest_bid()
             .map(|(bidder, amount, _)| (bidder.clone(), *amount))
     }
@@ -1,57 +1,125 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast::{Receiver, Sender, channel};
+use utoipa::ToSchema;
 
-use crate::models::{slot::Slot, transaction::Transaction};
+use crate::models::{
+    metrics::{Achievement, Leaderboard},
+    slot::Slot,
+    transaction::Transaction,
+    types::{Lamports, TransactionType},
+};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg(feature = "server")]
+mod broadcast;
+#[cfg(feature = "server")]
+mod history;
+#[cfg(feature = "server")]
+mod version;
+#[cfg(feature = "server")]
+pub use broadcast::*;
+#[cfg(feature = "server")]
+pub use history::*;
+#[cfg(feature = "server")]
+pub use version::*;
+
+/// Version of the event format. Streams send it as each event's
+/// `schema_version` and down-convert to older versions on request.
+pub const EVENT_SCHEMA_VERSION: u32 = 2;
+/// Oldest event format a stream can be down-converted to
+pub const MIN_EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// A message on an event stream. Each SSE `data` line is one event, tagged
+/// by its `type` and carrying the `schema_version` it is written in.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type")]
 pub enum AppEvent {
     SlotAdvanced {
         current_slot: u64,
     },
 
-    SlotsUpdated {
+    /// Visible slots that changed since the last tick, plus the one that just
+    /// came into view. When `replaces_window` is set, `slots` is the whole
+    /// visible window and replaces the client's copy.
+    SlotsChanged {
         slots: Vec<Slot>,
+        replaces_window: bool,
     },
 
     JitAuctionStarted {
         slot_number: u64,
-        min_bid: f64,
+        min_bid: Lamports,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
     },
 
     AotAuctionStarted {
         slot_number: u64,
-        min_bid: f64,
+        min_bid: Lamports,
         ends_at: DateTime<Utc>,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+    },
+
+    /// An AOT auction has `seconds_left` until it closes. Sent as it passes
+    /// each of the configured warning thresholds, so clients need not count
+    /// down to `ends_at` against their own clock.
+    AotAuctionClosing {
+        slot_number: u64,
+        seconds_left: u64,
     },
 
     JitBidSubmitted {
         slot_number: u64,
         bidder: String,
-        amount: f64,
+        amount: Lamports,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
     },
 
     AotBidSubmitted {
         slot_number: u64,
         bidder: String,
-        amount: f64,
+        amount: Lamports,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
+    },
+
+    /// A bidder withdrew a bid before its auction resolved and was refunded.
+    BidCancelled {
+        slot_number: u64,
+        auction_type: TransactionType,
+        bidder: String,
+        amount: Lamports,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        request_id: Option<String>,
     },
 
     JitAuctionResolved {
         slot_number: u64,
         winner: String,
-        winning_bid: f64,
+        winning_bid: Lamports,
     },
 
     AotAuctionResolved {
         slot_number: u64,
         winner: String,
-        winning_bid: f64,
+        winning_bid: Lamports,
     },
 
     TransactionUpdated {
-        transaction: Transaction,
+        transaction: Box<Transaction>,
+    },
+
+    /// Balances, escrow and burned fees no longer add up to the SOL issued.
+    LedgerImbalance {
+        issued: Lamports,
+        accounted: Lamports,
+        discrepancy_sol: f64,
     },
 
     MarketplaceStats {
@@ -60,24 +128,48 @@ pub enum AppEvent {
         active_aot_auctions: usize,
         total_transactions: usize,
     },
-}
 
-#[derive(Clone)]
-pub struct EventBroadcaster {
-    sender: Sender<AppEvent>,
-}
+    /// A matchmade room is ready for these players, who have been moved into
+    /// it. Sent on the stream of each room they were in and on the new room's.
+    MatchReady {
+        room_id: String,
+        players: Vec<String>,
+        starts_at: DateTime<Utc>,
+    },
+
+    /// The room was closed; its streams end shortly after.
+    RoomClosed {
+        room_id: String,
+        standings: Leaderboard,
+    },
+
+    /// The player deleted their data; clients should drop anything they hold
+    /// for them. Their past bids and transactions now carry an anonymous ID.
+    PlayerDeleted {
+        player: String,
+    },
 
-impl EventBroadcaster {
-    pub fn new() -> Self {
-        let (sender, _) = channel(10000);
-        Self { sender }
-    }
+    /// Another bidder passed the session's leading bid, which can still be
+    /// raised before the auction closes. Only sent on `/events/me`.
+    Outbid {
+        slot_number: u64,
+        auction_type: TransactionType,
+        your_bid: Lamports,
+        new_bid: Lamports,
+    },
 
-    pub fn broadcast(&self, event: AppEvent) {
-        let _ = self.sender.send(event);
-    }
+    /// The session unlocked an achievement. Only sent on `/events/me`.
+    AchievementUnlocked {
+        achievement: Achievement,
+    },
 
-    pub fn subscribe(&self) -> Receiver<AppEvent> {
-        self.sender.subscribe()
-    }
+    /// Sent to a subscriber that fell behind, in place of the events it missed.
+    StreamResync {
+        missed_events: u64,
+        current_slot: u64,
+        slots: Vec<Slot>,
+        active_jit_auctions: usize,
+        active_aot_auctions: usize,
+        total_transactions: usize,
+    },
 }
@@ -0,0 +1,752 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::broadcast::{Receiver, Sender, channel, error::RecvError};
+use utoipa::ToSchema;
+
+use super::{AppEvent, EVENT_SCHEMA_VERSION, EventHistory, RecordedEvent, versioned_payload};
+use crate::models::types::TransactionType;
+
+const EVENT_CHANNEL_CAPACITY: usize = 10000;
+const PRIVATE_CHANNEL_CAPACITY: usize = 256;
+
+/// Event groups buffered separately for replay, so a burst of bids can't
+/// evict the slot deltas a reconnecting subscriber needs, or the reverse
+#[derive(Clone, Copy)]
+enum Topic {
+    Slots,
+    Stats,
+    Auctions,
+    Room,
+}
+
+impl Topic {
+    const ALL: [Topic; 4] = [Topic::Slots, Topic::Stats, Topic::Auctions, Topic::Room];
+
+    fn of(event: &AppEvent) -> Self {
+        match event {
+            AppEvent::SlotAdvanced { .. } | AppEvent::SlotsChanged { .. } => Topic::Slots,
+            AppEvent::MarketplaceStats { .. } => Topic::Stats,
+            AppEvent::JitAuctionStarted { .. }
+            | AppEvent::AotAuctionStarted { .. }
+            | AppEvent::AotAuctionClosing { .. }
+            | AppEvent::JitBidSubmitted { .. }
+            | AppEvent::AotBidSubmitted { .. }
+            | AppEvent::BidCancelled { .. }
+            | AppEvent::JitAuctionResolved { .. }
+            | AppEvent::AotAuctionResolved { .. }
+            | AppEvent::TransactionUpdated { .. }
+            | AppEvent::Outbid { .. } => Topic::Auctions,
+            AppEvent::LedgerImbalance { .. }
+            | AppEvent::MatchReady { .. }
+            | AppEvent::RoomClosed { .. }
+            | AppEvent::PlayerDeleted { .. }
+            | AppEvent::AchievementUnlocked { .. }
+            | AppEvent::StreamResync { .. } => Topic::Room,
+        }
+    }
+
+    /// Events kept for replay; about a minute of slot ticks at 400 ms
+    fn capacity(self) -> usize {
+        match self {
+            Topic::Slots => 256,
+            // Only the latest stats matter
+            Topic::Stats => 1,
+            Topic::Auctions => 1024,
+            Topic::Room => 64,
+        }
+    }
+
+    /// Whether a `StreamResync` snapshot stands in for this topic's events
+    fn in_snapshot(self) -> bool {
+        matches!(self, Topic::Slots | Topic::Stats)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct SubscriberMetrics {
+    pub id: u64,
+    pub connected_at: DateTime<Utc>,
+    pub delivered_events: u64,
+    pub lagged_events: u64,
+    pub resyncs: u64,
+    /// Events merged into a later one of the same kind before being sent
+    pub coalesced_events: u64,
+    pub queued: usize,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct BusMetrics {
+    pub name: String,
+    pub published_events: u64,
+    /// Events dropped because the bus could not keep up or was disconnected
+    pub dropped_events: u64,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct BroadcastMetrics {
+    pub capacity: usize,
+    pub queued: usize,
+    pub total_lagged_events: u64,
+    pub total_resyncs: u64,
+    pub subscribers: Vec<SubscriberMetrics>,
+    /// Events kept for subscribers resuming with a token
+    pub replay_buffered: usize,
+    /// Events kept for `/events/replay`
+    pub history_events: usize,
+    /// External brokers the events are also published to
+    pub buses: Vec<BusMetrics>,
+}
+
+/// An event as it travels the channel: serialized once when broadcast, and
+/// shared by every subscriber that sends it on unchanged.
+#[derive(Clone, Debug)]
+pub struct SharedEvent {
+    pub event: Arc<AppEvent>,
+    pub payload: Arc<str>,
+    /// Position in the room's event sequence, or 0 for events a subscriber
+    /// made itself (resyncs, merged deltas)
+    pub seq: u64,
+}
+
+impl SharedEvent {
+    pub fn new(event: AppEvent) -> Self {
+        let payload = versioned_payload(&event, EVENT_SCHEMA_VERSION);
+        Self {
+            event: Arc::new(event),
+            payload: payload.into(),
+            seq: 0,
+        }
+    }
+}
+
+/// What a subscriber reconnecting with a resumption token is sent before
+/// live events.
+pub struct Resumption {
+    /// Events broadcast since the token, oldest first
+    pub events: Vec<SharedEvent>,
+    /// Set, to the number of events missed (0 if unknown), when some are no
+    /// longer buffered or the token is not from this stream, so a
+    /// `StreamResync` must come first
+    pub resync: Option<u64>,
+    /// Sequence number of the last event broadcast so far
+    pub last_seq: u64,
+}
+
+/// Per-session channels behind `/events/me`, each carrying only the events
+/// that concern its session.
+#[derive(Default)]
+struct PrivateChannels {
+    senders: DashMap<String, Sender<SharedEvent>>,
+    /// Sessions that bid in each unresolved auction
+    participants: DashMap<(TransactionType, u64), HashSet<String>>,
+}
+
+impl PrivateChannels {
+    /// The sessions `event` concerns: a transaction's sender, and everyone
+    /// bidding in the auction a bid, cancellation, closing warning or
+    /// resolution belongs to.
+    fn recipients(&self, event: &AppEvent) -> Vec<String> {
+        match event {
+            AppEvent::JitBidSubmitted {
+                slot_number,
+                bidder,
+                ..
+            } => self.join((TransactionType::Jit, *slot_number), bidder),
+            AppEvent::AotBidSubmitted {
+                slot_number,
+                bidder,
+                ..
+            } => self.join((TransactionType::Aot, *slot_number), bidder),
+            AppEvent::BidCancelled {
+                slot_number,
+                auction_type,
+                bidder,
+                ..
+            } => self.join((*auction_type, *slot_number), bidder),
+            AppEvent::JitAuctionResolved {
+                slot_number,
+                winner,
+                ..
+            } => self.resolve((TransactionType::Jit, *slot_number), winner),
+            AppEvent::AotAuctionResolved {
+                slot_number,
+                winner,
+                ..
+            } => self.resolve((TransactionType::Aot, *slot_number), winner),
+            AppEvent::AotAuctionClosing { slot_number, .. } => self
+                .participants
+                .get(&(TransactionType::Aot, *slot_number))
+                .map(|participants| participants.iter().cloned().collect())
+                .unwrap_or_default(),
+            AppEvent::TransactionUpdated { transaction } => vec![transaction.sender.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    fn join(&self, auction: (TransactionType, u64), bidder: &str) -> Vec<String> {
+        let mut participants = self.participants.entry(auction).or_default();
+        participants.insert(bidder.to_string());
+        participants.iter().cloned().collect()
+    }
+
+    fn resolve(&self, auction: (TransactionType, u64), winner: &str) -> Vec<String> {
+        let mut participants = self
+            .participants
+            .remove(&auction)
+            .map(|(_, participants)| participants)
+            .unwrap_or_default();
+        participants.insert(winner.to_string());
+        participants.into_iter().collect()
+    }
+
+    fn forget(&self, auction: (TransactionType, u64)) {
+        self.participants.remove(&auction);
+    }
+
+    fn send(&self, session_id: &str, event: &SharedEvent) {
+        if let Some(sender) = self.senders.get(session_id) {
+            let _ = sender.send(event.clone());
+        }
+    }
+}
+
+/// The most recent events of each topic, for subscribers resuming a stream.
+struct ReplayBuffer {
+    last_seq: u64,
+    topics: [VecDeque<SharedEvent>; 4],
+    /// Highest sequence number evicted from each topic
+    evicted: [u64; 4],
+}
+
+impl ReplayBuffer {
+    fn new() -> Self {
+        Self {
+            last_seq: 0,
+            topics: Default::default(),
+            evicted: [0; 4],
+        }
+    }
+
+    fn record(&mut self, mut event: SharedEvent) -> SharedEvent {
+        self.last_seq += 1;
+        event.seq = self.last_seq;
+
+        let topic = Topic::of(&event.event);
+        let buffer = &mut self.topics[topic as usize];
+        buffer.push_back(event.clone());
+        if buffer.len() > topic.capacity() {
+            if let Some(evicted) = buffer.pop_front() {
+                self.evicted[topic as usize] = evicted.seq;
+            }
+        }
+        event
+    }
+
+    /// Replays what followed `since`. Missed slot events can be made up for
+    /// with a snapshot; missed bids and room events cannot, so then nothing
+    /// is replayed and the subscriber has to resync from scratch.
+    fn since(&self, since: u64) -> Resumption {
+        let missed = |topic: Topic| self.evicted[topic as usize] > since;
+        let resync = |events| Resumption {
+            events,
+            resync: Some(self.last_seq - since),
+            last_seq: self.last_seq,
+        };
+
+        if Topic::ALL
+            .into_iter()
+            .any(|topic| !topic.in_snapshot() && missed(topic))
+        {
+            return resync(Vec::new());
+        }
+        let snapshot = missed(Topic::Slots);
+
+        let mut events: Vec<SharedEvent> = Topic::ALL
+            .into_iter()
+            .filter(|topic| !(snapshot && topic.in_snapshot()))
+            .flat_map(|topic| self.topics[topic as usize].iter())
+            .filter(|event| event.seq > since)
+            .cloned()
+            .collect();
+        events.sort_by_key(|event| event.seq);
+
+        if snapshot {
+            resync(events)
+        } else {
+            Resumption {
+                events,
+                resync: None,
+                last_seq: self.last_seq,
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.topics.iter().map(VecDeque::len).sum()
+    }
+}
+
+/// A destination for the event firehose besides the in-process channel
+/// behind `/events`, such as an external message broker.
+pub trait EventBus: Send + Sync {
+    /// Hands the event over without waiting; a bus that cannot keep up drops it.
+    fn publish(&self, event: &SharedEvent);
+
+    fn bus_metrics(&self) -> BusMetrics;
+}
+
+#[derive(Clone)]
+pub struct EventBroadcaster {
+    sender: Sender<SharedEvent>,
+    /// Tells this stream's resumption tokens apart from those of another room
+    /// or an earlier process
+    epoch: u64,
+    replay: Arc<Mutex<ReplayBuffer>>,
+    history: Arc<Mutex<EventHistory>>,
+    private: Arc<PrivateChannels>,
+    buses: Arc<RwLock<Vec<Arc<dyn EventBus>>>>,
+    subscribers: Arc<DashMap<u64, SubscriberMetrics>>,
+    next_subscriber_id: Arc<AtomicU64>,
+    total_lagged_events: Arc<AtomicU64>,
+    total_resyncs: Arc<AtomicU64>,
+}
+
+impl Default for EventBroadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBroadcaster {
+    pub fn new() -> Self {
+        let (sender, _) = channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            sender,
+            epoch: rand::random(),
+            replay: Arc::new(Mutex::new(ReplayBuffer::new())),
+            history: Arc::new(Mutex::new(EventHistory::default())),
+            private: Arc::new(PrivateChannels::default()),
+            buses: Arc::new(RwLock::new(Vec::new())),
+            subscribers: Arc::new(DashMap::new()),
+            next_subscriber_id: Arc::new(AtomicU64::new(1)),
+            total_lagged_events: Arc::new(AtomicU64::new(0)),
+            total_resyncs: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn broadcast(&self, event: AppEvent) {
+        // Auction participants are tracked even before anyone subscribes
+        let recipients = self.private.recipients(&event);
+
+        let Ok(buses) = self.buses.read() else {
+            return;
+        };
+        let Ok(mut replay) = self.replay.lock() else {
+            return;
+        };
+
+        // Sent while the buffer is locked, so the channel carries events in
+        // sequence order
+        let event = replay.record(SharedEvent::new(event));
+        if let Ok(mut history) = self.history.lock() {
+            history.record(event.clone());
+        }
+        for bus in buses.iter() {
+            bus.publish(&event);
+        }
+        for session_id in &recipients {
+            self.private.send(session_id, &event);
+        }
+        let _ = self.sender.send(event);
+    }
+
+    /// Stops sending an auction's events to the sessions that bid in it, once
+    /// it closed without a winner or its last bid was withdrawn.
+    pub fn forget_auction(&self, auction_type: TransactionType, slot_number: u64) {
+        self.private.forget((auction_type, slot_number));
+    }
+
+    /// Sends an event to the session's `/events/me` subscribers only.
+    pub fn send_private(&self, session_id: &str, event: AppEvent) {
+        if self.private.senders.contains_key(session_id) {
+            self.private.send(session_id, &SharedEvent::new(event));
+        }
+    }
+
+    /// Publishes every event broadcast from now on to `bus` as well.
+    pub fn attach(&self, bus: Arc<dyn EventBus>) {
+        if let Ok(mut buses) = self.buses.write() {
+            buses.push(bus);
+        }
+    }
+
+    /// The token a subscriber that received every event up to `seq` resumes
+    /// from.
+    pub fn resumption_token(&self, seq: u64) -> String {
+        format!("{:x}-{}", self.epoch, seq)
+    }
+
+    /// Up to `limit` events broadcast between `from` (or the oldest kept) and
+    /// `to`, after sequence number `after`.
+    pub fn history(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: DateTime<Utc>,
+        after: u64,
+        limit: usize,
+    ) -> Vec<RecordedEvent> {
+        self.history
+            .lock()
+            .map(|history| history.range(from, to, after, limit))
+            .unwrap_or_default()
+    }
+
+    fn resume(&self, token: &str) -> Resumption {
+        let Ok(replay) = self.replay.lock() else {
+            return Resumption {
+                events: Vec::new(),
+                resync: Some(0),
+                last_seq: 0,
+            };
+        };
+
+        let since = token.split_once('-').and_then(|(epoch, seq)| {
+            let epoch = u64::from_str_radix(epoch, 16).ok()?;
+            let seq = seq.parse::<u64>().ok()?;
+            (epoch == self.epoch && seq <= replay.last_seq).then_some(seq)
+        });
+        match since {
+            Some(since) => replay.since(since),
+            None => Resumption {
+                events: Vec::new(),
+                resync: Some(0),
+                last_seq: replay.last_seq,
+            },
+        }
+    }
+
+    pub fn subscribe(&self) -> EventSubscription {
+        self.track_subscriber(self.sender.subscribe(), None)
+    }
+
+    /// Subscribes to the events concerning `session_id` alone.
+    pub fn subscribe_private(&self, session_id: &str) -> EventSubscription {
+        let receiver = self
+            .private
+            .senders
+            .entry(session_id.to_string())
+            .or_insert_with(|| channel(PRIVATE_CHANNEL_CAPACITY).0)
+            .subscribe();
+        self.track_subscriber(receiver, Some(session_id.to_string()))
+    }
+
+    fn track_subscriber(
+        &self,
+        receiver: Receiver<SharedEvent>,
+        session_id: Option<String>,
+    ) -> EventSubscription {
+        let id = self.next_subscriber_id.fetch_add(1, Ordering::Relaxed);
+        self.subscribers.insert(
+            id,
+            SubscriberMetrics {
+                id,
+                connected_at: Utc::now(),
+                delivered_events: 0,
+                lagged_events: 0,
+                resyncs: 0,
+                coalesced_events: 0,
+                queued: 0,
+            },
+        );
+
+        EventSubscription {
+            id,
+            receiver,
+            session_id,
+            broadcaster: self.clone(),
+        }
+    }
+
+    pub fn metrics(&self) -> BroadcastMetrics {
+        let mut subscribers: Vec<SubscriberMetrics> = self
+            .subscribers
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        subscribers.sort_by_key(|subscriber| subscriber.id);
+
+        BroadcastMetrics {
+            capacity: self.capacity(),
+            queued: self.queued(),
+            total_lagged_events: self.total_lagged_events.load(Ordering::Relaxed),
+            total_resyncs: self.total_resyncs.load(Ordering::Relaxed),
+            subscribers,
+            replay_buffered: self.replay.lock().map(|replay| replay.len()).unwrap_or(0),
+            history_events: self
+                .history
+                .lock()
+                .map(|history| history.len())
+                .unwrap_or(0),
+            buses: self
+                .buses
+                .read()
+                .map(|buses| buses.iter().map(|bus| bus.bus_metrics()).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Number of events still queued for the slowest subscriber.
+    pub fn queued(&self) -> usize {
+        self.sender.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        EVENT_CHANNEL_CAPACITY
+    }
+
+    pub fn is_saturated(&self) -> bool {
+        self.queued() * 10 >= self.capacity() * 9
+    }
+}
+
+/// A tracked receiver that records delivery and lag metrics for its subscriber.
+pub struct EventSubscription {
+    id: u64,
+    receiver: Receiver<SharedEvent>,
+    /// Set for a private subscription, whose channel is dropped with its last
+    /// subscriber
+    session_id: Option<String>,
+    broadcaster: EventBroadcaster,
+}
+
+impl EventSubscription {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub async fn recv(&mut self) -> Result<SharedEvent, RecvError> {
+        let result = self.receiver.recv().await;
+        let queued = self.receiver.len();
+
+        let Some(mut metrics) = self.broadcaster.subscribers.get_mut(&self.id) else {
+            return Err(RecvError::Closed);
+        };
+        metrics.queued = queued;
+
+        match result {
+            Ok(event) => {
+                metrics.delivered_events += 1;
+                Ok(event)
+            }
+            Err(RecvError::Lagged(missed)) => {
+                metrics.lagged_events += missed;
+                metrics.resyncs += 1;
+                self.broadcaster
+                    .total_lagged_events
+                    .fetch_add(missed, Ordering::Relaxed);
+                self.broadcaster
+                    .total_resyncs
+                    .fetch_add(1, Ordering::Relaxed);
+                Err(RecvError::Lagged(missed))
+            }
+            Err(RecvError::Closed) => Err(RecvError::Closed),
+        }
+    }
+
+    /// The events broadcast since `token` was handed out, replayed before this
+    /// subscription's live events.
+    pub fn resume(&self, token: &str) -> Resumption {
+        let resumption = self.broadcaster.resume(token);
+        if resumption.resync.is_some() {
+            if let Some(mut metrics) = self.broadcaster.subscribers.get_mut(&self.id) {
+                metrics.resyncs += 1;
+            }
+            self.broadcaster
+                .total_resyncs
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        resumption
+    }
+
+    /// Records that `count` received events were merged away by coalescing.
+    pub fn record_coalesced(&self, count: u64) {
+        if let Some(mut metrics) = self.broadcaster.subscribers.get_mut(&self.id) {
+            metrics.coalesced_events += count;
+        }
+    }
+}
+
+/// Event kinds an `EventBatch` keeps at most one of.
+#[derive(Clone, Copy, PartialEq)]
+enum Coalesced {
+    SlotAdvanced,
+    SlotsChanged,
+    Stats,
+}
+
+/// One subscriber's events since its last flush. Events are kept in arrival
+/// order, except that only the latest `SlotAdvanced` and `MarketplaceStats`
+/// survive and slot deltas are merged into one `SlotsChanged`, each placed
+/// where its newest contribution arrived.
+#[derive(Default)]
+pub struct EventBatch {
+    events: Vec<Option<SharedEvent>>,
+    slot_advanced: Option<usize>,
+    slots_changed: Option<usize>,
+    stats: Option<usize>,
+    coalesced: u64,
+}
+
+impl EventBatch {
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn push(&mut self, event: SharedEvent) {
+        let kind = match event.event.as_ref() {
+            AppEvent::SlotAdvanced { .. } => Some(Coalesced::SlotAdvanced),
+            AppEvent::SlotsChanged { .. } => Some(Coalesced::SlotsChanged),
+            AppEvent::MarketplaceStats { .. } => Some(Coalesced::Stats),
+            // A resync carries the whole window and current stats
+            AppEvent::StreamResync { .. } => {
+                for kind in [Coalesced::SlotsChanged, Coalesced::Stats] {
+                    if self.take(kind).is_some() {
+                        self.coalesced += 1;
+                    }
+                }
+                None
+            }
+            _ => None,
+        };
+
+        let event = match kind {
+            Some(kind) => {
+                let previous = self.take(kind);
+                *self.index(kind) = Some(self.events.len());
+                match previous {
+                    Some(previous) => {
+                        self.coalesced += 1;
+                        if kind == Coalesced::SlotsChanged {
+                            merge_slot_deltas(&previous, event)
+                        } else {
+                            event
+                        }
+                    }
+                    None => event,
+                }
+            }
+            None => event,
+        };
+        self.events.push(Some(event));
+    }
+
+    /// Empties the batch, returning its events and how many were coalesced away.
+    pub fn drain(&mut self) -> (Vec<SharedEvent>, u64) {
+        let mut events: Vec<SharedEvent> = self.events.drain(..).flatten().collect();
+        let coalesced = std::mem::take(&mut self.coalesced);
+        self.slot_advanced = None;
+        self.slots_changed = None;
+        self.stats = None;
+
+        // A merged delta may still hold slots that have since scrolled out of view
+        let current_slot = events
+            .iter()
+            .rev()
+            .find_map(|event| match event.event.as_ref() {
+                AppEvent::SlotAdvanced { current_slot } => Some(*current_slot),
+                _ => None,
+            });
+        if let Some(current_slot) = current_slot {
+            for event in &mut events {
+                if let AppEvent::SlotsChanged {
+                    slots,
+                    replaces_window,
+                } = event.event.as_ref()
+                {
+                    if slots.iter().any(|slot| slot.slot_number < current_slot) {
+                        *event = SharedEvent::new(AppEvent::SlotsChanged {
+                            slots: slots
+                                .iter()
+                                .filter(|slot| slot.slot_number >= current_slot)
+                                .cloned()
+                                .collect(),
+                            replaces_window: *replaces_window,
+                        });
+                    }
+                }
+            }
+        }
+
+        (events, coalesced)
+    }
+
+    /// Removes the batched event of `kind`, if any, leaving a gap in its place.
+    fn take(&mut self, kind: Coalesced) -> Option<SharedEvent> {
+        let index = self.index(kind).take()?;
+        self.events[index].take()
+    }
+
+    fn index(&mut self, kind: Coalesced) -> &mut Option<usize> {
+        match kind {
+            Coalesced::SlotAdvanced => &mut self.slot_advanced,
+            Coalesced::SlotsChanged => &mut self.slots_changed,
+            Coalesced::Stats => &mut self.stats,
+        }
+    }
+}
+
+/// Applies `next` on top of `previous`. Only a merged delta needs serializing
+/// again; one that replaces the window is passed on as broadcast.
+fn merge_slot_deltas(previous: &SharedEvent, next: SharedEvent) -> SharedEvent {
+    let (
+        AppEvent::SlotsChanged {
+            slots: previous_slots,
+            replaces_window: replaced,
+        },
+        AppEvent::SlotsChanged {
+            slots,
+            replaces_window: false,
+        },
+    ) = (previous.event.as_ref(), next.event.as_ref())
+    else {
+        return next;
+    };
+
+    let mut merged = previous_slots.clone();
+    for slot in slots {
+        match merged
+            .iter_mut()
+            .find(|existing| existing.slot_number == slot.slot_number)
+        {
+            Some(existing) => *existing = slot.clone(),
+            None => merged.push(slot.clone()),
+        }
+    }
+    merged.sort_by_key(|slot| slot.slot_number);
+
+    SharedEvent::new(AppEvent::SlotsChanged {
+        slots: merged,
+        replaces_window: *replaced,
+    })
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        self.broadcaster.subscribers.remove(&self.id);
+        if let Some(session_id) = &self.session_id {
+            self.broadcaster
+                .private
+                .senders
+                .remove_if(session_id, |_, sender| sender.receiver_count() <= 1);
+        }
+    }
+}
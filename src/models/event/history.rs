@@ -0,0 +1,70 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use super::SharedEvent;
+
+/// How long broadcast events are kept in memory for `/events/replay`
+pub const EVENT_HISTORY_RETENTION_SECS: i64 = 15 * 60;
+/// Most events kept, however recent
+const EVENT_HISTORY_CAPACITY: usize = 100_000;
+
+/// A broadcast event and when it was sent.
+#[derive(Clone)]
+pub struct RecordedEvent {
+    pub at: DateTime<Utc>,
+    pub event: SharedEvent,
+}
+
+/// The room's recently broadcast events, oldest first. They are only kept in
+/// memory, so a restart clears them.
+#[derive(Default)]
+pub struct EventHistory {
+    events: VecDeque<RecordedEvent>,
+}
+
+impl EventHistory {
+    pub fn record(&mut self, event: SharedEvent) {
+        let at = Utc::now();
+        self.events.push_back(RecordedEvent { at, event });
+
+        let cutoff = at - TimeDelta::seconds(EVENT_HISTORY_RETENTION_SECS);
+        while self.events.len() > EVENT_HISTORY_CAPACITY
+            || self.events.front().is_some_and(|oldest| oldest.at < cutoff)
+        {
+            self.events.pop_front();
+        }
+    }
+
+    /// Up to `limit` events broadcast from `from` (or the oldest kept) up to
+    /// `to`, inclusive, that come after sequence number `after`.
+    pub fn range(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: DateTime<Utc>,
+        after: u64,
+        limit: usize,
+    ) -> Vec<RecordedEvent> {
+        let start = from.map_or(0, |from| {
+            self.events.partition_point(|event| event.at < from)
+        });
+        let start = start.max(
+            self.events
+                .partition_point(|event| event.event.seq <= after),
+        );
+        let end = self.events.partition_point(|event| event.at <= to);
+        self.events
+            .range(start..end.max(start))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
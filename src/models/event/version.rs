@@ -0,0 +1,123 @@
+use serde::Serialize;
+
+use super::{AppEvent, EVENT_SCHEMA_VERSION, SharedEvent};
+use crate::models::slot::Slot;
+
+#[derive(Serialize)]
+struct Versioned<'a> {
+    #[serde(flatten)]
+    event: &'a AppEvent,
+    schema_version: u32,
+}
+
+/// Events of older schema versions that no longer have an `AppEvent` variant
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum LegacyEvent<'a> {
+    /// Version 1: the whole visible window, sent whenever a slot changed
+    SlotsUpdated {
+        slots: &'a [Slot],
+        schema_version: u32,
+    },
+}
+
+/// The event's JSON in the current format, tagged with `version`.
+pub fn versioned_payload(event: &AppEvent, version: u32) -> String {
+    serde_json::to_string(&Versioned {
+        event,
+        schema_version: version,
+    })
+    .unwrap_or_default()
+}
+
+/// Rewrites a stream's events for a client that asked for an older schema
+/// version.
+///
+/// Version 1 predates slot deltas: every change sends the whole visible
+/// window as `SlotsUpdated`, so the window is rebuilt here from the deltas.
+/// Events added since have no version 1 form and are left out.
+pub struct EventDowngrade {
+    version: u32,
+    current_slot: u64,
+    window: Vec<Slot>,
+}
+
+impl EventDowngrade {
+    /// Starts from the visible window as of subscribing.
+    pub fn new(version: u32, current_slot: u64, window: Vec<Slot>) -> Self {
+        Self {
+            version,
+            current_slot,
+            window,
+        }
+    }
+
+    /// The event as the client's version has it, or `None` if it has no
+    /// equivalent there.
+    pub fn apply(&mut self, event: SharedEvent) -> Option<SharedEvent> {
+        if self.version >= EVENT_SCHEMA_VERSION {
+            return Some(event);
+        }
+
+        let payload = match event.event.as_ref() {
+            AppEvent::SlotAdvanced { current_slot } => {
+                self.advance(*current_slot);
+                versioned_payload(&event.event, self.version)
+            }
+            AppEvent::SlotsChanged {
+                slots,
+                replaces_window,
+            } => {
+                if *replaces_window {
+                    self.window = slots.clone();
+                } else {
+                    for slot in slots {
+                        match self
+                            .window
+                            .iter_mut()
+                            .find(|existing| existing.slot_number == slot.slot_number)
+                        {
+                            Some(existing) => *existing = slot.clone(),
+                            None => self.window.push(slot.clone()),
+                        }
+                    }
+                    self.window.sort_by_key(|slot| slot.slot_number);
+                }
+                self.advance(self.current_slot);
+                serde_json::to_string(&LegacyEvent::SlotsUpdated {
+                    slots: &self.window,
+                    schema_version: self.version,
+                })
+                .unwrap_or_default()
+            }
+            AppEvent::StreamResync {
+                current_slot,
+                slots,
+                ..
+            } => {
+                self.window = slots.clone();
+                self.advance(*current_slot);
+                versioned_payload(&event.event, self.version)
+            }
+            AppEvent::BidCancelled { .. }
+            | AppEvent::MatchReady { .. }
+            | AppEvent::RoomClosed { .. }
+            | AppEvent::PlayerDeleted { .. }
+            | AppEvent::Outbid { .. }
+            | AppEvent::AchievementUnlocked { .. }
+            | AppEvent::AotAuctionClosing { .. } => return None,
+            _ => versioned_payload(&event.event, self.version),
+        };
+
+        Some(SharedEvent {
+            payload: payload.into(),
+            ..event
+        })
+    }
+
+    /// Drops slots that have scrolled out of view.
+    fn advance(&mut self, current_slot: u64) {
+        self.current_slot = current_slot;
+        self.window.retain(|slot| slot.slot_number >= current_slot);
+    }
+}
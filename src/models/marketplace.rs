@@ -1,72 +1,177 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, hash_map::Entry};
 
 use chrono::{Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    models::slot::{Slot, SlotState},
+    models::{
+        slot::{Slot, SlotState},
+        types::Lamports,
+    },
     utils::transaction::calculate_base_fee,
 };
 
+/// How many slots are kept and listed ahead of the current one.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct SlotWindow {
+    /// Slots kept after the current one
+    pub lookahead: u64,
+    /// Slots from the current one that clients are shown and sent changes for
+    pub listing: u64,
+}
+
+/// The slot clock: which slot is current and the terms new slots are created with.
+/// The slots themselves live in `SlotShard`s.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SlotMarketplace {
-    pub slots: HashMap<u64, Slot>,
     pub current_slot: u64,
     pub slot_duration_ms: i64,
+    pub base_fee: Lamports,
+    pub slot_window: SlotWindow,
 }
 
 impl SlotMarketplace {
-    pub fn new(slot_duration_ms: i64) -> Self {
-        let mut marketplace = Self {
-            slots: HashMap::new(),
+    pub fn new(slot_duration_ms: i64, base_fee: Lamports, slot_window: SlotWindow) -> Self {
+        Self {
             current_slot: 0,
             slot_duration_ms,
-        };
+            base_fee,
+            slot_window,
+        }
+    }
 
-        // Initializes a rolling window of slots
-        marketplace.initialize_slots(100);
-        marketplace
+    /// Slots of a rolling window from the current slot to `lookahead` slots past it
+    pub fn window(&self) -> Vec<Slot> {
+        (0..=self.slot_window.lookahead)
+            .map(|i| self.new_slot(i))
+            .collect()
     }
 
-    fn initialize_slots(&mut self, num_slots_ahead: u64) {
-        for i in 0..num_slots_ahead {
-            let slot_number = self.current_slot + i;
-            let estimated_time =
-                Utc::now() + Duration::milliseconds(self.slot_duration_ms * i as i64);
+    /// Slot numbers listed to clients: from the current slot up to, but not including, the end
+    pub fn listing_range(&self) -> (u64, u64) {
+        (
+            self.current_slot,
+            self.current_slot + self.slot_window.listing,
+        )
+    }
 
-            let base_fee = calculate_base_fee().unwrap_or(0.001);
+    fn new_slot(&self, slots_ahead: u64) -> Slot {
+        let estimated_time =
+            Utc::now() + Duration::milliseconds(self.slot_duration_ms * slots_ahead as i64);
 
-            let slot = Slot::new(slot_number, estimated_time, base_fee);
-            self.slots.insert(slot_number, slot);
-        }
+        Slot::new(
+            self.current_slot + slots_ahead,
+            estimated_time,
+            calculate_base_fee(self.base_fee),
+        )
+    }
+
+    /// Applies reloaded settings to slots created from now on; existing slots keep theirs
+    pub fn apply_config(&mut self, slot_duration_ms: i64, base_fee: Lamports, listing: u64) {
+        self.slot_duration_ms = slot_duration_ms;
+        self.base_fee = base_fee;
+        self.slot_window.listing = listing;
     }
 
-    /// Advances to the next slot and expires old slots
-    pub fn advance_slot(&mut self) {
+    /// Re-anchors the clock at `slot_number`, returning the slots of its new window
+    pub fn anchor_to_slot(&mut self, slot_number: u64) -> Vec<Slot> {
+        self.current_slot = slot_number;
+        self.window()
+    }
+
+    /// Advances to the next slot, returning the slot that enters the far end of the window
+    pub fn advance_slot(&mut self) -> Slot {
         self.current_slot += 1;
+        self.new_slot(self.slot_window.lookahead)
+    }
+}
+
+/// The slots whose numbers fall in one shard, and which of them changed since
+/// changes were last taken.
+#[derive(Clone, Debug, Default)]
+pub struct SlotShard {
+    slots: HashMap<u64, Slot>,
+    changed: HashSet<u64>,
+}
+
+impl SlotShard {
+    pub fn new(slots: Vec<Slot>) -> Self {
+        Self {
+            slots: slots
+                .into_iter()
+                .map(|slot| (slot.slot_number, slot))
+                .collect(),
+            changed: HashSet::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn get(&self, slot_number: u64) -> Option<&Slot> {
+        self.slots.get(&slot_number)
+    }
+
+    /// Applies `change` to a slot and records that it changed
+    pub fn update<R>(
+        &mut self,
+        slot_number: u64,
+        change: impl FnOnce(&mut Slot) -> R,
+    ) -> Option<R> {
+        let slot = self.slots.get_mut(&slot_number)?;
+        self.changed.insert(slot_number);
+        Some(change(slot))
+    }
+
+    /// Adds a slot unless one with its number already exists
+    pub fn insert(&mut self, slot: Slot) {
+        if let Entry::Vacant(entry) = self.slots.entry(slot.slot_number) {
+            self.changed.insert(slot.slot_number);
+            entry.insert(slot);
+        }
+    }
 
+    /// Marks slots whose time has passed as expired, leaving filled slots as they are
+    pub fn expire_stale(&mut self) {
         for slot in self.slots.values_mut() {
             if slot.is_expired()
                 && !matches!(slot.state, SlotState::Expired | SlotState::Filled { .. })
             {
                 slot.state = SlotState::Expired;
+                self.changed.insert(slot.slot_number);
             }
         }
+    }
 
-        // Create the next slot in the rolling window
-        let furthest_slot = self.current_slot + 100;
-        if !self.slots.contains_key(&furthest_slot) {
-            let estimated_time = Utc::now() + Duration::milliseconds(self.slot_duration_ms * 100);
-
-            let base_fee = calculate_base_fee().unwrap_or(0.001);
+    /// Slots from `from` up to, but not including, `to` that changed since the
+    /// last call, plus slot `to - 1`, which has just come into view. Changes
+    /// outside the range are dropped; those slots are sent when they come into view.
+    pub fn take_changes(&mut self, from: u64, to: u64) -> Vec<Slot> {
+        let changed = std::mem::take(&mut self.changed);
+        self.slots
+            .values()
+            .filter(|slot| slot.slot_number >= from && slot.slot_number < to)
+            .filter(|slot| slot.slot_number + 1 == to || changed.contains(&slot.slot_number))
+            .cloned()
+            .collect()
+    }
 
-            let slot = Slot::new(furthest_slot, estimated_time, base_fee);
-            self.slots.insert(furthest_slot, slot);
-        }
+    /// Slots numbered from `from` up to, but not including, `to`
+    pub fn range(&self, from: u64, to: u64) -> Vec<Slot> {
+        self.slots
+            .values()
+            .filter(|slot| slot.slot_number >= from && slot.slot_number < to)
+            .cloned()
+            .collect()
     }
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Deserialize, serde::Serialize, utoipa::ToSchema)]
 pub struct MarketplaceStats {
     pub current_slot: u64,
     pub total_slots: usize,
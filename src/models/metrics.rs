@@ -1,8 +1,9 @@
 use chrono::{DateTime, Utc};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub enum AchievementType {
     // Beginner tier
     FirstWin,
@@ -31,7 +32,7 @@ pub enum AchievementType {
     PerfectRecord,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 pub struct Achievement {
     pub achievement_type: AchievementType,
     pub name: String,
@@ -221,15 +222,25 @@ impl Achievement {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "session_id": "7d0b3e1c-9a4f-4c2e-b6d5-1f8e2a3c4b5d",
+    "display_name": "Player 7d0b3e",
+    "rank": 1,
+    "rank_change": 2,
+    "level": 4
+}))]
 pub struct LeaderboardEntry {
     pub session_id: String,
     pub display_name: String,
     pub rank: u32,
+    /// Places gained (positive) or lost since the board was last built; `None`
+    /// for players new to it
+    pub rank_change: Option<i32>,
     pub level: u32,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct Leaderboard {
     pub top_by_wins: Vec<LeaderboardEntry>,
     pub top_by_balance: Vec<LeaderboardEntry>,
@@ -1,11 +1,15 @@
+pub mod admin;
+pub mod api_key;
 pub mod auction;
 pub mod event;
 pub mod marketplace;
 pub mod metrics;
 pub mod player;
+pub mod recovery;
 pub mod requests;
 pub mod responses;
 pub mod session;
 pub mod slot;
 pub mod transaction;
 pub mod types;
+pub mod wallet;
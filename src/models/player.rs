@@ -1,14 +1,50 @@
 use std::collections::HashSet;
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    INITIAL_PLAYER_BALANCE,
+    error::AppError,
+    models::{metrics::Achievement, types::Lamports, wallet::wallet_display_name},
+};
+
+/// How a room plays. Sandbox rooms have an unlimited faucet and are left off
+/// leaderboards; competitive rooms give every player the same fixed bankroll.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    Sandbox,
+    #[default]
+    Competitive,
+}
 
-use crate::models::metrics::Achievement;
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "session_id": "7d0b3e1c-9a4f-4c2e-b6d5-1f8e2a3c4b5d",
+    "balance": 99999.985,
+    "total_sol_spent": 0.015,
+    "total_auctions_participated": 1,
+    "total_auctions_won": 1,
+    "level": 1,
+    "current_streak": 1,
+    "best_streak": 1,
+    "xp": 150,
+    "achievements": [],
+    "participated_slots": [1042],
+    "resolved_slots": [1042],
+    "jit_wins": 1,
+    "aot_wins": 0,
+    "has_placed_first_bid": true,
+    "total_bids_placed": 1,
+    "wallet": null,
+    "banned": false,
+    "mode": "competitive"
+}))]
 pub struct PlayerStats {
     pub session_id: String,
-    pub balance: f64,
-    pub total_sol_spent: f64,
+    pub balance: Lamports,
+    pub total_sol_spent: Lamports,
     pub total_auctions_participated: u32,
     pub total_auctions_won: u32,
     pub level: u32,
@@ -22,14 +58,26 @@ pub struct PlayerStats {
     pub aot_wins: u32,
     pub has_placed_first_bid: bool,
     pub total_bids_placed: u32,
+    /// Solana wallet linked via Sign-in-with-Solana, which keeps this profile
+    /// alive after the session expires
+    pub wallet: Option<String>,
+    /// Set when an admin bans the player; the stats are kept but no longer change
+    pub banned: bool,
+    /// Mode of the room the profile belongs to; only competitive profiles are ranked
+    #[serde(default)]
+    pub mode: GameMode,
+    /// Hash of the recovery code that keeps this profile alive after the
+    /// session expires
+    #[serde(skip)]
+    pub recovery_code_hash: Option<String>,
 }
 
 impl PlayerStats {
     pub fn new(session_id: String) -> Self {
         Self {
             session_id,
-            balance: 100000.0,
-            total_sol_spent: 0.0,
+            balance: INITIAL_PLAYER_BALANCE,
+            total_sol_spent: Lamports::ZERO,
             total_auctions_participated: 0,
             total_auctions_won: 0,
             level: 1,
@@ -43,27 +91,50 @@ impl PlayerStats {
             aot_wins: 0,
             has_placed_first_bid: false,
             total_bids_placed: 0,
+            wallet: None,
+            banned: false,
+            mode: GameMode::default(),
+            recovery_code_hash: None,
         }
     }
 
-    pub fn increment_balance(&mut self, amount: f64) {
-        self.balance += amount;
+    pub fn is_ranked(&self) -> bool {
+        !self.banned && self.mode == GameMode::Competitive
     }
 
-    pub fn deduct_balance(&mut self, amount: f64) -> Result<(), String> {
-        if self.is_balance_sufficient(amount) {
-            self.balance -= amount;
-            self.total_sol_spent += amount;
-            Ok(())
-        } else {
-            Err(format!(
-                "Insufficient balance. Have: {}, Need: {}",
-                self.balance, amount
-            ))
+    pub fn display_name(&self) -> String {
+        match &self.wallet {
+            Some(wallet) => wallet_display_name(wallet),
+            None => session_display_name(&self.session_id),
         }
     }
 
-    pub fn is_balance_sufficient(&self, amount: f64) -> bool {
+    /// Credits `amount`, refusing a credit the balance cannot represent.
+    pub fn increment_balance(&mut self, amount: Lamports) -> Result<(), AppError> {
+        self.balance = self
+            .balance
+            .checked_add(amount)
+            .ok_or(AppError::BalanceLimitExceeded {
+                limit: Lamports(u64::MAX),
+            })?;
+        Ok(())
+    }
+
+    pub fn deduct_balance(&mut self, amount: Lamports) -> Result<(), AppError> {
+        match self.balance.checked_sub(amount) {
+            Some(remaining) => {
+                self.balance = remaining;
+                self.total_sol_spent += amount;
+                Ok(())
+            }
+            None => Err(AppError::InsufficientBalance {
+                available: self.balance,
+                required: amount,
+            }),
+        }
+    }
+
+    pub fn is_balance_sufficient(&self, amount: Lamports) -> bool {
         self.balance >= amount
     }
 
@@ -94,6 +165,18 @@ impl PlayerStats {
         self.total_bids_placed += 1;
     }
 
+    /// Undoes `deduct_balance` and `track_bid` for a bid that was never placed.
+    /// `other_bids_for_slot` keeps the slot counted as participated in.
+    pub fn revert_bid(&mut self, amount: Lamports, slot_number: u64, other_bids_for_slot: bool) {
+        self.balance += amount;
+        self.total_sol_spent = self.total_sol_spent.saturating_sub(amount);
+        self.total_bids_placed = self.total_bids_placed.saturating_sub(1);
+        self.has_placed_first_bid = self.total_bids_placed > 0;
+        if !other_bids_for_slot {
+            self.participated_slots.remove(&slot_number);
+        }
+    }
+
     pub fn mark_auction_resolved(&mut self, slot_number: u64) {
         if self.participated_slots.contains(&slot_number) && self.resolved_slots.insert(slot_number)
         {
@@ -119,3 +202,8 @@ impl PlayerStats {
             && self.total_auctions_won == self.total_auctions_participated
     }
 }
+
+/// Name shown for a session that has not linked a wallet.
+pub fn session_display_name(session_id: &str) -> String {
+    format!("Player {}", session_id.chars().take(6).collect::<String>())
+}
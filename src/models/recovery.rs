@@ -0,0 +1,31 @@
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// Characters recovery codes are drawn from, without look-alikes such as 0/O and 1/I.
+const RECOVERY_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const RECOVERY_GROUPS: usize = 4;
+const RECOVERY_GROUP_LENGTH: usize = 4;
+
+/// A new recovery code, e.g. `K7QM-2XRP-VD9H-T4NA`, for the player to write
+/// down. Only its hash is kept.
+pub fn generate_recovery_code() -> String {
+    let mut rng = rand::rng();
+    (0..RECOVERY_GROUPS)
+        .map(|_| {
+            (0..RECOVERY_GROUP_LENGTH)
+                .map(|_| RECOVERY_ALPHABET[rng.random_range(0..RECOVERY_ALPHABET.len())] as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Hashes a code as typed, ignoring case, spaces and dashes.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
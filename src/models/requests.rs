@@ -1,21 +1,418 @@
-use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-#[derive(Deserialize, ToSchema)]
+use crate::{
+    INITIAL_PLAYER_BALANCE, MAX_BID_AMOUNT, MAX_COMPUTE_UNITS_PER_SLOT, MAX_MEMO_LENGTH,
+    MAX_TAG_LENGTH, MAX_TAGS_PER_TRANSACTION,
+    managers::analytics::PriceResolution,
+    models::{
+        player::GameMode,
+        slot::SlotStateKind,
+        transaction::{RebidPolicy, TransactionStatusKind},
+        types::{Lamports, TransactionType},
+    },
+    utils::validation::{FieldError, Validate},
+};
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct JitBidRequest {
     pub session_id: Option<String>,
-    pub bid_amount: f64,
+    /// Bid in SOL, converted to whole lamports
+    #[schema(value_type = f64)]
+    pub bid_amount: Lamports,
     pub compute_units: u64,
     pub data: String,
+    pub depends_on: Option<String>,
+    /// Free-text note stored on the transaction
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels to find the transaction by, e.g. a strategy name
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Must be above every nonce the session has used; a bid with a reused
+    /// or out-of-order nonce is rejected
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    /// Rebids automatically for the following slots if this bid loses
+    #[serde(default)]
+    pub rebid: Option<RebidPolicy>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct AotBidRequest {
     pub session_id: Option<String>,
     pub slot_number: u64,
-    pub bid_amount: f64,
+    /// Bid in SOL, converted to whole lamports
+    #[schema(value_type = f64)]
+    pub bid_amount: Lamports,
     pub compute_units: u64,
     pub data: String,
+    pub depends_on: Option<String>,
+    /// Free-text note stored on the transaction
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Labels to find the transaction by, e.g. a strategy name
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Must be above every nonce the session has used; a bid with a reused
+    /// or out-of-order nonce is rejected
+    #[serde(default)]
+    pub nonce: Option<u64>,
+}
+
+/// Checks shared by JIT and AOT bids. The payload's limits are configurable,
+/// so `data` is checked when the bid is placed.
+pub(crate) fn validate_bid_fields(bid_amount: Lamports, compute_units: u64) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if bid_amount.is_zero() {
+        errors.push(FieldError::new("bid_amount", "must be greater than zero"));
+    } else if bid_amount > MAX_BID_AMOUNT {
+        errors.push(FieldError::new(
+            "bid_amount",
+            format!("must not exceed {} SOL", MAX_BID_AMOUNT),
+        ));
+    }
+
+    if compute_units == 0 {
+        errors.push(FieldError::new(
+            "compute_units",
+            "must be greater than zero",
+        ));
+    } else if compute_units > MAX_COMPUTE_UNITS_PER_SLOT {
+        errors.push(FieldError::new(
+            "compute_units",
+            format!(
+                "must not exceed the maximum per slot of {}",
+                MAX_COMPUTE_UNITS_PER_SLOT
+            ),
+        ));
+    }
+
+    errors
+}
+
+fn validate_labels(memo: Option<&str>, tags: &[String]) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if memo.is_some_and(|memo| memo.chars().count() > MAX_MEMO_LENGTH) {
+        errors.push(FieldError::new(
+            "memo",
+            format!("must not exceed {} characters", MAX_MEMO_LENGTH),
+        ));
+    }
+
+    if tags.len() > MAX_TAGS_PER_TRANSACTION {
+        errors.push(FieldError::new(
+            "tags",
+            format!("must not have more than {} tags", MAX_TAGS_PER_TRANSACTION),
+        ));
+    }
+    for (i, tag) in tags.iter().enumerate() {
+        let valid = !tag.is_empty()
+            && tag.len() <= MAX_TAG_LENGTH
+            && tag
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':'));
+        if !valid {
+            errors.push(FieldError::new(
+                format!("tags[{}]", i),
+                format!(
+                    "must be 1 to {} letters, digits, '-', '_' or ':'",
+                    MAX_TAG_LENGTH
+                ),
+            ));
+        }
+    }
+
+    errors
+}
+
+fn validate_rebid_policy(policy: &RebidPolicy, bid_amount: Lamports) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if policy.max_bid < bid_amount {
+        errors.push(FieldError::new(
+            "rebid.max_bid",
+            "must be at least bid_amount",
+        ));
+    } else if policy.max_bid > MAX_BID_AMOUNT {
+        errors.push(FieldError::new(
+            "rebid.max_bid",
+            format!("must not exceed {} SOL", MAX_BID_AMOUNT),
+        ));
+    }
+
+    if policy.max_slots == 0 || policy.max_slots > MAX_REBID_SLOTS {
+        errors.push(FieldError::new(
+            "rebid.max_slots",
+            format!("must be between 1 and {}", MAX_REBID_SLOTS),
+        ));
+    }
+
+    if policy.bump_percent.is_some_and(|percent| {
+        !(percent.is_finite() && (0.0..=MAX_RETRY_BUMP_PERCENT).contains(&percent))
+    }) {
+        errors.push(FieldError::new(
+            "rebid.bump_percent",
+            format!("must be between 0 and {}", MAX_RETRY_BUMP_PERCENT),
+        ));
+    }
+
+    errors
+}
+
+impl Validate for JitBidRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = validate_bid_fields(self.bid_amount, self.compute_units);
+        errors.extend(validate_labels(self.memo.as_deref(), &self.tags));
+        if let Some(policy) = &self.rebid {
+            errors.extend(validate_rebid_policy(policy, self.bid_amount));
+            if self.depends_on.is_some() {
+                errors.push(FieldError::new(
+                    "rebid",
+                    "cannot be set on a bid with depends_on",
+                ));
+            }
+        }
+        errors
+    }
+}
+
+impl Validate for AotBidRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = validate_bid_fields(self.bid_amount, self.compute_units);
+        errors.extend(validate_labels(self.memo.as_deref(), &self.tags));
+        errors
+    }
+}
+
+/// Most bids one bundle may carry
+pub const MAX_BUNDLE_LEGS: usize = 8;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BundleLeg {
+    Jit(JitBidRequest),
+    Aot(AotBidRequest),
+}
+
+/// Bids placed together: all of them are funded and entered, or none are.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BundleBidRequest {
+    pub session_id: Option<String>,
+    pub legs: Vec<BundleLeg>,
+    /// Sequences the bundle as a whole, like a single bid's nonce
+    #[serde(default)]
+    pub nonce: Option<u64>,
+}
+
+impl Validate for BundleBidRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.legs.is_empty() || self.legs.len() > MAX_BUNDLE_LEGS {
+            errors.push(FieldError::new(
+                "legs",
+                format!("must hold 1 to {} bids", MAX_BUNDLE_LEGS),
+            ));
+        }
+
+        // A JIT bid that took the lead cannot be withdrawn, so only one may
+        // enter the auction, after every other leg is in
+        let jit_bids = self
+            .legs
+            .iter()
+            .filter(|leg| matches!(leg, BundleLeg::Jit(req) if req.depends_on.is_none()))
+            .count();
+        if jit_bids > 1 {
+            errors.push(FieldError::new(
+                "legs",
+                "must hold at most one JIT bid without depends_on",
+            ));
+        }
+
+        for (i, leg) in self.legs.iter().enumerate() {
+            let (mut leg_errors, nonce) = match leg {
+                BundleLeg::Jit(req) => (req.validate(), req.nonce),
+                BundleLeg::Aot(req) => (req.validate(), req.nonce),
+            };
+            if nonce.is_some() {
+                leg_errors.push(FieldError::new("nonce", "must be set on the bundle"));
+            }
+            errors.extend(leg_errors.into_iter().map(|error| {
+                FieldError::new(format!("legs[{}].{}", i, error.field), error.message)
+            }));
+        }
+        errors
+    }
+}
+
+/// A bid to check without placing it.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SimulateBidRequest {
+    pub session_id: Option<String>,
+    #[serde(rename = "type")]
+    pub transaction_type: TransactionType,
+    /// Slot to bid on; required for AOT bids, JIT bids always target the next slot
+    pub slot_number: Option<u64>,
+    /// Bid in SOL, converted to whole lamports
+    #[schema(value_type = f64)]
+    pub bid_amount: Lamports,
+    pub compute_units: u64,
+    #[serde(default)]
+    pub data: String,
+    pub depends_on: Option<String>,
+    #[serde(default)]
+    pub nonce: Option<u64>,
+}
+
+impl Validate for SimulateBidRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = validate_bid_fields(self.bid_amount, self.compute_units);
+        if self.transaction_type == TransactionType::Aot && self.slot_number.is_none() {
+            errors.push(FieldError::new("slot_number", "is required for AOT bids"));
+        }
+        errors
+    }
+}
+
+/// Largest raise a retried bid may make over the original
+pub const MAX_RETRY_BUMP_PERCENT: f64 = 1_000.0;
+/// Most slots an automatic rebid policy may keep bidding for
+pub const MAX_REBID_SLOTS: u64 = 32;
+
+#[derive(Serialize, Deserialize, ToSchema, Default)]
+pub struct RetryBidRequest {
+    /// Raises the new bid over the original by this percentage
+    pub bump_percent: Option<f64>,
+    /// Sequences the new bid like a JIT or AOT bid's nonce
+    #[serde(default)]
+    pub nonce: Option<u64>,
+}
+
+impl Validate for RetryBidRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        match self.bump_percent {
+            Some(percent)
+                if !(percent.is_finite() && (0.0..=MAX_RETRY_BUMP_PERCENT).contains(&percent)) =>
+            {
+                vec![FieldError::new(
+                    "bump_percent",
+                    format!("must be between 0 and {}", MAX_RETRY_BUMP_PERCENT),
+                )]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateSessionQuery {
+    pub spectator: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema, Default)]
+pub struct CreateApiKeyRequest {
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct WalletChallengeRequest {
+    /// Base58-encoded Solana public key
+    pub public_key: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct WalletVerifyRequest {
+    /// Base58-encoded ed25519 signature of the challenge message
+    pub signature: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RecoverSessionRequest {
+    /// Code from `POST /sessions/recovery-code`; case, spaces and dashes are ignored
+    pub recovery_code: String,
+}
+
+/// Durable identity a guest session is upgraded with.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeMethod {
+    /// The wallet already linked with `POST /sessions/wallet/verify`
+    Wallet,
+    /// A recovery code issued with the upgrade
+    RecoveryCode,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpgradeSessionRequest {
+    pub method: UpgradeMethod,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BalanceAdjustmentRequest {
+    /// SOL to credit (positive) or debit (negative)
+    pub amount: f64,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema, Default)]
+pub struct BanRequest {
+    pub reason: Option<String>,
+}
+
+/// Restricts the auctions of `from_slot..=to_slot` to the listed sessions and teams.
+#[derive(Deserialize, ToSchema)]
+pub struct AuctionRestrictionRequest {
+    pub from_slot: u64,
+    /// Inclusive; defaults to `from_slot` to restrict a single auction
+    pub to_slot: Option<u64>,
+    pub label: Option<String>,
+    #[serde(default)]
+    pub session_ids: Vec<String>,
+    /// Team name to its members' session IDs
+    #[serde(default)]
+    pub teams: BTreeMap<String, Vec<String>>,
+}
+
+impl Validate for AuctionRestrictionRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.to_slot.is_some_and(|to_slot| to_slot < self.from_slot) {
+            errors.push(FieldError::new(
+                "to_slot",
+                "must not be less than from_slot",
+            ));
+        }
+        if self
+            .label
+            .as_ref()
+            .is_some_and(|label| label.chars().count() > MAX_ROOM_NAME_LENGTH)
+        {
+            errors.push(FieldError::new(
+                "label",
+                format!("must be at most {} characters", MAX_ROOM_NAME_LENGTH),
+            ));
+        }
+        if self.teams.keys().any(|team| team.trim().is_empty()) {
+            errors.push(FieldError::new("teams", "team names must not be empty"));
+        }
+        if self.session_ids.is_empty() && self.teams.values().all(Vec::is_empty) {
+            errors.push(FieldError::new(
+                "session_ids",
+                "at least one session must be allowed, directly or through a team",
+            ));
+        }
+        errors
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReconciliationQuery {
+    pub slot: u64,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -24,6 +421,104 @@ pub struct TransactionQuery {
     pub page: Option<u32>,
     pub limit: Option<u32>,
     pub show_all: Option<bool>,
+    pub status: Option<TransactionStatusKind>,
+    #[serde(rename = "type")]
+    pub transaction_type: Option<TransactionType>,
+    pub slot: Option<u64>,
+    pub slot_min: Option<u64>,
+    pub slot_max: Option<u64>,
+    pub tag: Option<String>,
+    pub memo: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EventStreamQuery {
+    /// Resumption token, the `id` of the last event received
+    pub resume: Option<String>,
+    /// Event schema version to receive, defaults to the latest
+    pub v: Option<u32>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EventReplayQuery {
+    /// Start of the window, defaults to the oldest event kept
+    pub from: Option<DateTime<Utc>>,
+    /// End of the window, defaults to now
+    pub to: Option<DateTime<Utc>>,
+    /// How many times faster than real time to play, defaults to 1
+    pub speed: Option<f64>,
+    /// Most events to play, defaults to and at most `MAX_REPLAY_EVENTS`
+    pub limit: Option<usize>,
+    /// Play only events after this one, the `next` of the previous page's
+    /// `end` event
+    pub after: Option<u64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SlotRangeQuery {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub state: Option<SlotStateKind>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SlotSearchQuery {
+    /// Highest base fee in SOL
+    #[schema(value_type = Option<f64>)]
+    pub max_fee: Option<Lamports>,
+    pub min_distance: Option<u64>,
+    pub compute_units: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BidEstimateQuery {
+    pub slot: u64,
+    /// Bid in SOL
+    #[schema(value_type = f64)]
+    pub bid: Lamports,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PriceSeriesQuery {
+    pub resolution: Option<PriceResolution>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ExportQuery {
+    pub session_id: Option<String>,
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ForecastQuery {
+    pub slot: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct HeatmapQuery {
+    /// Slot distances per bucket
+    pub bucket_size: Option<u64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct FeeStatsQuery {
+    /// Comma-separated trailing windows in minutes
+    pub windows: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AuctionHistoryQuery {
+    pub slot_min: Option<u64>,
+    pub slot_max: Option<u64>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -31,3 +526,197 @@ pub struct TransactionBatchQuery {
     pub page: Option<u32>,
     pub limit: Option<u32>,
 }
+
+/// Longest room name accepted, in characters.
+pub const MAX_ROOM_NAME_LENGTH: usize = 64;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateRoomRequest {
+    pub name: String,
+    /// Private rooms can only be joined with an invite code
+    #[serde(default)]
+    pub private: bool,
+    /// Competitive by default; sandbox rooms have a faucet and are not ranked
+    #[serde(default)]
+    pub mode: GameMode,
+}
+
+/// Longest an invite may stay valid, in minutes (one week).
+pub const MAX_INVITE_EXPIRY_MINUTES: u32 = 10_080;
+/// Most times a single invite code may be redeemed.
+pub const MAX_INVITE_USES: u32 = 1_000;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateInviteRequest {
+    /// Redemptions allowed, 10 by default
+    pub max_uses: Option<u32>,
+    /// Minutes until the code expires, 1440 (one day) by default
+    pub expires_in_minutes: Option<u32>,
+}
+
+impl Validate for CreateInviteRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self
+            .max_uses
+            .is_some_and(|uses| !(1..=MAX_INVITE_USES).contains(&uses))
+        {
+            errors.push(FieldError::new(
+                "max_uses",
+                format!("must be between 1 and {}", MAX_INVITE_USES),
+            ));
+        }
+        if self
+            .expires_in_minutes
+            .is_some_and(|minutes| !(1..=MAX_INVITE_EXPIRY_MINUTES).contains(&minutes))
+        {
+            errors.push(FieldError::new(
+                "expires_in_minutes",
+                format!("must be between 1 and {}", MAX_INVITE_EXPIRY_MINUTES),
+            ));
+        }
+        errors
+    }
+}
+
+/// Fastest slot duration a room's creator may set, in milliseconds.
+pub const MIN_ROOM_SLOT_DURATION_MS: i64 = 100;
+/// Slowest slot duration a room's creator may set, in milliseconds.
+pub const MAX_ROOM_SLOT_DURATION_MS: i64 = 10_000;
+/// Highest base fee a room's creator may set, in SOL.
+pub const MAX_ROOM_BASE_FEE_SOL: f64 = 1.0;
+
+/// Settings a room's creator may change; omitted ones are left as they are.
+#[derive(Deserialize, ToSchema)]
+pub struct RoomConfigRequest {
+    /// Also the pace of the room's slot clock
+    pub slot_duration_ms: Option<i64>,
+    pub base_fee_sol: Option<f64>,
+}
+
+impl Validate for RoomConfigRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.slot_duration_ms.is_none() && self.base_fee_sol.is_none() {
+            errors.push(FieldError::new(
+                "slot_duration_ms",
+                "set slot_duration_ms, base_fee_sol or both",
+            ));
+        }
+        if self.slot_duration_ms.is_some_and(|duration| {
+            !(MIN_ROOM_SLOT_DURATION_MS..=MAX_ROOM_SLOT_DURATION_MS).contains(&duration)
+        }) {
+            errors.push(FieldError::new(
+                "slot_duration_ms",
+                format!(
+                    "must be between {} and {}",
+                    MIN_ROOM_SLOT_DURATION_MS, MAX_ROOM_SLOT_DURATION_MS
+                ),
+            ));
+        }
+        if self
+            .base_fee_sol
+            .is_some_and(|fee| !(fee.is_finite() && fee > 0.0 && fee <= MAX_ROOM_BASE_FEE_SOL))
+        {
+            errors.push(FieldError::new(
+                "base_fee_sol",
+                format!("must be positive and at most {}", MAX_ROOM_BASE_FEE_SOL),
+            ));
+        }
+        errors
+    }
+}
+
+/// Most SOL a single faucet request may add.
+pub const MAX_FAUCET_AMOUNT: Lamports = INITIAL_PLAYER_BALANCE;
+
+#[derive(Deserialize, ToSchema)]
+pub struct FaucetRequest {
+    /// SOL to add to the balance, up to the starting balance; defaults to the starting balance
+    pub amount: Option<Lamports>,
+}
+
+impl Validate for FaucetRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        if self
+            .amount
+            .is_some_and(|amount| amount.is_zero() || amount > MAX_FAUCET_AMOUNT)
+        {
+            vec![FieldError::new(
+                "amount",
+                format!("must be positive and at most {} SOL", MAX_FAUCET_AMOUNT),
+            )]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Players in a competitive match unless the request asks for another size.
+pub const DEFAULT_MATCH_SIZE: usize = 4;
+pub const MIN_MATCH_SIZE: usize = 2;
+pub const MAX_MATCH_SIZE: usize = 16;
+
+#[derive(Deserialize, ToSchema)]
+pub struct JoinQueueRequest {
+    /// Players per match, 4 by default; only players asking for the same size
+    /// are matched together
+    pub match_size: Option<usize>,
+}
+
+impl Validate for JoinQueueRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        if self
+            .match_size
+            .is_some_and(|size| !(MIN_MATCH_SIZE..=MAX_MATCH_SIZE).contains(&size))
+        {
+            vec![FieldError::new(
+                "match_size",
+                format!("must be between {} and {}", MIN_MATCH_SIZE, MAX_MATCH_SIZE),
+            )]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct JoinRoomRequest {
+    pub code: String,
+}
+
+impl Validate for JoinRoomRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.code.trim().is_empty() {
+            vec![FieldError::new("code", "must not be empty")]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Validate for RecoverSessionRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.recovery_code.trim().is_empty() {
+            vec![FieldError::new("recovery_code", "must not be empty")]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Validate for CreateRoomRequest {
+    fn validate(&self) -> Vec<FieldError> {
+        let length = self.name.trim().chars().count();
+        if length == 0 {
+            vec![FieldError::new("name", "must not be empty")]
+        } else if length > MAX_ROOM_NAME_LENGTH {
+            vec![FieldError::new(
+                "name",
+                format!("must be at most {} characters", MAX_ROOM_NAME_LENGTH),
+            )]
+        } else {
+            Vec::new()
+        }
+    }
+}
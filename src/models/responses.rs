@@ -1,35 +1,1114 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use utoipa::ToSchema;
 
+#[cfg(feature = "server")]
+use crate::{
+    INITIAL_PLAYER_BALANCE, JIT_PREMIUM_MULTIPLIER, MAX_BID_AMOUNT, MAX_COMPUTE_UNITS_PER_SLOT,
+    MAX_MEMO_LENGTH, MAX_PAYLOAD_BYTES_PER_SLOT, MAX_SLOT_QUERY_RANGE, MAX_TAG_LENGTH,
+    MAX_TAGS_PER_TRANSACTION, MIN_AOT_BID_INCREMENT, app::room::Room, config::GlobalConfig,
+    error::AppError,
+};
+use crate::{
+    managers::{
+        analytics::{
+            BidSizeLatency, DistanceDemand, FeeWindow, PriceBucket, PriceResolution, TypeLatency,
+        },
+        restriction::{AuctionRestriction, RestrictionSummary},
+    },
+    models::{
+        admin::{AbuseFlag, BanRecord},
+        api_key::ApiKey,
+        auction::{AotAuction, JitAuction, ResolvedAuction},
+        marketplace::MarketplaceStats,
+        player::{GameMode, PlayerStats, session_display_name},
+        requests::UpgradeMethod,
+        slot::{Slot, SlotReservation, SlotState},
+        transaction::Transaction,
+        types::{Lamports, TransactionType},
+    },
+    utils::request_id::current_request_id,
+};
+
 #[derive(Deserialize, Serialize, ToSchema)]
-pub struct ApiResponse {
+pub struct ApiResponse<T = Value> {
     success: bool,
     message: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    data: Option<Value>,
+    data: Option<T>,
 
     #[schema(example = 200)]
     code: u32,
+
+    /// Machine-readable reason for a failure, e.g. `bid_too_low`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_code: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
-impl ApiResponse {
-    pub fn success(message: String, data: Value) -> Self {
+impl<T> ApiResponse<T> {
+    pub fn success(message: String, data: T) -> Self {
         Self {
             success: true,
             message,
-            data: { if data.is_null() { None } else { Some(data) } },
+            data: Some(data),
             code: 200,
+            error_code: None,
+            request_id: None,
         }
     }
 
+    pub fn is_success(&self) -> bool {
+        self.success
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn error_code(&self) -> Option<&str> {
+        self.error_code.as_deref()
+    }
+
+    pub fn into_data(self) -> Option<T> {
+        self.data
+    }
+}
+
+impl ApiResponse {
+    /// A failure with the generic error code for its HTTP status.
     pub fn failure(message: impl Into<String>, code: u32) -> Self {
         Self {
             success: false,
             message: message.into(),
             data: None,
             code,
+            error_code: Some(generic_error_code(code).as_str().to_string()),
+            request_id: current_request_id(),
+        }
+    }
+
+    pub fn with_error_code(mut self, error_code: impl Into<String>) -> Self {
+        self.error_code = Some(error_code.into());
+        self
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+fn generic_error_code(code: u32) -> ErrorCode {
+    match code {
+        400 => ErrorCode::BadRequest,
+        401 => ErrorCode::Unauthorized,
+        402 => ErrorCode::PaymentRequired,
+        403 => ErrorCode::Forbidden,
+        404 => ErrorCode::NotFound,
+        409 => ErrorCode::Conflict,
+        422 => ErrorCode::UnprocessableEntity,
+        429 => ErrorCode::RateLimited,
+        503 => ErrorCode::Unavailable,
+        _ => ErrorCode::InternalError,
+    }
+}
+
+/// Every `error_code` a failed request may carry. Specific codes come from
+/// `AppError`; the generic ones cover failures outside a handler, such as
+/// rate limiting or a malformed body.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Unauthorized,
+    InvalidSessionToken,
+    PlayerBanned,
+    SpectatorReadOnly,
+    BidCooldown,
+    InvalidSlot,
+    SlotBeyondWindow,
+    InvalidDependency,
+    PayloadTooLarge,
+    ValidationFailed,
+    InsufficientBalance,
+    BalanceLimitExceeded,
+    BidTooLow,
+    BidNotHigher,
+    AuctionClosed,
+    AuctionNotFound,
+    AuctionRestricted,
+    IdempotencyKeyInUse,
+    IdempotencyKeyReused,
+    StaleNonce,
+    TransactionNotFound,
+    TransactionNotCancellable,
+    TransactionNotRetryable,
+    RoomNotFound,
+    RoomMemberNotFound,
+    MatchNotStarted,
+    SandboxOnly,
+    AlreadyQueued,
+    NotQueued,
+    ChainNotFound,
+    RoomLimitReached,
+    NotRoomMember,
+    NotRoomCreator,
+    InvalidInvite,
+    InvalidRecoveryCode,
+    BidsPending,
+    WalletNotLinked,
+    SchemaNotFound,
+    UnsupportedSchemaVersion,
+    BadRequest,
+    PaymentRequired,
+    Forbidden,
+    NotFound,
+    Conflict,
+    UnprocessableEntity,
+    RateLimited,
+    Unavailable,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unauthorized => "unauthorized",
+            Self::InvalidSessionToken => "invalid_session_token",
+            Self::PlayerBanned => "player_banned",
+            Self::SpectatorReadOnly => "spectator_read_only",
+            Self::BidCooldown => "bid_cooldown",
+            Self::InvalidSlot => "invalid_slot",
+            Self::SlotBeyondWindow => "slot_beyond_window",
+            Self::InvalidDependency => "invalid_dependency",
+            Self::PayloadTooLarge => "payload_too_large",
+            Self::ValidationFailed => "validation_failed",
+            Self::InsufficientBalance => "insufficient_balance",
+            Self::BalanceLimitExceeded => "balance_limit_exceeded",
+            Self::BidTooLow => "bid_too_low",
+            Self::BidNotHigher => "bid_not_higher",
+            Self::AuctionClosed => "auction_closed",
+            Self::AuctionNotFound => "auction_not_found",
+            Self::AuctionRestricted => "auction_restricted",
+            Self::IdempotencyKeyInUse => "idempotency_key_in_use",
+            Self::IdempotencyKeyReused => "idempotency_key_reused",
+            Self::StaleNonce => "stale_nonce",
+            Self::TransactionNotFound => "transaction_not_found",
+            Self::TransactionNotCancellable => "transaction_not_cancellable",
+            Self::TransactionNotRetryable => "transaction_not_retryable",
+            Self::RoomNotFound => "room_not_found",
+            Self::RoomMemberNotFound => "room_member_not_found",
+            Self::MatchNotStarted => "match_not_started",
+            Self::SandboxOnly => "sandbox_only",
+            Self::AlreadyQueued => "already_queued",
+            Self::NotQueued => "not_queued",
+            Self::ChainNotFound => "chain_not_found",
+            Self::RoomLimitReached => "room_limit_reached",
+            Self::NotRoomMember => "not_room_member",
+            Self::NotRoomCreator => "not_room_creator",
+            Self::InvalidInvite => "invalid_invite",
+            Self::InvalidRecoveryCode => "invalid_recovery_code",
+            Self::BidsPending => "bids_pending",
+            Self::WalletNotLinked => "wallet_not_linked",
+            Self::SchemaNotFound => "schema_not_found",
+            Self::UnsupportedSchemaVersion => "unsupported_schema_version",
+            Self::BadRequest => "bad_request",
+            Self::PaymentRequired => "payment_required",
+            Self::Forbidden => "forbidden",
+            Self::NotFound => "not_found",
+            Self::Conflict => "conflict",
+            Self::UnprocessableEntity => "unprocessable_entity",
+            Self::RateLimited => "rate_limited",
+            Self::Unavailable => "unavailable",
+            Self::InternalError => "internal_error",
+        }
+    }
+}
+
+/// The body of every failed request: an `ApiResponse` with `success` false
+/// and an `error_code`. `data`, when present, holds details specific to the
+/// code, e.g. `minimum_bid` for `bid_too_low` or `errors` for
+/// `validation_failed`.
+#[derive(Deserialize, Serialize, ToSchema)]
+#[schema(example = json!({
+    "success": false,
+    "message": "Bid must exceed current highest bid of 0.0150 SOL",
+    "data": { "current_highest_bid": 0.015 },
+    "code": 400,
+    "error_code": "bid_not_higher",
+    "request_id": "0b6f7c52-0d4e-4a8f-9a57-3f8f2d1c9e41"
+}))]
+pub struct ErrorResponse {
+    #[schema(example = false)]
+    pub success: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub data: Option<Value>,
+    #[schema(example = 400)]
+    pub code: u32,
+    pub error_code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// A slot as listed to clients. `execution_order` is only filled in when a
+/// single slot is fetched.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SlotView {
+    pub slot_number: u64,
+    pub state: SlotState,
+    pub estimated_time: DateTime<Utc>,
+    pub base_fee: Lamports,
+    pub compute_units_available: u64,
+    pub compute_units_used: u64,
+    pub payload_bytes_available: usize,
+    pub payload_bytes_used: usize,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_order: Option<Vec<String>>,
+}
+
+impl SlotView {
+    pub fn summary(slot: &Slot) -> Self {
+        Self {
+            slot_number: slot.slot_number,
+            state: slot.state.clone(),
+            estimated_time: slot.estimated_time,
+            base_fee: slot.base_fee,
+            compute_units_available: slot.compute_units_available,
+            compute_units_used: slot.compute_units_used,
+            payload_bytes_available: slot.payload_bytes_available,
+            payload_bytes_used: slot.payload_bytes_used,
+            execution_order: None,
+        }
+    }
+
+    pub fn detail(slot: &Slot) -> Self {
+        Self {
+            execution_order: Some(slot.execution_order.clone()),
+            ..Self::summary(slot)
+        }
+    }
+}
+
+/// A transaction executed in a slot and what it consumed.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SlotTransaction {
+    pub transaction_id: String,
+    pub sender: String,
+    pub transaction_type: TransactionType,
+    pub compute_units: u64,
+    pub priority_fee: Lamports,
+}
+
+impl From<&Transaction> for SlotTransaction {
+    fn from(transaction: &Transaction) -> Self {
+        Self {
+            transaction_id: transaction.id.clone(),
+            sender: transaction.sender.clone(),
+            transaction_type: transaction.transaction_type(),
+            compute_units: transaction.compute_units,
+            priority_fee: transaction.priority_fee,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SlotDetail {
+    #[serde(flatten)]
+    pub slot: SlotView,
+    pub reservation: Option<SlotReservation>,
+    /// Executed transactions in execution order
+    pub transactions: Vec<SlotTransaction>,
+    /// Escrow paid out for the slot's executed bids
+    pub fees_collected: Lamports,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SlotList {
+    pub current_slot: u64,
+    pub slots: Vec<SlotView>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SlotSnapshot {
+    pub current_slot: u64,
+    pub slots: Vec<Slot>,
+}
+
+/// An open auction. JIT and AOT auctions share the leading fields; the
+/// AOT-only ones are omitted for JIT auctions.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AuctionView {
+    pub slot_number: u64,
+    pub min_bid: Lamports,
+    pub highest_bidder: Option<String>,
+    pub highest_bid: Option<Lamports>,
+    pub created_at: DateTime<Utc>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bids_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_ended: Option<bool>,
+    /// Present when only allowlisted sessions may bid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restriction: Option<RestrictionSummary>,
+}
+
+impl From<&JitAuction> for AuctionView {
+    fn from(auction: &JitAuction) -> Self {
+        let highest = auction.current_highest_bidder.clone();
+        Self {
+            slot_number: auction.slot_number,
+            min_bid: auction.min_bid,
+            highest_bid: highest.as_ref().map(|(_, amount)| *amount),
+            highest_bidder: highest.map(|(bidder, _)| bidder),
+            created_at: auction.created_at,
+            bids_count: None,
+            ends_at: None,
+            has_ended: None,
+            restriction: None,
+        }
+    }
+}
+
+impl From<&AotAuction> for AuctionView {
+    fn from(auction: &AotAuction) -> Self {
+        let highest = auction.get_highest_bid();
+        Self {
+            slot_number: auction.slot_number,
+            min_bid: auction.min_bid,
+            highest_bid: highest.map(|(_, amount, _)| *amount),
+            highest_bidder: highest.map(|(bidder, _, _)| bidder.clone()),
+            created_at: auction.created_at,
+            bids_count: Some(auction.bids.len()),
+            ends_at: Some(auction.ends_at),
+            has_ended: Some(auction.has_ended()),
+            restriction: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BidView {
+    pub bidder: String,
+    pub bidder_alias: String,
+    pub amount: Lamports,
+    /// Only tracked for AOT bids
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placed_at: Option<DateTime<Utc>>,
+}
+
+impl BidView {
+    fn new(
+        bidder: &str,
+        amount: Lamports,
+        placed_at: Option<DateTime<Utc>>,
+        aliases: &HashMap<String, String>,
+    ) -> Self {
+        Self {
+            bidder: bidder.to_string(),
+            bidder_alias: aliases
+                .get(bidder)
+                .cloned()
+                .unwrap_or_else(|| session_display_name(bidder)),
+            amount,
+            placed_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct JitAuctionDetail {
+    pub slot_number: u64,
+    pub min_bid: Lamports,
+    pub min_next_bid: Lamports,
+    pub leader: Option<BidView>,
+    pub created_at: DateTime<Utc>,
+    /// When the auction's slot starts and the leader wins
+    pub resolves_at: Option<DateTime<Utc>>,
+    pub time_remaining_ms: Option<i64>,
+    /// Present when only allowlisted sessions may bid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restriction: Option<RestrictionSummary>,
+}
+
+impl JitAuctionDetail {
+    pub fn new(
+        auction: &JitAuction,
+        resolves_at: Option<DateTime<Utc>>,
+        aliases: &HashMap<String, String>,
+    ) -> Self {
+        Self {
+            slot_number: auction.slot_number,
+            min_bid: auction.min_bid,
+            min_next_bid: auction.min_next_bid(),
+            leader: auction
+                .current_highest_bidder
+                .as_ref()
+                .map(|(bidder, amount)| BidView::new(bidder, *amount, None, aliases)),
+            created_at: auction.created_at,
+            resolves_at,
+            time_remaining_ms: resolves_at.map(time_remaining_ms),
+            restriction: None,
         }
     }
 }
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AotAuctionDetail {
+    pub slot_number: u64,
+    pub min_bid: Lamports,
+    pub min_next_bid: Lamports,
+    /// Every bid, highest first
+    pub bids: Vec<BidView>,
+    pub created_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub time_remaining_ms: i64,
+    pub has_ended: bool,
+    /// Present when only allowlisted sessions may bid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restriction: Option<RestrictionSummary>,
+}
+
+impl AotAuctionDetail {
+    pub fn new(auction: &AotAuction, aliases: &HashMap<String, String>) -> Self {
+        Self {
+            slot_number: auction.slot_number,
+            min_bid: auction.min_bid,
+            min_next_bid: auction.get_min_next_bid(),
+            bids: auction
+                .bid_ladder()
+                .into_iter()
+                .map(|(bidder, amount, placed_at)| {
+                    BidView::new(bidder, *amount, Some(*placed_at), aliases)
+                })
+                .collect(),
+            created_at: auction.created_at,
+            ends_at: auction.ends_at,
+            time_remaining_ms: time_remaining_ms(auction.ends_at),
+            has_ended: auction.has_ended(),
+            restriction: None,
+        }
+    }
+}
+
+fn time_remaining_ms(until: DateTime<Utc>) -> i64 {
+    (until - Utc::now()).num_milliseconds().max(0)
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AuctionList {
+    pub auctions: Vec<AuctionView>,
+    pub count: usize,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AuctionHistoryList {
+    pub auctions: Vec<ResolvedAuction>,
+    pub count: usize,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct MarketplaceStatus {
+    pub current_slot: u64,
+    pub stats: MarketplaceStats,
+    pub slot_time_ms: i64,
+    pub base_fee_sol: f64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct PriceSeriesView {
+    pub resolution: PriceResolution,
+    pub bucket_secs: i64,
+    /// Buckets with at least one sample, oldest first
+    pub buckets: Vec<PriceBucket>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct DemandHeatmapView {
+    pub bucket_size: u64,
+    pub total_bids: u64,
+    /// Distance ranges with at least one bid, nearest first
+    pub buckets: Vec<DistanceDemand>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct LatencyView {
+    pub by_type: TypeLatency,
+    /// Bid size ranges, smallest first
+    pub by_bid_size: Vec<BidSizeLatency>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct FeeStatsView {
+    /// One entry per requested window, in the order requested
+    pub windows: Vec<FeeWindow>,
+}
+
+/// Effective limits and settings, so clients need not hard-code them. Secrets
+/// such as admin tokens and the RPC URL are deliberately left out.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct MarketplaceSettings {
+    pub limits: BidLimits,
+    pub slots: SlotSettings,
+    pub auctions: AuctionSettings,
+    pub sessions: SessionSettings,
+    /// Requests per second allowed for each route class
+    pub rate_limits: RateLimitSettings,
+    pub sse_flush_interval_ms: u64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BidLimits {
+    pub max_compute_units_per_slot: u64,
+    pub max_bid_amount: Lamports,
+    pub max_transaction_data_bytes: usize,
+    /// Whether `data` must be standard base64
+    pub require_base64_payload: bool,
+    pub max_payload_bytes_per_slot: usize,
+    pub max_memo_length: usize,
+    pub max_tags_per_transaction: usize,
+    pub max_tag_length: usize,
+    pub min_aot_bid_increment: Lamports,
+    /// JIT minimum bid as a multiple of the base fee
+    pub jit_premium_multiplier: f64,
+    pub initial_player_balance: Lamports,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SlotSettings {
+    pub slot_duration_ms: i64,
+    pub advance_slot_interval_ms: u64,
+    pub base_fee_sol: f64,
+    pub slot_lookahead: u64,
+    pub listing_window_slots: u64,
+    pub aot_max_lookahead_slots: u64,
+    pub transaction_ttl_slots: u64,
+    pub max_slot_query_range: u64,
+    /// Whether slots follow a real cluster instead of a local timer
+    pub rpc_sync: bool,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AuctionSettings {
+    pub aot_default_duration_secs: i64,
+    pub self_outbid_limit_per_minute: u32,
+    pub abuse_cooldown_secs: u64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SessionSettings {
+    pub max_concurrent_per_identity: usize,
+    pub idempotency_key_ttl_secs: u64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RateLimitSettings {
+    pub read: u32,
+    pub write: u32,
+    pub auth: u32,
+}
+
+#[cfg(feature = "server")]
+impl From<&GlobalConfig> for MarketplaceSettings {
+    fn from(config: &GlobalConfig) -> Self {
+        let marketplace = &config.marketplace;
+        Self {
+            limits: BidLimits {
+                max_compute_units_per_slot: MAX_COMPUTE_UNITS_PER_SLOT,
+                max_bid_amount: MAX_BID_AMOUNT,
+                max_transaction_data_bytes: marketplace.max_payload_bytes,
+                require_base64_payload: marketplace.require_base64_payload,
+                max_payload_bytes_per_slot: MAX_PAYLOAD_BYTES_PER_SLOT,
+                max_memo_length: MAX_MEMO_LENGTH,
+                max_tags_per_transaction: MAX_TAGS_PER_TRANSACTION,
+                max_tag_length: MAX_TAG_LENGTH,
+                min_aot_bid_increment: MIN_AOT_BID_INCREMENT,
+                jit_premium_multiplier: JIT_PREMIUM_MULTIPLIER,
+                initial_player_balance: INITIAL_PLAYER_BALANCE,
+            },
+            slots: SlotSettings {
+                slot_duration_ms: marketplace.slot_duration_ms,
+                advance_slot_interval_ms: marketplace.advance_slot_interval_ms,
+                base_fee_sol: marketplace.base_fee_sol,
+                slot_lookahead: marketplace.slot_lookahead,
+                listing_window_slots: marketplace.listing_window_slots,
+                aot_max_lookahead_slots: marketplace.aot_max_lookahead_slots,
+                transaction_ttl_slots: marketplace.transaction_ttl_slots,
+                max_slot_query_range: MAX_SLOT_QUERY_RANGE,
+                rpc_sync: marketplace.rpc_sync_url.is_some(),
+            },
+            auctions: AuctionSettings {
+                aot_default_duration_secs: config.auction.aot_default_duration_sec,
+                self_outbid_limit_per_minute: config.abuse.self_outbid_limit_per_minute,
+                abuse_cooldown_secs: config.abuse.cooldown_secs,
+            },
+            sessions: SessionSettings {
+                max_concurrent_per_identity: config.session.max_concurrent_per_identity,
+                idempotency_key_ttl_secs: config.session.idempotency_key_ttl_secs,
+            },
+            rate_limits: RateLimitSettings {
+                read: config.rate_limit.read.requests_per_second,
+                write: config.rate_limit.write.requests_per_second,
+                auth: config.rate_limit.auth.requests_per_second,
+            },
+            sse_flush_interval_ms: config.events.flush_interval_ms,
+        }
+    }
+}
+
+/// The outcome of an accepted bid; the auction itself resolves later.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BidReceipt {
+    pub transaction_id: String,
+    pub slot_number: u64,
+    pub bid_amount: Lamports,
+    #[schema(example = "auction_pending")]
+    pub status: String,
+}
+
+impl BidReceipt {
+    pub fn pending(transaction_id: String, slot_number: u64, bid_amount: Lamports) -> Self {
+        Self {
+            transaction_id,
+            slot_number,
+            bid_amount,
+            status: "auction_pending".to_string(),
+        }
+    }
+}
+
+/// The bids a bundle placed, in the order they were sent.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BundleReceipt {
+    pub legs: Vec<BidReceipt>,
+    /// Escrow held across every leg
+    pub total_bid: Lamports,
+}
+
+/// What placing a bid right now would do, without placing it.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BidSimulation {
+    /// Whether the bid would be accepted
+    pub accepted: bool,
+    /// Every reason it would be rejected, with the error the bid would get
+    pub rejections: Vec<BidRejection>,
+    pub slot_number: u64,
+    pub auction_type: TransactionType,
+    pub bid_amount: Lamports,
+    /// Smallest bid that would take the lead right now
+    pub min_winning_bid: Lamports,
+    pub available_balance: Lamports,
+    pub competition: AuctionCompetition,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BidRejection {
+    pub error_code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub data: Option<Value>,
+}
+
+#[cfg(feature = "server")]
+impl From<&AppError> for BidRejection {
+    fn from(error: &AppError) -> Self {
+        Self {
+            error_code: error.error_code(),
+            message: error.to_string(),
+            data: error.details(),
+        }
+    }
+}
+
+/// The slot's auction as it stands.
+#[derive(Deserialize, Serialize, ToSchema, Default)]
+pub struct AuctionCompetition {
+    /// Whether the auction has opened; a bid on a slot without one opens it
+    pub open: bool,
+    pub highest_bid: Option<Lamports>,
+    /// Display name of the current leader
+    pub leader: Option<String>,
+    /// Whether the session itself leads
+    pub leading: bool,
+    pub bid_count: usize,
+    pub bidder_count: usize,
+    /// When bidding closes: the AOT auction's end, or the JIT slot's start
+    pub closes_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct Pagination {
+    pub current_page: u32,
+    pub total_pages: u32,
+    pub page_size: u32,
+    pub total_count: u32,
+    pub has_next: bool,
+    pub has_prev: bool,
+}
+
+impl Pagination {
+    pub fn new(page: u32, page_size: u32, total_count: u32) -> Self {
+        let total_pages = total_count.div_ceil(page_size);
+        Self {
+            current_page: page,
+            total_pages,
+            page_size,
+            total_count,
+            has_next: page < total_pages,
+            has_prev: page > 1,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionScope {
+    All,
+    SessionOnly,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct TransactionPage {
+    pub session_id: Option<String>,
+    pub transactions: Vec<Transaction>,
+    pub pagination: Pagination,
+    pub showing: TransactionScope,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct TransactionDetail {
+    pub transaction: Transaction,
+}
+
+/// A withdrawn bid, now `Cancelled`, and the escrow returned for it.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BidCancellation {
+    pub transaction: Transaction,
+    pub refunded: Lamports,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStatus {
+    Created,
+    Validated,
+    /// Moved to a new ID by `POST /sessions/refresh`
+    Refreshed,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SessionView {
+    pub session_id: String,
+    /// Signed form of `session_id`, as stored in the cookie; pass it as the
+    /// `session_id` query parameter where cookies aren't sent
+    pub session_token: String,
+    pub device_id: String,
+    pub status: SessionStatus,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Read-only session that can watch but not bid
+    pub spectator: bool,
+}
+
+/// A newly issued API key; `api_key` is the only time the plaintext is shown.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ApiKeyCreated {
+    pub api_key: String,
+    pub id: String,
+    pub name: Option<String>,
+    pub prefix: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ApiKeyList {
+    pub api_keys: Vec<ApiKey>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct DeviceView {
+    pub device_id: String,
+    /// Whether this is the session making the request
+    pub current: bool,
+    pub user_agent: Option<String>,
+    /// Address of the session's latest recorded request
+    #[schema(value_type = Option<String>)]
+    pub ip: Option<IpAddr>,
+    /// Address the session was created from
+    #[schema(value_type = Option<String>)]
+    pub created_ip: Option<IpAddr>,
+    pub created_at: DateTime<Utc>,
+    /// When the session was last seen
+    pub last_active: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ActiveSessions {
+    pub wallet: Option<String>,
+    pub sessions: Vec<DeviceView>,
+}
+
+/// A session that was ended, with the escrow returned from its open bids.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SessionEnded {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    pub refunded: Lamports,
+}
+
+/// A guest session moved to an account ID.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct GuestUpgraded {
+    pub session_id: String,
+    /// Signed form of `session_id`, also set as the cookie
+    pub session_token: String,
+    pub method: UpgradeMethod,
+    /// Issued for `recovery_code` upgrades; it is only shown once
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_code: Option<String>,
+    /// Transactions carried over to the account
+    pub transaction_count: u32,
+}
+
+/// What was left of a player who deleted their data.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct PlayerDataDeleted {
+    pub refunded: Lamports,
+    /// Transactions kept for market totals under an anonymous sender
+    pub anonymized_transactions: u32,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct WalletLinked {
+    pub wallet: String,
+    pub display_name: String,
+    pub player_stats: PlayerStats,
+    /// Device IDs signed out to stay within the per-wallet session limit
+    pub signed_out_devices: Vec<String>,
+}
+
+/// A recovery code; this is the only time it is shown.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RecoveryCodeIssued {
+    #[schema(example = "K7QM-2XRP-VD9H-T4NA")]
+    pub recovery_code: String,
+    pub issued_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SessionRecovered {
+    pub player_stats: PlayerStats,
+    /// Replaces the code that was used, which no longer works
+    #[schema(example = "K7QM-2XRP-VD9H-T4NA")]
+    pub recovery_code: String,
+    /// Transactions now listed under this session
+    pub transaction_count: u32,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SimulationReset {
+    pub current_slot: u64,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BalanceAdjusted {
+    pub session_id: String,
+    pub balance: Lamports,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct PlayerKicked {
+    pub session_id: String,
+    pub refunded: Lamports,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct PlayerBanned {
+    pub ban: BanRecord,
+    pub refunded: Lamports,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct BanList {
+    pub bans: Vec<BanRecord>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RestrictionList {
+    pub restrictions: Vec<AuctionRestriction>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AbuseFlagView {
+    pub flag: AbuseFlag,
+    pub cooling_down: bool,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AbuseFlagList {
+    pub flags: Vec<AbuseFlagView>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct HealthStatus {
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct SlotClockCheck {
+    pub last_tick: Option<DateTime<Utc>>,
+    pub age_ms: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct EventChannelCheck {
+    pub queued: usize,
+    pub capacity: usize,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct StorageCheck {
+    pub backend: String,
+    pub reachable: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct TaskFailure {
+    pub restarts: u32,
+    pub last_panic_at: DateTime<Utc>,
+    pub last_panic: String,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ReadinessChecks {
+    pub slot_clock: SlotClockCheck,
+    pub event_channel: EventChannelCheck,
+    /// Background tasks that have panicked and been restarted, by name
+    pub background_tasks: HashMap<String, TaskFailure>,
+    pub storage: StorageCheck,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ReadinessReport {
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+    pub checks: ReadinessChecks,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RoomView {
+    pub room_id: String,
+    pub name: String,
+    /// Display name of the player who created the room; absent for the global room
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub private: bool,
+    pub mode: GameMode,
+    pub current_slot: u64,
+    pub slot_duration_ms: i64,
+    pub base_fee_sol: f64,
+    /// Sessions that joined the room; the global room reports none
+    pub members: usize,
+}
+
+#[cfg(feature = "server")]
+impl RoomView {
+    pub async fn new(room: &Room, members: usize) -> Result<Self, AppError> {
+        let (current_slot, slot_duration_ms, base_fee) = room
+            .state
+            .marketplace
+            .call(|marketplace| {
+                (
+                    marketplace.current_slot,
+                    marketplace.slot_duration_ms,
+                    marketplace.base_fee,
+                )
+            })
+            .await?;
+        Ok(Self {
+            room_id: room.id.clone(),
+            name: room.name.clone(),
+            created_by: room.creator().as_deref().map(session_display_name),
+            created_at: room.created_at,
+            private: room.private,
+            mode: room.state.game_mode().await?,
+            current_slot,
+            slot_duration_ms,
+            base_fee_sol: base_fee.as_sol(),
+            members,
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct RoomList {
+    pub rooms: Vec<RoomView>,
+}
+
+/// A session's place in the matchmaking queue.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct QueueTicket {
+    pub session_id: String,
+    pub match_size: usize,
+    /// 1-based place among players waiting for the same match size
+    pub position: usize,
+    /// Players waiting for the same match size, including this one
+    pub waiting: usize,
+    pub joined_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Deserialize, Serialize, ToSchema)]
+pub struct MatchFound {
+    pub room_id: String,
+    pub players: Vec<String>,
+    /// Bidding opens and slots start advancing for every player at once
+    pub starts_at: DateTime<Utc>,
+}
+
+/// A simulated chain, served under `/chains/{chain_id}`.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ChainView {
+    pub chain_id: String,
+    pub name: String,
+    pub slot_duration_ms: i64,
+    pub advance_slot_interval_ms: u64,
+    pub base_fee_sol: f64,
+    pub current_slot: u64,
+}
+
+#[cfg(feature = "server")]
+impl ChainView {
+    /// `None` unless the room simulates a chain.
+    pub async fn new(room: &Room) -> Result<Option<Self>, AppError> {
+        let Some(chain) = room.chain.as_ref() else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            chain_id: chain.id.clone(),
+            name: chain.name.clone(),
+            slot_duration_ms: chain.slot_duration_ms,
+            advance_slot_interval_ms: chain.advance_slot_interval_ms,
+            base_fee_sol: chain.base_fee_sol,
+            current_slot: room.state.get_current_slot().await?,
+        }))
+    }
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ChainList {
+    pub chains: Vec<ChainView>,
+}
@@ -1,25 +1,52 @@
+use std::net::IpAddr;
+
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Prefix of the IDs of sessions upgraded to an account with
+/// `POST /sessions/upgrade`.
+pub const ACCOUNT_ID_PREFIX: &str = "acct-";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     pub id: String,
+    /// Public handle for the session, safe to show to other devices of the same player
+    pub device_id: String,
+    /// Wallet the session signed in with, tying it to that player's other devices
+    pub wallet: Option<String>,
+    pub user_agent: Option<String>,
+    /// Address the session was created from
+    #[serde(default)]
+    pub created_ip: Option<IpAddr>,
     pub created_at: DateTime<Utc>,
     pub last_active: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
+    /// Read-only sessions can watch the game but never bid
+    #[serde(default)]
+    pub spectator: bool,
 }
 
 impl Session {
-    pub fn new(id: String) -> Self {
+    pub fn new(id: String, user_agent: Option<String>) -> Self {
         let now = Utc::now();
         Self {
             id,
+            device_id: uuid::Uuid::new_v4().to_string(),
+            wallet: None,
+            user_agent,
+            created_ip: None,
             created_at: now,
             last_active: now,
             expires_at: now + Duration::hours(24), // 24-hour expiration
+            spectator: false,
         }
     }
 
+    /// Whether the session was upgraded from a guest to an account.
+    pub fn is_account(&self) -> bool {
+        self.id.starts_with(ACCOUNT_ID_PREFIX)
+    }
+
     pub fn is_expired(&self) -> bool {
         Utc::now() > self.expires_at
     }
@@ -1,27 +1,31 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-use crate::models::types::TransactionType;
+use crate::{
+    MAX_PAYLOAD_BYTES_PER_SLOT,
+    models::types::{Lamports, TransactionType},
+};
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub enum SlotState {
     Available,
 
     JitAuction {
-        current_bid: f64,
+        current_bid: Lamports,
         bidder: String,
     },
 
     AotAuction {
-        highest_bid: f64,
+        highest_bid: Lamports,
         highest_bidder: String,
-        bids: Vec<(String, f64)>,
+        bids: Vec<(String, Lamports)>,
         ends_at: DateTime<Utc>,
     },
 
     Reserved {
         winner: String,
-        winning_bid: f64,
+        winning_bid: Lamports,
         transaction_type: TransactionType,
     },
 
@@ -34,19 +38,71 @@ pub enum SlotState {
     Expired,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// A `SlotState` without its details, for filtering.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlotStateKind {
+    Available,
+    JitAuction,
+    AotAuction,
+    Reserved,
+    Filled,
+    Expired,
+}
+
+impl SlotState {
+    pub fn kind(&self) -> SlotStateKind {
+        match self {
+            Self::Available => SlotStateKind::Available,
+            Self::JitAuction { .. } => SlotStateKind::JitAuction,
+            Self::AotAuction { .. } => SlotStateKind::AotAuction,
+            Self::Reserved { .. } => SlotStateKind::Reserved,
+            Self::Filled { .. } => SlotStateKind::Filled,
+            Self::Expired => SlotStateKind::Expired,
+        }
+    }
+}
+
+/// Who won the slot and at what price. Kept once the slot fills or expires,
+/// since its state no longer carries it.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct SlotReservation {
+    pub winner: String,
+    pub winning_bid: Lamports,
+    pub transaction_type: TransactionType,
+    pub reserved_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[schema(example = json!({
+    "slot_number": 1042,
+    "state": { "JitAuction": { "current_bid": 0.015, "bidder": "7d0b3e1c-9a4f-4c2e-b6d5-1f8e2a3c4b5d" } },
+    "estimated_time": "2025-01-01T12:00:00.400Z",
+    "base_fee": 0.005,
+    "compute_units_available": 48000000,
+    "compute_units_used": 0,
+    "payload_bytes_available": 65536,
+    "payload_bytes_used": 0,
+    "execution_order": [],
+    "created_at": "2025-01-01T11:59:56.400Z"
+}))]
 pub struct Slot {
     pub slot_number: u64,
     pub state: SlotState,
     pub estimated_time: DateTime<Utc>,
-    pub base_fee: f64,
+    pub base_fee: Lamports,
     pub compute_units_available: u64,
     pub compute_units_used: u64,
+    pub payload_bytes_available: usize,
+    pub payload_bytes_used: usize,
+    pub execution_order: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reservation: Option<SlotReservation>,
     pub created_at: DateTime<Utc>,
 }
 
 impl Slot {
-    pub fn new(slot_number: u64, estimated_time: DateTime<Utc>, base_fee: f64) -> Self {
+    pub fn new(slot_number: u64, estimated_time: DateTime<Utc>, base_fee: Lamports) -> Self {
         Self {
             slot_number,
             state: SlotState::Available,
@@ -54,6 +110,10 @@ impl Slot {
             base_fee,
             compute_units_available: 48_000_000,
             compute_units_used: 0,
+            payload_bytes_available: MAX_PAYLOAD_BYTES_PER_SLOT,
+            payload_bytes_used: 0,
+            execution_order: Vec::new(),
+            reservation: None,
             created_at: Utc::now(),
         }
     }
@@ -62,11 +122,27 @@ impl Slot {
         matches!(self.state, SlotState::Available)
     }
 
+    pub fn compute_units_remaining(&self) -> u64 {
+        self.compute_units_available
+            .saturating_sub(self.compute_units_used)
+    }
+
     pub fn is_expired(&self) -> bool {
         self.estimated_time < Utc::now()
     }
 
-    pub fn reserve(&mut self, winner: String, winning_bid: f64, transaction_type: TransactionType) {
+    pub fn reserve(
+        &mut self,
+        winner: String,
+        winning_bid: Lamports,
+        transaction_type: TransactionType,
+    ) {
+        self.reservation = Some(SlotReservation {
+            winner: winner.clone(),
+            winning_bid,
+            transaction_type,
+            reserved_at: Utc::now(),
+        });
         self.state = SlotState::Reserved {
             winner,
             winning_bid,
@@ -74,6 +150,14 @@ impl Slot {
         }
     }
 
+    pub fn set_execution_order(&mut self, transaction_ids: Vec<String>) {
+        self.execution_order = transaction_ids;
+    }
+
+    pub fn record_payload_bytes(&mut self, bytes: usize) {
+        self.payload_bytes_used += bytes;
+    }
+
     pub fn fill(&mut self, winner: String, transaction_id: String, compute_units_used: u64) {
         self.compute_units_used += compute_units_used;
         self.state = SlotState::Filled {
@@ -1,10 +1,14 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::models::types::InclusionType;
+use crate::{
+    models::types::{InclusionType, Lamports, TransactionType},
+    utils::request_id::current_request_id,
+};
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub enum TransactionStatus {
     Pending,
 
@@ -19,25 +23,142 @@ pub enum TransactionStatus {
 
     AuctionWon {
         slot: u64,
-        winning_bid: f64,
+        winning_bid: Lamports,
     },
+
+    Expired {
+        slot: u64,
+    },
+
+    /// Withdrawn by its sender before the auction resolved; the escrow was refunded
+    Cancelled,
+}
+
+/// A `TransactionStatus` without its details, for filtering and indexing.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatusKind {
+    Pending,
+    Included,
+    Failed,
+    #[serde(alias = "won")]
+    AuctionWon,
+    Expired,
+    Cancelled,
+}
+
+impl TransactionStatusKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Included => "included",
+            Self::Failed => "failed",
+            Self::AuctionWon => "auction_won",
+            Self::Expired => "expired",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Raise over the winning bid an automatic rebid makes when the policy sets none
+pub const DEFAULT_REBID_BUMP_PERCENT: f64 = 10.0;
+
+/// Rebids a lost JIT bid for the next slot, raised over the bid that won,
+/// until it wins or runs out of slots.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct RebidPolicy {
+    /// Most an automatic rebid may bid, in SOL
+    #[schema(value_type = f64)]
+    pub max_bid: Lamports,
+    /// How many more slots to rebid for
+    pub max_slots: u64,
+    /// Raise over the bid that won, in percent; defaults to 10
+    pub bump_percent: Option<f64>,
+}
+
+/// One step in a transaction's status history.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct StatusChange {
+    pub status: TransactionStatusKind,
+    pub at: DateTime<Utc>,
+    /// Slot the change happened in, where it concerns one
+    pub slot: Option<u64>,
+    /// Why the bid failed or what it won with
+    pub reason: Option<String>,
+}
+
+impl TransactionStatus {
+    pub fn kind(&self) -> TransactionStatusKind {
+        match self {
+            Self::Pending => TransactionStatusKind::Pending,
+            Self::Included { .. } => TransactionStatusKind::Included,
+            Self::Failed { .. } => TransactionStatusKind::Failed,
+            Self::AuctionWon { .. } => TransactionStatusKind::AuctionWon,
+            Self::Expired { .. } => TransactionStatusKind::Expired,
+            Self::Cancelled => TransactionStatusKind::Cancelled,
+        }
+    }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[schema(example = json!({
+    "id": "6c1d5a0e-2b7f-4a39-8d11-5e0f2c9b7a43",
+    "sender": "7d0b3e1c-9a4f-4c2e-b6d5-1f8e2a3c4b5d",
+    "inclusion_type": "Jit",
+    "status": { "AuctionWon": { "slot": 1042, "winning_bid": 0.015 } },
+    "compute_units": 200000,
+    "priority_fee": 0.015,
+    "data": "swap",
+    "created_at": "2025-01-01T12:00:00.120Z",
+    "included_at": null,
+    "created_slot": 1041,
+    "expires_at_slot": null,
+    "depends_on": null,
+    "request_id": "0b6f7c52-0d4e-4a8f-9a57-3f8f2d1c9e41",
+    "memo": "arb leg 2",
+    "tags": ["arb", "mainnet-sim"],
+    "nonce": 42,
+    "status_history": [
+        { "status": "pending", "at": "2025-01-01T12:00:00.120Z", "slot": null, "reason": null },
+        { "status": "auction_won", "at": "2025-01-01T12:00:00.400Z", "slot": 1042, "reason": "Won with a bid of 0.015 SOL" },
+        { "status": "included", "at": "2025-01-01T12:00:00.400Z", "slot": 1042, "reason": null }
+    ]
+}))]
 pub struct Transaction {
     pub id: String,
     pub sender: String,
     pub inclusion_type: InclusionType,
     pub status: TransactionStatus,
     pub compute_units: u64,
-    pub priority_fee: f64,
+    pub priority_fee: Lamports,
     pub data: String,
     pub created_at: DateTime<Utc>,
     pub included_at: Option<DateTime<Utc>>,
+    pub created_slot: u64,
+    pub expires_at_slot: Option<u64>,
+    pub depends_on: Option<String>,
+    pub request_id: Option<String>,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Client-supplied sequence number the bid was placed with
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    /// Every status the transaction has been in, oldest first
+    #[serde(default)]
+    pub status_history: Vec<StatusChange>,
+    /// Rebids the bid automatically if it loses
+    #[serde(default)]
+    pub rebid_policy: Option<RebidPolicy>,
+    /// The lost bid this one was automatically placed to replace
+    #[serde(default)]
+    pub rebid_of: Option<String>,
 }
 
 impl Transaction {
-    pub fn jit(sender: String, compute_units: u64, bid_amount: f64, data: String) -> Self {
+    pub fn jit(sender: String, compute_units: u64, bid_amount: Lamports, data: String) -> Self {
+        let now = Utc::now();
         Self {
             id: Uuid::new_v4().to_string(),
             sender,
@@ -46,18 +167,34 @@ impl Transaction {
             compute_units,
             priority_fee: bid_amount,
             data,
-            created_at: Utc::now(),
+            created_at: now,
             included_at: None,
+            created_slot: 0,
+            expires_at_slot: None,
+            depends_on: None,
+            request_id: current_request_id(),
+            memo: None,
+            tags: Vec::new(),
+            nonce: None,
+            status_history: vec![StatusChange {
+                status: TransactionStatusKind::Pending,
+                at: now,
+                slot: None,
+                reason: None,
+            }],
+            rebid_policy: None,
+            rebid_of: None,
         }
     }
 
     pub fn aot(
         sender: String,
         compute_units: u64,
-        bid_amount: f64,
+        bid_amount: Lamports,
         reserved_slot: u64,
         data: String,
     ) -> Self {
+        let now = Utc::now();
         Self {
             id: Uuid::new_v4().to_string(),
             sender,
@@ -66,24 +203,136 @@ impl Transaction {
             compute_units,
             priority_fee: bid_amount,
             data,
-            created_at: Utc::now(),
+            created_at: now,
             included_at: None,
+            created_slot: 0,
+            expires_at_slot: None,
+            depends_on: None,
+            request_id: current_request_id(),
+            memo: None,
+            tags: Vec::new(),
+            nonce: None,
+            status_history: vec![StatusChange {
+                status: TransactionStatusKind::Pending,
+                at: now,
+                slot: None,
+                reason: None,
+            }],
+            rebid_policy: None,
+            rebid_of: None,
         }
     }
 
-    pub fn mark_included(&mut self, slot: u64) {
-        self.status = TransactionStatus::Included {
-            slot,
-            execution_time: Utc::now(),
+    /// Gives the transaction a blockhash-like lifetime of `ttl_slots`, counted from
+    /// creation for JIT and from the reserved slot for AOT.
+    pub fn with_ttl(mut self, created_slot: u64, ttl_slots: u64) -> Self {
+        let start_slot = match self.inclusion_type {
+            InclusionType::Jit => created_slot,
+            InclusionType::Aot { reserved_slot } => reserved_slot.max(created_slot),
         };
-        self.included_at = Some(Utc::now());
+
+        self.created_slot = created_slot;
+        self.expires_at_slot = Some(start_slot + ttl_slots);
+        self
+    }
+
+    pub fn with_dependency(mut self, depends_on: Option<String>) -> Self {
+        self.depends_on = depends_on;
+        self
+    }
+
+    pub fn with_labels(mut self, memo: Option<String>, tags: Vec<String>) -> Self {
+        self.memo = memo;
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_nonce(mut self, nonce: Option<u64>) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    pub fn with_rebid_policy(mut self, rebid_policy: Option<RebidPolicy>) -> Self {
+        self.rebid_policy = rebid_policy;
+        self
+    }
+
+    /// Auction the transaction bids in, from how it asked to be included.
+    pub fn transaction_type(&self) -> TransactionType {
+        match self.inclusion_type {
+            InclusionType::Jit => TransactionType::Jit,
+            InclusionType::Aot { .. } => TransactionType::Aot,
+        }
+    }
+
+    /// Slot the transaction is trying to land in.
+    pub fn target_slot(&self) -> u64 {
+        match self.inclusion_type {
+            InclusionType::Jit => self.created_slot + 1,
+            InclusionType::Aot { reserved_slot } => reserved_slot,
+        }
+    }
+
+    pub fn is_expired(&self, current_slot: u64) -> bool {
+        matches!(self.status, TransactionStatus::Pending)
+            && self
+                .expires_at_slot
+                .is_some_and(|expires_at_slot| current_slot >= expires_at_slot)
+    }
+
+    /// Won the slot's auction and executed in it. The status stays
+    /// `AuctionWon`, which carries the winning bid; the history records both.
+    pub fn mark_won(&mut self, slot: u64, winning_bid: Lamports) {
+        let now = Utc::now();
+        self.status = TransactionStatus::AuctionWon { slot, winning_bid };
+        self.included_at = Some(now);
+        self.record_status(
+            TransactionStatusKind::AuctionWon,
+            now,
+            Some(slot),
+            Some(format!("Won with a bid of {} SOL", winning_bid)),
+        );
+        self.record_status(TransactionStatusKind::Included, now, Some(slot), None);
     }
 
     pub fn mark_failed(&mut self, reason: String) {
+        let slot = self.target_slot();
+        self.record_status(
+            TransactionStatusKind::Failed,
+            Utc::now(),
+            Some(slot),
+            Some(reason.clone()),
+        );
         self.status = TransactionStatus::Failed { reason };
     }
 
-    pub fn mark_auction_won(&mut self, slot: u64, winning_bid: f64) {
-        self.status = TransactionStatus::AuctionWon { slot, winning_bid };
+    pub fn mark_expired(&mut self, slot: u64) {
+        self.status = TransactionStatus::Expired { slot };
+        self.record_status(TransactionStatusKind::Expired, Utc::now(), Some(slot), None);
+    }
+
+    pub fn mark_cancelled(&mut self) {
+        self.status = TransactionStatus::Cancelled;
+        self.record_status(
+            TransactionStatusKind::Cancelled,
+            Utc::now(),
+            None,
+            Some("Withdrawn by sender".to_string()),
+        );
+    }
+
+    fn record_status(
+        &mut self,
+        status: TransactionStatusKind,
+        at: DateTime<Utc>,
+        slot: Option<u64>,
+        reason: Option<String>,
+    ) {
+        self.status_history.push(StatusChange {
+            status,
+            at,
+            slot,
+            reason,
+        });
     }
 }
@@ -1,13 +1,142 @@
-use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use utoipa::{
+    PartialSchema, ToSchema,
+    openapi::{RefOr, Schema},
+};
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash, ToSchema)]
 pub enum TransactionType {
+    #[serde(alias = "jit")]
     Jit,
+    #[serde(alias = "aot")]
     Aot,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub enum InclusionType {
     Jit,
     Aot { reserved_slot: u64 },
 }
+
+/// Integer amount of lamports used for all internal money arithmetic.
+///
+/// Values are serialized as SOL so the wire format stays a plain number. The
+/// operators saturate instead of panicking or wrapping; paths that move a
+/// player's funds use `checked_add` and `checked_sub` and reject the change.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lamports(pub u64);
+
+impl Lamports {
+    pub const ZERO: Lamports = Lamports(0);
+
+    pub const fn from_whole_sol(sol: u64) -> Self {
+        Self(sol * LAMPORTS_PER_SOL)
+    }
+
+    /// Converts a SOL amount into lamports, rounding to the nearest lamport.
+    /// Negative and non-finite inputs saturate to zero.
+    pub fn from_sol(sol: f64) -> Self {
+        Self((sol * LAMPORTS_PER_SOL as f64).round() as u64)
+    }
+
+    pub fn as_sol(self) -> f64 {
+        self.0 as f64 / LAMPORTS_PER_SOL as f64
+    }
+
+    pub fn scale(self, multiplier: f64) -> Self {
+        Self((self.0 as f64 * multiplier).round() as u64)
+    }
+
+    pub fn checked_add(self, other: Lamports) -> Option<Lamports> {
+        self.0.checked_add(other.0).map(Lamports)
+    }
+
+    pub fn checked_sub(self, other: Lamports) -> Option<Lamports> {
+        self.0.checked_sub(other.0).map(Lamports)
+    }
+
+    pub fn saturating_sub(self, other: Lamports) -> Lamports {
+        Lamports(self.0.saturating_sub(other.0))
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Add for Lamports {
+    type Output = Lamports;
+
+    fn add(self, other: Lamports) -> Lamports {
+        Lamports(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Lamports {
+    fn add_assign(&mut self, other: Lamports) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Lamports {
+    type Output = Lamports;
+
+    fn sub(self, other: Lamports) -> Lamports {
+        self.saturating_sub(other)
+    }
+}
+
+impl SubAssign for Lamports {
+    fn sub_assign(&mut self, other: Lamports) {
+        *self = self.saturating_sub(other);
+    }
+}
+
+impl Sum for Lamports {
+    fn sum<I: Iterator<Item = Lamports>>(iter: I) -> Lamports {
+        iter.fold(Lamports::ZERO, |acc, amount| acc + amount)
+    }
+}
+
+impl fmt::Display for Lamports {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.as_sol(), f)
+    }
+}
+
+impl Serialize for Lamports {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.as_sol())
+    }
+}
+
+/// Documented as the SOL amount it is serialized as.
+impl PartialSchema for Lamports {
+    fn schema() -> RefOr<Schema> {
+        f64::schema()
+    }
+}
+
+impl ToSchema for Lamports {}
+
+impl<'de> Deserialize<'de> for Lamports {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let sol = f64::deserialize(deserializer)?;
+        if !sol.is_finite() || sol < 0.0 {
+            return Err(serde::de::Error::custom(format!(
+                "invalid SOL amount {}, expected a non-negative number",
+                sol
+            )));
+        }
+
+        Ok(Lamports::from_sol(sol))
+    }
+}
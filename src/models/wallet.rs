@@ -0,0 +1,91 @@
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+const SIGN_IN_DOMAIN: &str = "raiku-simulator";
+const CHALLENGE_TTL_SECS: i64 = 300;
+
+/// A pending Sign-in-with-Solana request: the wallet must sign `message` before `expires_at`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WalletChallenge {
+    pub public_key: String,
+    pub nonce: String,
+    pub message: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl WalletChallenge {
+    pub fn new(public_key: String) -> Self {
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        let issued_at = Utc::now();
+        let expires_at = issued_at + Duration::seconds(CHALLENGE_TTL_SECS);
+
+        let message = format!(
+            "{domain} wants you to sign in with your Solana account:\n\
+             {public_key}\n\n\
+             Link this wallet to your Raiku Simulator player.\n\n\
+             Nonce: {nonce}\n\
+             Issued At: {issued_at}\n\
+             Expiration Time: {expires_at}",
+            domain = SIGN_IN_DOMAIN,
+            public_key = public_key,
+            nonce = nonce,
+            issued_at = issued_at.to_rfc3339(),
+            expires_at = expires_at.to_rfc3339(),
+        );
+
+        Self {
+            public_key,
+            nonce,
+            message,
+            issued_at,
+            expires_at,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Utc::now() > self.expires_at
+    }
+
+    /// Checks that `signature` (base58) is the wallet's ed25519 signature of the challenge message.
+    pub fn verify(&self, signature: &str) -> Result<(), String> {
+        let public_key = parse_public_key(&self.public_key)?;
+
+        let signature: [u8; 64] = bs58::decode(signature)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "Signature must be 64 base58-encoded bytes".to_string())?;
+
+        public_key
+            .verify_strict(self.message.as_bytes(), &Signature::from_bytes(&signature))
+            .map_err(|_| "Signature does not match the challenge message".to_string())
+    }
+}
+
+pub fn parse_public_key(public_key: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = bs58::decode(public_key)
+        .into_vec()
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Public key must be 32 base58-encoded bytes".to_string())?;
+
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|_| "Public key is not a valid ed25519 key".to_string())
+}
+
+/// Shortened wallet address for display, e.g. `7xKX…gAsU`.
+pub fn wallet_display_name(public_key: &str) -> String {
+    let chars: Vec<char> = public_key.chars().collect();
+    if chars.len() <= 8 {
+        return public_key.to_string();
+    }
+
+    format!(
+        "{}…{}",
+        chars[..4].iter().collect::<String>(),
+        chars[chars.len() - 4..].iter().collect::<String>()
+    )
+}
@@ -0,0 +1,508 @@
+//! A single-player practice game that runs without the server: one player
+//! against a few rival bidders, advanced one slot per `tick`. It is built on
+//! the same auction, escrow, slot and game types as the server and settles
+//! auctions with the same functions in `utils::auction`, so bids are accepted,
+//! resolved, refunded and rewarded by the same rules, and it emits the same
+//! events the server streams.
+
+use std::collections::{BTreeMap, HashMap};
+
+use rand::Rng;
+use serde::Serialize;
+
+use crate::{
+    JIT_PREMIUM_MULTIPLIER,
+    error::AppError,
+    managers::{
+        auction::{AuctionHistory, AuctionManager},
+        escrow::EscrowManager,
+        game::GameManager,
+        restriction::{AuctionRestriction, RestrictionManager},
+    },
+    models::{
+        auction::ResolvedAuction,
+        event::AppEvent,
+        marketplace::{SlotMarketplace, SlotWindow},
+        player::PlayerStats,
+        requests::validate_bid_fields,
+        slot::Slot,
+        transaction::{Transaction, TransactionStatus},
+        types::{InclusionType, Lamports, TransactionType},
+    },
+    utils::auction::{
+        bid_slot_state, distinct_losers, expire_if_stale, fill_slot, record_losses, record_win,
+        resolved_auction, settle_losing_bid, settle_winning_bids, settles_with,
+    },
+};
+
+/// Session ID of the human player.
+pub const PRACTICE_PLAYER_ID: &str = "player";
+
+/// Compute units every practice bid asks for.
+const PRACTICE_COMPUTE_UNITS: u64 = 200_000;
+
+/// Rivals never bid more than this many times the base fee, so a player
+/// willing to pay more can always win.
+const RIVAL_MAX_FEE_MULTIPLIER: f64 = 5.0;
+
+/// Marketplace settings for a practice game. The defaults are the server's.
+#[derive(Clone, Debug)]
+pub struct PracticeSettings {
+    pub slot_duration_ms: i64,
+    pub base_fee: Lamports,
+    pub slot_window: SlotWindow,
+    pub aot_max_lookahead_slots: u64,
+    pub aot_duration_secs: i64,
+    pub transaction_ttl_slots: u64,
+    /// Rival bidders playing against the player
+    pub rivals: usize,
+    /// Allowlists for slot ranges, as an admin would set on the server
+    pub restrictions: Vec<AuctionRestriction>,
+}
+
+impl Default for PracticeSettings {
+    fn default() -> Self {
+        Self {
+            slot_duration_ms: 400,
+            base_fee: Lamports::from_sol(0.001),
+            slot_window: SlotWindow {
+                lookahead: 100,
+                listing: 50,
+            },
+            aot_max_lookahead_slots: 100,
+            aot_duration_secs: 35,
+            transaction_ttl_slots: 150,
+            rivals: 3,
+            restrictions: Vec::new(),
+        }
+    }
+}
+
+/// What the player sees of the game.
+#[derive(Debug, Serialize)]
+pub struct PracticeSnapshot {
+    pub current_slot: u64,
+    pub player: PlayerStats,
+    /// Listed slots, from the current one on
+    pub slots: Vec<Slot>,
+    /// The player's transactions, newest first
+    pub transactions: Vec<Transaction>,
+    /// Resolved auctions, latest slot first
+    pub auction_history: Vec<ResolvedAuction>,
+}
+
+pub struct PracticeGame {
+    settings: PracticeSettings,
+    marketplace: SlotMarketplace,
+    slots: BTreeMap<u64, Slot>,
+    auctions: AuctionManager,
+    escrow: EscrowManager,
+    restrictions: RestrictionManager,
+    game: GameManager,
+    history: AuctionHistory,
+    transactions: HashMap<String, Transaction>,
+    rivals: Vec<String>,
+}
+
+impl PracticeGame {
+    pub fn new(settings: PracticeSettings) -> Self {
+        let marketplace = SlotMarketplace::new(
+            settings.slot_duration_ms,
+            settings.base_fee,
+            settings.slot_window,
+        );
+        let slots = marketplace
+            .window()
+            .into_iter()
+            .map(|slot| (slot.slot_number, slot))
+            .collect();
+        let mut game = GameManager::new();
+        game.get_or_create_player(PRACTICE_PLAYER_ID.to_string());
+        let rivals = (1..=settings.rivals)
+            .map(|n| format!("rival-{}", n))
+            .collect();
+        let mut restrictions = RestrictionManager::new();
+        for restriction in settings.restrictions.clone() {
+            restrictions.add(restriction, marketplace.current_slot);
+        }
+
+        Self {
+            settings,
+            marketplace,
+            slots,
+            auctions: AuctionManager::new(),
+            escrow: EscrowManager::new(),
+            restrictions,
+            game,
+            history: AuctionHistory::new(),
+            transactions: HashMap::new(),
+            rivals,
+        }
+    }
+
+    pub fn current_slot(&self) -> u64 {
+        self.marketplace.current_slot
+    }
+
+    /// Bids for the next slot on the player's behalf.
+    pub fn submit_jit_bid(&mut self, amount: Lamports) -> Result<Transaction, AppError> {
+        self.place_bid(PRACTICE_PLAYER_ID.to_string(), None, amount)
+    }
+
+    /// Bids for a future slot on the player's behalf.
+    pub fn submit_aot_bid(&mut self, slot: u64, amount: Lamports) -> Result<Transaction, AppError> {
+        self.place_bid(PRACTICE_PLAYER_ID.to_string(), Some(slot), amount)
+    }
+
+    /// Lets the rivals bid, then advances one slot and settles every auction
+    /// that is due. Returns what happened, as the server would stream it.
+    pub fn tick(&mut self) -> Vec<AppEvent> {
+        let mut events = Vec::new();
+        self.rivals_bid(&mut events);
+
+        let next_slot = self.marketplace.advance_slot();
+        let current_slot = self.marketplace.current_slot;
+        self.slots.insert(next_slot.slot_number, next_slot);
+        self.slots = self.slots.split_off(&current_slot);
+        events.push(AppEvent::SlotAdvanced { current_slot });
+
+        if let Some((winner, bid)) = self.auctions.resolve_jit(current_slot) {
+            events.push(AppEvent::JitAuctionResolved {
+                slot_number: current_slot,
+                winner: winner.clone(),
+                winning_bid: bid,
+            });
+            // Outbid JIT bidders are settled now rather than waiting for their bids to expire
+            let losers = self
+                .escrow
+                .held_bidders(&TransactionType::Jit, current_slot, &winner);
+            self.settle(
+                current_slot,
+                TransactionType::Jit,
+                winner,
+                bid,
+                losers,
+                &mut events,
+            );
+        }
+
+        for (slot, winner, bid, losers) in self.auctions.resolve_ready_aot(current_slot) {
+            events.push(AppEvent::AotAuctionResolved {
+                slot_number: slot,
+                winner: winner.clone(),
+                winning_bid: bid,
+            });
+            self.settle(slot, TransactionType::Aot, winner, bid, losers, &mut events);
+        }
+
+        self.expire_stale(current_slot, &mut events);
+        events
+    }
+
+    pub fn snapshot(&self) -> PracticeSnapshot {
+        let (current_slot, listing_end) = self.marketplace.listing_range();
+        let mut transactions: Vec<Transaction> = self
+            .transactions
+            .values()
+            .filter(|transaction| transaction.sender == PRACTICE_PLAYER_ID)
+            .cloned()
+            .collect();
+        transactions.sort_by_key(|transaction| std::cmp::Reverse(transaction.created_at));
+
+        PracticeSnapshot {
+            current_slot,
+            player: self
+                .game
+                .player_stats
+                .get(PRACTICE_PLAYER_ID)
+                .cloned()
+                .unwrap_or_else(|| PlayerStats::new(PRACTICE_PLAYER_ID.to_string())),
+            slots: self
+                .slots
+                .range(current_slot..listing_end)
+                .map(|(_, slot)| slot.clone())
+                .collect(),
+            transactions,
+            auction_history: self.history.iter().cloned().collect(),
+        }
+    }
+
+    /// Escrows the bid and enters it in its slot's auction, opening the
+    /// auction if needed. Bids for `aot_slot` if given and for the next slot
+    /// otherwise. A rejected bid leaves the bidder as they were.
+    fn place_bid(
+        &mut self,
+        sender: String,
+        aot_slot: Option<u64>,
+        amount: Lamports,
+    ) -> Result<Transaction, AppError> {
+        let errors = validate_bid_fields(amount, PRACTICE_COMPUTE_UNITS);
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        let current_slot = self.marketplace.current_slot;
+        let transaction = match aot_slot {
+            Some(slot) => {
+                if slot < current_slot {
+                    return Err(AppError::InvalidSlot { slot, current_slot });
+                }
+                let max_slot = current_slot + self.settings.aot_max_lookahead_slots;
+                if slot > max_slot {
+                    return Err(AppError::SlotBeyondWindow { slot, max_slot });
+                }
+                Transaction::aot(sender, PRACTICE_COMPUTE_UNITS, amount, slot, String::new())
+            }
+            None => Transaction::jit(sender, PRACTICE_COMPUTE_UNITS, amount, String::new()),
+        }
+        .with_ttl(current_slot, self.settings.transaction_ttl_slots);
+        let (slot, transaction_type) = (transaction.target_slot(), transaction.transaction_type());
+        self.restrictions.check(&transaction.sender, slot)?;
+
+        self.escrow.hold(
+            transaction.id.clone(),
+            transaction.sender.clone(),
+            transaction_type,
+            slot,
+            amount,
+        )?;
+        if let Err(e) = self.game.fund_bid(transaction.sender.clone(), amount, slot) {
+            self.escrow.cancel(&transaction.id);
+            return Err(e);
+        }
+
+        if let Err(e) = self.enter_auction(&transaction) {
+            if let Some(record) = self.escrow.cancel(&transaction.id) {
+                let other_bids_for_slot = self.escrow.has_bid_for_slot(&record.bidder, slot);
+                self.game
+                    .revert_bid(&record.bidder, amount, slot, other_bids_for_slot);
+            }
+            return Err(e);
+        }
+
+        let aot_ends_at = self
+            .auctions
+            .aot_auctions
+            .get(&slot)
+            .map(|auction| auction.ends_at);
+        if let (Some(slot_obj), Some(slot_state)) = (
+            self.slots.get_mut(&slot),
+            bid_slot_state(&transaction, aot_ends_at),
+        ) {
+            slot_obj.state = slot_state;
+        }
+        self.transactions
+            .insert(transaction.id.clone(), transaction.clone());
+        Ok(transaction)
+    }
+
+    fn enter_auction(&mut self, transaction: &Transaction) -> Result<(), AppError> {
+        let slot = transaction.target_slot();
+        let (bidder, amount) = (transaction.sender.clone(), transaction.priority_fee);
+
+        match transaction.transaction_type() {
+            TransactionType::Jit => {
+                if !self.auctions.jit_auctions.contains_key(&slot) {
+                    self.auctions
+                        .start_jit_auction(slot, self.settings.base_fee)?;
+                }
+                self.auctions.submit_jit_bid(slot, bidder, amount)
+            }
+            TransactionType::Aot => {
+                if !self.auctions.aot_auctions.contains_key(&slot) {
+                    self.auctions.start_aot_auction(
+                        slot,
+                        self.settings.base_fee,
+                        self.settings.aot_duration_secs,
+                        self.slots
+                            .get(&slot)
+                            .map(|slot_obj| slot_obj.estimated_time),
+                        &[],
+                    )?;
+                }
+                self.auctions.submit_aot_bid(slot, bidder, amount)
+            }
+        }
+    }
+
+    /// Each rival may outbid the next slot's leader or reserve a later slot,
+    /// unless the price has gone past what rivals pay.
+    fn rivals_bid(&mut self, events: &mut Vec<AppEvent>) {
+        let mut rng = rand::rng();
+        let current_slot = self.marketplace.current_slot;
+        let jit_floor = self.settings.base_fee.scale(JIT_PREMIUM_MULTIPLIER);
+        let max_bid = self.settings.base_fee.scale(RIVAL_MAX_FEE_MULTIPLIER);
+
+        for rival in self.rivals.clone() {
+            if !rng.random_bool(0.4) {
+                continue;
+            }
+
+            let premium = rng.random_range(1.0..2.5);
+            let (aot_slot, minimum) =
+                if self.settings.aot_max_lookahead_slots >= 2 && rng.random_bool(0.3) {
+                    let slot =
+                        current_slot + rng.random_range(2..=self.settings.aot_max_lookahead_slots);
+                    let minimum = self
+                        .auctions
+                        .aot_auctions
+                        .get(&slot)
+                        .map_or(self.settings.base_fee, |auction| auction.get_min_next_bid());
+                    (Some(slot), minimum)
+                } else {
+                    let minimum = self
+                        .auctions
+                        .jit_auctions
+                        .get(&(current_slot + 1))
+                        .map_or(jit_floor, |auction| auction.min_next_bid());
+                    (None, minimum)
+                };
+            if minimum > max_bid {
+                continue;
+            }
+            let bid = self.place_bid(rival, aot_slot, minimum.scale(premium).min(max_bid));
+
+            if let Ok(transaction) = bid {
+                let (slot_number, bidder, amount) = (
+                    transaction.target_slot(),
+                    transaction.sender,
+                    transaction.priority_fee,
+                );
+                events.push(match transaction.inclusion_type {
+                    InclusionType::Jit => AppEvent::JitBidSubmitted {
+                        slot_number,
+                        bidder,
+                        amount,
+                        request_id: None,
+                    },
+                    InclusionType::Aot { .. } => AppEvent::AotBidSubmitted {
+                        slot_number,
+                        bidder,
+                        amount,
+                        request_id: None,
+                    },
+                });
+            }
+        }
+    }
+
+    /// Settles the winner's bids and the losers', refunds what did not
+    /// execute, and records the result on each bidder's stats, the slot and
+    /// the auction history.
+    fn settle(
+        &mut self,
+        slot: u64,
+        transaction_type: TransactionType,
+        winner: String,
+        winning_bid: Lamports,
+        losers: Vec<String>,
+        events: &mut Vec<AppEvent>,
+    ) {
+        let inclusion_type = match transaction_type {
+            TransactionType::Jit => InclusionType::Jit,
+            TransactionType::Aot => InclusionType::Aot {
+                reserved_slot: slot,
+            },
+        };
+
+        let mut included: Vec<Transaction> = Vec::new();
+        let winner_bids = self.pending_bids(&winner, &inclusion_type, slot);
+        for transaction in settle_winning_bids(winner_bids, slot, winning_bid) {
+            if matches!(transaction.status, TransactionStatus::AuctionWon { .. }) {
+                self.escrow.release(&transaction.id);
+                included.push(transaction.clone());
+            } else {
+                self.refund(&transaction);
+            }
+            self.update_transaction(transaction, events);
+        }
+
+        let losers = distinct_losers(losers);
+        for loser in &losers {
+            for mut transaction in self.pending_bids(loser, &inclusion_type, slot) {
+                settle_losing_bid(&mut transaction, slot);
+                self.refund(&transaction);
+                self.update_transaction(transaction, events);
+            }
+        }
+
+        record_win(&mut self.game, &winner, slot, transaction_type);
+        record_losses(&mut self.game, slot, &losers);
+
+        if let Some(slot_obj) = self.slots.get_mut(&slot) {
+            fill_slot(
+                slot_obj,
+                winner.clone(),
+                winning_bid,
+                transaction_type,
+                &included,
+            );
+        }
+
+        let transactions = &self.transactions;
+        self.history.record(resolved_auction(
+            &self.escrow.records_for_slot(slot),
+            slot,
+            transaction_type,
+            winner,
+            winning_bid,
+            |transaction_id| {
+                transactions
+                    .get(transaction_id)
+                    .map(|transaction| transaction.created_slot)
+            },
+        ));
+    }
+
+    /// The bidder's pending bids settled by the auction, oldest first.
+    fn pending_bids(
+        &self,
+        bidder: &str,
+        inclusion_type: &InclusionType,
+        slot: u64,
+    ) -> Vec<Transaction> {
+        let mut bids: Vec<Transaction> = self
+            .transactions
+            .values()
+            .filter(|transaction| {
+                transaction.sender == bidder && settles_with(transaction, inclusion_type, slot)
+            })
+            .cloned()
+            .collect();
+        bids.sort_by_key(|transaction| transaction.created_at);
+        bids
+    }
+
+    fn refund(&mut self, transaction: &Transaction) {
+        if let Some(record) = self.escrow.refund(&transaction.id) {
+            self.game.credit_refund(&record.bidder, record.amount);
+        }
+    }
+
+    /// Stores the settled transaction, telling the player if it is theirs.
+    fn update_transaction(&mut self, transaction: Transaction, events: &mut Vec<AppEvent>) {
+        if transaction.sender == PRACTICE_PLAYER_ID {
+            events.push(AppEvent::TransactionUpdated {
+                transaction: Box::new(transaction.clone()),
+            });
+        }
+        self.transactions
+            .insert(transaction.id.clone(), transaction);
+    }
+
+    /// Expires pending bids past their TTL and refunds them.
+    fn expire_stale(&mut self, current_slot: u64, events: &mut Vec<AppEvent>) {
+        let expired: Vec<Transaction> = self
+            .transactions
+            .values_mut()
+            .filter_map(|transaction| {
+                expire_if_stale(transaction, current_slot).then(|| transaction.clone())
+            })
+            .collect();
+
+        for transaction in expired {
+            self.refund(&transaction);
+            self.update_transaction(transaction, events);
+        }
+    }
+}
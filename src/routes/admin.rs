@@ -0,0 +1,578 @@
+use crate::{
+    app::{api::AppContext, room::RoomScoped},
+    error::AppError,
+    managers::{escrow::ESCROW_RETENTION_SLOTS, restriction::AuctionRestriction},
+    models::{
+        admin::AdminRole,
+        requests::{
+            AuctionRestrictionRequest, BalanceAdjustmentRequest, BanRequest, ReconciliationQuery,
+        },
+        responses::{
+            AbuseFlagList, AbuseFlagView, ApiResponse, BalanceAdjusted, BanList, ErrorResponse,
+            PlayerBanned, PlayerKicked, RestrictionList, SimulationReset,
+        },
+        types::Lamports,
+    },
+    services::{
+        config::{ConfigReload, reload_config},
+        ledger::{LedgerReport, audit_ledger},
+        reconciliation::{SlotReconciliation, is_slot_resolved, reconcile_slot},
+    },
+    utils::validation::{FieldError, ValidatedJson},
+};
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+
+#[utoipa::path(
+    post,
+    path = "/admin/config/reload",
+    tag = "Admin",
+    params(("x-admin-token" = String, Header, description = "Operator or admin token")),
+    responses(
+        (status = 200, description = "Configuration reloaded", body = ApiResponse<ConfigReload>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 500, description = "Configuration could not be reloaded", body = ErrorResponse),
+    )
+)]
+pub async fn reload_configuration(
+    State(context): State<AppContext>,
+    Extension(role): Extension<AdminRole>,
+) -> impl IntoResponse {
+    tracing::warn!(role = ?role, "Admin action: reload configuration");
+
+    match reload_config(&context.rooms, &context.config, &context.rate_limiter).await {
+        Ok(reload) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(
+                "Configuration reloaded.".to_string(),
+                reload,
+            )),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::failure(
+                format!("Failed to reload configuration: {}", e),
+                500,
+            )),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/reset",
+    tag = "Admin",
+    params(("x-admin-token" = String, Header, description = "Admin token")),
+    responses(
+        (status = 200, description = "Simulation reset", body = ApiResponse<SimulationReset>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+    )
+)]
+pub async fn reset_simulation(
+    RoomScoped(context): RoomScoped,
+    Extension(role): Extension<AdminRole>,
+) -> Result<Response, AppError> {
+    tracing::warn!(role = ?role, "Admin action: reset simulation");
+
+    context.state.reset_simulation().await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Simulation reset.".to_string(),
+            SimulationReset {
+                current_slot: context.state.get_current_slot().await?,
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/players/{session_id}/balance",
+    tag = "Admin",
+    params(
+        ("session_id" = String, Path, description = "Player session ID"),
+        ("x-admin-token" = String, Header, description = "Admin token"),
+    ),
+    request_body = BalanceAdjustmentRequest,
+    responses(
+        (status = 200, description = "Balance adjusted", body = ApiResponse<BalanceAdjusted>),
+        (status = 400, description = "Invalid amount or a balance over the limit", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 402, description = "Debit larger than the balance", body = ErrorResponse),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+        (status = 422, description = "Amount above the adjustment limit", body = ErrorResponse),
+    )
+)]
+pub async fn adjust_player_balance(
+    RoomScoped(context): RoomScoped,
+    Extension(role): Extension<AdminRole>,
+    Path(session_id): Path<String>,
+    Json(req): Json<BalanceAdjustmentRequest>,
+) -> impl IntoResponse {
+    if !req.amount.is_finite() || req.amount == 0.0 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::failure(
+                "Amount must be a non-zero number",
+                400,
+            )),
+        )
+            .into_response();
+    }
+
+    if context
+        .state
+        .sessions
+        .get_session(&session_id)
+        .await
+        .is_none()
+    {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::failure("Session not found", 404)),
+        )
+            .into_response();
+    }
+
+    let amount = Lamports::from_sol(req.amount.abs());
+    let credit = req.amount > 0.0;
+
+    match context
+        .state
+        .adjust_player_balance(&session_id, amount, credit)
+        .await
+    {
+        Ok(balance) => {
+            tracing::warn!(
+                role = ?role,
+                "Admin action: {} {} SOL {} player {} ({})",
+                if credit { "credited" } else { "debited" },
+                amount,
+                if credit { "to" } else { "from" },
+                session_id.chars().take(8).collect::<String>(),
+                req.reason.as_deref().unwrap_or("no reason given")
+            );
+
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success(
+                    "Balance adjusted.".to_string(),
+                    BalanceAdjusted {
+                        session_id,
+                        balance,
+                    },
+                )),
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/players/{session_id}/kick",
+    tag = "Admin",
+    params(
+        ("session_id" = String, Path, description = "Player session ID"),
+        ("x-admin-token" = String, Header, description = "Operator or admin token"),
+    ),
+    responses(
+        (status = 200, description = "Session ended and open bids refunded", body = ApiResponse<PlayerKicked>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+    )
+)]
+pub async fn kick_player(
+    State(context): State<AppContext>,
+    Extension(role): Extension<AdminRole>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    match context
+        .rooms
+        .end_session(&session_id, "Player was kicked")
+        .await
+    {
+        Ok(refund) => {
+            tracing::warn!(
+                role = ?role,
+                "Admin action: kicked player {} and refunded {} SOL",
+                session_id.chars().take(8).collect::<String>(),
+                refund
+            );
+
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success(
+                    "Player kicked.".to_string(),
+                    PlayerKicked {
+                        session_id,
+                        refunded: refund,
+                    },
+                )),
+            )
+                .into_response()
+        }
+        Err(e) => (StatusCode::NOT_FOUND, Json(ApiResponse::failure(e, 404))).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/players/{session_id}/ban",
+    tag = "Admin",
+    params(
+        ("session_id" = String, Path, description = "Player session ID"),
+        ("x-admin-token" = String, Header, description = "Admin token"),
+    ),
+    request_body(content = Option<BanRequest>, description = "Optional reason for the ban"),
+    responses(
+        (status = 200, description = "Player banned and open bids refunded", body = ApiResponse<PlayerBanned>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse),
+    )
+)]
+pub async fn ban_player(
+    State(context): State<AppContext>,
+    Extension(role): Extension<AdminRole>,
+    Path(session_id): Path<String>,
+    req: Option<Json<BanRequest>>,
+) -> impl IntoResponse {
+    let Json(req) = req.unwrap_or_default();
+
+    match context.state.ban_player(&session_id, req.reason).await {
+        Ok((ban, mut refund)) => {
+            refund += match context
+                .rooms
+                .remove_from_rooms(&session_id, "Player was banned")
+                .await
+            {
+                Ok(refund) => refund,
+                Err(e) => return e.into_response(),
+            };
+            tracing::warn!(
+                role = ?role,
+                "Admin action: banned player {} ({}) and refunded {} SOL",
+                session_id.chars().take(8).collect::<String>(),
+                ban.reason.as_deref().unwrap_or("no reason given"),
+                refund
+            );
+
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success(
+                    "Player banned.".to_string(),
+                    PlayerBanned {
+                        ban,
+                        refunded: refund,
+                    },
+                )),
+            )
+                .into_response()
+        }
+        Err(e) => (StatusCode::NOT_FOUND, Json(ApiResponse::failure(e, 404))).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/bans",
+    tag = "Admin",
+    params(("x-admin-token" = String, Header, description = "Operator or admin token")),
+    responses(
+        (status = 200, description = "Active bans", body = ApiResponse<BanList>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn list_bans(State(context): State<AppContext>) -> impl IntoResponse {
+    let bans = context.state.moderation.list_bans().await;
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Bans fetched successfully".to_string(),
+            BanList { bans },
+        )),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/restrictions",
+    tag = "Admin",
+    params(("x-admin-token" = String, Header, description = "Admin token")),
+    request_body = AuctionRestrictionRequest,
+    responses(
+        (status = 201, description = "Bidding on the slot range restricted to the allowlist", body = ApiResponse<AuctionRestriction>),
+        (status = 400, description = "Range starts before the current slot", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+        (status = 422, description = "Invalid allowlist or range overlaps an existing restriction", body = ErrorResponse),
+    )
+)]
+pub async fn create_restriction(
+    RoomScoped(context): RoomScoped,
+    Extension(role): Extension<AdminRole>,
+    ValidatedJson(req): ValidatedJson<AuctionRestrictionRequest>,
+) -> Result<Response, AppError> {
+    let current_slot = context.state.get_current_slot().await?;
+    if req.from_slot < current_slot {
+        return Err(AppError::InvalidSlot {
+            slot: req.from_slot,
+            current_slot,
+        });
+    }
+    let to_slot = req.to_slot.unwrap_or(req.from_slot);
+
+    let restriction = AuctionRestriction {
+        id: uuid::Uuid::new_v4().to_string(),
+        from_slot: req.from_slot,
+        to_slot,
+        label: req.label,
+        session_ids: req.session_ids.into_iter().collect(),
+        teams: req
+            .teams
+            .into_iter()
+            .map(|(team, members)| (team.trim().to_string(), members.into_iter().collect()))
+            .collect(),
+        created_at: Utc::now(),
+    };
+    let restriction = context
+        .state
+        .restrictions
+        .call(move |restrictions| {
+            if let Some(existing) = restrictions.overlapping(restriction.from_slot, to_slot) {
+                return Err(AppError::Validation(vec![FieldError::new(
+                    "from_slot",
+                    format!(
+                        "range overlaps restriction {} for slots {}-{}",
+                        existing.id, existing.from_slot, existing.to_slot
+                    ),
+                )]));
+            }
+            restrictions.add(restriction.clone(), current_slot);
+            Ok(restriction)
+        })
+        .await??;
+
+    tracing::warn!(
+        role = ?role,
+        "Admin action: restricted slots {}-{} to an allowlist",
+        restriction.from_slot,
+        restriction.to_slot
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(
+            "Auctions restricted.".to_string(),
+            restriction,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/restrictions",
+    tag = "Admin",
+    params(("x-admin-token" = String, Header, description = "Operator or admin token")),
+    responses(
+        (status = 200, description = "Allowlisted slot ranges with their full allowlists", body = ApiResponse<RestrictionList>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn list_restrictions(RoomScoped(context): RoomScoped) -> Result<Response, AppError> {
+    let restrictions = context
+        .state
+        .restrictions
+        .call(|restrictions| restrictions.list().to_vec())
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Restrictions fetched successfully".to_string(),
+            RestrictionList { restrictions },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/restrictions/{restriction_id}",
+    tag = "Admin",
+    params(
+        ("restriction_id" = String, Path, description = "Restriction ID"),
+        ("x-admin-token" = String, Header, description = "Admin token"),
+    ),
+    responses(
+        (status = 200, description = "Restriction lifted", body = ApiResponse<AuctionRestriction>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+        (status = 404, description = "Restriction not found", body = ErrorResponse),
+    )
+)]
+pub async fn delete_restriction(
+    RoomScoped(context): RoomScoped,
+    Extension(role): Extension<AdminRole>,
+    Path(restriction_id): Path<String>,
+) -> impl IntoResponse {
+    let removed = context
+        .state
+        .restrictions
+        .call(move |restrictions| restrictions.remove(&restriction_id))
+        .await;
+
+    match removed {
+        Ok(Some(restriction)) => {
+            tracing::warn!(
+                role = ?role,
+                "Admin action: lifted the allowlist on slots {}-{}",
+                restriction.from_slot,
+                restriction.to_slot
+            );
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success(
+                    "Restriction lifted.".to_string(),
+                    restriction,
+                )),
+            )
+                .into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::failure("Restriction not found", 404)),
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/abuse-flags",
+    tag = "Admin",
+    params(("x-admin-token" = String, Header, description = "Operator or admin token")),
+    responses(
+        (status = 200, description = "Sessions flagged for bid spam", body = ApiResponse<AbuseFlagList>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn list_abuse_flags(State(context): State<AppContext>) -> impl IntoResponse {
+    let flags: Vec<_> = context
+        .state
+        .moderation
+        .list_abuse_flags()
+        .await
+        .into_iter()
+        .map(|flag| AbuseFlagView {
+            cooling_down: flag.is_cooling_down(),
+            flag,
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Abuse flags fetched successfully".to_string(),
+            AbuseFlagList { flags },
+        )),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/ledger",
+    tag = "Admin",
+    params(("x-admin-token" = String, Header, description = "Operator or admin token")),
+    responses(
+        (status = 200, description = "SOL issued versus balances, escrow, burned fees and retired SOL", body = ApiResponse<LedgerReport>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn ledger_audit(RoomScoped(context): RoomScoped) -> Result<Response, AppError> {
+    let report = audit_ledger(&context.state).await?;
+    let message = if report.balanced {
+        "Ledger is balanced".to_string()
+    } else {
+        format!("Ledger is off by {} SOL", report.discrepancy_sol)
+    };
+
+    Ok((StatusCode::OK, Json(ApiResponse::success(message, report))).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/reconciliation",
+    tag = "Admin",
+    params(
+        ("x-admin-token" = String, Header, description = "Operator or admin token"),
+        ("slot" = u64, Query, description = "Resolved slot to reconcile")
+    ),
+    responses(
+        (status = 200, description = "Every bid for the slot with its escrow settlement", body = ApiResponse<SlotReconciliation>),
+        (status = 400, description = "The slot has not resolved yet, or its escrow is no longer retained", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+    )
+)]
+pub async fn slot_reconciliation(
+    RoomScoped(context): RoomScoped,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<Response, AppError> {
+    if !is_slot_resolved(&context.state, query.slot).await? {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::failure(
+                format!("Slot {} has not resolved yet", query.slot),
+                400,
+            )),
+        )
+            .into_response());
+    }
+
+    let current_slot = context.state.get_current_slot().await?;
+    if query.slot + ESCROW_RETENTION_SLOTS < current_slot {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::failure(
+                format!(
+                    "Escrow for slot {} is no longer retained, only the last {} slots can be reconciled",
+                    query.slot, ESCROW_RETENTION_SLOTS
+                ),
+                400,
+            )),
+        )
+            .into_response());
+    }
+
+    let report = reconcile_slot(&context.state, query.slot).await?;
+    let message = if report.consistent {
+        format!("Slot {} reconciled", query.slot)
+    } else {
+        format!(
+            "Slot {} has {} reconciliation issues",
+            query.slot,
+            report.issues.len()
+        )
+    };
+
+    Ok((StatusCode::OK, Json(ApiResponse::success(message, report))).into_response())
+}
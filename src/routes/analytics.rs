@@ -0,0 +1,216 @@
+use axum::{
+    Json,
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    app::room::RoomScoped,
+    error::AppError,
+    managers::analytics::{MAX_FEE_WINDOW_MINUTES, PriceResolution},
+    models::{
+        requests::{FeeStatsQuery, ForecastQuery, HeatmapQuery, PriceSeriesQuery},
+        responses::{
+            ApiResponse, DemandHeatmapView, ErrorResponse, FeeStatsView, LatencyView,
+            PriceSeriesView,
+        },
+    },
+    services::forecast::{DemandForecast, forecast_slot},
+    utils::validation::FieldError,
+};
+
+const DEFAULT_FEE_WINDOWS: &str = "5,60,1440";
+const DEFAULT_HEATMAP_BUCKET_SIZE: u64 = 5;
+
+#[utoipa::path(
+    get,
+    path = "/analytics/prices",
+    tag = "Analytics",
+    params(
+        ("resolution" = Option<PriceResolution>, Query, description = "Bucket size, `10s` (last hour) or `1m` (last day, default)")
+    ),
+    responses(
+        (status = 200, description = "Base fees and winning bids over time", body = ApiResponse<PriceSeriesView>)
+    )
+)]
+pub async fn price_series(
+    RoomScoped(context): RoomScoped,
+    Query(query): Query<PriceSeriesQuery>,
+) -> Result<Response, AppError> {
+    let resolution = query.resolution.unwrap_or(PriceResolution::OneMinute);
+    let buckets = context
+        .state
+        .prices
+        .call(move |prices| prices.series(resolution))
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Price series fetched successfully".into(),
+            PriceSeriesView {
+                resolution,
+                bucket_secs: resolution.bucket_secs(),
+                buckets,
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/fees",
+    tag = "Analytics",
+    params(
+        ("windows" = Option<String>, Query, description = "Comma-separated trailing windows in minutes, each at most 1440 (default `5,60,1440`)")
+    ),
+    responses(
+        (status = 200, description = "Winning bid percentiles, overbids and refund volume per window", body = ApiResponse<FeeStatsView>),
+        (status = 422, description = "A window is not a whole number of minutes between 1 and 1440", body = ErrorResponse)
+    )
+)]
+pub async fn fee_stats(
+    RoomScoped(context): RoomScoped,
+    Query(query): Query<FeeStatsQuery>,
+) -> Result<Response, AppError> {
+    let windows = query.windows.as_deref().unwrap_or(DEFAULT_FEE_WINDOWS);
+    let mut minutes = Vec::new();
+    for window in windows.split(',').map(str::trim) {
+        match window.parse::<u32>() {
+            Ok(window) if (1..=MAX_FEE_WINDOW_MINUTES).contains(&window) => minutes.push(window),
+            _ => {
+                return Err(AppError::Validation(vec![FieldError::new(
+                    "windows",
+                    format!(
+                        "'{}' is not a window between 1 and {} minutes",
+                        window, MAX_FEE_WINDOW_MINUTES
+                    ),
+                )]));
+            }
+        }
+    }
+
+    let windows = context
+        .state
+        .fees
+        .call(move |fees| {
+            minutes
+                .into_iter()
+                .map(|window| fees.window(window))
+                .collect()
+        })
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Fee statistics fetched successfully".into(),
+            FeeStatsView { windows },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/heatmap",
+    tag = "Analytics",
+    params(
+        ("bucket_size" = Option<u64>, Query, description = "Slot distances per bucket, up to the AOT lookahead (default 5)")
+    ),
+    responses(
+        (status = 200, description = "Bids and average price by how far ahead the slot was when bid on", body = ApiResponse<DemandHeatmapView>),
+        (status = 422, description = "bucket_size is zero or beyond the AOT lookahead", body = ErrorResponse)
+    )
+)]
+pub async fn demand_heatmap(
+    RoomScoped(context): RoomScoped,
+    Query(query): Query<HeatmapQuery>,
+) -> Result<Response, AppError> {
+    let max_lookahead = context.config.current().marketplace.aot_max_lookahead_slots;
+    let bucket_size = query.bucket_size.unwrap_or(DEFAULT_HEATMAP_BUCKET_SIZE);
+    if bucket_size == 0 || bucket_size > max_lookahead {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "bucket_size",
+            format!("must be between 1 and {}", max_lookahead),
+        )]));
+    }
+
+    let view = context
+        .state
+        .demand
+        .call(move |demand| DemandHeatmapView {
+            bucket_size,
+            total_bids: demand.total_bids(),
+            buckets: demand.buckets(bucket_size),
+        })
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Demand heatmap fetched successfully".into(),
+            view,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/forecast",
+    tag = "Analytics",
+    params(
+        ("slot" = u64, Query, description = "Future slot to forecast; the next slot is a JIT auction, later ones AOT")
+    ),
+    responses(
+        (status = 200, description = "Expected competition and clearing price for the slot", body = ApiResponse<DemandForecast>),
+        (status = 400, description = "Slot has passed or is beyond the AOT lookahead", body = ErrorResponse)
+    )
+)]
+pub async fn demand_forecast(
+    RoomScoped(context): RoomScoped,
+    Query(query): Query<ForecastQuery>,
+) -> Result<Response, AppError> {
+    let max_lookahead = context.config.current().marketplace.aot_max_lookahead_slots;
+    let forecast = forecast_slot(&context.state, query.slot, max_lookahead).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Demand forecast calculated".into(),
+            forecast,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/analytics/latency",
+    tag = "Analytics",
+    responses(
+        (status = 200, description = "Time from submission to inclusion or failure, by auction type and bid size", body = ApiResponse<LatencyView>)
+    )
+)]
+pub async fn inclusion_latency(RoomScoped(context): RoomScoped) -> Result<Response, AppError> {
+    let view = context
+        .state
+        .latency
+        .call(|latency| LatencyView {
+            by_type: latency.by_type(),
+            by_bid_size: latency.by_bid_size(),
+        })
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Inclusion latency fetched successfully".into(),
+            view,
+        )),
+    )
+        .into_response())
+}
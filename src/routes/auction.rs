@@ -1,43 +1,77 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
-use serde_json::{Value, json};
-
-use crate::{app::api::AppContext, models::responses::ApiResponse};
+use crate::{
+    app::room::RoomScoped,
+    error::AppError,
+    models::{
+        requests::{AuctionHistoryQuery, BidEstimateQuery},
+        responses::{
+            AotAuctionDetail, ApiResponse, AuctionHistoryList, AuctionList, AuctionView,
+            ErrorResponse, JitAuctionDetail,
+        },
+        types::Lamports,
+    },
+    services::estimate::{BidEstimate, estimate_bid},
+    utils::validation::FieldError,
+};
+use axum::{
+    Json,
+    extract::{Path, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
 
 #[utoipa::path(
     get,
     path = "/auctions/jit",
     tag = "Auction",
     responses(
-        (status = 200, description = "Active JIT auctions retrieved", body = ApiResponse),
+        (status = 200, description = "Active JIT auctions retrieved", body = ApiResponse<AuctionList>),
     )
 )]
-pub async fn list_jit_auctions(State(context): State<AppContext>) -> impl IntoResponse {
-    let auctions = context.state.auctions.read().await;
+pub async fn list_jit_auctions(RoomScoped(context): RoomScoped) -> Result<Response, AppError> {
+    let mut jit_auctions: Vec<_> = context
+        .state
+        .auctions
+        .call_all(|auctions| {
+            auctions
+                .get_active_jit_auctions()
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+    jit_auctions.sort_by_key(|auction| auction.slot_number);
 
-    let jit_auctions: Vec<Value> = auctions
-        .get_active_jit_auctions()
+    let mut restrictions = context
+        .state
+        .restriction_summaries(
+            jit_auctions
+                .iter()
+                .map(|auction| auction.slot_number)
+                .collect(),
+        )
+        .await?;
+    let auctions: Vec<AuctionView> = jit_auctions
         .iter()
-        .map(|auction| {
-            json!({
-                "slot_number": auction.slot_number,
-                "min_bid": auction.min_bid,
-                "current_winner": auction.current_highest_bidder,
-                "created_at": auction.created_at
-            })
+        .map(|auction| AuctionView {
+            restriction: restrictions.remove(&auction.slot_number),
+            ..AuctionView::from(auction)
         })
         .collect();
 
-    (
+    Ok((
         StatusCode::OK,
         Json(ApiResponse::success(
             "JIT auctions fetched successfully.".into(),
-            json!({
-                "auctions": jit_auctions,
-                "count": jit_auctions.len()
-            }),
+            AuctionList {
+                count: auctions.len(),
+                auctions,
+            },
         )),
     )
-        .into_response()
+        .into_response())
 }
 
 #[utoipa::path(
@@ -45,36 +79,227 @@ pub async fn list_jit_auctions(State(context): State<AppContext>) -> impl IntoRe
     path = "/auctions/aot",
     tag = "Auction",
     responses(
-        (status = 200, description = "Active AOT auctions retrieved", body = ApiResponse),
+        (status = 200, description = "Active AOT auctions retrieved", body = ApiResponse<AuctionList>),
     )
 )]
-pub async fn list_aot_auctions(State(context): State<AppContext>) -> impl IntoResponse {
-    let auctions = context.state.auctions.read().await;
+pub async fn list_aot_auctions(RoomScoped(context): RoomScoped) -> Result<Response, AppError> {
+    let mut aot_auctions: Vec<_> = context
+        .state
+        .auctions
+        .call_all(|auctions| {
+            auctions
+                .get_active_aot_auctions()
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+    aot_auctions.sort_by_key(|auction| auction.slot_number);
 
-    let aot_auctions: Vec<Value> = auctions
-        .get_active_aot_auctions()
+    let mut restrictions = context
+        .state
+        .restriction_summaries(
+            aot_auctions
+                .iter()
+                .map(|auction| auction.slot_number)
+                .collect(),
+        )
+        .await?;
+    let auctions: Vec<AuctionView> = aot_auctions
         .iter()
-        .map(|auction| {
-            json!({
-                "slot_number": auction.slot_number,
-                "min_bid": auction.min_bid,
-                "highest_bid": auction.get_highest_bid().map(|(_, amount, _)| amount),
-                "bids_count": auction.bids.len(),
-                "ends_at": auction.ends_at,
-                "has_ended": auction.has_ended()
-            })
+        .map(|auction| AuctionView {
+            restriction: restrictions.remove(&auction.slot_number),
+            ..AuctionView::from(auction)
         })
         .collect();
 
-    (
+    Ok((
         StatusCode::OK,
         Json(ApiResponse::success(
             "AOT auctions fetched successfully.".into(),
-            json!({
-                "auctions": aot_auctions,
-                "count": aot_auctions.len()
-            }),
+            AuctionList {
+                count: auctions.len(),
+                auctions,
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/auctions/history",
+    tag = "Auction",
+    params(
+        ("slot_min" = Option<u64>, Query, description = "Only auctions for this slot or later"),
+        ("slot_max" = Option<u64>, Query, description = "Only auctions for this slot or earlier")
+    ),
+    responses(
+        (status = 200, description = "Resolved auctions, latest slot first", body = ApiResponse<AuctionHistoryList>),
+        (status = 422, description = "slot_min is greater than slot_max", body = ErrorResponse),
+    )
+)]
+pub async fn auction_history(
+    RoomScoped(context): RoomScoped,
+    Query(query): Query<AuctionHistoryQuery>,
+) -> Result<Response, AppError> {
+    let slot_min = query.slot_min.unwrap_or(0);
+    let slot_max = query.slot_max.unwrap_or(u64::MAX);
+    if slot_min > slot_max {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "slot_min",
+            "must not be greater than slot_max",
+        )]));
+    }
+
+    let auctions = context
+        .state
+        .auction_history
+        .call(move |history| history.range(slot_min, slot_max))
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Auction history fetched successfully.".into(),
+            AuctionHistoryList {
+                count: auctions.len(),
+                auctions,
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/auctions/jit/{slot_number}",
+    tag = "Auction",
+    params(("slot_number" = u64, Path, description = "Slot the auction is for")),
+    responses(
+        (status = 200, description = "JIT auction with its current leader", body = ApiResponse<JitAuctionDetail>),
+        (status = 404, description = "No open JIT auction for the slot", body = ErrorResponse),
+    )
+)]
+pub async fn get_jit_auction(
+    RoomScoped(context): RoomScoped,
+    Path(slot_number): Path<u64>,
+) -> Result<Response, AppError> {
+    let auction = context
+        .state
+        .auctions
+        .shard(slot_number)
+        .call(move |auctions| auctions.jit_auctions.get(&slot_number).cloned())
+        .await?
+        .ok_or(AppError::AuctionNotFound { slot: slot_number })?;
+
+    // The leader wins when the auction's slot becomes current
+    let resolves_at = context
+        .state
+        .slots
+        .shard(slot_number)
+        .call(move |shard| shard.get(slot_number).map(|slot| slot.estimated_time))
+        .await?;
+    let bidders = auction
+        .current_highest_bidder
+        .iter()
+        .map(|(bidder, _)| bidder.clone())
+        .collect();
+    let aliases = context.state.display_names(bidders).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "JIT auction fetched successfully.".into(),
+            JitAuctionDetail {
+                restriction: context.state.restriction_summary(slot_number).await?,
+                ..JitAuctionDetail::new(&auction, resolves_at, &aliases)
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/auctions/aot/{slot_number}",
+    tag = "Auction",
+    params(("slot_number" = u64, Path, description = "Slot the auction is for")),
+    responses(
+        (status = 200, description = "AOT auction with its full bid ladder", body = ApiResponse<AotAuctionDetail>),
+        (status = 404, description = "No open AOT auction for the slot", body = ErrorResponse),
+    )
+)]
+pub async fn get_aot_auction(
+    RoomScoped(context): RoomScoped,
+    Path(slot_number): Path<u64>,
+) -> Result<Response, AppError> {
+    let auction = context
+        .state
+        .auctions
+        .shard(slot_number)
+        .call(move |auctions| auctions.aot_auctions.get(&slot_number).cloned())
+        .await?
+        .ok_or(AppError::AuctionNotFound { slot: slot_number })?;
+
+    let mut bidders: Vec<String> = auction
+        .bids
+        .iter()
+        .map(|(bidder, _, _)| bidder.clone())
+        .collect();
+    bidders.sort();
+    bidders.dedup();
+    let aliases = context.state.display_names(bidders).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "AOT auction fetched successfully.".into(),
+            AotAuctionDetail {
+                restriction: context.state.restriction_summary(slot_number).await?,
+                ..AotAuctionDetail::new(&auction, &aliases)
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/auctions/estimate",
+    tag = "Auction",
+    params(
+        ("slot" = u64, Query, description = "Slot to bid on; the next slot is a JIT auction, later ones AOT"),
+        ("bid" = f64, Query, description = "Bid in SOL")
+    ),
+    responses(
+        (status = 200, description = "Estimated chance of winning and a suggested bid", body = ApiResponse<BidEstimate>),
+        (status = 400, description = "Slot has passed or is beyond the AOT lookahead", body = ErrorResponse),
+    )
+)]
+pub async fn estimate_auction_bid(
+    RoomScoped(context): RoomScoped,
+    Query(query): Query<BidEstimateQuery>,
+) -> Result<Response, AppError> {
+    let config = context.config.current();
+    let estimate = estimate_bid(
+        &context.state,
+        query.slot,
+        query.bid,
+        Lamports::from_sol(config.marketplace.base_fee_sol),
+        config.marketplace.aot_max_lookahead_slots,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Bid estimate calculated.".into(),
+            estimate,
         )),
     )
-        .into_response()
+        .into_response())
 }
@@ -0,0 +1,67 @@
+use axum::{
+    Json, Router,
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use tower::ServiceExt;
+
+use crate::{
+    app::{
+        api::AppContext,
+        room::{ROOM_HEADER, RoomManager},
+    },
+    error::AppError,
+    models::responses::{ApiResponse, ChainList, ChainView},
+};
+
+#[utoipa::path(
+    get,
+    path = "/chains",
+    tag = "Rooms",
+    responses(
+        (status = 200, description = "Simulated chains; any endpoint can be called on one under `/chains/{chain_id}`, e.g. `/chains/solana/marketplace/status`", body = ApiResponse<ChainList>)
+    )
+)]
+pub async fn list_chains(State(context): State<AppContext>) -> Result<Response, AppError> {
+    let mut chains = Vec::new();
+    for room in context.rooms.chains() {
+        chains.extend(ChainView::new(&room).await?);
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Chains fetched successfully.".into(),
+            ChainList { chains },
+        )),
+    )
+        .into_response())
+}
+
+/// Serves `/chains/{chain_id}/{*path}` by calling `/{path}` on `routes` with
+/// the request scoped to the chain's room.
+pub async fn forward_to_chain(
+    routes: Router,
+    rooms: RoomManager,
+    chain_id: String,
+    path: String,
+    mut req: Request,
+) -> Result<Response, AppError> {
+    let room = rooms
+        .chain(&chain_id)
+        .ok_or(AppError::ChainNotFound { chain_id })?;
+
+    let path_and_query = match req.uri().query() {
+        Some(query) => format!("/{}?{}", path, query),
+        None => format!("/{}", path),
+    };
+    *req.uri_mut() = path_and_query
+        .parse::<Uri>()
+        .map_err(|e| anyhow::anyhow!("Invalid chain request path: {}", e))?;
+    let room_id =
+        HeaderValue::from_str(&room.id).map_err(|e| anyhow::anyhow!("Invalid chain ID: {}", e))?;
+    req.headers_mut().insert(ROOM_HEADER, room_id);
+
+    Ok(routes.oneshot(req).await.into_response())
+}
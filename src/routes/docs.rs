@@ -0,0 +1,48 @@
+use std::sync::LazyLock;
+
+use axum::{Json, extract::Path};
+use serde_json::Value;
+use utoipa::OpenApi;
+
+use crate::{
+    app::api::ApiDoc, error::AppError, models::responses::ErrorResponse,
+    services::json_schema::JsonSchemas,
+};
+
+static JSON_SCHEMAS: LazyLock<anyhow::Result<JsonSchemas>> =
+    LazyLock::new(|| JsonSchemas::from_openapi(&ApiDoc::openapi()));
+
+fn json_schemas() -> Result<&'static JsonSchemas, AppError> {
+    JSON_SCHEMAS
+        .as_ref()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("{:#}", e)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/docs/schemas",
+    tag = "Docs",
+    responses(
+        (status = 200, description = "JSON Schema (2020-12) document defining every API model and `AppEvent` under `$defs`", body = Object),
+    )
+)]
+pub async fn list_json_schemas() -> Result<Json<Value>, AppError> {
+    Ok(Json(json_schemas()?.bundle()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/docs/schemas/{name}",
+    tag = "Docs",
+    params(("name" = String, Path, description = "Model name, e.g. `AppEvent` or `Transaction`")),
+    responses(
+        (status = 200, description = "Standalone JSON Schema (2020-12) document for the model and the models it references", body = Object),
+        (status = 404, description = "No model with this name", body = ErrorResponse),
+    )
+)]
+pub async fn get_json_schema(Path(name): Path<String>) -> Result<Json<Value>, AppError> {
+    json_schemas()?
+        .document(&name)
+        .map(Json)
+        .ok_or(AppError::SchemaNotFound { name })
+}
@@ -1,37 +1,482 @@
-use std::convert::Infallible;
+use std::{collections::VecDeque, convert::Infallible, time::Duration};
 
-use axum::{extract::State, response::Sse};
-use futures_util::{Stream, stream};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Sse},
+};
+use chrono::Utc;
+use futures_util::{Stream, StreamExt, stream};
+use serde_json::json;
+use tokio::{sync::broadcast::error::RecvError, time::Instant};
 
-use crate::app::api::AppContext;
+use crate::{
+    MAX_REPLAY_EVENTS, MAX_REPLAY_SPEED,
+    app::{
+        api::AppContext,
+        room::{CurrentRoom, Room, RoomScoped},
+        state::AppState,
+    },
+    config::SharedConfig,
+    error::AppError,
+    models::{
+        event::{
+            AppEvent, BroadcastMetrics, EVENT_SCHEMA_VERSION, EventBatch, EventDowngrade,
+            EventSubscription, MIN_EVENT_SCHEMA_VERSION, SharedEvent,
+        },
+        requests::{EventReplayQuery, EventStreamQuery},
+        responses::{ApiResponse, ErrorResponse},
+    },
+    services::session::get_session_from_cookie,
+    utils::validation::FieldError,
+};
+
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
 
 #[utoipa::path(
     get,
     path = "/events",
     tag = "SSE",
+    params(
+        ("resume" = Option<String>, Query, description = "Resumption token to continue from, the `id` of the last event received; a `Last-Event-ID` header works the same"),
+        ("v" = Option<u32>, Query, description = "Event schema version to receive, from 1 to the latest (2, the default); newer events are down-converted or left out"),
+    ),
     responses(
-        (status = 200, description = "Event stream", content_type = "text/event-stream"),
+        (status = 200, description = "Event stream of the room the request applies to; each `data` line is one JSON-encoded `AppEvent`", body = AppEvent, content_type = "text/event-stream"),
+        (status = 400, description = "Unsupported schema version", body = ErrorResponse),
+        (status = 401, description = "Private room and no session", body = ErrorResponse),
+        (status = 403, description = "Private room the session was not invited to", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
     )
 )]
 pub async fn sse_handler(
     State(context): State<AppContext>,
-) -> Sse<impl Stream<Item = Result<axum::response::sse::Event, Infallible>>> {
-    let receiver = context.state.events.subscribe();
+    CurrentRoom(room): CurrentRoom,
+    Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<axum::response::sse::Event, Infallible>>>, AppError> {
+    room_event_stream(&context, &room, query, &headers).await
+}
 
-    let stream = stream::unfold(receiver, |mut rx| async move {
-        match rx.recv().await {
-            Ok(event) => {
-                let event_data = serde_json::to_string(&event).unwrap_or_default();
-                let sse_event = axum::response::sse::Event::default().data(event_data);
-                Some((Ok(sse_event), rx))
+#[utoipa::path(
+    get,
+    path = "/rooms/{room_id}/events",
+    tag = "SSE",
+    params(
+        ("room_id" = String, Path, description = "Room ID, `global` for the shared room"),
+        ("resume" = Option<String>, Query, description = "Resumption token to continue from, the `id` of the last event received; a `Last-Event-ID` header works the same"),
+        ("v" = Option<u32>, Query, description = "Event schema version to receive, from 1 to the latest (2, the default); newer events are down-converted or left out"),
+    ),
+    responses(
+        (status = 200, description = "Event stream of the room; each `data` line is one JSON-encoded `AppEvent`", body = AppEvent, content_type = "text/event-stream"),
+        (status = 400, description = "Unsupported schema version", body = ErrorResponse),
+        (status = 401, description = "Private room and no session", body = ErrorResponse),
+        (status = 403, description = "Private room the session was not invited to", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+pub async fn room_sse_handler(
+    State(context): State<AppContext>,
+    Path(room_id): Path<String>,
+    Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<axum::response::sse::Event, Infallible>>>, AppError> {
+    let room = context
+        .rooms
+        .get(&room_id)
+        .ok_or(AppError::RoomNotFound { room_id })?;
+    room_event_stream(&context, &room, query, &headers).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/events/me",
+    tag = "SSE",
+    params(
+        ("v" = Option<u32>, Query, description = "Event schema version to receive, from 1 to the latest (2, the default); newer events are down-converted or left out"),
+    ),
+    responses(
+        (status = 200, description = "Events concerning the session in the room the request applies to: its transactions, bids and resolutions of auctions it bid in, and achievements it unlocked", body = AppEvent, content_type = "text/event-stream"),
+        (status = 400, description = "Unsupported schema version", body = ErrorResponse),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+    )
+)]
+pub async fn private_sse_handler(
+    State(context): State<AppContext>,
+    CurrentRoom(room): CurrentRoom,
+    Query(query): Query<EventStreamQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<axum::response::sse::Event, Infallible>>>, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+
+    let subscription = room.state.events.subscribe_private(&session_id);
+    let downgrade = event_downgrade(&room.state, query.v).await?;
+    let stream = stream::unfold(
+        (subscription, downgrade, room.state.clone()),
+        |(mut subscription, mut downgrade, state)| async move {
+            loop {
+                let event = match subscription.recv().await {
+                    Ok(event) => event,
+                    // The client refetches its transactions on a resync
+                    Err(RecvError::Lagged(missed)) => match state.stream_resync(missed).await {
+                        Ok(resync) => SharedEvent::new(resync),
+                        Err(_) => return None,
+                    },
+                    Err(RecvError::Closed) => return None,
+                };
+                let event = match downgrade.as_mut() {
+                    Some(downgrade) => downgrade.apply(event),
+                    None => Some(event),
+                };
+                if let Some(event) = event {
+                    let sse_event = axum::response::sse::Event::default().data(&*event.payload);
+                    return Some((Ok(sse_event), (subscription, downgrade, state)));
+                }
             }
-            Err(_) => None,
+        },
+    );
+    let stream = stream.take_until(room.shutdown.clone().cancelled_owned());
+
+    Ok(Sse::new(stream).keep_alive(
+        axum::response::sse::KeepAlive::new()
+            .interval(std::time::Duration::from_secs(30))
+            .text("keep-alive"),
+    ))
+}
+
+/// Subscribes to the room's own event channel, so subscribers only receive
+/// (and only cost) the events of the room they watch. Private rooms are only
+/// streamed to sessions admitted to them.
+///
+/// Every batch's last event carries a resumption token as its `id`. A
+/// subscriber reconnecting with one, either from `EventSource`'s automatic
+/// `Last-Event-ID` or the `resume` parameter, is first sent what it missed.
+async fn room_event_stream(
+    context: &AppContext,
+    room: &Room,
+    query: EventStreamQuery,
+    headers: &HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<axum::response::sse::Event, Infallible>>>, AppError> {
+    check_room_access(context, room, headers).await?;
+
+    let subscription = room.state.events.subscribe();
+    // Seeded after subscribing, so no slot change falls between the two
+    let downgrade = event_downgrade(&room.state, query.v).await?;
+    let mut stream = SubscriberStream {
+        subscription,
+        state: room.state.clone(),
+        config: context.config.clone(),
+        batch: EventBatch::default(),
+        ready: VecDeque::new(),
+        downgrade,
+        last_seq: 0,
+        closed: false,
+    };
+
+    let token = query.resume.or_else(|| {
+        headers
+            .get(LAST_EVENT_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    });
+    if let Some(token) = token {
+        stream.resume(&token).await;
+    }
+
+    let stream = stream::unfold(stream, |mut stream| async move {
+        let (event, token) = stream.next().await?;
+        let mut sse_event = axum::response::sse::Event::default().data(&*event.payload);
+        if let Some(token) = token {
+            sse_event = sse_event.id(token);
         }
+        Some((Ok(sse_event), stream))
     });
 
-    Sse::new(stream).keep_alive(
+    // End the stream when the room closes or the server shuts down, so graceful
+    // shutdown isn't held open by subscribers
+    let stream = stream.take_until(room.shutdown.clone().cancelled_owned());
+
+    Ok(Sse::new(stream).keep_alive(
         axum::response::sse::KeepAlive::new()
             .interval(std::time::Duration::from_secs(30))
             .text("keep-alive"),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/events/replay",
+    tag = "SSE",
+    params(
+        ("from" = Option<String>, Query, description = "RFC 3339 start of the window, defaults to the oldest event kept (events are kept in memory for 15 minutes, at most 100,000, and cleared on restart)"),
+        ("to" = Option<String>, Query, description = "RFC 3339 end of the window, defaults to now"),
+        ("speed" = Option<f64>, Query, description = "How many times faster than real time to play, above 0 and at most 1000 (defaults to 1)"),
+        ("limit" = Option<usize>, Query, description = "Most events to play, 1 to 1000 (defaults to 1000)"),
+        ("after" = Option<u64>, Query, description = "Play only events after this one; pass the `next` of the previous page's `end` event"),
+    ),
+    responses(
+        (status = 200, description = "A page of the room's recent events from the window, spaced as they were broadcast and divided by `speed`; each `id` is the time the event was broadcast, and an `end` event follows the last one with the `next` page's `after`, or null on the last page", body = AppEvent, content_type = "text/event-stream"),
+        (status = 401, description = "Private room and no session", body = ErrorResponse),
+        (status = 403, description = "Private room the session was not invited to", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+        (status = 422, description = "Inverted window, or speed or limit out of range", body = ErrorResponse),
+    )
+)]
+pub async fn replay_events(
+    State(context): State<AppContext>,
+    CurrentRoom(room): CurrentRoom,
+    Query(query): Query<EventReplayQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<axum::response::sse::Event, Infallible>>>, AppError> {
+    check_room_access(&context, &room, &headers).await?;
+
+    let to = query.to.unwrap_or_else(Utc::now);
+    if query.from.is_some_and(|from| from > to) {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "from",
+            "must not be later than to",
+        )]));
+    }
+    let speed = query.speed.unwrap_or(1.0);
+    if !(speed > 0.0 && speed <= MAX_REPLAY_SPEED) {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "speed",
+            format!("must be above 0 and at most {}", MAX_REPLAY_SPEED),
+        )]));
+    }
+
+    let limit = query.limit.unwrap_or(MAX_REPLAY_EVENTS);
+    if !(1..=MAX_REPLAY_EVENTS).contains(&limit) {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "limit",
+            format!("must be between 1 and {}", MAX_REPLAY_EVENTS),
+        )]));
+    }
+
+    // One extra event shows whether another page follows
+    let mut events = room
+        .state
+        .events
+        .history(query.from, to, query.after.unwrap_or(0), limit + 1);
+    let next = if events.len() > limit {
+        events.truncate(limit);
+        events.last().map(|recorded| recorded.event.seq)
+    } else {
+        None
+    };
+    let count = events.len();
+    let mut previous_at = events.first().map(|recorded| recorded.at);
+
+    let replay = stream::iter(events).then(move |recorded| {
+        let gap = previous_at
+            .replace(recorded.at)
+            .and_then(|previous| (recorded.at - previous).to_std().ok())
+            .unwrap_or_default();
+        async move {
+            tokio::time::sleep(gap.div_f64(speed)).await;
+            Ok(axum::response::sse::Event::default()
+                .id(recorded.at.to_rfc3339())
+                .data(&*recorded.event.payload))
+        }
+    });
+    // Without it, EventSource would reconnect and play the window again
+    let end = stream::once(async move {
+        Ok(axum::response::sse::Event::default()
+            .event("end")
+            .data(json!({ "events": count, "next": next }).to_string()))
+    });
+    let stream = replay
+        .chain(end)
+        .take_until(room.shutdown.clone().cancelled_owned());
+
+    Ok(Sse::new(stream).keep_alive(
+        axum::response::sse::KeepAlive::new()
+            .interval(std::time::Duration::from_secs(30))
+            .text("keep-alive"),
+    ))
+}
+
+/// Private rooms only stream to sessions admitted to them.
+async fn check_room_access(
+    context: &AppContext,
+    room: &Room,
+    headers: &HeaderMap,
+) -> Result<(), AppError> {
+    if room.private {
+        let session_id = get_session_from_cookie(headers, None, &context.state.sessions).await?;
+        if !room.admits(&session_id) {
+            return Err(AppError::NotRoomMember {
+                room_id: room.id.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Down-converts a stream's events when the client asked for an older schema
+/// version than the latest.
+async fn event_downgrade(
+    state: &AppState,
+    version: Option<u32>,
+) -> Result<Option<EventDowngrade>, AppError> {
+    let version = version.unwrap_or(EVENT_SCHEMA_VERSION);
+    if !(MIN_EVENT_SCHEMA_VERSION..=EVENT_SCHEMA_VERSION).contains(&version) {
+        return Err(AppError::UnsupportedSchemaVersion {
+            version,
+            min: MIN_EVENT_SCHEMA_VERSION,
+            latest: EVENT_SCHEMA_VERSION,
+        });
+    }
+    if version == EVENT_SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    let (current_slot, window) = state.get_upcoming_slots().await?;
+    Ok(Some(EventDowngrade::new(version, current_slot, window)))
+}
+
+/// One subscriber's view of the event channel. Events arriving within the
+/// configured flush interval of each other are sent as a coalesced batch.
+struct SubscriberStream {
+    subscription: EventSubscription,
+    state: AppState,
+    config: SharedConfig,
+    batch: EventBatch,
+    ready: VecDeque<SharedEvent>,
+    /// Set when the client asked for an older schema version
+    downgrade: Option<EventDowngrade>,
+    /// Sequence number of the newest event received; older ones arriving
+    /// from the channel were already replayed
+    last_seq: u64,
+    closed: bool,
+}
+
+impl SubscriberStream {
+    /// The next event, with a resumption token if it ends its batch.
+    async fn next(&mut self) -> Option<(SharedEvent, Option<String>)> {
+        while self.ready.is_empty() && !self.closed {
+            // A resumed stream starts with its replay already batched
+            if self.batch.is_empty() {
+                self.fill_batch().await;
+            }
+
+            let (events, coalesced) = self.batch.drain();
+            if coalesced > 0 {
+                self.subscription.record_coalesced(coalesced);
+            }
+            match self.downgrade.as_mut() {
+                Some(downgrade) => self.ready.extend(
+                    events
+                        .into_iter()
+                        .filter_map(|event| downgrade.apply(event)),
+                ),
+                None => self.ready.extend(events),
+            }
+        }
+
+        let event = self.ready.pop_front()?;
+        let token = (self.ready.is_empty() && self.last_seq > 0)
+            .then(|| self.state.events.resumption_token(self.last_seq));
+        Some((event, token))
+    }
+
+    /// Batches what was missed since `token`, or a resync when that is no
+    /// longer buffered.
+    async fn resume(&mut self, token: &str) {
+        let resumption = self.subscription.resume(token);
+        if let Some(missed) = resumption.resync {
+            match self.state.stream_resync(missed).await {
+                Ok(resync) => self.batch.push(SharedEvent::new(resync)),
+                // The room has stopped, so there is nothing left to stream
+                Err(_) => self.closed = true,
+            }
+        }
+        for event in resumption.events {
+            self.batch.push(event);
+        }
+        self.last_seq = resumption.last_seq;
+    }
+
+    /// Waits for an event, then keeps collecting until the flush interval has
+    /// passed since it arrived.
+    async fn fill_batch(&mut self) {
+        let received = self.subscription.recv().await;
+        if !self.receive(received).await {
+            return;
+        }
+
+        let flush_interval_ms = self.config.current().events.flush_interval_ms;
+        if flush_interval_ms == 0 {
+            return;
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(flush_interval_ms);
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => return,
+                // Only the receive races the deadline; a resync it triggers always completes
+                received = self.subscription.recv() => if !self.receive(received).await {
+                    return;
+                },
+            }
+        }
+    }
+
+    /// Adds a received event to the batch, returning false once the channel closes.
+    async fn receive(&mut self, received: Result<SharedEvent, RecvError>) -> bool {
+        let lagged = match received {
+            Ok(event) => {
+                if event.seq > self.last_seq {
+                    self.last_seq = event.seq;
+                    self.batch.push(event);
+                }
+                return true;
+            }
+            Err(RecvError::Lagged(missed)) => missed,
+            Err(RecvError::Closed) => {
+                self.closed = true;
+                return false;
+            }
+        };
+
+        // Replace the dropped events with a snapshot rather than ending the stream
+        tracing::warn!(
+            "SSE subscriber {} lagged by {} events, sending resync",
+            self.subscription.id(),
+            lagged
+        );
+        match self.state.stream_resync(lagged).await {
+            Ok(resync) => {
+                self.batch.push(SharedEvent::new(resync));
+                true
+            }
+            Err(_) => {
+                self.closed = true;
+                false
+            }
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/events/metrics",
+    tag = "SSE",
+    responses(
+        (status = 200, description = "Event stream subscriber metrics", body = ApiResponse<BroadcastMetrics>),
+    )
+)]
+pub async fn event_metrics(RoomScoped(context): RoomScoped) -> impl IntoResponse {
+    let metrics = context.state.events.metrics();
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Event stream metrics fetched successfully".into(),
+            metrics,
+        )),
     )
+        .into_response()
 }
@@ -1,22 +1,27 @@
-use axum::{Json, http::StatusCode, response::IntoResponse};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use chrono::Utc;
-use serde_json::json;
 
-use crate::models::responses::ApiResponse;
+use crate::{
+    app::api::AppContext,
+    models::responses::{
+        ApiResponse, ErrorResponse, EventChannelCheck, HealthStatus, ReadinessChecks,
+        ReadinessReport, SlotClockCheck, StorageCheck,
+    },
+};
 
 #[utoipa::path(
     get,
     path = "/health",
     tag = "Health",
     responses(
-        (status = 200, description = "Server is healthy", body = ApiResponse),
+        (status = 200, description = "Server is healthy", body = ApiResponse<HealthStatus>),
     )
 )]
 pub async fn health_check() -> impl IntoResponse {
-    let data = json!({
-        "status": "healthy",
-        "timestamp": Utc::now()
-    });
+    let data = HealthStatus {
+        status: "healthy".to_string(),
+        timestamp: Utc::now(),
+    };
 
     (
         StatusCode::OK,
@@ -24,3 +29,113 @@ pub async fn health_check() -> impl IntoResponse {
     )
         .into_response()
 }
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "Health",
+    responses(
+        (status = 200, description = "Process is alive", body = ApiResponse<HealthStatus>),
+    )
+)]
+pub async fn liveness() -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Server is alive.".to_string(),
+            HealthStatus {
+                status: "alive".to_string(),
+                timestamp: Utc::now(),
+            },
+        )),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "Health",
+    responses(
+        (status = 200, description = "Server is ready to serve traffic", body = ApiResponse<ReadinessReport>),
+        (status = 503, description = "Server is not ready", body = ErrorResponse),
+    )
+)]
+pub async fn readiness(State(context): State<AppContext>) -> impl IntoResponse {
+    let now = Utc::now();
+    let max_tick_age_ms = 2 * context.config.current().marketplace.tick_interval_ms() as i64;
+    let last_tick = context.state.health.last_tick();
+    let tick_age_ms = last_tick.map(|tick| (now - tick).num_milliseconds());
+
+    let mut failures = Vec::new();
+
+    match tick_age_ms {
+        None => failures.push("slot clock has not ticked yet".to_string()),
+        Some(age) if age > max_tick_age_ms => failures.push(format!(
+            "slot clock stalled, last tick {}ms ago (limit {}ms)",
+            age, max_tick_age_ms
+        )),
+        _ => {}
+    }
+
+    let events = &context.state.events;
+    if events.is_saturated() {
+        failures.push(format!(
+            "event channel saturated ({}/{} queued)",
+            events.queued(),
+            events.capacity()
+        ));
+    }
+
+    // A recently restarted task may have left state half-updated
+    for task in context.state.health.degraded_tasks() {
+        failures.push(format!(
+            "degraded, {} task panicked and was restarted",
+            task
+        ));
+    }
+
+    if !failures.is_empty() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::failure(
+                format!("Not ready: {}", failures.join("; ")),
+                503,
+            )),
+        )
+            .into_response();
+    }
+
+    let data = ReadinessReport {
+        status: "ready".to_string(),
+        timestamp: now,
+        checks: ReadinessChecks {
+            slot_clock: SlotClockCheck {
+                last_tick,
+                age_ms: tick_age_ms,
+            },
+            event_channel: EventChannelCheck {
+                queued: events.queued(),
+                capacity: events.capacity(),
+            },
+            background_tasks: context
+                .state
+                .health
+                .task_failures()
+                .into_iter()
+                .map(|(task, failure)| (task.to_string(), failure))
+                .collect(),
+            // All state is held in memory, so there is no external storage to probe
+            storage: StorageCheck {
+                backend: "in_memory".to_string(),
+                reachable: true,
+            },
+        },
+    };
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success("Server is ready.".to_string(), data)),
+    )
+        .into_response()
+}
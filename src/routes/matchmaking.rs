@@ -0,0 +1,112 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    app::api::AppContext,
+    error::AppError,
+    managers::matchmaking::QueuedPlayer,
+    models::{
+        requests::{DEFAULT_MATCH_SIZE, JoinQueueRequest},
+        responses::{ApiResponse, ErrorResponse, MatchFound, QueueTicket},
+    },
+    services::{
+        matchmaking::{QueueOutcome, enqueue, queue_ticket},
+        session::get_session_from_cookie,
+    },
+    utils::validation::ValidatedJson,
+};
+
+#[utoipa::path(
+    post,
+    path = "/matchmaking/queue",
+    tag = "Rooms",
+    request_body = JoinQueueRequest,
+    responses(
+        (status = 201, description = "This player completed a group; the match room was opened and every player moved into it", body = ApiResponse<MatchFound>),
+        (status = 202, description = "Waiting for more players; a MatchReady event follows on the player's event stream", body = ApiResponse<QueueTicket>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Spectators cannot play matches", body = ErrorResponse),
+        (status = 409, description = "Already waiting for a match, or too many rooms are open", body = ErrorResponse),
+        (status = 422, description = "Invalid match size", body = ErrorResponse)
+    )
+)]
+pub async fn join_matchmaking_queue(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<JoinQueueRequest>,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+    if context.state.sessions.is_spectator(&session_id).await {
+        return Err(AppError::SpectatorReadOnly);
+    }
+
+    let match_size = req.match_size.unwrap_or(DEFAULT_MATCH_SIZE);
+    Ok(match enqueue(&context, &session_id, match_size)? {
+        QueueOutcome::Matched(found) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::success("Match found.".into(), found)),
+        )
+            .into_response(),
+        QueueOutcome::Queued(ticket) => (
+            StatusCode::ACCEPTED,
+            Json(ApiResponse::success("Waiting for a match.".into(), ticket)),
+        )
+            .into_response(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/matchmaking/queue",
+    tag = "Rooms",
+    responses(
+        (status = 200, description = "The player's place in the queue", body = ApiResponse<QueueTicket>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Not waiting for a match", body = ErrorResponse)
+    )
+)]
+pub async fn get_matchmaking_ticket(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+    let ticket = queue_ticket(&context, &session_id).ok_or(AppError::NotQueued)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("Waiting for a match.".into(), ticket)),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/matchmaking/queue",
+    tag = "Rooms",
+    responses(
+        (status = 200, description = "Left the queue", body = ApiResponse<QueuedPlayer>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Not waiting for a match", body = ErrorResponse)
+    )
+)]
+pub async fn leave_matchmaking_queue(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+    let player = context
+        .rooms
+        .matchmaking()
+        .leave(&session_id)
+        .ok_or(AppError::NotQueued)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("Left the queue.".into(), player)),
+    )
+        .into_response())
+}
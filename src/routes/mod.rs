@@ -1,7 +1,14 @@
+pub mod admin;
+pub mod analytics;
 pub mod auction;
+pub mod chain;
+pub mod docs;
 pub mod event;
 pub mod health;
+pub mod matchmaking;
+pub mod room;
 pub mod session;
 pub mod slot;
 pub mod stats;
 pub mod transaction;
+pub mod wallet;
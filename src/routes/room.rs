@@ -0,0 +1,464 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{Duration, Utc};
+
+use crate::{
+    app::{
+        api::AppContext,
+        room::{GLOBAL_ROOM_ID, Room, RoomInvite},
+    },
+    error::AppError,
+    models::{
+        admin::AdminRole,
+        requests::{CreateInviteRequest, CreateRoomRequest, JoinRoomRequest, RoomConfigRequest},
+        responses::{
+            ApiResponse, ErrorResponse, PlayerKicked, RoomList, RoomView, SimulationReset,
+        },
+    },
+    services::{
+        room::{configure_room, create_room, end_room},
+        session::get_session_from_cookie,
+    },
+    utils::validation::{FieldError, ValidatedJson},
+};
+
+const DEFAULT_INVITE_USES: u32 = 10;
+const DEFAULT_INVITE_EXPIRY_MINUTES: u32 = 1440;
+
+#[utoipa::path(
+    post,
+    path = "/rooms",
+    tag = "Rooms",
+    request_body = CreateRoomRequest,
+    responses(
+        (status = 201, description = "Room created and joined", body = ApiResponse<RoomView>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 409, description = "Too many rooms are open", body = ErrorResponse),
+        (status = 422, description = "Invalid room name", body = ErrorResponse)
+    )
+)]
+pub async fn create_game_room(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<CreateRoomRequest>,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+
+    let room = create_room(
+        &context,
+        req.name.trim().to_string(),
+        session_id.clone(),
+        req.private,
+        req.mode,
+    )?;
+    context.rooms.join(&session_id, &room.id);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(
+            "Room created.".into(),
+            RoomView::new(&room, context.rooms.member_count(&room.id)).await?,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/rooms",
+    tag = "Rooms",
+    responses(
+        (status = 200, description = "Open rooms, the global room first", body = ApiResponse<RoomList>)
+    )
+)]
+pub async fn list_rooms(State(context): State<AppContext>) -> Result<Response, AppError> {
+    let mut rooms = Vec::new();
+    for room in context.rooms.list() {
+        rooms.push(RoomView::new(&room, context.rooms.member_count(&room.id)).await?);
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Rooms fetched successfully.".into(),
+            RoomList { rooms },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/rooms/{room_id}",
+    tag = "Rooms",
+    params(("room_id" = String, Path, description = "Room ID, `global` for the shared room")),
+    responses(
+        (status = 200, description = "Room details", body = ApiResponse<RoomView>),
+        (status = 404, description = "Room not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_room(
+    State(context): State<AppContext>,
+    Path(room_id): Path<String>,
+) -> Result<Response, AppError> {
+    let room = context
+        .rooms
+        .get(&room_id)
+        .ok_or(AppError::RoomNotFound { room_id })?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Room fetched successfully.".into(),
+            RoomView::new(&room, context.rooms.member_count(&room.id)).await?,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/rooms/{room_id}/join",
+    tag = "Rooms",
+    params(("room_id" = String, Path, description = "Room ID, `global` to go back to the shared room")),
+    responses(
+        (status = 200, description = "Joined; requests without a room scope now apply to this room", body = ApiResponse<RoomView>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Room is private and the session was not invited", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse)
+    )
+)]
+pub async fn join_room(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Path(room_id): Path<String>,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+    let room = context
+        .rooms
+        .get(&room_id)
+        .ok_or(AppError::RoomNotFound { room_id })?;
+    if !room.admits(&session_id) {
+        return Err(AppError::NotRoomMember {
+            room_id: room.id.clone(),
+        });
+    }
+
+    context.rooms.join(&session_id, &room.id);
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            format!("Joined room {}.", room.name),
+            RoomView::new(&room, context.rooms.member_count(&room.id)).await?,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/rooms/join",
+    tag = "Rooms",
+    request_body = JoinRoomRequest,
+    responses(
+        (status = 200, description = "Admitted to and joined the invite's room", body = ApiResponse<RoomView>),
+        (status = 400, description = "Invite code is unknown, expired or used up", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 422, description = "Missing invite code", body = ErrorResponse)
+    )
+)]
+pub async fn join_room_with_invite(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<JoinRoomRequest>,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+
+    let room = context.rooms.redeem_invite(&req.code, &session_id)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            format!("Joined room {}.", room.name),
+            RoomView::new(&room, context.rooms.member_count(&room.id)).await?,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/rooms/{room_id}/invites",
+    tag = "Rooms",
+    params(("room_id" = String, Path, description = "Room ID")),
+    request_body = CreateInviteRequest,
+    responses(
+        (status = 201, description = "Invite code issued", body = ApiResponse<RoomInvite>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the room's creator can invite players", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+        (status = 422, description = "Invalid limits", body = ErrorResponse)
+    )
+)]
+pub async fn create_room_invite(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Path(room_id): Path<String>,
+    ValidatedJson(req): ValidatedJson<CreateInviteRequest>,
+) -> Result<Response, AppError> {
+    let (room, _) = created_room(&context, &headers, room_id).await?;
+
+    let expires_at = Utc::now()
+        + Duration::minutes(i64::from(
+            req.expires_in_minutes
+                .unwrap_or(DEFAULT_INVITE_EXPIRY_MINUTES),
+        ));
+    let invite = context.rooms.create_invite(
+        &room.id,
+        req.max_uses.unwrap_or(DEFAULT_INVITE_USES),
+        expires_at,
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("Invite created.".into(), invite)),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/rooms/{room_id}/members/{session_id}",
+    tag = "Rooms",
+    params(
+        ("room_id" = String, Path, description = "Room ID"),
+        ("session_id" = String, Path, description = "Member's session ID"),
+    ),
+    responses(
+        (status = 200, description = "Member removed from the room and their open bids in it refunded", body = ApiResponse<PlayerKicked>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the room's creator can kick members", body = ErrorResponse),
+        (status = 404, description = "Room not found, or the session is not in it", body = ErrorResponse),
+        (status = 422, description = "The creator cannot kick themselves", body = ErrorResponse)
+    )
+)]
+pub async fn kick_room_member(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Path((room_id, member_id)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    let (room, session_id) = created_room(&context, &headers, room_id).await?;
+    if member_id == session_id {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "session_id",
+            "the room's creator cannot kick themselves",
+        )]));
+    }
+    if !context.rooms.remove_member(&member_id, &room.id) {
+        return Err(AppError::RoomMemberNotFound {
+            room_id: room.id.clone(),
+            session_id: member_id,
+        });
+    }
+
+    let refunded = room
+        .state
+        .remove_player(&member_id, "Kicked from the room")
+        .await?;
+    tracing::info!(
+        room_id = %room.id,
+        "Room creator kicked {} and refunded {} SOL",
+        member_id.chars().take(8).collect::<String>(),
+        refunded
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Member kicked.".into(),
+            PlayerKicked {
+                session_id: member_id,
+                refunded,
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/rooms/{room_id}/reset",
+    tag = "Rooms",
+    params(("room_id" = String, Path, description = "Room ID")),
+    responses(
+        (status = 200, description = "Room's marketplace, players and ledger reset", body = ApiResponse<SimulationReset>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the room's creator can reset it", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse)
+    )
+)]
+pub async fn reset_room(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Path(room_id): Path<String>,
+) -> Result<Response, AppError> {
+    let (room, _) = created_room(&context, &headers, room_id).await?;
+
+    room.state.reset_simulation().await?;
+    tracing::info!(room_id = %room.id, "Room creator reset the room");
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Room reset.".into(),
+            SimulationReset {
+                current_slot: room.state.get_current_slot().await?,
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    patch,
+    path = "/rooms/{room_id}/config",
+    tag = "Rooms",
+    params(("room_id" = String, Path, description = "Room ID")),
+    request_body = RoomConfigRequest,
+    responses(
+        (status = 200, description = "Settings applied to slots created from now on", body = ApiResponse<RoomView>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the room's creator can configure it", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse),
+        (status = 422, description = "Invalid settings", body = ErrorResponse)
+    )
+)]
+pub async fn configure_game_room(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Path(room_id): Path<String>,
+    ValidatedJson(req): ValidatedJson<RoomConfigRequest>,
+) -> Result<Response, AppError> {
+    let (room, _) = created_room(&context, &headers, room_id).await?;
+
+    let mut overrides = room.overrides();
+    if req.slot_duration_ms.is_some() {
+        overrides.slot_duration_ms = req.slot_duration_ms;
+    }
+    if req.base_fee_sol.is_some() {
+        overrides.base_fee_sol = req.base_fee_sol;
+    }
+    configure_room(&room, &context.config.current(), overrides).await?;
+    tracing::info!(room_id = %room.id, "Room creator changed settings: {:?}", overrides);
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Room settings updated.".into(),
+            RoomView::new(&room, context.rooms.member_count(&room.id)).await?,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/rooms/{room_id}",
+    tag = "Rooms",
+    params(("room_id" = String, Path, description = "Room ID")),
+    responses(
+        (status = 200, description = "Room closed, its final standings sent to its event stream and its members sent back to the global room", body = ApiResponse<RoomView>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only the room's creator can close it", body = ErrorResponse),
+        (status = 404, description = "Room not found", body = ErrorResponse)
+    )
+)]
+pub async fn close_own_room(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Path(room_id): Path<String>,
+) -> Result<Response, AppError> {
+    let (room, _) = created_room(&context, &headers, room_id).await?;
+    let members = context.rooms.member_count(&room.id);
+    let room = end_room(&context, &room.id)
+        .await?
+        .ok_or(AppError::RoomNotFound {
+            room_id: room.id.clone(),
+        })?;
+
+    tracing::info!(room_id = %room.id, name = %room.name, "Room creator closed the room");
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Room closed.".into(),
+            RoomView::new(&room, members).await?,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/rooms/{room_id}",
+    tag = "Admin",
+    params(
+        ("room_id" = String, Path, description = "Room ID"),
+        ("x-admin-token" = String, Header, description = "Admin token"),
+    ),
+    responses(
+        (status = 200, description = "Room closed and its members sent back to the global room", body = ApiResponse<RoomView>),
+        (status = 401, description = "Missing or invalid admin token", body = ErrorResponse),
+        (status = 403, description = "Admin role required", body = ErrorResponse),
+        (status = 404, description = "Room not found; the global room cannot be closed", body = ErrorResponse),
+    )
+)]
+pub async fn close_room(
+    State(context): State<AppContext>,
+    Extension(role): Extension<AdminRole>,
+    Path(room_id): Path<String>,
+) -> Result<Response, AppError> {
+    let members = context.rooms.member_count(&room_id);
+    let room = match room_id.as_str() {
+        GLOBAL_ROOM_ID => None,
+        _ => end_room(&context, &room_id).await?,
+    }
+    .ok_or(AppError::RoomNotFound { room_id })?;
+
+    tracing::warn!(role = ?role, "Admin action: closed room {} ({})", room.id, room.name);
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Room closed.".into(),
+            RoomView::new(&room, members).await?,
+        )),
+    )
+        .into_response())
+}
+
+/// The room, if the caller's session created it, and that session.
+async fn created_room(
+    context: &AppContext,
+    headers: &HeaderMap,
+    room_id: String,
+) -> Result<(Arc<Room>, String), AppError> {
+    let session_id = get_session_from_cookie(headers, None, &context.state.sessions).await?;
+    let room = context
+        .rooms
+        .get(&room_id)
+        .ok_or(AppError::RoomNotFound { room_id })?;
+    if room.creator().as_deref() != Some(session_id.as_str()) {
+        return Err(AppError::NotRoomCreator {
+            room_id: room.id.clone(),
+        });
+    }
+    Ok((room, session_id))
+}
@@ -1,74 +1,560 @@
+use std::net::SocketAddr;
+
+use chrono::Utc;
+
+use crate::app::api::AppContext;
 use axum::{
     Json,
-    extract::State,
+    extract::{ConnectInfo, Path, Query, State},
     http::{HeaderMap, StatusCode, header},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
-use serde_json::json;
 
-use crate::app::api::AppContext;
-
-use crate::models::responses::ApiResponse;
+use crate::{
+    error::AppError,
+    models::{
+        recovery::{generate_recovery_code, hash_recovery_code},
+        requests::{
+            CreateApiKeyRequest, CreateSessionQuery, RecoverSessionRequest, UpgradeSessionRequest,
+        },
+        responses::{
+            ActiveSessions, ApiKeyCreated, ApiKeyList, ApiResponse, DeviceView, ErrorResponse,
+            GuestUpgraded, RecoveryCodeIssued, SessionEnded, SessionRecovered, SessionStatus,
+            SessionView,
+        },
+    },
+    services::session::{
+        expired_session_cookie, get_session_from_cookie, refresh_session, session_cookie,
+        session_token_from_cookie, upgrade_guest,
+    },
+    utils::validation::ValidatedJson,
+};
 
 #[utoipa::path(
     post,
     path = "/sessions",
     tag = "Session",
+    params(
+        ("spectator" = Option<bool>, Query, description = "Create a read-only session that can watch but not bid; ignored when validating an existing session")
+    ),
     responses(
-        (status = 200, description = "Session created or validated", body = ApiResponse),
+        (status = 200, description = "Session created or validated", body = ApiResponse<SessionView>),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn create_or_validate_session(
     State(context): State<AppContext>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
+    Query(query): Query<CreateSessionQuery>,
 ) -> impl IntoResponse {
-    let session_id = headers
-        .get(header::COOKIE)
+    let spectator = query.spectator.unwrap_or(false);
+    let session_token = session_token_from_cookie(&headers, &context.state.sessions.cookie().name);
+
+    let user_agent = headers
+        .get(header::USER_AGENT)
         .and_then(|h| h.to_str().ok())
-        .and_then(|cookies| {
-            cookies
-                .split(';')
-                .find(|c| c.trim().starts_with("raiku_session="))
-                .and_then(|c| c.split('=').nth(1))
-        });
+        .map(str::to_string);
+
+    let existing = match session_token {
+        Some(token) => match context.state.sessions.verify_session_token(&token) {
+            Some(sid) => context.state.sessions.get_session(&sid).await,
+            None => {
+                tracing::warn!(
+                    "Session cookie from {} has an invalid signature, starting a new session",
+                    addr.ip()
+                );
+                None
+            }
+        },
+        None => None,
+    };
+
+    let (session, is_new) = match existing {
+        Some(sess) => (sess, false),
+        None => (
+            context
+                .state
+                .sessions
+                .create_session(user_agent, Some(addr.ip()), spectator)
+                .await,
+            true,
+        ),
+    };
+
+    context
+        .state
+        .sessions
+        .record_client_ip(&session.id, addr.ip())
+        .await;
+
+    let cookie = session_cookie(&context.state.sessions, &session.id);
 
-    let (session, is_new) = if let Some(sid) = session_id {
-        if let Some(sess) = context.state.sessions.get_session(sid).await {
-            (sess, false)
+    let data = SessionView {
+        session_token: context.state.sessions.session_token(&session.id),
+        session_id: session.id,
+        device_id: session.device_id,
+        status: if is_new {
+            SessionStatus::Created
         } else {
-            (context.state.sessions.create_session().await, true)
+            SessionStatus::Validated
+        },
+        created_at: session.created_at,
+        expires_at: session.expires_at,
+        spectator: session.spectator,
+    };
+
+    let api_response = ApiResponse::success("Session created or validated.".to_string(), data);
+
+    let mut response = Json(api_response).into_response();
+
+    match cookie {
+        Ok(cookie) => {
+            response.headers_mut().insert(header::SET_COOKIE, cookie);
+            response
         }
-    } else {
-        (context.state.sessions.create_session().await, true)
+        Err(e) => e.into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/refresh",
+    tag = "Session",
+    responses(
+        (status = 200, description = "Session moved to a new ID with its stats, history, rooms and API keys; the old ID no longer works", body = ApiResponse<SessionView>),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse),
+        (status = 409, description = "A bid is still pending", body = ErrorResponse)
+    )
+)]
+pub async fn refresh_session_id(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+
+    let session = refresh_session(&context.rooms, &session_id).await?;
+
+    tracing::info!(
+        "Session {} refreshed as {}",
+        session_id.chars().take(8).collect::<String>(),
+        session.id.chars().take(8).collect::<String>()
+    );
+
+    let cookie = session_cookie(&context.state.sessions, &session.id)?;
+
+    let data = SessionView {
+        session_token: context.state.sessions.session_token(&session.id),
+        session_id: session.id,
+        device_id: session.device_id,
+        status: SessionStatus::Refreshed,
+        created_at: session.created_at,
+        expires_at: session.expires_at,
+        spectator: session.spectator,
     };
 
-    let cookie_value = format!(
-        "raiku_session={}; Path=/; HttpOnly; SameSite=None; Secure; Max-Age={}",
-        session.id, 86400
+    Ok((
+        StatusCode::OK,
+        [(header::SET_COOKIE, cookie)],
+        Json(ApiResponse::success("Session refreshed.".to_string(), data)),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/upgrade",
+    tag = "Session",
+    request_body = UpgradeSessionRequest,
+    responses(
+        (status = 200, description = "Session moved to an account ID with its stats, history, escrow records, rooms and API keys", body = ApiResponse<GuestUpgraded>),
+        (status = 400, description = "`wallet` upgrade without a linked wallet", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse),
+        (status = 409, description = "A bid is still pending", body = ErrorResponse)
+    )
+)]
+pub async fn upgrade_session(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Json(req): Json<UpgradeSessionRequest>,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+
+    let upgraded = upgrade_guest(&context.rooms, &session_id, req.method).await?;
+
+    tracing::info!(
+        "Session {} upgraded to account {}",
+        session_id.chars().take(8).collect::<String>(),
+        upgraded.session_id.chars().take(13).collect::<String>()
     );
 
-    let data = json!({
-        "session_id": session.id,
-        "status": if is_new { "created" } else { "validated" },
-        "created_at": session.created_at,
-        "expires_at": session.expires_at
-    });
+    let cookie = session_cookie(&context.state.sessions, &upgraded.session_id)?;
 
-    let api_response = ApiResponse::success("Session created or validated.".to_string(), data);
+    Ok((
+        StatusCode::OK,
+        [(header::SET_COOKIE, cookie)],
+        Json(ApiResponse::success(
+            "Session upgraded to an account.".to_string(),
+            upgraded,
+        )),
+    )
+        .into_response())
+}
 
-    let mut response = Json(api_response).into_response();
+#[utoipa::path(
+    post,
+    path = "/sessions/recovery-code",
+    tag = "Session",
+    responses(
+        (status = 200, description = "Recovery code issued, replacing any earlier one; it is only shown once", body = ApiResponse<RecoveryCodeIssued>),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse)
+    )
+)]
+pub async fn create_recovery_code(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+
+    let recovery_code = generate_recovery_code();
+    let (session, code_hash) = (session_id.clone(), hash_recovery_code(&recovery_code));
+    context
+        .state
+        .game
+        .call(move |game| game.set_recovery_code(&session, code_hash))
+        .await?;
+
+    tracing::info!(
+        "Issued recovery code for session {}",
+        session_id.chars().take(8).collect::<String>()
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Recovery code issued. Store it now; it will not be shown again.".to_string(),
+            RecoveryCodeIssued {
+                recovery_code,
+                issued_at: Utc::now(),
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/recover",
+    tag = "Session",
+    request_body = RecoverSessionRequest,
+    responses(
+        (status = 200, description = "Profile and transaction history attached to this session; returns a new recovery code", body = ApiResponse<SessionRecovered>),
+        (status = 400, description = "Unknown or already used recovery code", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse),
+        (status = 409, description = "A bid is still pending", body = ErrorResponse),
+        (status = 422, description = "Missing recovery code", body = ErrorResponse)
+    )
+)]
+pub async fn recover_session(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<RecoverSessionRequest>,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+
+    let recovery_code = generate_recovery_code();
+    let player_stats = context
+        .state
+        .recover_profile(&session_id, &req.recovery_code, &recovery_code)
+        .await?;
+    let transaction_count = context
+        .state
+        .get_session_transaction_count(&session_id)
+        .await;
+
+    tracing::info!(
+        "Recovered a profile into session {}",
+        session_id.chars().take(8).collect::<String>()
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Profile recovered. Store the new recovery code; the old one no longer works."
+                .to_string(),
+            SessionRecovered {
+                player_stats,
+                recovery_code,
+                transaction_count,
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/api-keys",
+    tag = "Session",
+    request_body(content = Option<CreateApiKeyRequest>, description = "Optional label for the key"),
+    responses(
+        (status = 200, description = "API key issued; the key is only shown once", body = ApiResponse<ApiKeyCreated>),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse)
+    )
+)]
+pub async fn create_api_key(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    req: Option<Json<CreateApiKeyRequest>>,
+) -> impl IntoResponse {
+    let session_id = match get_session_from_cookie(&headers, None, &context.state.sessions).await {
+        Ok(sid) => sid,
+        Err(e) => return e.into_response(),
+    };
+
+    let Json(req) = req.unwrap_or_default();
+    let (key, api_key) = context
+        .state
+        .sessions
+        .create_api_key(&session_id, req.name)
+        .await;
+
+    tracing::info!(
+        "Issued API key {} for session {}",
+        api_key.prefix,
+        session_id.chars().take(8).collect::<String>()
+    );
+
+    let data = ApiKeyCreated {
+        api_key: key,
+        id: api_key.id,
+        name: api_key.name,
+        prefix: api_key.prefix,
+        created_at: api_key.created_at,
+    };
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "API key created. Store it now; it will not be shown again.".to_string(),
+            data,
+        )),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/api-keys",
+    tag = "Session",
+    responses(
+        (status = 200, description = "API keys for the session with usage", body = ApiResponse<ApiKeyList>),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse)
+    )
+)]
+pub async fn list_api_keys(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let session_id = match get_session_from_cookie(&headers, None, &context.state.sessions).await {
+        Ok(sid) => sid,
+        Err(e) => return e.into_response(),
+    };
+
+    let api_keys = context.state.sessions.list_api_keys(&session_id).await;
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "API keys fetched successfully".to_string(),
+            ApiKeyList { api_keys },
+        )),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sessions",
+    tag = "Session",
+    responses(
+        (status = 200, description = "Logged out; open bids were refunded", body = ApiResponse<SessionEnded>),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse)
+    )
+)]
+pub async fn logout(State(context): State<AppContext>, headers: HeaderMap) -> impl IntoResponse {
+    let session_id = match get_session_from_cookie(&headers, None, &context.state.sessions).await {
+        Ok(sid) => sid,
+        Err(e) => return e.into_response(),
+    };
+
+    let cookie = match expired_session_cookie(context.state.sessions.cookie()) {
+        Ok(cookie) => cookie,
+        Err(e) => return e.into_response(),
+    };
+
+    let refunded = match context
+        .rooms
+        .end_session(&session_id, "Player logged out")
+        .await
+    {
+        Ok(refunded) => refunded,
+        Err(e) => {
+            return (StatusCode::UNAUTHORIZED, Json(ApiResponse::failure(e, 401))).into_response();
+        }
+    };
 
-    if let Ok(cookie_header) = cookie_value.parse() {
-        response
-            .headers_mut()
-            .insert(header::SET_COOKIE, cookie_header);
-        response
-    } else {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse::failure("Failed to set session cookie", 500)),
+    tracing::info!(
+        "Session {} logged out",
+        session_id.chars().take(8).collect::<String>()
+    );
+
+    (
+        StatusCode::OK,
+        [(header::SET_COOKIE, cookie)],
+        Json(ApiResponse::success(
+            "Logged out.".to_string(),
+            SessionEnded {
+                device_id: None,
+                refunded,
+            },
+        )),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/devices",
+    tag = "Session",
+    responses(
+        (status = 200, description = "Sessions of the caller's wallet, or just the caller's session", body = ApiResponse<ActiveSessions>),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse)
+    )
+)]
+pub async fn list_active_sessions(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let session = match get_session_from_cookie(&headers, None, &context.state.sessions).await {
+        Ok(sid) => context.state.sessions.get_session(&sid).await,
+        Err(e) => return e.into_response(),
+    };
+    let Some(session) = session else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ApiResponse::failure(
+                "Session ID is missing or invalid",
+                401,
+            )),
+        )
+            .into_response();
+    };
+
+    let sessions = match &session.wallet {
+        Some(wallet) => context.state.sessions.wallet_sessions(wallet).await,
+        None => vec![session.clone()],
+    };
+
+    let mut devices = Vec::with_capacity(sessions.len());
+    for device in sessions {
+        devices.push(DeviceView {
+            current: device.id == session.id,
+            ip: context.state.sessions.get_client_ip(&device.id).await,
+            device_id: device.device_id,
+            user_agent: device.user_agent,
+            created_ip: device.created_ip,
+            created_at: device.created_at,
+            last_active: device.last_active,
+            expires_at: device.expires_at,
+        });
+    }
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Active sessions fetched successfully".to_string(),
+            ActiveSessions {
+                wallet: session.wallet,
+                sessions: devices,
+            },
+        )),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sessions/devices/{device_id}",
+    tag = "Session",
+    params(("device_id" = String, Path, description = "Device ID from GET /sessions/devices")),
+    responses(
+        (status = 200, description = "Session revoked; its open bids were refunded", body = ApiResponse<SessionEnded>),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse),
+        (status = 404, description = "No such session for this player", body = ErrorResponse)
+    )
+)]
+pub async fn revoke_session(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Path(device_id): Path<String>,
+) -> impl IntoResponse {
+    let session = match get_session_from_cookie(&headers, None, &context.state.sessions).await {
+        Ok(sid) => context.state.sessions.get_session(&sid).await,
+        Err(e) => return e.into_response(),
+    };
+    let Some(session) = session else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ApiResponse::failure(
+                "Session ID is missing or invalid",
+                401,
+            )),
+        )
+            .into_response();
+    };
+
+    // Only the caller's own session or another session of the same wallet can be revoked
+    let target = context
+        .state
+        .sessions
+        .find_by_device(&device_id)
+        .await
+        .filter(|target| {
+            target.id == session.id || (session.wallet.is_some() && target.wallet == session.wallet)
+        });
+    let Some(target) = target else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::failure("Session not found", 404)),
         )
-            .into_response()
+            .into_response();
+    };
+
+    match context
+        .rooms
+        .end_session(&target.id, "Session was revoked")
+        .await
+    {
+        Ok(refunded) => {
+            tracing::info!(
+                "Session {} revoked device {}",
+                session.id.chars().take(8).collect::<String>(),
+                device_id
+            );
+
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success(
+                    "Session revoked.".to_string(),
+                    SessionEnded {
+                        device_id: Some(device_id),
+                        refunded,
+                    },
+                )),
+            )
+                .into_response()
+        }
+        Err(e) => (StatusCode::NOT_FOUND, Json(ApiResponse::failure(e, 404))).into_response(),
     }
 }
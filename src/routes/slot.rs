@@ -1,53 +1,159 @@
-use axum::extract::Path;
+use crate::MAX_SLOT_QUERY_RANGE;
+use crate::app::room::RoomScoped;
+use crate::error::AppError;
+use crate::managers::escrow::EscrowStatus;
+use crate::models::requests::{SlotRangeQuery, SlotSearchQuery};
+use crate::models::responses::{
+    ApiResponse, ErrorResponse, SlotDetail, SlotList, SlotSnapshot, SlotTransaction, SlotView,
+};
+use crate::models::slot::{Slot, SlotStateKind};
+use crate::models::types::Lamports;
+use crate::utils::validation::FieldError;
+use axum::Json;
+use axum::extract::{Path, Query};
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::{Json, extract::State};
-use serde_json::{Value, json};
-
-use crate::app::api::AppContext;
-use crate::models::responses::ApiResponse;
+use axum::response::{IntoResponse, Response};
 
 #[utoipa::path(
     get,
     path = "/marketplace/slots",
     tag = "Marketplace",
+    params(
+        ("from" = Option<u64>, Query, description = "First slot to return, defaults to the current slot"),
+        ("to" = Option<u64>, Query, description = "Last slot to return, defaults to the end of the listing window"),
+        ("state" = Option<SlotStateKind>, Query, description = "Only slots in this state")
+    ),
     responses(
-        (status = 200, description = "List of available slots", body = ApiResponse)
+        (status = 200, description = "Slots in the requested range", body = ApiResponse<SlotList>),
+        (status = 422, description = "Range is inverted or too wide", body = ErrorResponse)
     )
 )]
-pub async fn list_slots(State(context): State<AppContext>) -> impl IntoResponse {
-    let marketplace = context.state.marketplace.read().await;
-    let current_slot = marketplace.current_slot;
+pub async fn list_slots(
+    RoomScoped(context): RoomScoped,
+    Query(query): Query<SlotRangeQuery>,
+) -> Result<Response, AppError> {
+    let (current_slot, listing_end) = context.state.get_listing_range().await?;
+    let listing = listing_end - current_slot;
 
-    let slots: Vec<Value> = marketplace
-        .slots
+    // `to` is inclusive; an open end covers as many slots as the listing window
+    let from = query.from.unwrap_or(current_slot);
+    let to = query
+        .to
+        .unwrap_or_else(|| from.saturating_add(listing.saturating_sub(1)));
+    if from > to {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "from",
+            "must not be greater than to",
+        )]));
+    }
+    if to - from >= MAX_SLOT_QUERY_RANGE {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "to",
+            format!("range may cover at most {} slots", MAX_SLOT_QUERY_RANGE),
+        )]));
+    }
+
+    let slots = context
+        .state
+        .get_slots(from, to.saturating_add(1))
+        .await?
         .iter()
-        .filter(|(slot_num, _)| **slot_num >= current_slot && **slot_num < current_slot + 50)
-        .map(|(slot_num, slot)| {
-            json!({
-                "slot_number": slot_num,
-                "state": slot.state,
-                "estimated_time": slot.estimated_time,
-                "base_fee": slot.base_fee,
-                "compute_units_available": slot.compute_units_available,
-                "compute_units_used": slot.compute_units_used
-            })
+        .filter(|slot| query.state.is_none_or(|state| slot.state.kind() == state))
+        .map(SlotView::summary)
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Slots fetched successfully.".into(),
+            SlotList {
+                current_slot,
+                slots,
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/marketplace/slots/search",
+    tag = "Marketplace",
+    params(
+        ("max_fee" = Option<f64>, Query, description = "Highest base fee in SOL"),
+        ("min_distance" = Option<u64>, Query, description = "Fewest slots ahead of the current slot, default 1"),
+        ("compute_units" = Option<u64>, Query, description = "Compute units the slot must still have room for"),
+        ("limit" = Option<usize>, Query, description = "Most slots to return, 1 to 50, default 10")
+    ),
+    responses(
+        (status = 200, description = "Available slots within AOT reach, cheapest first", body = ApiResponse<SlotList>)
+    )
+)]
+pub async fn search_slots(
+    RoomScoped(context): RoomScoped,
+    Query(query): Query<SlotSearchQuery>,
+) -> Result<Response, AppError> {
+    let current_slot = context.state.get_current_slot().await?;
+    let max_lookahead = context.config.current().marketplace.aot_max_lookahead_slots;
+    let from = current_slot + query.min_distance.unwrap_or(1);
+    let to = current_slot + max_lookahead + 1;
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+
+    let mut candidates: Vec<Slot> = context
+        .state
+        .get_slots(from, to)
+        .await?
+        .into_iter()
+        .filter(|slot| slot.is_available())
+        .filter(|slot| query.max_fee.is_none_or(|max_fee| slot.base_fee <= max_fee))
+        .filter(|slot| {
+            query
+                .compute_units
+                .is_none_or(|compute_units| slot.compute_units_remaining() >= compute_units)
         })
         .collect();
+    // Slots are already in order, so equal fees keep the soonest first
+    candidates.sort_by_key(|slot| slot.base_fee);
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Slot search completed.".into(),
+            SlotList {
+                current_slot,
+                slots: candidates
+                    .iter()
+                    .take(limit)
+                    .map(SlotView::summary)
+                    .collect(),
+            },
+        )),
+    )
+        .into_response())
+}
 
-    let data = json!({
-        "current_slot": current_slot,
-        "slots": slots
-    });
+#[utoipa::path(
+    get,
+    path = "/marketplace/slots/snapshot",
+    tag = "Marketplace",
+    responses(
+        (status = 200, description = "Every visible slot in full, for syncing before applying SlotsChanged events", body = ApiResponse<SlotSnapshot>)
+    )
+)]
+pub async fn slots_snapshot(RoomScoped(context): RoomScoped) -> Result<Response, AppError> {
+    let (current_slot, slots) = context.state.get_upcoming_slots().await?;
 
-    (
+    Ok((
         StatusCode::OK,
         Json(ApiResponse::success(
-            "Slots fetched successfully.".into(),
-            data,
+            "Slot snapshot fetched successfully.".into(),
+            SlotSnapshot {
+                current_slot,
+                slots,
+            },
         )),
     )
-        .into_response()
+        .into_response())
 }
 
 #[utoipa::path(
@@ -58,36 +164,54 @@ pub async fn list_slots(State(context): State<AppContext>) -> impl IntoResponse
         ("slot_number" = u64, Path, description = "Slot number to fetch")
     ),
     responses(
-        (status = 200, description = "Slot details", body = ApiResponse),
-        (status = 404, description = "Slot not found", body = ApiResponse)
+        (status = 200, description = "Slot details with its reservation and executed transactions", body = ApiResponse<SlotDetail>),
+        (status = 404, description = "Slot not found", body = ErrorResponse)
     )
 )]
 pub async fn get_slot(
-    State(context): State<AppContext>,
+    RoomScoped(context): RoomScoped,
     Path(slot_number): Path<u64>,
-) -> impl IntoResponse {
-    let marketplace = context.state.marketplace.read().await;
-
-    if let Some(slot) = marketplace.slots.get(&slot_number) {
-        let data = json!({
-            "slot_number": slot_number,
-            "state": slot.state,
-            "estimated_time": slot.estimated_time,
-            "base_fee": slot.base_fee,
-            "compute_units_available": slot.compute_units_available,
-            "compute_units_used": slot.compute_units_used
-        });
+) -> Result<Response, AppError> {
+    let slot = context
+        .state
+        .slots
+        .shard(slot_number)
+        .call(move |shard| shard.get(slot_number).cloned())
+        .await?;
 
-        (
-            StatusCode::OK,
-            Json(ApiResponse::success("Slot found.".into(), data)),
-        )
-            .into_response()
-    } else {
-        (
+    let Some(slot) = slot else {
+        return Ok((
             StatusCode::NOT_FOUND,
             Json(ApiResponse::failure("Slot not found", 404)),
         )
-            .into_response()
+            .into_response());
+    };
+
+    let mut transactions = Vec::with_capacity(slot.execution_order.len());
+    for transaction_id in &slot.execution_order {
+        if let Some(transaction) = context.state.get_transaction_by_id(transaction_id).await {
+            transactions.push(SlotTransaction::from(&transaction));
+        }
     }
+    let fees_collected = context
+        .state
+        .escrow_records_for_slot(slot_number)
+        .await?
+        .iter()
+        .filter(|record| record.status == EscrowStatus::Released)
+        .fold(Lamports::ZERO, |total, record| total + record.amount);
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Slot found.".into(),
+            SlotDetail {
+                slot: SlotView::detail(&slot),
+                reservation: slot.reservation.clone(),
+                transactions,
+                fees_collected,
+            },
+        )),
+    )
+        .into_response())
 }
@@ -1,15 +1,32 @@
+use crate::{
+    app::{
+        api::AppContext,
+        room::{CurrentRoom, RoomScoped},
+    },
+    error::AppError,
+    models::{
+        metrics::Leaderboard,
+        player::GameMode,
+        player::PlayerStats,
+        requests::{ExportFormat, ExportQuery, FaucetRequest, MAX_FAUCET_AMOUNT, TransactionQuery},
+        responses::{
+            ApiResponse, BalanceAdjusted, ErrorResponse, MarketplaceSettings, MarketplaceStatus,
+            PlayerDataDeleted,
+        },
+    },
+    services::{
+        export::{PlayerDataExport, export_player_data, export_stats},
+        leaderboard::{GlobalLeaderboard, global_leaderboard},
+        player_analytics::{PlayerAnalytics, player_analytics},
+        session::{delete_player_data, expired_session_cookie, get_session_from_cookie},
+    },
+    utils::validation::ValidatedJson,
+};
 use axum::{
     Json,
     extract::{Query, State},
-    http::{HeaderMap, StatusCode},
-    response::IntoResponse,
-};
-use serde_json::json;
-
-use crate::{
-    app::api::AppContext,
-    models::{requests::TransactionQuery, responses::ApiResponse},
-    services::session::get_session_from_cookie,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
 };
 
 #[utoipa::path(
@@ -20,59 +37,185 @@ use crate::{
         ("session_id" = String, Query, description = "Optional session id in query")
     ),
     responses(
-        (status = 200, description = "Player stats retrieved", body = ApiResponse),
-        (status = 401, description = "Unauthorized", body = ApiResponse)
+        (status = 200, description = "Player stats retrieved", body = ApiResponse<PlayerStats>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
     )
 )]
 pub async fn get_player_stats(
-    State(context): State<AppContext>,
+    RoomScoped(context): RoomScoped,
     headers: HeaderMap,
     Query(query): Query<TransactionQuery>,
 ) -> impl IntoResponse {
-    if let Ok(session_id) =
-        get_session_from_cookie(&headers, query.session_id.as_ref(), &context.state.sessions).await
+    match get_session_from_cookie(&headers, query.session_id.as_ref(), &context.state.sessions)
+        .await
     {
-        let mut game = context.state.game.write().await;
-        let stats = game.get_or_create_player(session_id.clone());
-
-        (
-            StatusCode::OK,
-            Json(ApiResponse::success(
-                "Player stats fetched.".into(),
-                json!(stats),
-            )),
-        )
-            .into_response()
-    } else {
-        (
-            StatusCode::UNAUTHORIZED,
-            Json(ApiResponse::failure(
-                "Session ID is missing or invalid",
-                401,
-            )),
-        )
-            .into_response()
+        Ok(session_id) => match context.state.player_stats(session_id).await {
+            Ok(stats) => (
+                StatusCode::OK,
+                Json(ApiResponse::success("Player stats fetched.".into(), stats)),
+            )
+                .into_response(),
+            Err(e) => e.into_response(),
+        },
+        Err(e) => e.into_response(),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/game/stats/export",
+    tag = "Game",
+    params(
+        ("session_id" = Option<String>, Query, description = "Optional session id in query"),
+        ("format" = Option<ExportFormat>, Query, description = "`csv` (default) or `json`")
+    ),
+    responses(
+        (status = 200, description = "The player's stats as a file download", content_type = "text/csv"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    )
+)]
+pub async fn export_player_stats(
+    RoomScoped(context): RoomScoped,
+    headers: HeaderMap,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    let session_id =
+        get_session_from_cookie(&headers, query.session_id.as_ref(), &context.state.sessions)
+            .await?;
+
+    let stats = context.state.player_stats(session_id).await?;
+
+    Ok(export_stats(&stats, query.format.unwrap_or_default()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/game/export",
+    tag = "Game",
+    params(
+        ("session_id" = Option<String>, Query, description = "Optional session id in query")
+    ),
+    responses(
+        (status = 200, description = "The player's stats, achievements, bids and transactions as one JSON download", body = PlayerDataExport),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    )
+)]
+pub async fn export_player(
+    RoomScoped(context): RoomScoped,
+    headers: HeaderMap,
+    Query(query): Query<TransactionQuery>,
+) -> Result<Response, AppError> {
+    let session_id =
+        get_session_from_cookie(&headers, query.session_id.as_ref(), &context.state.sessions)
+            .await?;
+
+    export_player_data(&context.state, session_id).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/game/me",
+    tag = "Game",
+    responses(
+        (status = 200, description = "Player deleted; open bids refunded, transactions anonymized and the session ended", body = ApiResponse<PlayerDataDeleted>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    )
+)]
+pub async fn delete_player(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+
+    let cookie = expired_session_cookie(context.state.sessions.cookie())?;
+    let deleted = delete_player_data(&context.rooms, &session_id).await?;
+
+    tracing::info!(
+        "Session {} deleted its player data",
+        session_id.chars().take(8).collect::<String>()
+    );
+
+    Ok((
+        StatusCode::OK,
+        [(header::SET_COOKIE, cookie)],
+        Json(ApiResponse::success("Player data deleted.".into(), deleted)),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/game/analytics",
+    tag = "Game",
+    params(
+        ("session_id" = Option<String>, Query, description = "Optional session id in query")
+    ),
+    responses(
+        (status = 200, description = "Spend, win rates and returns from the player's bid history", body = ApiResponse<PlayerAnalytics>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    )
+)]
+pub async fn get_player_analytics(
+    RoomScoped(context): RoomScoped,
+    headers: HeaderMap,
+    Query(query): Query<TransactionQuery>,
+) -> Result<Response, AppError> {
+    let session_id =
+        get_session_from_cookie(&headers, query.session_id.as_ref(), &context.state.sessions)
+            .await?;
+
+    let analytics = player_analytics(&context.state, &session_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Player analytics fetched.".into(),
+            analytics,
+        )),
+    )
+        .into_response())
+}
+
 #[utoipa::path(
     get,
     path = "/game/leaderboard",
     tag = "Game",
     responses(
-        (status = 200, description = "Leaderboard retrieved", body = ApiResponse)
+        (status = 200, description = "Leaderboard retrieved", body = ApiResponse<Leaderboard>)
     )
 )]
-pub async fn get_leaderboard(State(context): State<AppContext>) -> impl IntoResponse {
-    let leaderboard = context.state.get_leaderboard().await;
-    (
+pub async fn get_leaderboard(RoomScoped(context): RoomScoped) -> Result<Response, AppError> {
+    let leaderboard = context.state.get_leaderboard().await?;
+    Ok((
         StatusCode::OK,
         Json(ApiResponse::success(
             "Leaderboard fetched successfully".into(),
-            json!(leaderboard),
+            leaderboard,
         )),
     )
-        .into_response()
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/game/leaderboard/global",
+    tag = "Game",
+    responses(
+        (status = 200, description = "Players across all rooms, ranked by wins relative to their room", body = ApiResponse<GlobalLeaderboard>)
+    )
+)]
+pub async fn get_global_leaderboard(
+    State(context): State<AppContext>,
+) -> Result<Response, AppError> {
+    let leaderboard = global_leaderboard(&context.rooms).await?;
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Global leaderboard fetched successfully".into(),
+            leaderboard,
+        )),
+    )
+        .into_response())
 }
 
 #[utoipa::path(
@@ -80,24 +223,113 @@ pub async fn get_leaderboard(State(context): State<AppContext>) -> impl IntoResp
     path = "/marketplace/status",
     tag = "Marketplace",
     responses(
-        (status = 200, description = "Marketplace status retrieved", body = ApiResponse)
+        (status = 200, description = "Marketplace status retrieved", body = ApiResponse<MarketplaceStatus>)
     )
 )]
-pub async fn marketplace_status(State(context): State<AppContext>) -> impl IntoResponse {
-    let stats = context.state.get_marketplace_stats().await;
-    let current_slot = context.state.get_current_slot().await;
+pub async fn marketplace_status(RoomScoped(context): RoomScoped) -> Result<Response, AppError> {
+    let stats = context.state.get_marketplace_stats().await?;
+    let (current_slot, slot_time_ms, base_fee) = context
+        .state
+        .marketplace
+        .call(|marketplace| {
+            (
+                marketplace.current_slot,
+                marketplace.slot_duration_ms,
+                marketplace.base_fee,
+            )
+        })
+        .await?;
 
-    (
+    Ok((
         StatusCode::OK,
         Json(ApiResponse::success(
             "Marketplace status fetched successfully".into(),
-            json!({
-                "current_slot": current_slot,
-                "stats": stats,
-                "slot_time_ms": context.config.marketplace.slot_duration_ms,
-                "base_fee_sol": context.config.marketplace.base_fee_sol
-            }),
+            MarketplaceStatus {
+                current_slot,
+                stats,
+                slot_time_ms,
+                base_fee_sol: base_fee.as_sol(),
+            },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/marketplace/config",
+    tag = "Marketplace",
+    responses(
+        (status = 200, description = "Effective marketplace limits and settings", body = ApiResponse<MarketplaceSettings>)
+    )
+)]
+pub async fn marketplace_config(
+    State(context): State<AppContext>,
+    CurrentRoom(room): CurrentRoom,
+) -> impl IntoResponse {
+    let config = context.config.current();
+    let mut settings = MarketplaceSettings::from(&*config);
+    let timing = room.timing(&config.marketplace);
+    settings.slots.slot_duration_ms = timing.slot_duration_ms;
+    settings.slots.advance_slot_interval_ms = timing.advance_slot_interval_ms;
+    settings.slots.base_fee_sol = timing.base_fee_sol;
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Marketplace configuration fetched successfully".into(),
+            settings,
         )),
     )
         .into_response()
 }
+
+#[utoipa::path(
+    post,
+    path = "/game/faucet",
+    tag = "Game",
+    request_body = FaucetRequest,
+    responses(
+        (status = 200, description = "Balance topped up", body = ApiResponse<BalanceAdjusted>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Not a sandbox room, a private room the session was not invited to, or a spectator session", body = ErrorResponse),
+        (status = 422, description = "Invalid amount", body = ErrorResponse)
+    )
+)]
+pub async fn request_faucet(
+    State(context): State<AppContext>,
+    CurrentRoom(room): CurrentRoom,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<FaucetRequest>,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+    if context.state.sessions.is_spectator(&session_id).await {
+        return Err(AppError::SpectatorReadOnly);
+    }
+    if !room.admits(&session_id) {
+        return Err(AppError::NotRoomMember {
+            room_id: room.id.clone(),
+        });
+    }
+    if room.state.game_mode().await? != GameMode::Sandbox {
+        return Err(AppError::SandboxOnly);
+    }
+
+    let amount = req.amount.unwrap_or(MAX_FAUCET_AMOUNT);
+    let balance = room
+        .state
+        .adjust_player_balance(&session_id, amount, true)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            format!("Added {} SOL.", amount),
+            BalanceAdjusted {
+                session_id,
+                balance,
+            },
+        )),
+    )
+        .into_response())
+}
@@ -1,21 +1,39 @@
+use std::net::SocketAddr;
+
 use axum::{
     Json,
-    extract::{Path, Query, State},
+    extract::{ConnectInfo, Path, Query, State},
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
-use serde_json::json;
+use chrono::Duration;
 
 use crate::{
-    MAX_COMPUTE_UNITS_PER_SLOT,
-    app::api::AppContext,
+    app::{
+        api::AppContext,
+        room::{CurrentRoom, RoomScoped},
+    },
+    error::AppError,
+    managers::transaction::TransactionFilter,
     models::{
-        requests::{AotBidRequest, JitBidRequest, TransactionQuery},
-        responses::ApiResponse,
-        slot::SlotState,
-        transaction::Transaction,
+        requests::{
+            AotBidRequest, BundleBidRequest, ExportFormat, ExportQuery, JitBidRequest,
+            RetryBidRequest, SimulateBidRequest, TransactionQuery,
+        },
+        responses::{
+            ApiResponse, BidCancellation, BidReceipt, BidRejection, BidSimulation, BundleReceipt,
+            ErrorResponse, Pagination, TransactionDetail, TransactionPage, TransactionScope,
+        },
+        transaction::{Transaction, TransactionStatusKind},
+        types::TransactionType,
     },
-    services::session::get_session_from_cookie,
+    services::{
+        bid,
+        export::export_transactions,
+        idempotency::{request_fingerprint, run_idempotent},
+        session::get_session_from_cookie,
+    },
+    utils::validation::{FieldError, Validate, ValidatedJson},
 };
 
 #[utoipa::path(
@@ -24,153 +42,74 @@ use crate::{
     tag = "Transactions",
     request_body = JitBidRequest,
     responses(
-        (status = 200, description = "JIT transaction submitted", body = ApiResponse),
-        (status = 402, description = "Insufficient balance", body = ApiResponse),
-        (status = 400, description = "Bad request", body = ApiResponse),
-        (status = 500, description = "Internal server error", body = ApiResponse)
+        (status = 201, description = "JIT transaction submitted", body = ApiResponse<BidReceipt>),
+        (status = 402, description = "Insufficient balance", body = ErrorResponse),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 403, description = "Player is banned, not a member of the private room or not allowlisted for the slot", body = ErrorResponse),
+        (status = 409, description = "The room's match has not started yet", body = ErrorResponse),
+        (status = 422, description = "Invalid bid payload", body = ErrorResponse),
+        (status = 429, description = "Bidding paused for bid spam", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
+#[tracing::instrument(skip_all, fields(slot_number = tracing::field::Empty))]
 pub async fn submit_jit_transaction(
     State(context): State<AppContext>,
+    CurrentRoom(room): CurrentRoom,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Json(req): Json<JitBidRequest>,
-) -> impl IntoResponse {
+    ValidatedJson(req): ValidatedJson<JitBidRequest>,
+) -> Result<Response, AppError> {
     let session_id =
-        match get_session_from_cookie(&headers, req.session_id.as_ref(), &context.state.sessions)
-            .await
-        {
-            Ok(sid) => sid,
-            Err(_) => {
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(ApiResponse::failure(
-                        "Session ID is missing or invalid",
-                        401,
-                    )),
-                )
-                    .into_response();
-            }
-        };
-
-    let next_available_slot = {
-        let marketplace = context.state.marketplace.read().await;
-        marketplace.current_slot + 1
-    };
-
-    // Lock and update the game state for the current player
-    {
-        let mut game = context.state.game.write().await;
-        let stats = game.get_or_create_player(session_id.clone());
-
-        // Ensure the player has sufficient balance
-        if !stats.is_balance_sufficient(req.bid_amount) {
-            return (
-                StatusCode::PAYMENT_REQUIRED,
-                Json(ApiResponse::failure("Insufficient balance", 400)),
-            )
-                .into_response();
-        }
-
-        // Deduct balance or return an error
-        if let Err(_) = stats.deduct_balance(req.bid_amount) {
-            return (
-                StatusCode::PAYMENT_REQUIRED,
-                Json(ApiResponse::failure("Payment failed", 400)),
-            )
-                .into_response();
-        } else {
-            stats.track_bid(next_available_slot);
-        }
-    }
-
-    // Reject if compute units exceed the max per slot
-    if req.compute_units > MAX_COMPUTE_UNITS_PER_SLOT {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ApiResponse::failure(
-                &format!(
-                    "Compute units exceed maximum per slot: {}",
-                    MAX_COMPUTE_UNITS_PER_SLOT
-                ),
-                400,
-            )),
-        )
-            .into_response();
-    }
-
-    // Start JIT auction if it doesn't already exist
-    if !context
-        .state
-        .auctions
-        .read()
-        .await
-        .jit_auctions
-        .contains_key(&next_available_slot)
-    {
-        if let Err(_) = context
-            .state
-            .start_jit_auction(next_available_slot, context.config.marketplace.base_fee_sol)
-            .await
-        {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiResponse::failure("JIT auction failed to start", 400)),
-            )
-                .into_response();
-        }
-    }
-
-    // Submit the JIT bid for this slot
-    if let Err(_) = context
-        .state
-        .submit_jit_bid(next_available_slot, session_id.clone(), req.bid_amount)
-        .await
-    {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ApiResponse::failure("JIT Bid submission failed", 400)),
-        )
-            .into_response();
-    }
-
-    // Update marketplace slot state with the bid
-    {
-        let mut marketplace = context.state.marketplace.write().await;
-        if let Some(slot) = marketplace.slots.get_mut(&next_available_slot) {
-            slot.state = SlotState::JitAuction {
-                current_bid: req.bid_amount,
-                bidder: session_id.clone(),
-            };
-        }
-    }
-
-    // Create and store the transaction
-    let transaction = Transaction::jit(
-        session_id.clone(),
-        req.compute_units,
-        req.bid_amount,
-        req.data,
-    );
+        get_session_from_cookie(&headers, req.session_id.as_ref(), &context.state.sessions).await?;
+    room.check_bid_access(&session_id)?;
+    let context = context.scoped_to(&room);
+    let ttl = Duration::seconds(context.config.current().session.idempotency_key_ttl_secs as i64);
+
+    run_idempotent(
+        &context.state,
+        &session_id,
+        &headers,
+        ttl,
+        request_fingerprint(&req)?,
+        place_jit_bid(&context, addr, session_id.clone(), req),
+    )
+    .await
+}
 
-    let transaction_id = transaction.id.clone();
+async fn place_jit_bid(
+    context: &AppContext,
+    addr: SocketAddr,
+    session_id: String,
+    req: JitBidRequest,
+) -> Result<Response, AppError> {
     context
         .state
-        .add_transaction(session_id.clone(), transaction)
+        .sessions
+        .record_client_ip(&session_id, addr.ip())
         .await;
+    context
+        .state
+        .check_bid_allowed(&session_id, Some(addr.ip()))
+        .await?;
+
+    let bid_amount = req.bid_amount;
+    // Escrow, auction entry and storage succeed or roll back together
+    let transaction =
+        bid::place_jit_bid(&context.state, &context.config.current(), session_id, req).await?;
 
-    (
+    Ok((
         StatusCode::CREATED,
         Json(ApiResponse::success(
             "JIT bid submitted for next available slot".into(),
-            json!({
-                "transaction_id": transaction_id,
-                "slot_number": next_available_slot,
-                "bid_amount": req.bid_amount,
-                "status": "auction_pending",
-            }),
+            BidReceipt::pending(
+                transaction.id.clone(),
+                transaction.target_slot(),
+                bid_amount,
+            ),
         )),
     )
-        .into_response()
+        .into_response())
 }
 
 #[utoipa::path(
@@ -179,266 +118,333 @@ pub async fn submit_jit_transaction(
     tag = "Transactions",
     request_body = AotBidRequest,
     responses(
-        (status = 200, description = "AOT transaction submitted", body = ApiResponse),
-        (status = 402, description = "Insufficient balance", body = ApiResponse),
-        (status = 400, description = "Bad request", body = ApiResponse),
-        (status = 500, description = "Internal server error", body = ApiResponse)
+        (status = 201, description = "AOT transaction submitted", body = ApiResponse<BidReceipt>),
+        (status = 402, description = "Insufficient balance", body = ErrorResponse),
+        (status = 400, description = "Slot already passed or beyond the AOT lookahead", body = ErrorResponse),
+        (status = 403, description = "Player is banned, not a member of the private room or not allowlisted for the slot", body = ErrorResponse),
+        (status = 409, description = "The room's match has not started yet", body = ErrorResponse),
+        (status = 422, description = "Invalid bid payload", body = ErrorResponse),
+        (status = 429, description = "Bidding paused for bid spam", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
+#[tracing::instrument(skip_all, fields(slot_number = req.slot_number))]
 pub async fn submit_aot_transaction(
     State(context): State<AppContext>,
+    CurrentRoom(room): CurrentRoom,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Json(req): Json<AotBidRequest>,
-) -> impl IntoResponse {
+    ValidatedJson(req): ValidatedJson<AotBidRequest>,
+) -> Result<Response, AppError> {
     let session_id =
-        match get_session_from_cookie(&headers, req.session_id.as_ref(), &context.state.sessions)
-            .await
-        {
-            Ok(sid) => sid,
-            Err(_) => {
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(ApiResponse::failure(
-                        "Session ID is missing or invalid",
-                        401,
-                    )),
-                )
-                    .into_response();
-            }
-        };
-
-    // Validate the requested slot number
-    let current_slot = context.state.get_current_slot().await;
-    if req.slot_number < current_slot {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ApiResponse::failure("Invalid slot number", 400)),
-        )
-            .into_response();
-    }
-
-    // Lock and update the game state for the current player
-    {
-        let mut game = context.state.game.write().await;
-        let stats = game.get_or_create_player(session_id.clone());
-
-        // Ensure the player has sufficient balance
-        if !stats.is_balance_sufficient(req.bid_amount) {
-            return (
-                StatusCode::PAYMENT_REQUIRED,
-                Json(ApiResponse::failure("Insufficient balance", 400)),
-            )
-                .into_response();
-        }
-
-        // Deduct balance or return an error
-        if let Err(_) = stats.deduct_balance(req.bid_amount) {
-            return (
-                StatusCode::PAYMENT_REQUIRED,
-                Json(ApiResponse::failure("Payment failed", 400)),
-            )
-                .into_response();
-        } else {
-            stats.track_bid(req.slot_number);
-        }
-    }
-
-    // Reject if compute units exceed the max per slot
-    if req.compute_units > MAX_COMPUTE_UNITS_PER_SLOT {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ApiResponse::failure(
-                &format!(
-                    "Compute units exceed maximum per slot: {}",
-                    MAX_COMPUTE_UNITS_PER_SLOT
-                ),
-                400,
-            )),
-        )
-            .into_response();
-    }
+        get_session_from_cookie(&headers, req.session_id.as_ref(), &context.state.sessions).await?;
+    room.check_bid_access(&session_id)?;
+    let context = context.scoped_to(&room);
+    let ttl = Duration::seconds(context.config.current().session.idempotency_key_ttl_secs as i64);
+
+    run_idempotent(
+        &context.state,
+        &session_id,
+        &headers,
+        ttl,
+        request_fingerprint(&req)?,
+        place_aot_bid(&context, addr, session_id.clone(), req),
+    )
+    .await
+}
 
-    // Start AOT auction for the requested slot if it doesn't already exist
-    if !context
+async fn place_aot_bid(
+    context: &AppContext,
+    addr: SocketAddr,
+    session_id: String,
+    req: AotBidRequest,
+) -> Result<Response, AppError> {
+    context
         .state
-        .auctions
-        .read()
-        .await
-        .aot_auctions
-        .contains_key(&req.slot_number)
-    {
-        if let Err(_) = context
-            .state
-            .start_aot_auction(
-                req.slot_number,
-                context.config.marketplace.base_fee_sol,
-                context.config.auction.aot_default_duration_sec,
-            )
-            .await
-        {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiResponse::failure("AOT auction failed to start", 400)),
-            )
-                .into_response();
-        }
-    }
-
-    // Submit the AOT bid for this slot
-    if let Err(_) = context
+        .sessions
+        .record_client_ip(&session_id, addr.ip())
+        .await;
+    context
         .state
-        .submit_aot_bid(req.slot_number, session_id.clone(), req.bid_amount)
-        .await
-    {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ApiResponse::failure("AOT Bid submission failed", 400)),
-        )
-            .into_response();
-    }
+        .check_bid_allowed(&session_id, Some(addr.ip()))
+        .await?;
 
-    // Update marketplace slot state with the bid
-    {
-        let mut marketplace = context.state.marketplace.write().await;
-        if let Some(slot) = marketplace.slots.get_mut(&req.slot_number) {
-            let auctions = context.state.auctions.read().await;
-            if let Some(auction) = auctions.aot_auctions.get(&req.slot_number) {
-                let ends_at = auction.ends_at;
-                slot.state = SlotState::AotAuction {
-                    highest_bid: req.bid_amount,
-                    highest_bidder: session_id.clone(),
-                    bids: vec![(session_id.clone(), req.bid_amount)],
-                    ends_at,
-                };
-            }
-        }
-    }
+    let bid_amount = req.bid_amount;
+    let transaction =
+        bid::place_aot_bid(&context.state, &context.config.current(), session_id, req).await?;
 
-    // Create and store the transaction
-    let transaction = Transaction::aot(
-        session_id.clone(),
-        req.compute_units,
-        req.bid_amount,
-        req.slot_number,
-        req.data,
-    );
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(
+            "AOT bid submitted for future slot".into(),
+            BidReceipt::pending(
+                transaction.id.clone(),
+                transaction.target_slot(),
+                bid_amount,
+            ),
+        )),
+    )
+        .into_response())
+}
 
-    let transaction_id = transaction.id.clone();
+#[utoipa::path(
+    post,
+    path = "/transactions/bundle",
+    tag = "Transactions",
+    request_body = BundleBidRequest,
+    responses(
+        (status = 201, description = "Every bid in the bundle funded and entered", body = ApiResponse<BundleReceipt>),
+        (status = 402, description = "Insufficient balance for the bundle; no leg was charged", body = ErrorResponse),
+        (status = 400, description = "A leg's slot already passed or is beyond the lookahead; no leg was charged", body = ErrorResponse),
+        (status = 403, description = "Player is banned, not a member of the private room or not allowlisted for a leg's slot", body = ErrorResponse),
+        (status = 409, description = "A leg was outbid or its auction closed; no leg was charged", body = ErrorResponse),
+        (status = 422, description = "Invalid bundle payload", body = ErrorResponse),
+        (status = 429, description = "Bidding paused for bid spam", body = ErrorResponse),
+        (status = 500, description = "Internal server error", body = ErrorResponse)
+    )
+)]
+#[tracing::instrument(skip_all, fields(legs = req.legs.len()))]
+pub async fn submit_bundle_transaction(
+    State(context): State<AppContext>,
+    CurrentRoom(room): CurrentRoom,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<BundleBidRequest>,
+) -> Result<Response, AppError> {
+    let session_id =
+        get_session_from_cookie(&headers, req.session_id.as_ref(), &context.state.sessions).await?;
+    room.check_bid_access(&session_id)?;
+    let context = context.scoped_to(&room);
+    let ttl = Duration::seconds(context.config.current().session.idempotency_key_ttl_secs as i64);
+
+    run_idempotent(
+        &context.state,
+        &session_id,
+        &headers,
+        ttl,
+        request_fingerprint(&req)?,
+        place_bundle(&context, addr, session_id.clone(), req),
+    )
+    .await
+}
+
+async fn place_bundle(
+    context: &AppContext,
+    addr: SocketAddr,
+    session_id: String,
+    req: BundleBidRequest,
+) -> Result<Response, AppError> {
     context
         .state
-        .add_transaction(session_id.clone(), transaction)
+        .sessions
+        .record_client_ip(&session_id, addr.ip())
         .await;
+    context
+        .state
+        .check_bid_allowed(&session_id, Some(addr.ip()))
+        .await?;
+
+    let transactions = bid::place_bundle(
+        &context.state,
+        &context.config.current(),
+        session_id,
+        req.legs,
+        req.nonce,
+    )
+    .await?;
+
+    let legs: Vec<BidReceipt> = transactions
+        .iter()
+        .map(|transaction| {
+            BidReceipt::pending(
+                transaction.id.clone(),
+                transaction.target_slot(),
+                transaction.priority_fee,
+            )
+        })
+        .collect();
+    let total_bid = legs.iter().map(|leg| leg.bid_amount).sum();
 
-    (
+    Ok((
         StatusCode::CREATED,
         Json(ApiResponse::success(
-            "AOT bid submitted for future slot".into(),
-            json!({
-                "transaction_id": transaction_id,
-                "slot_number": req.slot_number,
-                "bid_amount": req.bid_amount,
-                "status": "auction_pending",
-            }),
+            format!("Bundle of {} bids submitted", legs.len()),
+            BundleReceipt { legs, total_bid },
         )),
     )
-        .into_response()
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/transactions/simulate",
+    tag = "Transactions",
+    request_body = SimulateBidRequest,
+    responses(
+        (status = 200, description = "Whether the bid would be accepted, why not, and the auction it would enter; nothing is placed or charged", body = ApiResponse<BidSimulation>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 422, description = "Invalid bid payload", body = ErrorResponse),
+    )
+)]
+pub async fn simulate_transaction(
+    State(context): State<AppContext>,
+    CurrentRoom(room): CurrentRoom,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ValidatedJson(req): ValidatedJson<SimulateBidRequest>,
+) -> Result<Response, AppError> {
+    let session_id =
+        get_session_from_cookie(&headers, req.session_id.as_ref(), &context.state.sessions).await?;
+    let access = room.check_bid_access(&session_id);
+    let context = context.scoped_to(&room);
+    let allowed = context
+        .state
+        .check_bid_allowed(&session_id, Some(addr.ip()))
+        .await;
+
+    let mut simulation =
+        bid::simulate_bid(&context.state, &context.config.current(), &session_id, &req).await?;
+    let denied: Vec<BidRejection> = [access, allowed]
+        .iter()
+        .filter_map(|result| result.as_ref().err())
+        .map(BidRejection::from)
+        .collect();
+    if !denied.is_empty() {
+        simulation.accepted = false;
+        simulation.rejections.splice(0..0, denied);
+    }
+
+    let message = if simulation.accepted {
+        "Bid would be accepted"
+    } else {
+        "Bid would be rejected"
+    };
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(message.into(), simulation)),
+    )
+        .into_response())
 }
 
 #[utoipa::path(
     get,
     path = "/transactions",
     tag = "Transactions",
+    params(
+        ("page" = Option<u32>, Query, description = "Page number, starting at 1"),
+        ("limit" = Option<u32>, Query, description = "Page size, 1 to 100"),
+        ("show_all" = Option<bool>, Query, description = "List every session's transactions, newest first"),
+        ("status" = Option<TransactionStatusKind>, Query, description = "Only transactions with this status; `won` is accepted for `auction_won`"),
+        ("type" = Option<TransactionType>, Query, description = "Only `jit` or `aot` transactions"),
+        ("slot" = Option<u64>, Query, description = "Only transactions targeting this slot"),
+        ("slot_min" = Option<u64>, Query, description = "Only transactions targeting this slot or later"),
+        ("slot_max" = Option<u64>, Query, description = "Only transactions targeting this slot or earlier"),
+        ("tag" = Option<String>, Query, description = "Only transactions carrying this tag"),
+        ("memo" = Option<String>, Query, description = "Only transactions whose memo contains this text, ignoring case")
+    ),
     responses(
-        (status = 200, description = "List of transactions", body = ApiResponse),
-        (status = 401, description = "Unauthorized", body = ApiResponse),
+        (status = 200, description = "List of transactions", body = ApiResponse<TransactionPage>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 422, description = "slot_min is greater than slot_max", body = ErrorResponse),
     )
 )]
 pub async fn list_transactions(
-    State(context): State<AppContext>,
+    RoomScoped(context): RoomScoped,
     headers: HeaderMap,
     Query(query): Query<TransactionQuery>,
-) -> impl IntoResponse {
+) -> Result<Response, AppError> {
     let session_id =
-        match get_session_from_cookie(&headers, query.session_id.as_ref(), &context.state.sessions)
-            .await
-        {
-            Ok(sid) => sid,
-            Err(_) => {
-                return (
-                    StatusCode::UNAUTHORIZED,
-                    Json(ApiResponse::failure(
-                        "Session ID is missing or invalid",
-                        401,
-                    )),
-                )
-                    .into_response();
-            }
-        };
+        get_session_from_cookie(&headers, query.session_id.as_ref(), &context.state.sessions)
+            .await?;
 
     let page = query.page.unwrap_or(1).max(1);
-    let limit = query.limit.unwrap_or(20).min(100).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
     let offset = (page - 1) * limit;
 
+    // An exact slot takes precedence over a range
+    let (slot_min, slot_max) = match query.slot {
+        Some(slot) => (Some(slot), Some(slot)),
+        None => (query.slot_min, query.slot_max),
+    };
+    if let (Some(min), Some(max)) = (slot_min, slot_max) {
+        if min > max {
+            return Err(AppError::Validation(vec![FieldError::new(
+                "slot_min",
+                "must not be greater than slot_max",
+            )]));
+        }
+    }
+
+    let filter = TransactionFilter {
+        status: query.status,
+        transaction_type: query.transaction_type,
+        slot_min,
+        slot_max,
+        tag: query.tag,
+        memo: query.memo.filter(|memo| !memo.is_empty()),
+    };
+
     if query.show_all.unwrap_or(false) {
-        let all_transactions = context
+        let (all_transactions, total_count) = context
             .state
-            .get_all_transactions_paginated(offset, limit)
+            .get_all_transactions_paginated(filter, offset, limit)
             .await;
-        let total_count = context.state.get_global_transaction_count().await;
-        let total_pages = (total_count + limit - 1) / limit;
 
-        return (
+        return Ok((
             StatusCode::OK,
             Json(ApiResponse::success(
                 "Global transactions fetched successfully".into(),
-                json!({
-                    "transactions": all_transactions,
-                    "pagination": {
-                        "current_page": page,
-                        "total_pages": total_pages,
-                        "page_size": limit,
-                        "total_count": total_count,
-                        "has_next": page < total_pages,
-                        "has_prev": page > 1
-                    },
-                    "session_id": query.session_id,
-                    "showing": "all"
-                }),
+                TransactionPage {
+                    session_id: query.session_id,
+                    transactions: all_transactions,
+                    pagination: Pagination::new(page, limit, total_count),
+                    showing: TransactionScope::All,
+                },
             )),
         )
-            .into_response();
+            .into_response());
     }
 
-    let session_transactions = context
-        .state
-        .get_session_transactions_paginated(&session_id, offset, limit)
-        .await;
-    let total_count = context
-        .state
-        .get_session_transaction_count(&session_id)
-        .await;
-    let total_pages = (total_count + limit - 1) / limit;
+    let (session_transactions, total_count) = if filter.matches_all() {
+        let total_count = context
+            .state
+            .get_session_transaction_count(&session_id)
+            .await;
+        let page = context
+            .state
+            .get_session_transactions_paginated(&session_id, offset, limit)
+            .await;
+        (page, total_count)
+    } else {
+        let matching: Vec<Transaction> = context
+            .state
+            .get_session_transactions(&session_id)
+            .await
+            .into_iter()
+            .filter(|transaction| filter.matches(transaction))
+            .collect();
+        let total_count = matching.len() as u32;
+        let page = matching
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect();
+        (page, total_count)
+    };
 
-    (
+    Ok((
         StatusCode::OK,
         Json(ApiResponse::success(
             "Transactions for current session fetched successfully".into(),
-            json!({
-                "session_id": session_id,
-                "transactions": session_transactions,
-                "pagination": {
-                    "current_page": page,
-                    "total_pages": total_pages,
-                    "page_size": limit,
-                    "total_count": total_count,
-                    "has_next": page < total_pages,
-                    "has_prev": page > 1
-                },
-                "showing": "session_only"
-            }),
+            TransactionPage {
+                session_id: Some(session_id),
+                transactions: session_transactions,
+                pagination: Pagination::new(page, limit, total_count),
+                showing: TransactionScope::SessionOnly,
+            },
         )),
     )
-        .into_response()
+        .into_response())
 }
 
 #[utoipa::path(
@@ -449,30 +455,195 @@ pub async fn list_transactions(
         ("transaction_id" = u64, Path, description = "ID of transaction to fetch")
     ),
     responses(
-        (status = 200, description = "Transaction details", body = ApiResponse),
-        (status = 404, description = "Transaction not found", body = ApiResponse)
+        (status = 200, description = "Transaction details", body = ApiResponse<TransactionDetail>),
+        (status = 404, description = "Transaction not found", body = ErrorResponse)
     )
 )]
 pub async fn get_transaction(
+    RoomScoped(context): RoomScoped,
+    Path(transaction_id): Path<String>,
+) -> Result<Response, AppError> {
+    let transaction = context
+        .state
+        .get_transaction_by_id(&transaction_id)
+        .await
+        .ok_or(AppError::TransactionNotFound)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Transaction fetched successfully".into(),
+            TransactionDetail { transaction },
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/transactions/{transaction_id}/cancel",
+    tag = "Transactions",
+    params(
+        ("transaction_id" = String, Path, description = "ID of the pending bid to withdraw")
+    ),
+    responses(
+        (status = 200, description = "Bid withdrawn from its auction and its escrow refunded", body = ApiResponse<BidCancellation>),
+        (status = 400, description = "The AOT auction has ended", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "No such transaction sent by this session", body = ErrorResponse),
+        (status = 409, description = "The bid is no longer pending, its auction resolved or it leads the JIT auction", body = ErrorResponse)
+    )
+)]
+pub async fn cancel_transaction(
+    RoomScoped(context): RoomScoped,
+    headers: HeaderMap,
+    Path(transaction_id): Path<String>,
+) -> Result<Response, AppError> {
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+
+    let cancellation = bid::cancel_bid(&context.state, &session_id, &transaction_id).await?;
+
+    tracing::info!(
+        "Transaction {} cancelled by {}, refunded {} SOL",
+        transaction_id.chars().take(8).collect::<String>(),
+        session_id.chars().take(8).collect::<String>(),
+        cancellation.refunded
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Bid cancelled and refunded.".into(),
+            cancellation,
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/transactions/{transaction_id}/retry",
+    tag = "Transactions",
+    params(
+        ("transaction_id" = String, Path, description = "ID of the failed or expired bid to retry")
+    ),
+    request_body(content = Option<RetryBidRequest>, description = "Optional raise over the original bid"),
+    responses(
+        (status = 201, description = "New bid placed with the original's compute units and data", body = ApiResponse<BidReceipt>),
+        (status = 400, description = "The new bid was rejected by its auction", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 402, description = "Insufficient balance", body = ErrorResponse),
+        (status = 403, description = "Player is banned, not a member of the private room or not allowlisted for the slot", body = ErrorResponse),
+        (status = 404, description = "No such transaction sent by this session", body = ErrorResponse),
+        (status = 409, description = "The transaction didn't fail or no slot is open for it", body = ErrorResponse),
+        (status = 422, description = "Invalid bump or the bumped bid exceeds the bid limits", body = ErrorResponse),
+        (status = 429, description = "Bidding paused for bid spam", body = ErrorResponse)
+    )
+)]
+pub async fn retry_transaction(
     State(context): State<AppContext>,
+    CurrentRoom(room): CurrentRoom,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Path(transaction_id): Path<String>,
-) -> impl IntoResponse {
-    if let Some(transaction) = context.state.get_transaction_by_id(&transaction_id).await {
-        (
-            StatusCode::OK,
-            Json(ApiResponse::success(
-                "Transaction fetched successfully".into(),
-                json!({
-                    "transaction": transaction
-                }),
-            )),
-        )
-            .into_response()
-    } else {
-        (
-            StatusCode::NOT_FOUND,
-            Json(ApiResponse::failure("Transaction not found", 404)),
-        )
-            .into_response()
+    req: Option<Json<RetryBidRequest>>,
+) -> Result<Response, AppError> {
+    let Json(req) = req.unwrap_or_default();
+    let errors = req.validate();
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
     }
+
+    let session_id = get_session_from_cookie(&headers, None, &context.state.sessions).await?;
+    room.check_bid_access(&session_id)?;
+    let context = context.scoped_to(&room);
+    let ttl = Duration::seconds(context.config.current().session.idempotency_key_ttl_secs as i64);
+
+    run_idempotent(
+        &context.state,
+        &session_id,
+        &headers,
+        ttl,
+        request_fingerprint(&(&transaction_id, &req))?,
+        place_retried_bid(
+            &context,
+            addr,
+            session_id.clone(),
+            transaction_id,
+            req.bump_percent.unwrap_or(0.0),
+            req.nonce,
+        ),
+    )
+    .await
+}
+
+async fn place_retried_bid(
+    context: &AppContext,
+    addr: SocketAddr,
+    session_id: String,
+    transaction_id: String,
+    bump_percent: f64,
+    nonce: Option<u64>,
+) -> Result<Response, AppError> {
+    context
+        .state
+        .sessions
+        .record_client_ip(&session_id, addr.ip())
+        .await;
+    context
+        .state
+        .check_bid_allowed(&session_id, Some(addr.ip()))
+        .await?;
+
+    let transaction = bid::retry_bid(
+        &context.state,
+        &context.config.current(),
+        session_id,
+        &transaction_id,
+        bump_percent,
+        nonce,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(
+            format!("Bid retried for slot {}", transaction.target_slot()),
+            BidReceipt::pending(
+                transaction.id.clone(),
+                transaction.target_slot(),
+                transaction.priority_fee,
+            ),
+        )),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/transactions/export",
+    tag = "Transactions",
+    params(
+        ("session_id" = Option<String>, Query, description = "Optional session id in query"),
+        ("format" = Option<ExportFormat>, Query, description = "`csv` (default) or `json`")
+    ),
+    responses(
+        (status = 200, description = "The player's full transaction history as a file download", content_type = "text/csv"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    )
+)]
+pub async fn export_transaction_history(
+    RoomScoped(context): RoomScoped,
+    headers: HeaderMap,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    let session_id =
+        get_session_from_cookie(&headers, query.session_id.as_ref(), &context.state.sessions)
+            .await?;
+
+    Ok(export_transactions(
+        context.state.transactions.clone(),
+        session_id,
+        query.format.unwrap_or_default(),
+    ))
 }
@@ -0,0 +1,156 @@
+use crate::{
+    app::api::AppContext,
+    models::{
+        requests::{WalletChallengeRequest, WalletVerifyRequest},
+        responses::{ApiResponse, ErrorResponse, WalletLinked},
+        wallet::{WalletChallenge, parse_public_key},
+    },
+    services::session::get_session_from_cookie,
+};
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+#[utoipa::path(
+    post,
+    path = "/sessions/wallet/challenge",
+    tag = "Session",
+    request_body = WalletChallengeRequest,
+    responses(
+        (status = 200, description = "Sign-in message for the wallet to sign", body = ApiResponse<WalletChallenge>),
+        (status = 400, description = "Invalid public key", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse)
+    )
+)]
+pub async fn create_wallet_challenge(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Json(req): Json<WalletChallengeRequest>,
+) -> impl IntoResponse {
+    let session_id = match get_session_from_cookie(&headers, None, &context.state.sessions).await {
+        Ok(sid) => sid,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Err(e) = parse_public_key(&req.public_key) {
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::failure(e, 400))).into_response();
+    }
+
+    let challenge = context
+        .state
+        .sessions
+        .create_wallet_challenge(&session_id, req.public_key)
+        .await;
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "Sign the message with your wallet and submit the signature.".to_string(),
+            challenge,
+        )),
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/wallet/verify",
+    tag = "Session",
+    request_body = WalletVerifyRequest,
+    responses(
+        (status = 200, description = "Wallet linked; returns the player's stats", body = ApiResponse<WalletLinked>),
+        (status = 400, description = "No pending challenge or invalid signature", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = ErrorResponse),
+        (status = 403, description = "Wallet is banned", body = ErrorResponse),
+        (status = 409, description = "Wallet cannot be linked right now", body = ErrorResponse)
+    )
+)]
+pub async fn verify_wallet(
+    State(context): State<AppContext>,
+    headers: HeaderMap,
+    Json(req): Json<WalletVerifyRequest>,
+) -> impl IntoResponse {
+    let session_id = match get_session_from_cookie(&headers, None, &context.state.sessions).await {
+        Ok(sid) => sid,
+        Err(e) => return e.into_response(),
+    };
+
+    let Some(challenge) = context
+        .state
+        .sessions
+        .take_wallet_challenge(&session_id)
+        .await
+    else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::failure(
+                "No pending wallet challenge; request a new one",
+                400,
+            )),
+        )
+            .into_response();
+    };
+
+    if let Err(e) = challenge.verify(&req.signature) {
+        return (StatusCode::BAD_REQUEST, Json(ApiResponse::failure(e, 400))).into_response();
+    }
+
+    if context
+        .state
+        .moderation
+        .is_wallet_banned(&challenge.public_key)
+        .await
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::failure("This wallet is banned", 403)),
+        )
+            .into_response();
+    }
+
+    match context
+        .state
+        .link_wallet(&session_id, &challenge.public_key)
+        .await
+    {
+        Ok(stats) => {
+            tracing::info!(
+                "Linked wallet {} to session {}",
+                stats.display_name(),
+                session_id.chars().take(8).collect::<String>()
+            );
+
+            context
+                .state
+                .sessions
+                .bind_wallet(&session_id, &challenge.public_key)
+                .await;
+            let signed_out = context
+                .state
+                .enforce_session_limit(
+                    &challenge.public_key,
+                    &session_id,
+                    context.config.current().session.max_concurrent_per_identity,
+                )
+                .await;
+
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success(
+                    "Wallet linked.".to_string(),
+                    WalletLinked {
+                        wallet: challenge.public_key,
+                        display_name: stats.display_name(),
+                        player_stats: stats,
+                        signed_out_devices: signed_out,
+                    },
+                )),
+            )
+                .into_response()
+        }
+        Err(e) => (StatusCode::CONFLICT, Json(ApiResponse::failure(e, 409))).into_response(),
+    }
+}
@@ -0,0 +1,26 @@
+//! The wire schema in one place: every request and response body, the
+//! `AppEvent` stream and the models they carry. None of it needs the `server`
+//! feature, so bots and type generators can depend on the crate with
+//! `default-features = false` and get the exact types the server serializes.
+
+pub use crate::managers::{
+    analytics::{
+        BidSizeLatency, DemandStats, DistanceDemand, FeeWindow, LatencySummary, OutcomeLatency,
+        PriceBucket, PriceResolution, PriceSummary, SettlementOutcome, TypeLatency,
+        WinningBidStats,
+    },
+    restriction::{AuctionRestriction, RestrictionSummary},
+};
+pub use crate::models::{
+    admin::{AbuseFlag, AdminRole, BanRecord},
+    auction::ResolvedAuction,
+    event::AppEvent,
+    marketplace::MarketplaceStats,
+    metrics::{Achievement, AchievementType, Leaderboard, LeaderboardEntry},
+    player::{GameMode, PlayerStats},
+    requests::*,
+    responses::*,
+    slot::{Slot, SlotReservation, SlotState, SlotStateKind},
+    transaction::{Transaction, TransactionStatus, TransactionStatusKind},
+    types::{InclusionType, Lamports, TransactionType},
+};
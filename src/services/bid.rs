@@ -0,0 +1,979 @@
+use std::{collections::HashSet, future::Future};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+
+use crate::{
+    INITIAL_PLAYER_BALANCE,
+    app::state::{AppState, EnteredBid},
+    config::GlobalConfig,
+    error::AppError,
+    managers::transaction::TransactionFilter,
+    models::{
+        event::AppEvent,
+        requests::{AotBidRequest, BundleLeg, JitBidRequest, SimulateBidRequest},
+        responses::{AuctionCompetition, BidCancellation, BidRejection, BidSimulation},
+        slot::SlotState,
+        transaction::{DEFAULT_REBID_BUMP_PERCENT, RebidPolicy, Transaction, TransactionStatus},
+        types::{Lamports, TransactionType},
+    },
+    services::{estimate::min_acceptable_bid, transaction::validate_dependency},
+    utils::{
+        auction::bid_slot_state,
+        request_id::current_request_id,
+        validation::{FieldError, Validate},
+    },
+};
+
+/// Terms for the auction a bid opens if its slot has none yet.
+#[derive(Clone)]
+pub struct AuctionTerms {
+    pub base_fee: Lamports,
+    pub aot_duration_secs: i64,
+    pub aot_closing_warnings_secs: Vec<u64>,
+}
+
+impl AuctionTerms {
+    pub fn from_config(config: &GlobalConfig) -> Self {
+        Self {
+            base_fee: Lamports::from_sol(config.marketplace.base_fee_sol),
+            aot_duration_secs: config.auction.aot_default_duration_sec,
+            aot_closing_warnings_secs: config.auction.aot_closing_warnings_secs.clone(),
+        }
+    }
+}
+
+/// Places a JIT bid for the next slot once the session's cooldown, the slot's
+/// restrictions and any dependency allow it. Ban checks are left to the caller,
+/// which knows where the bid came from.
+pub async fn place_jit_bid(
+    state: &AppState,
+    config: &GlobalConfig,
+    session_id: String,
+    req: JitBidRequest,
+) -> Result<Transaction, AppError> {
+    check_cooldown(state, &session_id).await?;
+    sequenced(state, &session_id.clone(), req.nonce, async {
+        let transaction = prepare_jit_bid(state, config, session_id, req).await?;
+        submit_bid(state, transaction, AuctionTerms::from_config(config)).await
+    })
+    .await
+}
+
+/// Places an AOT bid for a slot within the lookahead window, with the same
+/// checks as `place_jit_bid`.
+pub async fn place_aot_bid(
+    state: &AppState,
+    config: &GlobalConfig,
+    session_id: String,
+    req: AotBidRequest,
+) -> Result<Transaction, AppError> {
+    check_cooldown(state, &session_id).await?;
+    sequenced(state, &session_id.clone(), req.nonce, async {
+        let transaction = prepare_aot_bid(state, config, session_id, req).await?;
+        submit_bid(state, transaction, AuctionTerms::from_config(config)).await
+    })
+    .await
+}
+
+/// Places every bid in `legs` or none of them, with the same checks as
+/// `place_jit_bid` and `place_aot_bid`. A rejection names the leg it came from.
+pub async fn place_bundle(
+    state: &AppState,
+    config: &GlobalConfig,
+    session_id: String,
+    legs: Vec<BundleLeg>,
+    nonce: Option<u64>,
+) -> Result<Vec<Transaction>, AppError> {
+    check_cooldown(state, &session_id).await?;
+
+    sequenced(state, &session_id.clone(), nonce, async {
+        let mut transactions = Vec::with_capacity(legs.len());
+        for (leg, bid) in legs.into_iter().enumerate() {
+            let transaction = match bid {
+                BundleLeg::Jit(req) => {
+                    prepare_jit_bid(state, config, session_id.clone(), req).await
+                }
+                BundleLeg::Aot(req) => {
+                    prepare_aot_bid(state, config, session_id.clone(), req).await
+                }
+            }
+            .map_err(|e| e.in_bundle_leg(leg))?;
+            transactions.push(transaction.with_nonce(nonce));
+        }
+        submit_bundle(state, transactions, AuctionTerms::from_config(config)).await
+    })
+    .await
+}
+
+/// Runs `place` under the session's bid nonce, if the client sent one. A
+/// nonce is only used up by a bid that is accepted, so a rejected bid can be
+/// fixed and sent again with the same nonce.
+async fn sequenced<T, F>(
+    state: &AppState,
+    session_id: &str,
+    nonce: Option<u64>,
+    place: F,
+) -> Result<T, AppError>
+where
+    F: Future<Output = Result<T, AppError>>,
+{
+    let Some(nonce) = nonce else {
+        return place.await;
+    };
+    let previous = state
+        .nonces
+        .claim(session_id, nonce)
+        .await
+        .map_err(|last_nonce| AppError::StaleNonce { nonce, last_nonce })?;
+
+    let result = place.await;
+    if result.is_err() {
+        state.nonces.release(session_id, nonce, previous).await;
+    }
+    result
+}
+
+async fn check_nonce(state: &AppState, session_id: &str, nonce: u64) -> Result<(), AppError> {
+    match state.nonces.last_nonce(session_id).await {
+        Some(last_nonce) if nonce <= last_nonce => Err(AppError::StaleNonce { nonce, last_nonce }),
+        _ => Ok(()),
+    }
+}
+
+/// The transaction for a JIT bid once the slot's restrictions and any
+/// dependency allow it; nothing is funded or entered yet.
+async fn prepare_jit_bid(
+    state: &AppState,
+    config: &GlobalConfig,
+    session_id: String,
+    req: JitBidRequest,
+) -> Result<Transaction, AppError> {
+    check_payload(config, &req.data)?;
+
+    let next_available_slot = state.get_current_slot().await? + 1;
+    tracing::Span::current().record("slot_number", next_available_slot);
+    state
+        .check_restriction(&session_id, next_available_slot)
+        .await?;
+
+    // Dependent transactions ride behind their prerequisite instead of bidding
+    if let Some(dependency_id) = &req.depends_on {
+        validate_dependency(state, &session_id, dependency_id, next_available_slot).await?;
+    }
+
+    let transaction = Transaction::jit(session_id, req.compute_units, req.bid_amount, req.data)
+        .with_ttl(
+            next_available_slot - 1,
+            config.marketplace.transaction_ttl_slots,
+        )
+        .with_dependency(req.depends_on)
+        .with_labels(req.memo, req.tags)
+        .with_nonce(req.nonce)
+        .with_rebid_policy(req.rebid);
+    Ok(transaction)
+}
+
+/// The transaction for an AOT bid within the lookahead window, with the same
+/// checks as `prepare_jit_bid`.
+async fn prepare_aot_bid(
+    state: &AppState,
+    config: &GlobalConfig,
+    session_id: String,
+    req: AotBidRequest,
+) -> Result<Transaction, AppError> {
+    check_payload(config, &req.data)?;
+
+    let current_slot = state.get_current_slot().await?;
+    if req.slot_number < current_slot {
+        return Err(AppError::InvalidSlot {
+            slot: req.slot_number,
+            current_slot,
+        });
+    }
+    let max_slot = current_slot + config.marketplace.aot_max_lookahead_slots;
+    if req.slot_number > max_slot {
+        return Err(AppError::SlotBeyondWindow {
+            slot: req.slot_number,
+            max_slot,
+        });
+    }
+    state
+        .check_restriction(&session_id, req.slot_number)
+        .await?;
+
+    if let Some(dependency_id) = &req.depends_on {
+        validate_dependency(state, &session_id, dependency_id, req.slot_number).await?;
+    }
+
+    let transaction = Transaction::aot(
+        session_id,
+        req.compute_units,
+        req.bid_amount,
+        req.slot_number,
+        req.data,
+    )
+    .with_ttl(current_slot, config.marketplace.transaction_ttl_slots)
+    .with_dependency(req.depends_on)
+    .with_labels(req.memo, req.tags)
+    .with_nonce(req.nonce);
+    Ok(transaction)
+}
+
+/// Holds `data` to the configured size limit and, if required, to base64.
+fn check_payload(config: &GlobalConfig, data: &str) -> Result<(), AppError> {
+    let max_bytes = config.marketplace.max_payload_bytes;
+    if data.len() > max_bytes {
+        return Err(AppError::PayloadTooLarge {
+            size: data.len(),
+            max_bytes,
+        });
+    }
+    if config.marketplace.require_base64_payload && BASE64.decode(data).is_err() {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "data",
+            "must be standard base64",
+        )]));
+    }
+    Ok(())
+}
+
+async fn check_cooldown(state: &AppState, session_id: &str) -> Result<(), AppError> {
+    match state.moderation.active_cooldown(session_id).await {
+        Some(flag) => Err(AppError::BidCooldown {
+            retry_after_secs: flag.retry_after_secs(),
+            reason: flag.reason,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Places a bid as one unit: its priority fee is escrowed, the slot's auction is
+/// started if needed, the bid is submitted, the slot updated and the transaction
+/// stored. If the auction rejects the bid, the escrow is rolled back and the
+/// sender's balance and stats are left as they were.
+pub async fn submit_bid(
+    state: &AppState,
+    transaction: Transaction,
+    terms: AuctionTerms,
+) -> Result<Transaction, AppError> {
+    let _bid_guard = state.lock_bidder(&transaction.sender).await?;
+    state.hold_escrow(&transaction).await?;
+
+    // Dependent transactions ride behind their prerequisite instead of bidding
+    if transaction.depends_on.is_none() {
+        let entered = match enter_auction(state, &transaction, &terms).await {
+            Ok(entered) => entered,
+            Err(e) => {
+                if let Some(amount) = state.rollback_escrow(&transaction).await? {
+                    tracing::info!(
+                        "Rolled back {} SOL escrow for rejected bid {}: {}",
+                        amount,
+                        transaction.id.chars().take(8).collect::<String>(),
+                        e
+                    );
+                }
+                return Err(e);
+            }
+        };
+        state.announce_bid(entered).await;
+        record_entry(state, &transaction).await?;
+    }
+
+    state
+        .add_transaction(transaction.sender.clone(), transaction.clone())
+        .await;
+
+    Ok(transaction)
+}
+
+/// Places a bundle's transactions as one unit, like `submit_bid` does for a
+/// single one. Every leg is funded before any enters an auction, and the JIT
+/// leg enters last: an AOT bid can be withdrawn again, but a JIT bid that took
+/// the lead cannot hand it back. If any leg is rejected, the legs already
+/// entered are withdrawn and every escrow is rolled back. Legs are announced
+/// only once all of them have entered, so a rejected bundle never shows up on
+/// the event stream or in the abuse detector.
+pub async fn submit_bundle(
+    state: &AppState,
+    transactions: Vec<Transaction>,
+    terms: AuctionTerms,
+) -> Result<Vec<Transaction>, AppError> {
+    let Some(sender) = transactions.first().map(|t| t.sender.clone()) else {
+        return Ok(transactions);
+    };
+    let _bid_guard = state.lock_bidder(&sender).await?;
+
+    for (leg, transaction) in transactions.iter().enumerate() {
+        if let Err(e) = state.hold_escrow(transaction).await {
+            rollback_bundle(state, &transactions[..leg], &[]).await;
+            return Err(e.in_bundle_leg(leg));
+        }
+    }
+
+    // Dependent transactions ride behind their prerequisite instead of bidding
+    let mut bidding: Vec<usize> = (0..transactions.len())
+        .filter(|&leg| transactions[leg].depends_on.is_none())
+        .collect();
+    bidding.sort_by_key(|&leg| transactions[leg].transaction_type() == TransactionType::Jit);
+    let mut entered = Vec::with_capacity(bidding.len());
+    let mut announcements = Vec::with_capacity(bidding.len());
+    for leg in bidding {
+        match enter_auction(state, &transactions[leg], &terms).await {
+            Ok(announcement) => announcements.push(announcement),
+            Err(e) => {
+                rollback_bundle(state, &transactions, &entered).await;
+                return Err(e.in_bundle_leg(leg));
+            }
+        }
+        entered.push(&transactions[leg]);
+    }
+
+    for announcement in announcements {
+        state.announce_bid(announcement).await;
+    }
+    for transaction in &transactions {
+        if transaction.depends_on.is_none() {
+            record_entry(state, transaction).await?;
+        }
+        state
+            .add_transaction(transaction.sender.clone(), transaction.clone())
+            .await;
+    }
+    Ok(transactions)
+}
+
+/// Undoes a bundle that was rejected partway. Only AOT legs can have entered
+/// their auctions by then.
+async fn rollback_bundle(state: &AppState, funded: &[Transaction], entered: &[&Transaction]) {
+    for transaction in entered {
+        let slot_number = transaction.target_slot();
+        let bidder = transaction.sender.clone();
+        let amount = transaction.priority_fee;
+        let withdrawn = state
+            .auctions
+            .shard(slot_number)
+            .call(move |auctions| auctions.withdraw_aot_bid(slot_number, &bidder, amount))
+            .await;
+        if let Err(e) = withdrawn {
+            tracing::warn!(
+                "Failed to withdraw bundled bid {} from slot {}: {}",
+                transaction.id.chars().take(8).collect::<String>(),
+                slot_number,
+                e
+            );
+        }
+    }
+
+    for transaction in funded {
+        match state.rollback_escrow(transaction).await {
+            Ok(Some(amount)) => tracing::info!(
+                "Rolled back {} SOL escrow for bundled bid {}",
+                amount,
+                transaction.id.chars().take(8).collect::<String>()
+            ),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(
+                "Failed to roll back escrow for bundled bid {}: {}",
+                transaction.id.chars().take(8).collect::<String>(),
+                e
+            ),
+        }
+    }
+}
+
+/// Reflects a bid that entered its auction in the slot and demand stats.
+async fn record_entry(state: &AppState, transaction: &Transaction) -> Result<(), AppError> {
+    update_slot_state(state, transaction).await?;
+    let (transaction_type, priority_fee) =
+        (transaction.transaction_type(), transaction.priority_fee);
+    let lead_slots = transaction
+        .target_slot()
+        .saturating_sub(transaction.created_slot);
+    state
+        .demand
+        .call(move |demand| demand.record_bid(transaction_type, lead_slots, priority_fee))
+        .await
+}
+
+async fn enter_auction(
+    state: &AppState,
+    transaction: &Transaction,
+    terms: &AuctionTerms,
+) -> Result<EnteredBid, AppError> {
+    let slot_number = transaction.target_slot();
+    let bidder = transaction.sender.clone();
+    let amount = transaction.priority_fee;
+
+    match transaction.transaction_type() {
+        TransactionType::Jit => {
+            if !auction_exists(state, TransactionType::Jit, slot_number).await? {
+                if let Err(e) = state.start_jit_auction(slot_number, terms.base_fee).await {
+                    // Another bid may have opened the auction in the meantime
+                    if !auction_exists(state, TransactionType::Jit, slot_number).await? {
+                        return Err(e.context("JIT auction failed to start").into());
+                    }
+                }
+            }
+            state.submit_jit_bid(slot_number, bidder, amount).await
+        }
+        TransactionType::Aot => {
+            if !auction_exists(state, TransactionType::Aot, slot_number).await? {
+                if let Err(e) = state
+                    .start_aot_auction(
+                        slot_number,
+                        terms.base_fee,
+                        terms.aot_duration_secs,
+                        terms.aot_closing_warnings_secs.clone(),
+                    )
+                    .await
+                {
+                    if !auction_exists(state, TransactionType::Aot, slot_number).await? {
+                        return Err(e.context("AOT auction failed to start").into());
+                    }
+                }
+            }
+            state.submit_aot_bid(slot_number, bidder, amount).await
+        }
+    }
+}
+
+async fn auction_exists(
+    state: &AppState,
+    auction_type: TransactionType,
+    slot_number: u64,
+) -> Result<bool, AppError> {
+    state
+        .auctions
+        .shard(slot_number)
+        .call(move |auctions| match auction_type {
+            TransactionType::Jit => auctions.jit_auctions.contains_key(&slot_number),
+            TransactionType::Aot => auctions.aot_auctions.contains_key(&slot_number),
+        })
+        .await
+}
+
+async fn update_slot_state(state: &AppState, transaction: &Transaction) -> Result<(), AppError> {
+    let slot_number = transaction.target_slot();
+    let ends_at = match transaction.transaction_type() {
+        TransactionType::Jit => None,
+        TransactionType::Aot => {
+            state
+                .auctions
+                .shard(slot_number)
+                .call(move |auctions| {
+                    auctions
+                        .aot_auctions
+                        .get(&slot_number)
+                        .map(|auction| auction.ends_at)
+                })
+                .await?
+        }
+    };
+    let Some(slot_state) = bid_slot_state(transaction, ends_at) else {
+        return Ok(());
+    };
+
+    state
+        .slots
+        .shard(slot_number)
+        .call(move |shard| shard.update(slot_number, |slot| slot.state = slot_state))
+        .await?;
+    Ok(())
+}
+
+/// Withdraws one of the player's pending bids and refunds its escrow, marking
+/// the transaction `Cancelled`. AOT bids can be withdrawn until their auction
+/// ends; JIT bids only while someone else leads the auction. A dependent
+/// transaction never entered an auction, so it is just refunded.
+pub async fn cancel_bid(
+    state: &AppState,
+    session_id: &str,
+    transaction_id: &str,
+) -> Result<BidCancellation, AppError> {
+    let _bid_guard = state.lock_bidder(session_id).await?;
+
+    let mut transaction = state
+        .get_transaction_by_id(transaction_id)
+        .await
+        .filter(|transaction| transaction.sender == session_id)
+        .ok_or(AppError::TransactionNotFound)?;
+    if !matches!(transaction.status, TransactionStatus::Pending) {
+        return Err(AppError::TransactionNotCancellable(format!(
+            "Only pending bids can be cancelled, this one is {}",
+            transaction.status.kind().as_str()
+        )));
+    }
+
+    let slot_number = transaction.target_slot();
+    let auction_type = transaction.transaction_type();
+    let mut auction_emptied = false;
+    if transaction.depends_on.is_none() {
+        let (bidder, amount) = (session_id.to_string(), transaction.priority_fee);
+        match auction_type {
+            TransactionType::Jit => {
+                state
+                    .auctions
+                    .shard(slot_number)
+                    .call(move |auctions| auctions.withdraw_jit_bid(slot_number, &bidder))
+                    .await??
+            }
+            TransactionType::Aot => {
+                let remaining = state
+                    .auctions
+                    .shard(slot_number)
+                    .call(move |auctions| {
+                        auctions.withdraw_aot_bid(slot_number, &bidder, amount)?;
+                        Ok::<_, AppError>(auctions.aot_auctions.get(&slot_number).map(|auction| {
+                            let bids: Vec<(String, Lamports)> = auction
+                                .bid_ladder()
+                                .into_iter()
+                                .map(|(bidder, bid, _)| (bidder.clone(), *bid))
+                                .collect();
+                            (bids, auction.ends_at)
+                        }))
+                    })
+                    .await??;
+
+                // The slot shows the bids left, or none if this was the last
+                if let Some((bids, ends_at)) = remaining {
+                    auction_emptied = bids.is_empty();
+                    let slot_state = match bids.first().cloned() {
+                        Some((highest_bidder, highest_bid)) => SlotState::AotAuction {
+                            highest_bid,
+                            highest_bidder,
+                            bids,
+                            ends_at,
+                        },
+                        None => SlotState::Available,
+                    };
+                    state
+                        .slots
+                        .shard(slot_number)
+                        .call(move |shard| {
+                            shard.update(slot_number, |slot| slot.state = slot_state)
+                        })
+                        .await?;
+                }
+            }
+        }
+    }
+
+    // Refunded before it is marked cancelled, so a failed refund leaves the
+    // bid pending and cancellable again rather than settled with its escrow held
+    let refunded = state.refund_escrow(&transaction).await?.unwrap_or_default();
+    transaction.mark_cancelled();
+    state
+        .update_transaction_by_id(&transaction.id, transaction.clone())
+        .await?;
+
+    state.events.broadcast(AppEvent::BidCancelled {
+        slot_number,
+        auction_type,
+        bidder: session_id.to_string(),
+        amount: transaction.priority_fee,
+        request_id: current_request_id(),
+    });
+    if auction_emptied {
+        state.events.forget_auction(auction_type, slot_number);
+    }
+
+    Ok(BidCancellation {
+        transaction,
+        refunded,
+    })
+}
+
+/// Places a new bid cloned from one of the player's failed or expired bids,
+/// with the same compute units and data and the amount raised by
+/// `bump_percent`. A JIT bid goes to the next slot, an AOT bid to the first
+/// slot still open for bidding at least as far ahead as the original was.
+pub async fn retry_bid(
+    state: &AppState,
+    config: &GlobalConfig,
+    session_id: String,
+    transaction_id: &str,
+    bump_percent: f64,
+    nonce: Option<u64>,
+) -> Result<Transaction, AppError> {
+    let original = state
+        .get_transaction_by_id(transaction_id)
+        .await
+        .filter(|transaction| transaction.sender == session_id)
+        .ok_or(AppError::TransactionNotFound)?;
+    if !matches!(
+        original.status,
+        TransactionStatus::Failed { .. } | TransactionStatus::Expired { .. }
+    ) {
+        return Err(AppError::TransactionNotRetryable(format!(
+            "Only failed or expired bids can be retried, this one is {}",
+            original.status.kind().as_str()
+        )));
+    }
+
+    let bid_amount = original.priority_fee.scale(1.0 + bump_percent / 100.0);
+    match original.transaction_type() {
+        TransactionType::Jit => {
+            let req = JitBidRequest {
+                session_id: None,
+                bid_amount,
+                compute_units: original.compute_units,
+                data: original.data,
+                depends_on: None,
+                memo: original.memo,
+                tags: original.tags,
+                nonce,
+                rebid: None,
+            };
+            check_retried_bid(&req)?;
+            place_jit_bid(state, config, session_id, req).await
+        }
+        TransactionType::Aot => {
+            let lead = original
+                .target_slot()
+                .saturating_sub(original.created_slot)
+                .max(1);
+            let req = AotBidRequest {
+                session_id: None,
+                slot_number: next_open_aot_slot(state, config, lead).await?,
+                bid_amount,
+                compute_units: original.compute_units,
+                data: original.data,
+                depends_on: None,
+                memo: original.memo,
+                tags: original.tags,
+                nonce,
+            };
+            check_retried_bid(&req)?;
+            place_aot_bid(state, config, session_id, req).await
+        }
+    }
+}
+
+/// Rebids each JIT bid for `slot` that lost to `winner` under a rebid policy,
+/// for the next slot at the higher of its bid and the winning bid, raised by
+/// the policy's bump and capped at its `max_bid`. A player with several
+/// losing bids gets one rebid, for the highest.
+pub async fn rebid_lost_jit_bids(state: &AppState, slot: u64, winner: &str, winning_bid: Lamports) {
+    let mut lost: Vec<Transaction> = state
+        .transactions
+        .filtered(TransactionFilter::slot(slot))
+        .into_iter()
+        .filter(|transaction| {
+            transaction.transaction_type() == TransactionType::Jit
+                && transaction.sender != winner
+                && matches!(transaction.status, TransactionStatus::Failed { .. })
+                && transaction
+                    .rebid_policy
+                    .as_ref()
+                    .is_some_and(|policy| policy.max_slots > 0)
+        })
+        .collect();
+    lost.sort_by_key(|transaction| std::cmp::Reverse(transaction.priority_fee));
+    let mut rebid_senders = HashSet::new();
+    lost.retain(|transaction| rebid_senders.insert(transaction.sender.clone()));
+
+    for transaction in lost {
+        let short_id = transaction.id.chars().take(8).collect::<String>();
+        match place_rebid(state, &transaction, winning_bid).await {
+            Ok(rebid) => tracing::info!(
+                "Rebid lost bid {} as {} for slot {} with {} SOL",
+                short_id,
+                rebid.id.chars().take(8).collect::<String>(),
+                rebid.target_slot(),
+                rebid.priority_fee
+            ),
+            Err(e) => tracing::info!("Stopped rebidding lost bid {}: {}", short_id, e),
+        }
+    }
+}
+
+async fn place_rebid(
+    state: &AppState,
+    lost: &Transaction,
+    winning_bid: Lamports,
+) -> Result<Transaction, AppError> {
+    let Some(policy) = lost.rebid_policy.clone() else {
+        return Err(anyhow::anyhow!("Transaction {} has no rebid policy", lost.id).into());
+    };
+    state.check_bid_allowed(&lost.sender, None).await?;
+    check_cooldown(state, &lost.sender).await?;
+
+    let next_available_slot = state.get_current_slot().await? + 1;
+    state
+        .check_restriction(&lost.sender, next_available_slot)
+        .await?;
+
+    let bump_percent = policy.bump_percent.unwrap_or(DEFAULT_REBID_BUMP_PERCENT);
+    let bid_amount = lost
+        .priority_fee
+        .max(winning_bid)
+        .scale(1.0 + bump_percent / 100.0)
+        .min(policy.max_bid);
+    let ttl_slots = lost
+        .expires_at_slot
+        .map_or(1, |expires_at_slot| {
+            expires_at_slot.saturating_sub(lost.created_slot)
+        })
+        .max(1);
+
+    let mut transaction = Transaction::jit(
+        lost.sender.clone(),
+        lost.compute_units,
+        bid_amount,
+        lost.data.clone(),
+    )
+    .with_ttl(next_available_slot - 1, ttl_slots)
+    .with_labels(lost.memo.clone(), lost.tags.clone())
+    .with_rebid_policy(Some(RebidPolicy {
+        max_slots: policy.max_slots - 1,
+        ..policy
+    }));
+    transaction.rebid_of = Some(lost.id.clone());
+
+    let base_fee = state
+        .marketplace
+        .call(|marketplace| marketplace.base_fee)
+        .await?;
+    let terms = AuctionTerms {
+        base_fee,
+        aot_duration_secs: 0,
+        aot_closing_warnings_secs: Vec::new(),
+    };
+    submit_bid(state, transaction, terms).await
+}
+
+/// A bumped bid can outgrow the limits the original was placed within.
+fn check_retried_bid(req: &impl Validate) -> Result<(), AppError> {
+    let errors = req.validate();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Validation(errors))
+    }
+}
+
+/// The first slot at least `lead` slots ahead whose AOT auction is still open
+/// or not yet started, within the AOT lookahead.
+async fn next_open_aot_slot(
+    state: &AppState,
+    config: &GlobalConfig,
+    lead: u64,
+) -> Result<u64, AppError> {
+    let current_slot = state.get_current_slot().await?;
+    let max_slot = current_slot + config.marketplace.aot_max_lookahead_slots;
+    let lead = lead.min(config.marketplace.aot_max_lookahead_slots);
+    let now = chrono::Utc::now();
+
+    state
+        .get_slots(current_slot + lead, max_slot + 1)
+        .await?
+        .into_iter()
+        .find(|slot| match &slot.state {
+            SlotState::Available => true,
+            SlotState::AotAuction { ends_at, .. } => *ends_at > now,
+            _ => false,
+        })
+        .map(|slot| slot.slot_number)
+        .ok_or_else(|| {
+            AppError::TransactionNotRetryable(format!(
+                "No slot between {} and {} is open for AOT bids",
+                current_slot + lead,
+                max_slot
+            ))
+        })
+}
+
+/// Runs a bid through the checks placing it would, without escrowing funds or
+/// entering the auction, and reports every check it fails along with the
+/// slot's minimum winning bid and competition. Ban and room access checks are
+/// left to the caller, as for placed bids.
+pub async fn simulate_bid(
+    state: &AppState,
+    config: &GlobalConfig,
+    session_id: &str,
+    req: &SimulateBidRequest,
+) -> Result<BidSimulation, AppError> {
+    let mut rejections = Vec::new();
+    let mut check = |result: Result<(), AppError>| {
+        if let Err(e) = result {
+            rejections.push(e);
+        }
+    };
+
+    check(check_payload(config, &req.data));
+    check(check_cooldown(state, session_id).await);
+    if let Some(nonce) = req.nonce {
+        check(check_nonce(state, session_id, nonce).await);
+    }
+
+    let current_slot = state.get_current_slot().await?;
+    let slot_number = match req.transaction_type {
+        TransactionType::Jit => current_slot + 1,
+        TransactionType::Aot => req.slot_number.unwrap_or(current_slot + 1),
+    };
+    let max_slot = current_slot + config.marketplace.aot_max_lookahead_slots;
+    let slot_in_window = if req.transaction_type == TransactionType::Jit {
+        true
+    } else if slot_number < current_slot {
+        check(Err(AppError::InvalidSlot {
+            slot: slot_number,
+            current_slot,
+        }));
+        false
+    } else if slot_number > max_slot {
+        check(Err(AppError::SlotBeyondWindow {
+            slot: slot_number,
+            max_slot,
+        }));
+        false
+    } else {
+        true
+    };
+    check(state.check_restriction(session_id, slot_number).await);
+
+    let base_fee = Lamports::from_sol(config.marketplace.base_fee_sol);
+    let min_winning_bid =
+        min_acceptable_bid(state, slot_number, req.transaction_type, base_fee).await?;
+    let competition =
+        auction_competition(state, session_id, slot_number, req.transaction_type).await?;
+
+    // Dependent transactions ride behind their prerequisite instead of bidding
+    if let Some(dependency_id) = &req.depends_on {
+        check(validate_dependency(state, session_id, dependency_id, slot_number).await);
+    } else if slot_in_window {
+        check(auction_entry(
+            slot_number,
+            req.transaction_type,
+            req.bid_amount,
+            min_winning_bid,
+            &competition,
+        ));
+    }
+
+    let session = session_id.to_string();
+    let available_balance = state
+        .game
+        .call(move |game| {
+            game.player_stats
+                .get(&session)
+                .map(|stats| stats.balance)
+                .unwrap_or(INITIAL_PLAYER_BALANCE)
+        })
+        .await?;
+    if req.bid_amount > available_balance {
+        check(Err(AppError::InsufficientBalance {
+            available: available_balance,
+            required: req.bid_amount,
+        }));
+    }
+
+    Ok(BidSimulation {
+        accepted: rejections.is_empty(),
+        rejections: rejections.iter().map(BidRejection::from).collect(),
+        slot_number,
+        auction_type: req.transaction_type,
+        bid_amount: req.bid_amount,
+        min_winning_bid,
+        available_balance,
+        competition,
+    })
+}
+
+/// Why the slot's auction would turn the bid away, as `enter_auction` would.
+fn auction_entry(
+    slot_number: u64,
+    auction_type: TransactionType,
+    bid_amount: Lamports,
+    min_winning_bid: Lamports,
+    competition: &AuctionCompetition,
+) -> Result<(), AppError> {
+    if auction_type == TransactionType::Aot {
+        if let Some(closed_at) = competition
+            .closes_at
+            .filter(|ends_at| *ends_at < chrono::Utc::now())
+        {
+            return Err(AppError::AuctionClosed {
+                slot: slot_number,
+                closed_at,
+            });
+        }
+    }
+    if auction_type == TransactionType::Jit {
+        if let Some(current) = competition
+            .highest_bid
+            .filter(|current| bid_amount <= *current)
+        {
+            return Err(AppError::BidNotHigher { current });
+        }
+    }
+    if bid_amount < min_winning_bid {
+        return Err(AppError::BidTooLow {
+            minimum: min_winning_bid,
+        });
+    }
+    Ok(())
+}
+
+async fn auction_competition(
+    state: &AppState,
+    session_id: &str,
+    slot_number: u64,
+    auction_type: TransactionType,
+) -> Result<AuctionCompetition, AppError> {
+    let snapshot = state
+        .auctions
+        .shard(slot_number)
+        .call(move |auctions| match auction_type {
+            TransactionType::Jit => auctions.jit_auctions.get(&slot_number).map(|auction| {
+                let leader = auction.current_highest_bidder.clone();
+                let bidders = leader.iter().map(|(bidder, _)| bidder.clone()).collect();
+                (leader, bidders, None)
+            }),
+            TransactionType::Aot => auctions.aot_auctions.get(&slot_number).map(|auction| {
+                let leader = auction
+                    .get_highest_bid()
+                    .map(|(bidder, amount, _)| (bidder.clone(), *amount));
+                let bidders: Vec<String> = auction
+                    .bids
+                    .iter()
+                    .map(|(bidder, _, _)| bidder.clone())
+                    .collect();
+                (leader, bidders, Some(auction.ends_at))
+            }),
+        })
+        .await?;
+    let Some((leader, mut bidders, ends_at)) = snapshot else {
+        return Ok(AuctionCompetition::default());
+    };
+
+    let closes_at = match ends_at {
+        Some(ends_at) => Some(ends_at),
+        None => {
+            state
+                .slots
+                .shard(slot_number)
+                .call(move |shard| shard.get(slot_number).map(|slot| slot.estimated_time))
+                .await?
+        }
+    };
+    let bid_count = bidders.len();
+    bidders.sort();
+    bidders.dedup();
+    let aliases = state
+        .display_names(leader.iter().map(|(bidder, _)| bidder.clone()).collect())
+        .await?;
+
+    Ok(AuctionCompetition {
+        open: true,
+        highest_bid: leader.as_ref().map(|(_, amount)| *amount),
+        leader: leader
+            .as_ref()
+            .and_then(|(bidder, _)| aliases.get(bidder).cloned()),
+        leading: leader
+            .as_ref()
+            .is_some_and(|(bidder, _)| bidder == session_id),
+        bid_count,
+        bidder_count: bidders.len(),
+        closes_at,
+    })
+}
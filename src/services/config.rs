@@ -0,0 +1,142 @@
+use serde::Serialize;
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+use crate::{
+    app::room::RoomManager,
+    config::{GlobalConfig, RESTART_REQUIRED_SETTINGS, SharedConfig},
+    models::types::Lamports,
+    utils::rate_limiter::RateLimiter,
+};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConfigReload {
+    /// Settings that changed and now apply to new slots, auctions and requests
+    pub applied: Vec<String>,
+    /// Settings that changed but only take effect after a restart
+    pub restart_required: Vec<String>,
+}
+
+/// Re-reads the configuration and applies it to every room without touching
+/// existing state. Slots, auctions and transactions created before the reload
+/// keep their terms.
+pub async fn reload_config(
+    rooms: &RoomManager,
+    config: &SharedConfig,
+    rate_limiter: &RateLimiter,
+) -> anyhow::Result<ConfigReload> {
+    let new_config = GlobalConfig::load()?;
+    let old_config = config.current();
+
+    let mut changed = Vec::new();
+    diff_settings(
+        "",
+        &serde_json::to_value(old_config.as_ref())?,
+        &serde_json::to_value(&new_config)?,
+        &mut changed,
+    );
+
+    let (restart_required, applied): (Vec<String>, Vec<String>) =
+        changed.into_iter().partition(|setting| {
+            RESTART_REQUIRED_SETTINGS
+                .iter()
+                .any(|prefix| setting == prefix || setting.starts_with(&format!("{}.", prefix)))
+        });
+
+    let listing = new_config.marketplace.listing_window_slots;
+    for room in rooms.list() {
+        // Chains and rooms with creator overrides keep their own slot timing and base fee
+        let timing = room.timing(&new_config.marketplace);
+        let (slot_duration_ms, base_fee) = (
+            timing.slot_duration_ms,
+            Lamports::from_sol(timing.base_fee_sol),
+        );
+        room.state
+            .marketplace
+            .call(move |marketplace| marketplace.apply_config(slot_duration_ms, base_fee, listing))
+            .await?;
+    }
+    rooms
+        .global()
+        .state
+        .moderation
+        .apply_config(new_config.abuse.clone())
+        .await;
+    rate_limiter.apply_config(&new_config.rate_limit);
+    config.replace(new_config);
+
+    if !restart_required.is_empty() {
+        tracing::warn!(
+            "Configuration reloaded, but these settings need a restart to take effect: {}",
+            restart_required.join(", ")
+        );
+    }
+    tracing::info!("Configuration reloaded, applied: {:?}", applied);
+
+    Ok(ConfigReload {
+        applied,
+        restart_required,
+    })
+}
+
+/// Collects the dotted paths of every leaf setting that differs between two configs.
+fn diff_settings(path: &str, old: &Value, new: &Value, changed: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            for (key, new_value) in new_map {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff_settings(
+                    &child,
+                    old_map.get(key).unwrap_or(&Value::Null),
+                    new_value,
+                    changed,
+                );
+            }
+        }
+        _ if old != new => changed.push(path.to_string()),
+        _ => {}
+    }
+}
+
+/// Reloads the configuration whenever the process receives SIGHUP.
+pub async fn run_config_reload_on_sighup(
+    rooms: RoomManager,
+    config: SharedConfig,
+    rate_limiter: RateLimiter,
+    shutdown: CancellationToken,
+) {
+    #[cfg(unix)]
+    {
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+        {
+            Ok(signal) => signal,
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGHUP: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = hangup.recv() => {}
+            }
+
+            tracing::info!("Received SIGHUP, reloading configuration");
+            if let Err(e) = reload_config(&rooms, &config, &rate_limiter).await {
+                tracing::error!("Failed to reload configuration: {}", e);
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (rooms, config, rate_limiter);
+        shutdown.cancelled().await;
+    }
+}
@@ -0,0 +1,179 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    JIT_PREMIUM_MULTIPLIER,
+    app::state::AppState,
+    error::AppError,
+    models::{
+        auction::ResolvedAuction,
+        types::{Lamports, TransactionType},
+    },
+};
+
+/// Recent auctions sampled for an estimate.
+const MAX_SAMPLES: usize = 50;
+/// Fewest comparable auctions before the sample widens to every distance.
+const MIN_SAMPLES: usize = 5;
+
+/// How a bid compares with what recent auctions at a similar distance cleared at.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BidEstimate {
+    pub slot_number: u64,
+    pub auction_type: TransactionType,
+    /// Slots between now and the target slot
+    pub distance: u64,
+    pub bid: Lamports,
+    /// Smallest bid the slot's auction would accept right now
+    pub min_acceptable_bid: Lamports,
+    /// Share of sampled auctions the bid would have won, or `None` without history
+    pub win_probability: Option<f64>,
+    /// Bid that would have won three in four sampled auctions
+    pub suggested_bid: Lamports,
+    pub sample_size: usize,
+    /// Whether the sample had to include auctions at any distance
+    pub widened_sample: bool,
+    pub median_clearing_price: Option<Lamports>,
+    pub p90_clearing_price: Option<Lamports>,
+}
+
+/// Estimates the chance that `bid` wins the auction for `slot_number`, from the
+/// clearing prices of recent auctions won at a similar distance ahead.
+pub async fn estimate_bid(
+    state: &AppState,
+    slot_number: u64,
+    bid: Lamports,
+    base_fee: Lamports,
+    max_lookahead: u64,
+) -> Result<BidEstimate, AppError> {
+    let (auction_type, distance) = auction_for_slot(state, slot_number, max_lookahead).await?;
+    let min_acceptable_bid = min_acceptable_bid(state, slot_number, auction_type, base_fee).await?;
+
+    let (mut prices, widened_sample) = state
+        .auction_history
+        .call(move |history| {
+            let comparable = |auction: &&ResolvedAuction| {
+                auction.auction_type == auction_type
+                    && auction.lead_slots.abs_diff(distance) <= (distance / 4).max(2)
+            };
+            let prices: Vec<Lamports> = history
+                .iter()
+                .filter(comparable)
+                .take(MAX_SAMPLES)
+                .map(|auction| auction.clearing_price)
+                .collect();
+            if prices.len() >= MIN_SAMPLES {
+                return (prices, false);
+            }
+            let prices = history
+                .iter()
+                .filter(|auction| auction.auction_type == auction_type)
+                .take(MAX_SAMPLES)
+                .map(|auction| auction.clearing_price)
+                .collect();
+            (prices, true)
+        })
+        .await?;
+    prices.sort();
+
+    let win_probability = if bid < min_acceptable_bid {
+        Some(0.0)
+    } else if prices.is_empty() {
+        None
+    } else {
+        // A tie with the clearing price counts as half a win
+        let beaten = prices.iter().filter(|price| **price < bid).count() as f64;
+        let tied = prices.iter().filter(|price| **price == bid).count() as f64;
+        Some((beaten + tied / 2.0) / prices.len() as f64)
+    };
+    let suggested_bid = percentile(&prices, 75)
+        .map(|price| price + Lamports(1))
+        .unwrap_or(min_acceptable_bid)
+        .max(min_acceptable_bid);
+
+    Ok(BidEstimate {
+        slot_number,
+        auction_type,
+        distance,
+        bid,
+        min_acceptable_bid,
+        win_probability,
+        suggested_bid,
+        sample_size: prices.len(),
+        widened_sample,
+        median_clearing_price: percentile(&prices, 50),
+        p90_clearing_price: percentile(&prices, 90),
+    })
+}
+
+/// The auction a bid for `slot_number` would enter right now, and how many
+/// slots ahead the slot is.
+pub(crate) async fn auction_for_slot(
+    state: &AppState,
+    slot_number: u64,
+    max_lookahead: u64,
+) -> Result<(TransactionType, u64), AppError> {
+    let current_slot = state.get_current_slot().await?;
+    if slot_number <= current_slot {
+        return Err(AppError::InvalidSlot {
+            slot: slot_number,
+            current_slot,
+        });
+    }
+    let max_slot = current_slot + max_lookahead;
+    if slot_number > max_slot {
+        return Err(AppError::SlotBeyondWindow {
+            slot: slot_number,
+            max_slot,
+        });
+    }
+
+    // JIT bids always target the next slot
+    let distance = slot_number - current_slot;
+    let auction_type = if distance == 1 {
+        TransactionType::Jit
+    } else {
+        TransactionType::Aot
+    };
+    Ok((auction_type, distance))
+}
+
+pub(crate) async fn min_acceptable_bid(
+    state: &AppState,
+    slot_number: u64,
+    auction_type: TransactionType,
+    base_fee: Lamports,
+) -> Result<Lamports, AppError> {
+    let open_auction = state
+        .auctions
+        .shard(slot_number)
+        .call(move |auctions| match auction_type {
+            TransactionType::Jit => auctions
+                .jit_auctions
+                .get(&slot_number)
+                .map(|auction| auction.min_next_bid()),
+            TransactionType::Aot => auctions
+                .aot_auctions
+                .get(&slot_number)
+                .map(|auction| auction.get_min_next_bid()),
+        })
+        .await?;
+    if let Some(minimum) = open_auction {
+        return Ok(minimum);
+    }
+
+    // The bid would open the auction at the configured base fee
+    Ok(match auction_type {
+        TransactionType::Jit => base_fee.scale(JIT_PREMIUM_MULTIPLIER),
+        TransactionType::Aot => base_fee,
+    })
+}
+
+/// Nearest-rank percentile of sorted `prices`.
+pub(crate) fn percentile(prices: &[Lamports], percent: usize) -> Option<Lamports> {
+    if prices.is_empty() {
+        return None;
+    }
+    let rank = (percent * prices.len()).div_ceil(100).max(1);
+    Some(prices[rank - 1])
+}
@@ -0,0 +1,262 @@
+//! Publishes the global room's event firehose to external brokers, so
+//! analytics pipelines and other simulator instances can consume it.
+
+use std::{
+    fmt::Display,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::{sync::mpsc, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    config::EventsConfig,
+    models::event::{BusMetrics, EventBroadcaster, EventBus, SharedEvent},
+};
+
+/// Events queued for a broker before new ones are dropped
+const BUS_QUEUE_CAPACITY: usize = 10_000;
+/// Wait before reconnecting to a broker that could not be reached; events
+/// broadcast meanwhile are dropped
+#[cfg(any(feature = "nats", feature = "redis"))]
+const BUS_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct BusCounters {
+    published: AtomicU64,
+    dropped: AtomicU64,
+    failing: AtomicBool,
+}
+
+/// An `EventBus` that queues event payloads for a task publishing them to a
+/// broker, so a slow or unreachable broker never holds up the simulation.
+pub struct BrokerBus {
+    name: String,
+    queue: mpsc::Sender<Arc<str>>,
+    counters: Arc<BusCounters>,
+}
+
+impl BrokerBus {
+    /// The bus and the queue its publishing task reads from.
+    pub fn new(name: String) -> (Self, BusQueue) {
+        let (sender, receiver) = mpsc::channel(BUS_QUEUE_CAPACITY);
+        let counters = Arc::new(BusCounters::default());
+        let queue = BusQueue {
+            name: name.clone(),
+            receiver,
+            counters: counters.clone(),
+        };
+        (
+            Self {
+                name,
+                queue: sender,
+                counters,
+            },
+            queue,
+        )
+    }
+}
+
+impl EventBus for BrokerBus {
+    fn publish(&self, event: &SharedEvent) {
+        if self.queue.try_send(event.payload.clone()).is_err() {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn bus_metrics(&self) -> BusMetrics {
+        BusMetrics {
+            name: self.name.clone(),
+            published_events: self.counters.published.load(Ordering::Relaxed),
+            dropped_events: self.counters.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Payloads waiting to be published by a `BrokerBus`'s task.
+pub struct BusQueue {
+    name: String,
+    receiver: mpsc::Receiver<Arc<str>>,
+    counters: Arc<BusCounters>,
+}
+
+impl BusQueue {
+    /// The next payload, or `None` once `shutdown` is cancelled.
+    pub async fn next(&mut self, shutdown: &CancellationToken) -> Option<Arc<str>> {
+        tokio::select! {
+            _ = shutdown.cancelled() => None,
+            payload = self.receiver.recv() => payload,
+        }
+    }
+
+    pub fn record_published(&self) {
+        self.counters.published.fetch_add(1, Ordering::Relaxed);
+        if self.counters.failing.swap(false, Ordering::Relaxed) {
+            tracing::info!("Publishing events to {} again", self.name);
+        }
+    }
+
+    /// Counts the payload as dropped, logging only the first failure in a row.
+    pub fn record_failure(&self, error: impl Display) {
+        self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        if !self.counters.failing.swap(true, Ordering::Relaxed) {
+            tracing::warn!("Failed to publish events to {}: {}", self.name, error);
+        }
+    }
+
+    /// Drops every payload queued until `delay` has passed.
+    pub async fn drop_for(&mut self, delay: Duration, shutdown: &CancellationToken) {
+        let deadline = tokio::time::sleep(delay);
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => return,
+                _ = &mut deadline => return,
+                payload = self.receiver.recv() => match payload {
+                    Some(_) => {
+                        self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    None => return,
+                },
+            }
+        }
+    }
+}
+
+/// Attaches a bus to `events` for each broker configured in `config` and
+/// spawns the tasks publishing to them.
+#[cfg_attr(
+    not(any(feature = "nats", feature = "redis")),
+    allow(unused_mut, unused_variables)
+)]
+pub fn start_event_buses(
+    config: &EventsConfig,
+    events: &EventBroadcaster,
+    shutdown: &CancellationToken,
+) -> Vec<(&'static str, JoinHandle<()>)> {
+    let mut tasks = Vec::new();
+
+    #[cfg(feature = "nats")]
+    if let Some(url) = config.nats_url.clone() {
+        let (bus, queue) = BrokerBus::new(format!("NATS at {}", url));
+        events.attach(Arc::new(bus));
+        tasks.push((
+            "NATS event bus",
+            tokio::spawn(run_nats_publisher(
+                url,
+                config.bus_subject.clone(),
+                queue,
+                shutdown.clone(),
+            )),
+        ));
+    }
+
+    #[cfg(feature = "redis")]
+    if let Some(url) = config.redis_url.clone() {
+        let (bus, queue) = BrokerBus::new(format!("Redis at {}", url));
+        events.attach(Arc::new(bus));
+        tasks.push((
+            "Redis event bus",
+            tokio::spawn(run_redis_publisher(
+                url,
+                config.bus_subject.clone(),
+                queue,
+                shutdown.clone(),
+            )),
+        ));
+    }
+
+    tasks
+}
+
+/// Publishes each event's JSON on `subject`. The client reconnects on its own
+/// once the first connection succeeds.
+#[cfg(feature = "nats")]
+async fn run_nats_publisher(
+    url: String,
+    subject: String,
+    mut queue: BusQueue,
+    shutdown: CancellationToken,
+) {
+    let client = loop {
+        match async_nats::connect(url.as_str()).await {
+            Ok(client) => break client,
+            Err(e) => queue.record_failure(e),
+        }
+        queue.drop_for(BUS_RECONNECT_DELAY, &shutdown).await;
+        if shutdown.is_cancelled() {
+            return;
+        }
+    };
+    tracing::info!("Publishing events to NATS at {} on {}", url, subject);
+
+    while let Some(payload) = queue.next(&shutdown).await {
+        match client
+            .publish(subject.clone(), payload.as_bytes().to_vec().into())
+            .await
+        {
+            Ok(()) => queue.record_published(),
+            Err(e) => queue.record_failure(e),
+        }
+    }
+
+    if let Err(e) = client.flush().await {
+        tracing::warn!("Failed to flush events to NATS at {}: {}", url, e);
+    }
+    tracing::info!("NATS event bus stopped");
+}
+
+/// Publishes each event's JSON to the `channel` pub/sub channel, reconnecting
+/// after a failed publish.
+#[cfg(feature = "redis")]
+async fn run_redis_publisher(
+    url: String,
+    channel: String,
+    mut queue: BusQueue,
+    shutdown: CancellationToken,
+) {
+    let client = match redis::Client::open(url.as_str()) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Invalid Redis URL {}: {}", url, e);
+            return;
+        }
+    };
+    tracing::info!("Publishing events to Redis at {} on {}", url, channel);
+
+    let mut connection = None;
+    while let Some(payload) = queue.next(&shutdown).await {
+        if connection.is_none() {
+            match client.get_multiplexed_async_connection().await {
+                Ok(new_connection) => connection = Some(new_connection),
+                Err(e) => {
+                    queue.record_failure(e);
+                    queue.drop_for(BUS_RECONNECT_DELAY, &shutdown).await;
+                    continue;
+                }
+            }
+        }
+        let Some(open) = connection.as_mut() else {
+            continue;
+        };
+
+        let published: redis::RedisResult<()> = redis::cmd("PUBLISH")
+            .arg(&channel)
+            .arg(payload.as_ref())
+            .query_async(open)
+            .await;
+        match published {
+            Ok(()) => queue.record_published(),
+            Err(e) => {
+                connection = None;
+                queue.record_failure(e);
+            }
+        }
+    }
+
+    tracing::info!("Redis event bus stopped");
+}
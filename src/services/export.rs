@@ -0,0 +1,247 @@
+use std::convert::Infallible;
+
+use axum::{
+    body::Body,
+    http::{
+        HeaderValue,
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use futures_util::stream;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::state::AppState,
+    error::AppError,
+    managers::{escrow::EscrowRecord, transaction::TransactionStore},
+    models::{
+        metrics::Achievement,
+        player::PlayerStats,
+        requests::ExportFormat,
+        transaction::{Transaction, TransactionStatus},
+    },
+};
+
+/// Transactions read from the session index per streamed chunk.
+const EXPORT_PAGE_SIZE: usize = 100;
+
+const TRANSACTION_CSV_HEADER: &str = "id,type,status,slot,compute_units,priority_fee_sol,\
+    created_at,created_slot,included_at,expires_at_slot,depends_on,winning_bid_sol,failure_reason,memo,tags\n";
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Json => "application/json",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Streams every transaction of the session, oldest first, as a download.
+/// Only one page of the history is held in memory at a time.
+pub fn export_transactions(
+    transactions: TransactionStore,
+    session_id: String,
+    format: ExportFormat,
+) -> Response {
+    let filename = export_filename("transactions", &session_id, format);
+
+    // `None` offset means the closing chunk has been sent
+    let chunks = stream::unfold(Some(0), move |offset| {
+        let transactions = transactions.clone();
+        let session_id = session_id.clone();
+        async move {
+            let offset = offset?;
+            let page = transactions.session_page(&session_id, offset, EXPORT_PAGE_SIZE);
+            if page.is_empty() {
+                let closing = match (format, offset) {
+                    (ExportFormat::Csv, 0) => TRANSACTION_CSV_HEADER.to_string(),
+                    (ExportFormat::Csv, _) => return None,
+                    (ExportFormat::Json, 0) => "[]".to_string(),
+                    (ExportFormat::Json, _) => "\n]".to_string(),
+                };
+                return Some((Ok::<_, Infallible>(closing), None));
+            }
+
+            let mut chunk = String::new();
+            for (index, transaction) in page.iter().enumerate() {
+                match format {
+                    ExportFormat::Csv => {
+                        if offset + index == 0 {
+                            chunk.push_str(TRANSACTION_CSV_HEADER);
+                        }
+                        chunk.push_str(&transaction_csv_row(transaction));
+                    }
+                    ExportFormat::Json => {
+                        chunk.push_str(if offset + index == 0 { "[\n" } else { ",\n" });
+                        chunk.push_str(&serde_json::to_string(transaction).unwrap_or_default());
+                    }
+                }
+            }
+            Some((Ok(chunk), Some(offset + page.len())))
+        }
+    });
+
+    download(format, &filename, Body::from_stream(chunks))
+}
+
+/// The player's stats as a download; CSV flattens them into a single row.
+pub fn export_stats(stats: &PlayerStats, format: ExportFormat) -> Response {
+    let filename = export_filename("stats", &stats.session_id, format);
+    let body = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(stats).unwrap_or_default(),
+        ExportFormat::Csv => {
+            let achievements: Vec<&str> = stats
+                .achievements
+                .iter()
+                .map(|achievement| achievement.name.as_str())
+                .collect();
+            let row = [
+                csv_field(&stats.display_name()),
+                stats.balance.to_string(),
+                stats.total_sol_spent.to_string(),
+                stats.total_bids_placed.to_string(),
+                stats.total_auctions_participated.to_string(),
+                stats.total_auctions_won.to_string(),
+                stats.jit_wins.to_string(),
+                stats.aot_wins.to_string(),
+                stats.level.to_string(),
+                stats.xp.to_string(),
+                stats.current_streak.to_string(),
+                stats.best_streak.to_string(),
+                csv_field(&achievements.join("; ")),
+            ];
+            format!(
+                "player,balance_sol,total_sol_spent,bids_placed,auctions_participated,\
+                 auctions_won,jit_wins,aot_wins,level,xp,current_streak,best_streak,achievements\n{}\n",
+                row.join(",")
+            )
+        }
+    };
+
+    download(format, &filename, Body::from(body))
+}
+
+/// Everything the simulator holds about one player, for moving it elsewhere
+/// or checking a balance against the bids and transactions behind it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PlayerDataExport {
+    pub session_id: String,
+    pub exported_at: DateTime<Utc>,
+    pub player_stats: PlayerStats,
+    pub achievements: Vec<Achievement>,
+    /// Escrow record of every bid still retained, oldest first, showing what
+    /// each locked and whether it was paid out, refunded or is still held.
+    /// Records of settled bids are pruned a while after their slot
+    pub bids: Vec<EscrowRecord>,
+    /// Oldest first
+    pub transactions: Vec<Transaction>,
+}
+
+/// The player's stats, achievements, bids and transactions as one JSON
+/// download.
+pub async fn export_player_data(
+    state: &AppState,
+    session_id: String,
+) -> Result<Response, AppError> {
+    let player_stats = state.player_stats(session_id.clone()).await?;
+    let transactions = state.transactions.session_transactions(&session_id);
+    let mut escrow = state.escrow_records(&transactions).await?;
+    let bids = transactions
+        .iter()
+        .filter_map(|transaction| escrow.remove(&transaction.id))
+        .collect();
+
+    let filename = export_filename("player", &session_id, ExportFormat::Json);
+    let export = PlayerDataExport {
+        session_id,
+        exported_at: Utc::now(),
+        achievements: player_stats.achievements.clone(),
+        player_stats,
+        bids,
+        transactions,
+    };
+    let body = serde_json::to_string_pretty(&export).unwrap_or_default();
+
+    Ok(download(ExportFormat::Json, &filename, Body::from(body)))
+}
+
+fn download(format: ExportFormat, filename: &str, body: Body) -> Response {
+    let mut response = body.into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    if let Ok(disposition) =
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", filename))
+    {
+        headers.insert(CONTENT_DISPOSITION, disposition);
+    }
+    response
+}
+
+fn export_filename(kind: &str, session_id: &str, format: ExportFormat) -> String {
+    format!(
+        "raiku-{}-{}-{}.{}",
+        kind,
+        session_id.chars().take(8).collect::<String>(),
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        format.extension()
+    )
+}
+
+fn transaction_csv_row(transaction: &Transaction) -> String {
+    let (winning_bid, failure_reason) = match &transaction.status {
+        TransactionStatus::AuctionWon { winning_bid, .. } => (winning_bid.to_string(), ""),
+        TransactionStatus::Failed { reason } => (String::new(), reason.as_str()),
+        _ => (String::new(), ""),
+    };
+    let optional = |value: Option<u64>| value.map(|value| value.to_string()).unwrap_or_default();
+    let timestamp =
+        |value: Option<DateTime<Utc>>| value.map(|value| value.to_rfc3339()).unwrap_or_default();
+
+    let row = [
+        transaction.id.clone(),
+        format!("{:?}", transaction.transaction_type()),
+        transaction.status.kind().as_str().to_string(),
+        transaction.target_slot().to_string(),
+        transaction.compute_units.to_string(),
+        transaction.priority_fee.to_string(),
+        transaction.created_at.to_rfc3339(),
+        transaction.created_slot.to_string(),
+        timestamp(transaction.included_at),
+        optional(transaction.expires_at_slot),
+        transaction.depends_on.clone().unwrap_or_default(),
+        winning_bid,
+        csv_field(failure_reason),
+        csv_field(transaction.memo.as_deref().unwrap_or_default()),
+        transaction.tags.join(" "),
+    ];
+    format!("{}\n", row.join(","))
+}
+
+/// Quotes a field containing a delimiter, quote or line break, and defuses
+/// values a spreadsheet would otherwise evaluate as a formula.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
@@ -0,0 +1,130 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::state::AppState,
+    error::AppError,
+    managers::transaction::TransactionFilter,
+    models::types::{Lamports, TransactionType},
+    services::estimate::{auction_for_slot, percentile},
+};
+
+/// Recent auctions whose bid counts and clearing prices set the baseline.
+const BASELINE_AUCTIONS: usize = 50;
+/// Expected competition at or above this multiple of the baseline is high.
+const HIGH_DEMAND_RATIO: f64 = 1.5;
+/// Expected competition at or below this multiple of the baseline is low.
+const LOW_DEMAND_RATIO: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DemandLevel {
+    /// Not enough history to compare against
+    Unknown,
+    Low,
+    Normal,
+    High,
+}
+
+/// Projected competition and clearing price for a future slot's auction.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DemandForecast {
+    pub slot_number: u64,
+    pub auction_type: TransactionType,
+    /// Slots between now and the target slot
+    pub distance: u64,
+    /// Bids already placed for the slot's auction
+    pub current_bids: usize,
+    pub current_highest_bid: Option<Lamports>,
+    /// Bids already placed plus those a slot usually draws over the remaining distance
+    pub expected_bids: Option<f64>,
+    /// Average bids per recent auction of the same type
+    pub typical_bids: Option<f64>,
+    pub expected_clearing_price: Option<Lamports>,
+    pub demand: DemandLevel,
+}
+
+/// Projects how contested the auction for `slot_number` will be from the bids
+/// slots have historically drawn at each remaining distance, and what it will
+/// clear at from recent auctions of the same type.
+pub async fn forecast_slot(
+    state: &AppState,
+    slot_number: u64,
+    max_lookahead: u64,
+) -> Result<DemandForecast, AppError> {
+    let (auction_type, distance) = auction_for_slot(state, slot_number, max_lookahead).await?;
+
+    let current: Vec<Lamports> = state
+        .transactions
+        .filtered(TransactionFilter {
+            transaction_type: Some(auction_type),
+            ..TransactionFilter::slot(slot_number)
+        })
+        .into_iter()
+        .filter(|transaction| transaction.depends_on.is_none())
+        .map(|transaction| transaction.priority_fee)
+        .collect();
+
+    // JIT bids arrive one slot ahead, AOT bids anywhere further out
+    let (min_distance, max_distance) = match auction_type {
+        TransactionType::Jit => (1, 1),
+        TransactionType::Aot => (2, distance),
+    };
+    let remaining = state
+        .demand
+        .call(move |demand| demand.expected_bids(auction_type, min_distance, max_distance))
+        .await?;
+    let expected_bids = remaining.map(|remaining| current.len() as f64 + remaining);
+
+    let (bid_counts, mut prices): (Vec<usize>, Vec<Lamports>) = state
+        .auction_history
+        .call(move |history| {
+            history
+                .iter()
+                .filter(|auction| auction.auction_type == auction_type)
+                .take(BASELINE_AUCTIONS)
+                .map(|auction| (auction.bid_count, auction.clearing_price))
+                .unzip()
+        })
+        .await?;
+    prices.sort();
+    let typical_bids = (!bid_counts.is_empty())
+        .then(|| bid_counts.iter().sum::<usize>() as f64 / bid_counts.len() as f64);
+
+    let demand = match (expected_bids, typical_bids) {
+        (Some(expected), Some(typical)) if typical > 0.0 => {
+            let ratio = expected / typical;
+            if ratio >= HIGH_DEMAND_RATIO {
+                DemandLevel::High
+            } else if ratio <= LOW_DEMAND_RATIO {
+                DemandLevel::Low
+            } else {
+                DemandLevel::Normal
+            }
+        }
+        _ => DemandLevel::Unknown,
+    };
+
+    // A hot auction is expected to clear near the top of recent prices
+    let projected = match demand {
+        DemandLevel::High => percentile(&prices, 90),
+        _ => percentile(&prices, 50),
+    };
+    let current_highest_bid = current.iter().max().copied();
+    let expected_clearing_price = match (projected, current_highest_bid) {
+        (Some(projected), Some(highest)) => Some(projected.max(highest)),
+        (projected, highest) => projected.or(highest),
+    };
+
+    Ok(DemandForecast {
+        slot_number,
+        auction_type,
+        distance,
+        current_bids: current.len(),
+        current_highest_bid,
+        expected_bids,
+        typical_bids,
+        expected_clearing_price,
+        demand,
+    })
+}
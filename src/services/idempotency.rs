@@ -0,0 +1,154 @@
+use std::future::Future;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{HeaderMap, HeaderValue, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use chrono::Duration;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    app::state::AppState,
+    error::AppError,
+    managers::idempotency::{
+        IdempotencyClaim, IdempotencyManager, RequestFingerprint, StoredResponse,
+    },
+    utils::validation::FieldError,
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Releases a claimed key if the request is dropped before it finishes, e.g.
+/// when the client disconnects, so a retry is not locked out for the TTL.
+struct ClaimGuard<'a> {
+    idempotency: &'a IdempotencyManager,
+    session_id: &'a str,
+    key: &'a str,
+    settled: bool,
+}
+
+impl Drop for ClaimGuard<'_> {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.idempotency.release_detached(self.session_id, self.key);
+        }
+    }
+}
+
+/// Runs `submit` at most once per session and `Idempotency-Key` header within
+/// `ttl`; retries with the same `fingerprint` get the recorded response back,
+/// and retries with a different one are rejected. Requests without the header
+/// run unconditionally.
+pub async fn run_idempotent<F>(
+    state: &AppState,
+    session_id: &str,
+    headers: &HeaderMap,
+    ttl: Duration,
+    fingerprint: RequestFingerprint,
+    submit: F,
+) -> Result<Response, AppError>
+where
+    F: Future<Output = Result<Response, AppError>>,
+{
+    let Some(key) = idempotency_key(headers)? else {
+        return submit.await;
+    };
+
+    match state
+        .idempotency
+        .claim(session_id, &key, fingerprint, ttl)
+        .await
+    {
+        IdempotencyClaim::Claimed => {}
+        IdempotencyClaim::InFlight => return Err(AppError::IdempotencyKeyInUse),
+        IdempotencyClaim::Mismatch => return Err(AppError::IdempotencyKeyReused),
+        IdempotencyClaim::Replay(stored) => {
+            tracing::info!(
+                "Replaying response for idempotency key {} from {}",
+                key,
+                session_id.chars().take(8).collect::<String>()
+            );
+            return Ok(replay(stored));
+        }
+    }
+
+    let mut guard = ClaimGuard {
+        idempotency: &state.idempotency,
+        session_id,
+        key: &key,
+        settled: false,
+    };
+    let response = submit.await.unwrap_or_else(IntoResponse::into_response);
+    guard.settled = true;
+
+    // Throttling and server errors are transient, so the key stays reusable
+    let status = response.status();
+    if status.is_server_error() || status.as_u16() == 429 {
+        state.idempotency.release(session_id, &key).await;
+        return Ok(response);
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            state.idempotency.release(session_id, &key).await;
+            return Err(anyhow::anyhow!("Failed to buffer response: {}", e).into());
+        }
+    };
+
+    state
+        .idempotency
+        .complete(
+            session_id,
+            &key,
+            fingerprint,
+            StoredResponse {
+                status,
+                body: body.clone(),
+            },
+            ttl,
+        )
+        .await;
+
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, AppError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => {
+            Ok(Some(key.to_string()))
+        }
+        _ => Err(AppError::Validation(vec![FieldError::new(
+            "Idempotency-Key",
+            format!(
+                "must be 1 to {} visible ASCII characters",
+                MAX_IDEMPOTENCY_KEY_LEN
+            ),
+        )])),
+    }
+}
+
+/// Hashes a request body so a reused key can be told apart from a retry.
+pub fn request_fingerprint<R: Serialize>(request: &R) -> Result<RequestFingerprint, AppError> {
+    let body = serde_json::to_vec(request)
+        .map_err(|e| anyhow::anyhow!("Failed to fingerprint request: {}", e))?;
+    Ok(Sha256::digest(body).into())
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = (stored.status, stored.body).into_response();
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
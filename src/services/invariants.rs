@@ -0,0 +1,152 @@
+use serde::Serialize;
+
+use crate::{
+    app::state::AppState,
+    error::AppError,
+    managers::{
+        invariants::{Invariant, InvariantViolation},
+        transaction::TransactionFilter,
+    },
+    models::{
+        auction::ResolvedAuction,
+        slot::Slot,
+        transaction::{Transaction, TransactionStatus},
+        types::TransactionType,
+    },
+    services::ledger::{LedgerReport, audit_ledger},
+};
+
+/// An auction resolved by the tick being checked.
+pub struct TickWinner {
+    pub slot_number: u64,
+    pub auction_type: TransactionType,
+    pub winner: String,
+}
+
+/// Everything needed to debug a violation, logged as one JSON document.
+#[derive(Serialize)]
+struct InvariantDump {
+    current_slot: u64,
+    violations: Vec<InvariantViolation>,
+    ledger: LedgerReport,
+    slots: Vec<Slot>,
+    auctions: Vec<ResolvedAuction>,
+    winner_transactions: Vec<Transaction>,
+}
+
+/// Checks the state a tick left behind: no slot in the window moved back,
+/// the ledger balances, and every winner it picked holds a transaction for
+/// the slot won.
+pub async fn check_invariants(
+    state: &AppState,
+    current_slot: u64,
+    winners: &[TickWinner],
+) -> Result<Vec<InvariantViolation>, AppError> {
+    let slots = state.get_slots(0, u64::MAX).await?;
+    let mut violations = state
+        .invariants
+        .call(move |invariants| invariants.observe_slots(&slots))
+        .await?;
+
+    let ledger = audit_ledger(state).await?;
+    if !ledger.balanced {
+        violations.push(InvariantViolation {
+            invariant: Invariant::LedgerImbalance,
+            slot_number: None,
+            detail: format!(
+                "Accounted {} SOL against {} SOL issued after slot {}",
+                ledger.accounted(),
+                ledger.issued,
+                current_slot
+            ),
+        });
+    }
+
+    for winner in winners {
+        if winning_transaction(state, winner).is_none() {
+            violations.push(InvariantViolation {
+                invariant: Invariant::WinnerWithoutTransaction,
+                slot_number: Some(winner.slot_number),
+                detail: format!(
+                    "{:?} auction for slot {} was won by {} with no matching transaction",
+                    winner.auction_type, winner.slot_number, winner.winner
+                ),
+            });
+        }
+    }
+
+    Ok(violations)
+}
+
+/// Runs `check_invariants` and, if any fails, logs a full state dump and
+/// panics, so a broken resolution pipeline stops at the tick that broke it.
+/// Meant for debug builds, where every tick is checked.
+pub async fn enforce_invariants(
+    state: &AppState,
+    current_slot: u64,
+    winners: &[TickWinner],
+) -> Result<(), AppError> {
+    let violations = check_invariants(state, current_slot, winners).await?;
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let count = violations.len();
+    let slot_numbers: Vec<u64> = violations.iter().filter_map(|v| v.slot_number).collect();
+    let mut winner_transactions = Vec::new();
+    for winner in winners {
+        winner_transactions.extend(
+            state
+                .transactions
+                .filtered(TransactionFilter::slot(winner.slot_number))
+                .into_iter()
+                .filter(|transaction| transaction.sender == winner.winner),
+        );
+    }
+
+    let dump = InvariantDump {
+        current_slot,
+        ledger: audit_ledger(state).await?,
+        slots: state
+            .get_slots(0, u64::MAX)
+            .await?
+            .into_iter()
+            .filter(|slot| slot_numbers.contains(&slot.slot_number))
+            .collect(),
+        auctions: state
+            .auction_history
+            .call(move |history| history.range(current_slot.saturating_sub(1), u64::MAX))
+            .await?,
+        winner_transactions,
+        violations,
+    };
+    let dump = serde_json::to_string_pretty(&dump)
+        .unwrap_or_else(|e| format!("State dump failed to serialize: {}", e));
+
+    tracing::error!(
+        alert = true,
+        "Invariant violated after slot {}:\n{}",
+        current_slot,
+        dump
+    );
+    panic!(
+        "{} invariant violation(s) after slot {}; see the state dump logged above",
+        count, current_slot
+    );
+}
+
+fn winning_transaction(state: &AppState, winner: &TickWinner) -> Option<Transaction> {
+    state
+        .transactions
+        .filtered(TransactionFilter::slot(winner.slot_number))
+        .into_iter()
+        .find(|transaction| {
+            transaction.sender == winner.winner
+                && transaction.transaction_type() == winner.auction_type
+                && matches!(
+                    transaction.status,
+                    TransactionStatus::AuctionWon { slot, .. } | TransactionStatus::Included { slot, .. }
+                        if slot == winner.slot_number
+                )
+        })
+}
@@ -0,0 +1,130 @@
+//! JSON Schema documents for the API's models, built from the same schemas as
+//! the OpenAPI spec. OpenAPI 3.1 schema objects are JSON Schema 2020-12, so
+//! only references and the OpenAPI-only `example` keyword need rewriting.
+
+use std::collections::BTreeSet;
+
+use serde_json::{Map, Value, json};
+use utoipa::openapi::OpenApi;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+const OPENAPI_REF_PREFIX: &str = "#/components/schemas/";
+const DEFS_REF_PREFIX: &str = "#/$defs/";
+/// Keywords whose values map names to schemas rather than being schemas
+const SCHEMA_MAP_KEYWORDS: [&str; 3] = ["properties", "$defs", "patternProperties"];
+
+pub struct JsonSchemas {
+    defs: Map<String, Value>,
+}
+
+impl JsonSchemas {
+    pub fn from_openapi(spec: &OpenApi) -> anyhow::Result<Self> {
+        let mut defs = Map::new();
+        if let Some(components) = &spec.components {
+            for (name, schema) in &components.schemas {
+                let mut schema = serde_json::to_value(schema)
+                    .map_err(|e| anyhow::anyhow!("Failed to serialize schema {}: {}", name, e))?;
+                to_json_schema(&mut schema, false);
+                defs.insert(name.clone(), schema);
+            }
+        }
+        Ok(Self { defs })
+    }
+
+    /// Every model in one document, each under `$defs`.
+    pub fn bundle(&self) -> Value {
+        json!({
+            "$schema": DRAFT,
+            "$id": "/docs/schemas",
+            "title": "Raiku Simulator API models",
+            "$defs": self.defs,
+        })
+    }
+
+    /// A standalone document for one model, carrying only the definitions it
+    /// references.
+    pub fn document(&self, name: &str) -> Option<Value> {
+        self.defs.get(name)?;
+
+        let mut included = BTreeSet::new();
+        let mut pending = vec![name.to_string()];
+        while let Some(next) = pending.pop() {
+            if !included.insert(next.clone()) {
+                continue;
+            }
+            if let Some(schema) = self.defs.get(&next) {
+                collect_refs(schema, &mut pending);
+            }
+        }
+
+        let defs: Map<String, Value> = included
+            .into_iter()
+            .filter_map(|name| {
+                let schema = self.defs.get(&name)?.clone();
+                Some((name, schema))
+            })
+            .collect();
+        Some(json!({
+            "$schema": DRAFT,
+            "$id": format!("/docs/schemas/{}", name),
+            "title": name,
+            "$ref": format!("{}{}", DEFS_REF_PREFIX, name),
+            "$defs": defs,
+        }))
+    }
+}
+
+/// Points references at `$defs` and turns `example` into `examples`. Inside
+/// a name-to-schema map the keys are names, so only the values are visited.
+fn to_json_schema(value: &mut Value, is_schema_map: bool) {
+    match value {
+        Value::Object(object) => {
+            if !is_schema_map {
+                if let Some(Value::String(reference)) = object.get_mut("$ref") {
+                    if let Some(name) = reference.strip_prefix(OPENAPI_REF_PREFIX) {
+                        *reference = format!("{}{}", DEFS_REF_PREFIX, name);
+                    }
+                }
+                if let Some(example) = object.remove("example") {
+                    object.insert("examples".to_string(), Value::Array(vec![example]));
+                }
+            }
+            for (key, child) in object.iter_mut() {
+                let child_is_map = !is_schema_map && SCHEMA_MAP_KEYWORDS.contains(&key.as_str());
+                if !is_schema_map && (key == "examples" || key == "default" || key == "const") {
+                    continue;
+                }
+                to_json_schema(child, child_is_map);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                to_json_schema(item, false);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_refs(value: &Value, names: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object {
+                match child {
+                    Value::String(reference) if key == "$ref" => {
+                        if let Some(name) = reference.strip_prefix(DEFS_REF_PREFIX) {
+                            names.push(name.to_string());
+                        }
+                    }
+                    _ => collect_refs(child, names),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_refs(item, names);
+            }
+        }
+        _ => {}
+    }
+}
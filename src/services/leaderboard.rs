@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{app::room::RoomManager, error::AppError};
+
+const GLOBAL_LEADERBOARD_SIZE: usize = 10;
+/// Phantom average players mixed into every room, so topping a room of two
+/// counts for less than topping a room of twenty.
+const ROOM_SIZE_PRIOR: f64 = 4.0;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GlobalLeaderboardEntry {
+    pub rank: u32,
+    pub session_id: String,
+    pub display_name: String,
+    /// Room the score was earned in; a player in several rooms is ranked by their best
+    pub room_id: String,
+    pub room_name: String,
+    /// Players who have bid in the room this season
+    pub room_players: usize,
+    pub wins: u32,
+    pub win_rate: f64,
+    pub level: u32,
+    /// Wins relative to the room's average this season, damped for small rooms;
+    /// 1.0 is an average player in a large room
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GlobalLeaderboard {
+    pub entries: Vec<GlobalLeaderboardEntry>,
+    pub rooms: usize,
+    pub last_updated: DateTime<Utc>,
+}
+
+struct RoomPlayer {
+    session_id: String,
+    display_name: String,
+    wins: u32,
+    win_rate: f64,
+    level: u32,
+}
+
+/// Ranks players across every room. A room's season is everything since it was
+/// created or last reset, so comparing each player's wins with their room's
+/// average cancels out both how long the season has run and how many auctions
+/// the room's players split between them.
+pub async fn global_leaderboard(rooms: &RoomManager) -> Result<GlobalLeaderboard, AppError> {
+    let rooms = rooms.list();
+    let mut entries: Vec<GlobalLeaderboardEntry> = Vec::new();
+
+    for room in &rooms {
+        let players: Vec<RoomPlayer> = room
+            .state
+            .game
+            .call(|game| {
+                game.player_stats
+                    .values()
+                    .filter(|stats| stats.is_ranked() && stats.total_bids_placed > 0)
+                    .map(|stats| RoomPlayer {
+                        session_id: stats.session_id.clone(),
+                        display_name: stats.display_name(),
+                        wins: stats.total_auctions_won,
+                        win_rate: stats.win_rate(),
+                        level: stats.level,
+                    })
+                    .collect()
+            })
+            .await?;
+
+        let total_wins: u32 = players.iter().map(|player| player.wins).sum();
+        if total_wins == 0 {
+            continue;
+        }
+        let room_players = players.len();
+        let average_wins = f64::from(total_wins) / room_players as f64;
+        let damping = room_players as f64 / (room_players as f64 + ROOM_SIZE_PRIOR);
+
+        for player in players {
+            let score = f64::from(player.wins) / average_wins * damping;
+            match entries
+                .iter_mut()
+                .find(|entry| entry.session_id == player.session_id)
+            {
+                Some(entry) if entry.score >= score => {}
+                existing => {
+                    let entry = GlobalLeaderboardEntry {
+                        rank: 0,
+                        session_id: player.session_id,
+                        display_name: player.display_name,
+                        room_id: room.id.clone(),
+                        room_name: room.name.clone(),
+                        room_players,
+                        wins: player.wins,
+                        win_rate: player.win_rate,
+                        level: player.level,
+                        score,
+                    };
+                    match existing {
+                        Some(existing) => *existing = entry,
+                        None => entries.push(entry),
+                    }
+                }
+            }
+        }
+    }
+
+    // Ties are broken by session so the order is stable between requests
+    entries.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.session_id.cmp(&b.session_id))
+    });
+    entries.truncate(GLOBAL_LEADERBOARD_SIZE);
+    for (index, entry) in entries.iter_mut().enumerate() {
+        entry.rank = index as u32 + 1;
+    }
+
+    Ok(GlobalLeaderboard {
+        entries,
+        rooms: rooms.len(),
+        last_updated: Utc::now(),
+    })
+}
@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::time::interval;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+use crate::{
+    app::state::AppState,
+    error::AppError,
+    models::{
+        event::AppEvent,
+        types::{LAMPORTS_PER_SOL, Lamports},
+    },
+};
+
+const LEDGER_AUDIT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Snapshot of where every issued lamport currently sits.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LedgerReport {
+    pub issued: Lamports,
+    pub player_balances: Lamports,
+    pub escrow_held: Lamports,
+    pub fees_burned: Lamports,
+    pub retired: Lamports,
+    /// Accounted minus issued; positive means SOL was created out of thin air
+    pub discrepancy_sol: f64,
+    pub balanced: bool,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl LedgerReport {
+    pub fn accounted(&self) -> Lamports {
+        self.player_balances + self.escrow_held + self.fees_burned + self.retired
+    }
+}
+
+/// Checks that balances, open escrow, burned fees and retired SOL add up to
+/// everything issued. The snapshot is taken under the ledger lock, which every
+/// bid and refund holds while moving funds, so none is counted twice or missed.
+pub async fn audit_ledger(state: &AppState) -> Result<LedgerReport, AppError> {
+    let _ledger = state.ledger.write().await;
+    let (escrow_held, fees_burned) = state
+        .escrow
+        .call_all(|escrow| (escrow.total_held(), escrow.total_released()))
+        .await?
+        .into_iter()
+        .fold(
+            (Lamports::ZERO, Lamports::ZERO),
+            |(held, released), (shard_held, shard_released)| {
+                (held + shard_held, released + shard_released)
+            },
+        );
+    let (issued, player_balances, retired) = state
+        .game
+        .call(|game| (game.total_issued, game.total_balances(), game.total_retired))
+        .await?;
+
+    let mut report = LedgerReport {
+        issued,
+        player_balances,
+        escrow_held,
+        fees_burned,
+        retired,
+        discrepancy_sol: 0.0,
+        balanced: true,
+        checked_at: Utc::now(),
+    };
+
+    let discrepancy = i128::from(report.accounted().0) - i128::from(report.issued.0);
+    report.discrepancy_sol = discrepancy as f64 / LAMPORTS_PER_SOL as f64;
+    report.balanced = discrepancy == 0;
+    Ok(report)
+}
+
+/// Audits the ledger periodically until `shutdown` is cancelled, alerting each
+/// time the discrepancy changes so a single bug is not reported every interval.
+pub async fn run_ledger_audit(state: AppState, shutdown: CancellationToken) {
+    let mut interval = interval(LEDGER_AUDIT_INTERVAL);
+    let mut last_discrepancy = 0.0;
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        let report = match audit_ledger(&state).await {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::error!("Ledger audit failed: {}", e);
+                continue;
+            }
+        };
+        if report.discrepancy_sol == last_discrepancy {
+            continue;
+        }
+        last_discrepancy = report.discrepancy_sol;
+
+        if report.balanced {
+            tracing::info!("Ledger balanced again at {} SOL issued", report.issued);
+            continue;
+        }
+
+        tracing::error!(
+            alert = true,
+            issued = %report.issued,
+            player_balances = %report.player_balances,
+            escrow_held = %report.escrow_held,
+            fees_burned = %report.fees_burned,
+            retired = %report.retired,
+            "Ledger imbalance of {} SOL",
+            report.discrepancy_sol
+        );
+        state.events.broadcast(AppEvent::LedgerImbalance {
+            issued: report.issued,
+            accounted: report.accounted(),
+            discrepancy_sol: report.discrepancy_sol,
+        });
+    }
+
+    tracing::info!("Ledger audit stopped");
+}
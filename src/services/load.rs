@@ -0,0 +1,505 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::Serialize;
+use tokio::time::Instant;
+
+use crate::{
+    client::{ClientError, RaikuClient},
+    models::{
+        event::AppEvent,
+        requests::{AotBidRequest, JitBidRequest},
+        types::Lamports,
+    },
+};
+
+/// Compute units every load bid asks for.
+const LOAD_COMPUTE_UNITS: u64 = 200_000;
+/// Bid amounts are drawn between these, in lamports
+const MIN_BID: Lamports = Lamports(1_000_000);
+const MAX_BID: Lamports = Lamports(50_000_000);
+/// Share of bids that reserve a future slot instead of the next one
+const AOT_BID_SHARE: f64 = 0.3;
+/// Furthest ahead an AOT bid reaches, kept well inside the default lookahead
+const MAX_AOT_DISTANCE: u64 = 20;
+/// Pause before retrying a rate-limited session request
+const SESSION_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+pub struct LoadSettings {
+    /// Root of the instance under load, e.g. `http://localhost:8080`
+    pub base_url: String,
+    pub sessions: usize,
+    pub duration: Duration,
+    /// Average pause between one session's bids; each pause is jittered by half
+    pub bid_interval: Duration,
+    /// How many of the sessions also hold an event stream open
+    pub streams: usize,
+    /// Session starts are spread evenly over this long
+    pub ramp_up: Duration,
+    /// Seeds bid timing and amounts; a random seed is picked and reported if unset
+    pub seed: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    CreateSession,
+    JitBid,
+    AotBid,
+}
+
+impl Operation {
+    const ALL: [Operation; 3] = [Self::CreateSession, Self::JitBid, Self::AotBid];
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::CreateSession => "session",
+            Self::JitBid => "jit_bid",
+            Self::AotBid => "aot_bid",
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LatencySummary {
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencySummary {
+    /// Nearest-rank percentiles; `None` without samples.
+    fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let at = |percent: usize| {
+            let rank = (samples.len() * percent).div_ceil(100).max(1);
+            samples[rank - 1].as_secs_f64() * 1000.0
+        };
+
+        Some(Self {
+            p50_ms: at(50),
+            p90_ms: at(90),
+            p99_ms: at(99),
+            max_ms: at(100),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OperationSummary {
+    pub operation: Operation,
+    pub requests: u64,
+    pub succeeded: u64,
+    /// Refused by the marketplace, e.g. a bid that was too low
+    pub rejected: u64,
+    /// Rejections by `error_code`
+    pub rejections: BTreeMap<String, u64>,
+    pub rate_limited: u64,
+    /// Server errors, timeouts and broken connections
+    pub failed: u64,
+    pub latency: Option<LatencySummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreamSummary {
+    pub opened: u64,
+    pub failed: u64,
+    /// Streams the server closed before the run ended
+    pub dropped: u64,
+    pub events: u64,
+    /// From opening the stream to its first event
+    pub first_event: Option<LatencySummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoadReport {
+    pub base_url: String,
+    pub seed: u64,
+    pub sessions_requested: usize,
+    pub sessions_created: usize,
+    pub elapsed_ms: u64,
+    pub requests_per_second: f64,
+    pub operations: Vec<OperationSummary>,
+    pub streams: StreamSummary,
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Ran {} of {} sessions against {} for {} ms ({:.0} requests/s, seed {})",
+            self.sessions_created,
+            self.sessions_requested,
+            self.base_url,
+            self.elapsed_ms,
+            self.requests_per_second,
+            self.seed
+        )?;
+        writeln!(
+            f,
+            "{:<10} {:>8} {:>8} {:>9} {:>8} {:>7} {:>9} {:>9} {:>9} {:>9}",
+            "operation",
+            "requests",
+            "ok",
+            "rejected",
+            "limited",
+            "failed",
+            "p50 (ms)",
+            "p90 (ms)",
+            "p99 (ms)",
+            "max (ms)"
+        )?;
+        for summary in &self.operations {
+            write!(
+                f,
+                "{:<10} {:>8} {:>8} {:>9} {:>8} {:>7}",
+                summary.operation.to_string(),
+                summary.requests,
+                summary.succeeded,
+                summary.rejected,
+                summary.rate_limited,
+                summary.failed
+            )?;
+            match &summary.latency {
+                Some(latency) => writeln!(
+                    f,
+                    " {:>9.1} {:>9.1} {:>9.1} {:>9.1}",
+                    latency.p50_ms, latency.p90_ms, latency.p99_ms, latency.max_ms
+                )?,
+                None => writeln!(f, " {:>9} {:>9} {:>9} {:>9}", "-", "-", "-", "-")?,
+            }
+        }
+        for summary in &self.operations {
+            if summary.rejections.is_empty() {
+                continue;
+            }
+            let reasons: Vec<String> = summary
+                .rejections
+                .iter()
+                .map(|(reason, count)| format!("{} {}", reason, count))
+                .collect();
+            writeln!(
+                f,
+                "{} rejections: {}",
+                summary.operation,
+                reasons.join(", ")
+            )?;
+        }
+        write!(
+            f,
+            "Event streams: {} opened, {} failed, {} dropped, {} events",
+            self.streams.opened, self.streams.failed, self.streams.dropped, self.streams.events
+        )?;
+        match &self.streams.first_event {
+            Some(latency) => writeln!(
+                f,
+                ", first event p50 {:.1} / p99 {:.1} ms",
+                latency.p50_ms, latency.p99_ms
+            ),
+            None => writeln!(f),
+        }
+    }
+}
+
+#[derive(Default)]
+struct OperationSamples {
+    latencies: Vec<Duration>,
+    succeeded: u64,
+    rejections: BTreeMap<String, u64>,
+    rate_limited: u64,
+    failed: u64,
+}
+
+impl OperationSamples {
+    fn record<T>(&mut self, started: Instant, result: &Result<T, ClientError>) {
+        self.latencies.push(started.elapsed());
+        match result {
+            Ok(_) => self.succeeded += 1,
+            Err(ClientError::Api { status: 429, .. }) => self.rate_limited += 1,
+            Err(ClientError::Api {
+                status, error_code, ..
+            }) if *status < 500 => {
+                let reason = error_code.clone().unwrap_or_else(|| status.to_string());
+                *self.rejections.entry(reason).or_default() += 1;
+            }
+            Err(_) => self.failed += 1,
+        }
+    }
+
+    fn merge(&mut self, other: OperationSamples) {
+        self.latencies.extend(other.latencies);
+        self.succeeded += other.succeeded;
+        for (reason, count) in other.rejections {
+            *self.rejections.entry(reason).or_default() += count;
+        }
+        self.rate_limited += other.rate_limited;
+        self.failed += other.failed;
+    }
+}
+
+#[derive(Default)]
+struct StreamOutcome {
+    opened: bool,
+    dropped: bool,
+    events: u64,
+    first_event: Option<Duration>,
+}
+
+#[derive(Default)]
+struct SessionOutcome {
+    created: bool,
+    operations: HashMap<Operation, OperationSamples>,
+    stream: Option<StreamOutcome>,
+}
+
+/// Puts a running instance under load: every session bids at jittered
+/// intervals, mixing JIT and AOT bids, and the first `settings.streams` of
+/// them hold an event stream open for the whole run. Rate limits apply per
+/// client IP, so raise them on the instance to measure anything else.
+pub async fn run_load(settings: LoadSettings) -> anyhow::Result<LoadReport> {
+    if settings.sessions == 0 {
+        anyhow::bail!("A load run needs at least one session");
+    }
+    if settings.bid_interval.is_zero() {
+        anyhow::bail!("The bid interval must be positive");
+    }
+
+    let seed = settings.seed.unwrap_or_else(|| rand::rng().random());
+    let settings = Arc::new(settings);
+    // Highest slot seen in receipts or events, so AOT bids land ahead of it
+    let latest_slot = Arc::new(AtomicU64::new(0));
+    let started = Instant::now();
+    let deadline = started + settings.ramp_up + settings.duration;
+
+    // Sessions are cloned from one client so they share its connection pool
+    let client = RaikuClient::new(settings.base_url.clone());
+    let tasks: Vec<_> = (0..settings.sessions)
+        .map(|index| {
+            let client = client.clone();
+            let settings = settings.clone();
+            let latest_slot = latest_slot.clone();
+            let rng = StdRng::seed_from_u64(seed.wrapping_add(index as u64));
+            tokio::spawn(run_session(
+                index,
+                client,
+                settings,
+                latest_slot,
+                deadline,
+                rng,
+            ))
+        })
+        .collect();
+
+    let mut operations: HashMap<Operation, OperationSamples> = HashMap::new();
+    let mut sessions_created = 0;
+    let mut streams = Vec::new();
+    for task in tasks {
+        let outcome = task
+            .await
+            .map_err(|e| anyhow::anyhow!("Load session task failed: {}", e))?;
+        if outcome.created {
+            sessions_created += 1;
+        }
+        for (operation, samples) in outcome.operations {
+            operations.entry(operation).or_default().merge(samples);
+        }
+        streams.extend(outcome.stream);
+    }
+    let elapsed = started.elapsed();
+
+    let requests: u64 = operations
+        .values()
+        .map(|samples| samples.latencies.len() as u64)
+        .sum();
+    let operations = Operation::ALL
+        .into_iter()
+        .map(|operation| {
+            let samples = operations.remove(&operation).unwrap_or_default();
+            OperationSummary {
+                operation,
+                requests: samples.latencies.len() as u64,
+                succeeded: samples.succeeded,
+                rejected: samples.rejections.values().sum(),
+                rejections: samples.rejections,
+                rate_limited: samples.rate_limited,
+                failed: samples.failed,
+                latency: LatencySummary::from_samples(samples.latencies),
+            }
+        })
+        .collect();
+    let streams = StreamSummary {
+        opened: streams.iter().filter(|stream| stream.opened).count() as u64,
+        failed: streams.iter().filter(|stream| !stream.opened).count() as u64,
+        dropped: streams.iter().filter(|stream| stream.dropped).count() as u64,
+        events: streams.iter().map(|stream| stream.events).sum(),
+        first_event: LatencySummary::from_samples(
+            streams
+                .iter()
+                .filter_map(|stream| stream.first_event)
+                .collect(),
+        ),
+    };
+
+    Ok(LoadReport {
+        base_url: settings.base_url.clone(),
+        seed,
+        sessions_requested: settings.sessions,
+        sessions_created,
+        elapsed_ms: elapsed.as_millis() as u64,
+        requests_per_second: requests as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        operations,
+        streams,
+    })
+}
+
+async fn run_session(
+    index: usize,
+    mut client: RaikuClient,
+    settings: Arc<LoadSettings>,
+    latest_slot: Arc<AtomicU64>,
+    deadline: Instant,
+    mut rng: StdRng,
+) -> SessionOutcome {
+    let mut outcome = SessionOutcome::default();
+    let ramp_delay = settings
+        .ramp_up
+        .mul_f64(index as f64 / settings.sessions as f64);
+    tokio::time::sleep(ramp_delay).await;
+
+    while Instant::now() < deadline {
+        let started = Instant::now();
+        let result = client.create_session().await;
+        outcome
+            .operations
+            .entry(Operation::CreateSession)
+            .or_default()
+            .record(started, &result);
+        match result {
+            Ok(_) => {
+                outcome.created = true;
+                break;
+            }
+            Err(ClientError::Api { status: 429, .. }) => {
+                tokio::time::sleep(SESSION_RETRY_DELAY).await;
+            }
+            Err(_) => return outcome,
+        }
+    }
+    if !outcome.created {
+        return outcome;
+    }
+
+    let stream = (index < settings.streams)
+        .then(|| tokio::spawn(hold_stream(client.clone(), latest_slot.clone(), deadline)));
+
+    loop {
+        let interval = settings.bid_interval;
+        let pause = rng.random_range(interval / 2..=interval + interval / 2);
+        let next_bid = Instant::now() + pause;
+        if next_bid >= deadline {
+            break;
+        }
+        tokio::time::sleep_until(next_bid).await;
+
+        let bid_amount = Lamports(rng.random_range(MIN_BID.0..=MAX_BID.0));
+        let current_slot = latest_slot.load(Ordering::Relaxed);
+        let started = Instant::now();
+
+        if current_slot > 0 && rng.random_bool(AOT_BID_SHARE) {
+            let bid = AotBidRequest {
+                session_id: None,
+                slot_number: current_slot + rng.random_range(2..=MAX_AOT_DISTANCE),
+                bid_amount,
+                compute_units: LOAD_COMPUTE_UNITS,
+                data: "load".to_string(),
+                depends_on: None,
+                memo: None,
+                tags: Vec::new(),
+                nonce: None,
+            };
+            let result = client.submit_aot_bid(&bid).await;
+            outcome
+                .operations
+                .entry(Operation::AotBid)
+                .or_default()
+                .record(started, &result);
+        } else {
+            let bid = JitBidRequest {
+                session_id: None,
+                bid_amount,
+                compute_units: LOAD_COMPUTE_UNITS,
+                data: "load".to_string(),
+                depends_on: None,
+                memo: None,
+                tags: Vec::new(),
+                nonce: None,
+                rebid: None,
+            };
+            let result = client.submit_jit_bid(&bid).await;
+            outcome
+                .operations
+                .entry(Operation::JitBid)
+                .or_default()
+                .record(started, &result);
+            if let Ok(receipt) = result {
+                // The receipt names the next slot, so the current one is just before it
+                latest_slot.fetch_max(receipt.slot_number.saturating_sub(1), Ordering::Relaxed);
+            }
+        }
+    }
+
+    if let Some(stream) = stream {
+        outcome.stream = Some(stream.await.unwrap_or_default());
+    }
+    outcome
+}
+
+/// Reads the session's event stream until the run ends or the server closes it.
+async fn hold_stream(
+    client: RaikuClient,
+    latest_slot: Arc<AtomicU64>,
+    deadline: Instant,
+) -> StreamOutcome {
+    let mut outcome = StreamOutcome::default();
+    let started = Instant::now();
+    let Ok(Ok(events)) = tokio::time::timeout_at(deadline, client.events()).await else {
+        return outcome;
+    };
+    outcome.opened = true;
+
+    let mut events = Box::pin(events);
+    loop {
+        match tokio::time::timeout_at(deadline, events.next()).await {
+            Err(_) => break,
+            // An event this build doesn't know is skipped, not a broken stream
+            Ok(Some(Err(ClientError::Decode(_)))) => outcome.events += 1,
+            Ok(None) | Ok(Some(Err(_))) => {
+                outcome.dropped = true;
+                break;
+            }
+            Ok(Some(Ok(event))) => {
+                outcome.events += 1;
+                outcome.first_event.get_or_insert_with(|| started.elapsed());
+                if let AppEvent::SlotAdvanced { current_slot } = event {
+                    latest_slot.fetch_max(current_slot, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+    outcome
+}
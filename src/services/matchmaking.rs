@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    app::api::AppContext,
+    error::AppError,
+    managers::matchmaking::QueuedPlayer,
+    models::{
+        event::AppEvent,
+        responses::{MatchFound, QueueTicket},
+    },
+    services::room::create_match_room,
+};
+
+/// Countdown between a match being found and bidding opening in its room, so
+/// every player has time to switch over.
+const MATCH_START_DELAY_SECS: i64 = 10;
+
+pub enum QueueOutcome {
+    Queued(QueueTicket),
+    Matched(MatchFound),
+}
+
+/// Queues the session for a match of `match_size` players. The player that
+/// completes a group starts the match for all of them.
+pub fn enqueue(
+    context: &AppContext,
+    session_id: &str,
+    match_size: usize,
+) -> Result<QueueOutcome, AppError> {
+    let matched = {
+        let mut queue = context.rooms.matchmaking();
+        if queue.get(session_id).is_some() {
+            return Err(AppError::AlreadyQueued);
+        }
+        queue.join(QueuedPlayer {
+            session_id: session_id.to_string(),
+            match_size,
+            joined_at: Utc::now(),
+        })
+    };
+
+    let Some(players) = matched else {
+        return queue_ticket(context, session_id)
+            .map(QueueOutcome::Queued)
+            .ok_or(AppError::NotQueued);
+    };
+
+    match start_match(context, &players) {
+        Ok(found) => Ok(QueueOutcome::Matched(found)),
+        Err(e) => {
+            // Keep the group's places so the next attempt can match them
+            context.rooms.matchmaking().restore(players);
+            Err(e)
+        }
+    }
+}
+
+pub fn queue_ticket(context: &AppContext, session_id: &str) -> Option<QueueTicket> {
+    let queue = context.rooms.matchmaking();
+    let player = queue.get(session_id)?;
+    let (position, waiting) = queue.position(session_id)?;
+    Some(QueueTicket {
+        session_id: player.session_id.clone(),
+        match_size: player.match_size,
+        position,
+        waiting,
+        joined_at: player.joined_at,
+    })
+}
+
+/// Opens the group's room, moves every player into it and tells them on the
+/// streams they are likely watching.
+fn start_match(context: &AppContext, players: &[QueuedPlayer]) -> Result<MatchFound, AppError> {
+    let session_ids: Vec<String> = players
+        .iter()
+        .map(|player| player.session_id.clone())
+        .collect();
+    let starts_at = Utc::now() + Duration::seconds(MATCH_START_DELAY_SECS);
+    let room = create_match_room(context, &session_ids, starts_at)?;
+
+    let event = AppEvent::MatchReady {
+        room_id: room.id.clone(),
+        players: session_ids.clone(),
+        starts_at,
+    };
+    let mut notified = HashSet::new();
+    for session_id in &session_ids {
+        let current = context.rooms.room_of(session_id);
+        if notified.insert(current.id.clone()) {
+            current.state.events.broadcast(event.clone());
+        }
+        context.rooms.join(session_id, &room.id);
+    }
+    room.state.events.broadcast(event);
+
+    tracing::info!(
+        room_id = %room.id,
+        "Matched {} players, starting at {}",
+        session_ids.len(),
+        starts_at
+    );
+
+    Ok(MatchFound {
+        room_id: room.id.clone(),
+        players: session_ids,
+        starts_at,
+    })
+}
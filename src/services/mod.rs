@@ -1,2 +1,22 @@
+pub mod bid;
+pub mod config;
+pub mod estimate;
+pub mod event_bus;
+pub mod export;
+pub mod forecast;
+pub mod idempotency;
+pub mod invariants;
+pub mod json_schema;
+pub mod leaderboard;
+pub mod ledger;
+#[cfg(feature = "client")]
+pub mod load;
+pub mod matchmaking;
+pub mod player_analytics;
+pub mod reconciliation;
+pub mod room;
 pub mod session;
+pub mod simulation;
+pub mod slot;
+pub mod supervisor;
 pub mod transaction;
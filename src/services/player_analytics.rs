@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::state::AppState,
+    error::AppError,
+    managers::escrow::EscrowStatus,
+    models::{
+        transaction::Transaction,
+        types::{LAMPORTS_PER_SOL, Lamports, TransactionType},
+    },
+};
+
+/// Slot distance ranges win rates are reported for; the last is open-ended.
+const DISTANCE_RANGES: [(u64, Option<u64>); 4] =
+    [(1, Some(1)), (2, Some(5)), (6, Some(20)), (21, None)];
+
+/// Bids placed within one minute.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SpendBucket {
+    pub start: DateTime<Utc>,
+    pub bids: u32,
+    pub bid_volume: Lamports,
+    /// Charged for the minute's bids that won
+    pub spent: Lamports,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DistanceWinRate {
+    pub min_distance: u64,
+    /// `None` for the open-ended range
+    pub max_distance: Option<u64>,
+    /// Settled bids placed this many slots ahead
+    pub bids: u32,
+    pub wins: u32,
+    pub win_rate: Option<f64>,
+}
+
+/// What a player got for the SOL spent in one auction type. Winning buys
+/// blockspace rather than SOL, so the return is compute units per SOL spent.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct AuctionReturn {
+    /// Settled bids
+    pub bids: u32,
+    pub wins: u32,
+    pub win_rate: Option<f64>,
+    pub spent: Lamports,
+    pub refunded: Lamports,
+    pub cost_per_win: Option<Lamports>,
+    pub compute_units_won: u64,
+    pub compute_units_per_sol: Option<f64>,
+}
+
+impl AuctionReturn {
+    fn finish(mut self) -> Self {
+        self.win_rate = (self.bids > 0).then(|| f64::from(self.wins) / f64::from(self.bids));
+        self.cost_per_win = (self.wins > 0).then(|| Lamports(self.spent.0 / u64::from(self.wins)));
+        self.compute_units_per_sol = (!self.spent.is_zero())
+            .then(|| self.compute_units_won as f64 * LAMPORTS_PER_SOL as f64 / self.spent.0 as f64);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BidOutcome {
+    pub transaction_id: String,
+    pub slot_number: u64,
+    pub auction_type: TransactionType,
+    pub amount: Lamports,
+    pub placed_at: DateTime<Utc>,
+}
+
+impl From<&Transaction> for BidOutcome {
+    fn from(transaction: &Transaction) -> Self {
+        Self {
+            transaction_id: transaction.id.clone(),
+            slot_number: transaction.target_slot(),
+            auction_type: transaction.transaction_type(),
+            amount: transaction.priority_fee,
+            placed_at: transaction.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PlayerAnalytics {
+    pub session_id: String,
+    pub total_bids: u32,
+    /// Bids whose escrow is still held
+    pub open_bids: u32,
+    pub average_bid: Option<Lamports>,
+    pub total_spent: Lamports,
+    /// Per-minute bid activity, oldest first; minutes without bids are omitted
+    pub spend_over_time: Vec<SpendBucket>,
+    pub win_rate_by_distance: Vec<DistanceWinRate>,
+    pub jit: AuctionReturn,
+    pub aot: AuctionReturn,
+    /// Largest bid that won its slot
+    pub biggest_win: Option<BidOutcome>,
+    /// Largest bid that was refunded after losing, failing or expiring
+    pub biggest_loss: Option<BidOutcome>,
+}
+
+/// Aggregates the session's bid history, settling each bid by its escrow: a
+/// released escrow is a win, a refunded one a loss.
+pub async fn player_analytics(
+    state: &AppState,
+    session_id: &str,
+) -> Result<PlayerAnalytics, AppError> {
+    let transactions = state.transactions.session_transactions(session_id);
+    let escrow = state.escrow_records(&transactions).await?;
+    let outcomes: Vec<(Transaction, EscrowStatus)> = transactions
+        .into_iter()
+        .map(|transaction| {
+            // Pruned records settled the way their transaction did
+            let status = escrow.get(&transaction.id).map_or_else(
+                || EscrowStatus::settled_as(&transaction.status),
+                |record| record.status,
+            );
+            (transaction, status)
+        })
+        .collect();
+
+    let mut spend: BTreeMap<i64, SpendBucket> = BTreeMap::new();
+    let mut distances: Vec<DistanceWinRate> = DISTANCE_RANGES
+        .iter()
+        .map(|(min_distance, max_distance)| DistanceWinRate {
+            min_distance: *min_distance,
+            max_distance: *max_distance,
+            bids: 0,
+            wins: 0,
+            win_rate: None,
+        })
+        .collect();
+    let mut jit = AuctionReturn::default();
+    let mut aot = AuctionReturn::default();
+    let mut open_bids = 0;
+    let mut bid_volume = Lamports::ZERO;
+    let mut biggest_win: Option<&Transaction> = None;
+    let mut biggest_loss: Option<&Transaction> = None;
+
+    for (transaction, status) in &outcomes {
+        let amount = transaction.priority_fee;
+        let won = *status == EscrowStatus::Released;
+        bid_volume += amount;
+
+        let minute = transaction.created_at.timestamp().div_euclid(60);
+        let bucket = spend.entry(minute).or_insert_with(|| SpendBucket {
+            start: Utc
+                .timestamp_opt(minute * 60, 0)
+                .single()
+                .unwrap_or(transaction.created_at),
+            bids: 0,
+            bid_volume: Lamports::ZERO,
+            spent: Lamports::ZERO,
+        });
+        bucket.bids += 1;
+        bucket.bid_volume += amount;
+        if won {
+            bucket.spent += amount;
+        }
+
+        let returns = match transaction.transaction_type() {
+            TransactionType::Jit => &mut jit,
+            TransactionType::Aot => &mut aot,
+        };
+        match status {
+            EscrowStatus::Released => {
+                returns.bids += 1;
+                returns.wins += 1;
+                returns.spent += amount;
+                returns.compute_units_won += transaction.compute_units;
+                if biggest_win.is_none_or(|biggest| amount > biggest.priority_fee) {
+                    biggest_win = Some(transaction);
+                }
+            }
+            EscrowStatus::Refunded => {
+                returns.bids += 1;
+                returns.refunded += amount;
+                if biggest_loss.is_none_or(|biggest| amount > biggest.priority_fee) {
+                    biggest_loss = Some(transaction);
+                }
+            }
+            EscrowStatus::Held => {
+                open_bids += 1;
+                continue;
+            }
+        }
+
+        let distance = transaction
+            .target_slot()
+            .saturating_sub(transaction.created_slot)
+            .max(1);
+        if let Some(range) = distances.iter_mut().find(|range| {
+            distance >= range.min_distance && range.max_distance.is_none_or(|max| distance <= max)
+        }) {
+            range.bids += 1;
+            if won {
+                range.wins += 1;
+            }
+        }
+    }
+
+    for range in &mut distances {
+        range.win_rate = (range.bids > 0).then(|| f64::from(range.wins) / f64::from(range.bids));
+    }
+    let total_bids = outcomes.len() as u32;
+
+    Ok(PlayerAnalytics {
+        session_id: session_id.to_string(),
+        total_bids,
+        open_bids,
+        average_bid: (total_bids > 0).then(|| Lamports(bid_volume.0 / u64::from(total_bids))),
+        total_spent: jit.spent + aot.spent,
+        spend_over_time: spend.into_values().collect(),
+        win_rate_by_distance: distances,
+        jit: jit.finish(),
+        aot: aot.finish(),
+        biggest_win: biggest_win.map(BidOutcome::from),
+        biggest_loss: biggest_loss.map(BidOutcome::from),
+    })
+}
@@ -0,0 +1,214 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    app::state::AppState,
+    error::AppError,
+    managers::{
+        escrow::{EscrowRecord, EscrowStatus},
+        transaction::TransactionFilter,
+    },
+    models::{
+        transaction::{Transaction, TransactionStatus},
+        types::{Lamports, TransactionType},
+    },
+};
+
+/// One bid for the slot, matched with the escrow that funded it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BidReconciliation {
+    pub transaction_id: String,
+    pub bidder: String,
+    pub amount: Lamports,
+    pub depends_on: Option<String>,
+    pub transaction_status: Option<TransactionStatus>,
+    pub escrow_status: Option<EscrowStatus>,
+    pub escrow_settled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AuctionReconciliation {
+    pub auction_type: TransactionType,
+    /// Bidder whose escrow was paid out, if any bid executed
+    pub winner: Option<String>,
+    pub winner_charged: Lamports,
+    pub refunded: Lamports,
+    pub still_held: Lamports,
+    pub bids: Vec<BidReconciliation>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SlotReconciliation {
+    pub slot_number: u64,
+    pub auctions: Vec<AuctionReconciliation>,
+    /// Bids whose transaction outcome and escrow settlement disagree
+    pub issues: Vec<String>,
+    pub consistent: bool,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Whether every auction for `slot_number` has resolved, so its escrow should
+/// all be settled.
+pub async fn is_slot_resolved(state: &AppState, slot_number: u64) -> Result<bool, AppError> {
+    let current_slot = state.get_current_slot().await?;
+    let auction_open = state
+        .auctions
+        .shard(slot_number)
+        .call(move |auctions| {
+            auctions.jit_auctions.contains_key(&slot_number)
+                || auctions.aot_auctions.contains_key(&slot_number)
+        })
+        .await?;
+
+    Ok(slot_number <= current_slot && !auction_open)
+}
+
+/// Matches every bid for a resolved slot with its escrow, showing what the
+/// winner was charged and whether each loser was refunded exactly once.
+pub async fn reconcile_slot(
+    state: &AppState,
+    slot_number: u64,
+) -> Result<SlotReconciliation, AppError> {
+    let records = state.escrow_records_for_slot(slot_number).await?;
+    let transactions: Vec<Transaction> = state
+        .transactions
+        .filtered(TransactionFilter::slot(slot_number));
+
+    let mut issues = Vec::new();
+    let auctions = [TransactionType::Jit, TransactionType::Aot]
+        .into_iter()
+        .filter_map(|auction_type| {
+            reconcile_auction(auction_type, &records, &transactions, &mut issues)
+        })
+        .collect();
+
+    Ok(SlotReconciliation {
+        slot_number,
+        auctions,
+        consistent: issues.is_empty(),
+        issues,
+        checked_at: Utc::now(),
+    })
+}
+
+fn reconcile_auction(
+    auction_type: TransactionType,
+    records: &[EscrowRecord],
+    transactions: &[Transaction],
+    issues: &mut Vec<String>,
+) -> Option<AuctionReconciliation> {
+    let mut bids: Vec<BidReconciliation> = transactions
+        .iter()
+        .filter(|transaction| transaction.transaction_type() == auction_type)
+        .map(|transaction| {
+            let escrow = records
+                .iter()
+                .find(|record| record.transaction_id == transaction.id);
+            BidReconciliation {
+                transaction_id: transaction.id.clone(),
+                bidder: transaction.sender.clone(),
+                amount: transaction.priority_fee,
+                depends_on: transaction.depends_on.clone(),
+                transaction_status: Some(transaction.status.clone()),
+                escrow_status: escrow.map(|record| record.status),
+                escrow_settled_at: escrow.and_then(|record| record.settled_at),
+            }
+        })
+        .collect();
+
+    // Escrow whose transaction is gone, e.g. after its session was cleaned up
+    let orphaned: Vec<BidReconciliation> = records
+        .iter()
+        .filter(|record| record.auction_type == auction_type)
+        .filter(|record| {
+            !bids
+                .iter()
+                .any(|bid| bid.transaction_id == record.transaction_id)
+        })
+        .map(|record| BidReconciliation {
+            transaction_id: record.transaction_id.clone(),
+            bidder: record.bidder.clone(),
+            amount: record.amount,
+            depends_on: None,
+            transaction_status: None,
+            escrow_status: Some(record.status),
+            escrow_settled_at: record.settled_at,
+        })
+        .collect();
+    bids.extend(orphaned);
+
+    if bids.is_empty() {
+        return None;
+    }
+    bids.sort_by_key(|bid| std::cmp::Reverse(bid.amount));
+
+    let mut report = AuctionReconciliation {
+        auction_type,
+        winner: None,
+        winner_charged: Lamports::ZERO,
+        refunded: Lamports::ZERO,
+        still_held: Lamports::ZERO,
+        bids: Vec::new(),
+    };
+    let mut winners: Vec<&str> = Vec::new();
+
+    for bid in &bids {
+        let short_id = bid.transaction_id.chars().take(8).collect::<String>();
+        let executed = matches!(
+            bid.transaction_status,
+            Some(TransactionStatus::Included { .. } | TransactionStatus::AuctionWon { .. })
+        );
+
+        match bid.escrow_status {
+            Some(EscrowStatus::Released) => {
+                report.winner_charged += bid.amount;
+                if !winners.contains(&bid.bidder.as_str()) {
+                    winners.push(&bid.bidder);
+                }
+                if !executed {
+                    issues.push(format!(
+                        "Bid {} was charged but its transaction did not execute",
+                        short_id
+                    ));
+                }
+            }
+            Some(EscrowStatus::Refunded) => {
+                report.refunded += bid.amount;
+                if executed {
+                    issues.push(format!(
+                        "Bid {} executed but its escrow was refunded",
+                        short_id
+                    ));
+                }
+            }
+            Some(EscrowStatus::Held) => {
+                report.still_held += bid.amount;
+                issues.push(format!(
+                    "Bid {} still has {} SOL in escrow after the slot resolved",
+                    short_id, bid.amount
+                ));
+            }
+            None => issues.push(format!("Bid {} has no escrow record", short_id)),
+        }
+
+        if matches!(bid.transaction_status, Some(TransactionStatus::Pending)) {
+            issues.push(format!(
+                "Bid {} is still pending after the slot resolved",
+                short_id
+            ));
+        }
+    }
+
+    if winners.len() > 1 {
+        issues.push(format!(
+            "{:?} auction charged {} different bidders",
+            report.auction_type,
+            winners.len()
+        ));
+    }
+    report.winner = winners.first().map(|winner| winner.to_string());
+    report.bids = bids;
+
+    Some(report)
+}
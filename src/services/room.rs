@@ -0,0 +1,240 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    app::{
+        api::AppContext,
+        room::{MAX_ROOMS, Room, RoomManager, RoomOverrides},
+        state::AppState,
+    },
+    config::GlobalConfig,
+    error::AppError,
+    models::{event::AppEvent, marketplace::SlotWindow, player::GameMode, types::Lamports},
+    services::{ledger::run_ledger_audit, slot::run_local_slot_clock, supervisor::supervise},
+};
+
+/// Time a closing room's streams are kept open past the event flush interval,
+/// so the final standings reach every subscriber.
+const ROOM_CLOSE_GRACE_MS: u64 = 1_000;
+
+/// Opens a room with a fresh marketplace on the current configuration and
+/// starts its slot clock and ledger audit. Rooms always keep their own local
+/// clock, even when the global room follows a Solana RPC node.
+pub fn create_room(
+    context: &AppContext,
+    name: String,
+    created_by: String,
+    private: bool,
+    mode: GameMode,
+) -> Result<Arc<Room>, AppError> {
+    let room = Room::new(
+        uuid::Uuid::new_v4().to_string(),
+        name,
+        Some(created_by),
+        private,
+        None,
+        new_room_state(context, mode),
+        context.shutdown.child_token(),
+    );
+    open_room(context, room)
+}
+
+/// Opens a private room for a matchmade group, admitting only its players.
+/// Bidding opens and the slot clock starts for everyone at `starts_at`.
+pub fn create_match_room(
+    context: &AppContext,
+    players: &[String],
+    starts_at: DateTime<Utc>,
+) -> Result<Arc<Room>, AppError> {
+    let room = Room {
+        starts_at: Some(starts_at),
+        ..Room::new(
+            uuid::Uuid::new_v4().to_string(),
+            format!("Competitive match ({} players)", players.len()),
+            None,
+            true,
+            None,
+            new_room_state(context, GameMode::Competitive),
+            context.shutdown.child_token(),
+        )
+    };
+    for session_id in players {
+        room.admitted.insert(session_id.clone());
+    }
+    open_room(context, room)
+}
+
+fn new_room_state(context: &AppContext, mode: GameMode) -> AppState {
+    let config = context.config.current();
+    context.state.new_room(
+        config.marketplace.slot_duration_ms,
+        Lamports::from_sol(config.marketplace.base_fee_sol),
+        SlotWindow {
+            lookahead: config.marketplace.slot_lookahead,
+            listing: config.marketplace.listing_window_slots,
+        },
+        mode,
+    )
+}
+
+fn open_room(context: &AppContext, room: Room) -> Result<Arc<Room>, AppError> {
+    let room = context
+        .rooms
+        .insert(room)
+        .map_err(|_| AppError::RoomLimitReached {
+            max_rooms: MAX_ROOMS,
+        })?;
+
+    spawn_room_tasks(
+        &room,
+        context
+            .config
+            .current()
+            .marketplace
+            .advance_slot_interval_ms,
+    );
+
+    tracing::info!(room_id = %room.id, name = %room.name, "Room created");
+
+    Ok(room)
+}
+
+/// Opens a room for each configured chain, with the chain's slot timing and
+/// base fee and the rest of the marketplace config.
+pub fn open_chains(
+    rooms: &RoomManager,
+    config: &GlobalConfig,
+    shutdown: &CancellationToken,
+) -> anyhow::Result<()> {
+    for chain in &config.chains {
+        let state = rooms.global().state.new_room(
+            chain.slot_duration_ms,
+            Lamports::from_sol(chain.base_fee_sol),
+            SlotWindow {
+                lookahead: config.marketplace.slot_lookahead,
+                listing: config.marketplace.listing_window_slots,
+            },
+            GameMode::Competitive,
+        );
+
+        let room = rooms.insert(Room::new(
+            chain.id.clone(),
+            chain.name.clone(),
+            None,
+            false,
+            Some(chain.clone()),
+            state,
+            shutdown.child_token(),
+        ))?;
+        spawn_room_tasks(&room, chain.advance_slot_interval_ms);
+
+        tracing::info!(
+            chain_id = %chain.id,
+            "Simulating chain {} with {}ms slots and a {} SOL base fee",
+            chain.name,
+            chain.slot_duration_ms,
+            chain.base_fee_sol
+        );
+    }
+
+    Ok(())
+}
+
+/// Starts a room's slot clock, at its start time if it has one, and its
+/// ledger audit, both stopped with the room.
+fn spawn_room_tasks(room: &Arc<Room>, interval_ms: u64) {
+    let delay = room
+        .starts_at
+        .and_then(|starts_at| (starts_at - Utc::now()).to_std().ok());
+    match delay {
+        Some(delay) => {
+            let room = room.clone();
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = room.shutdown.cancelled() => {}
+                    _ = tokio::time::sleep(delay) => start_slot_clock(&room, interval_ms),
+                }
+            });
+        }
+        None => start_slot_clock(room, interval_ms),
+    }
+
+    let (task_state, task_shutdown) = (room.state.clone(), room.shutdown.clone());
+    tokio::spawn(supervise(
+        "ledger audit",
+        room.state.health.clone(),
+        room.shutdown.clone(),
+        move || run_ledger_audit(task_state.clone(), task_shutdown.clone()),
+    ));
+}
+
+/// Starts the room's slot clock at `interval_ms`, stopping the one running.
+fn start_slot_clock(room: &Room, interval_ms: u64) {
+    let clock = room.replace_clock();
+    let (task_state, task_clock) = (room.state.clone(), clock.clone());
+    tokio::spawn(supervise(
+        "slot",
+        room.state.health.clone(),
+        clock,
+        move || run_local_slot_clock(task_state.clone(), interval_ms, task_clock.clone()),
+    ));
+}
+
+/// Applies the creator's settings to the room's marketplace. Slots already
+/// created keep their terms; a new slot duration also restarts the slot clock
+/// at that pace.
+pub async fn configure_room(
+    room: &Room,
+    config: &GlobalConfig,
+    overrides: RoomOverrides,
+) -> Result<(), AppError> {
+    let previous = room.timing(&config.marketplace);
+    room.set_overrides(overrides);
+    let timing = room.timing(&config.marketplace);
+
+    let (slot_duration_ms, base_fee, listing) = (
+        timing.slot_duration_ms,
+        Lamports::from_sol(timing.base_fee_sol),
+        config.marketplace.listing_window_slots,
+    );
+    room.state
+        .marketplace
+        .call(move |marketplace| marketplace.apply_config(slot_duration_ms, base_fee, listing))
+        .await?;
+
+    if timing.advance_slot_interval_ms != previous.advance_slot_interval_ms {
+        start_slot_clock(room, timing.advance_slot_interval_ms);
+    }
+    Ok(())
+}
+
+/// Closes a room, sending its members back to the global room. The room's
+/// final standings are broadcast to its subscribers, whose streams end once
+/// the event has been flushed to them.
+pub async fn end_room(context: &AppContext, room_id: &str) -> Result<Option<Arc<Room>>, AppError> {
+    let Some(room) = context.rooms.get(room_id) else {
+        return Ok(None);
+    };
+    let standings = room.state.get_leaderboard().await?;
+    let Some(room) = context.rooms.remove(room_id) else {
+        return Ok(None);
+    };
+
+    room.state.events.broadcast(AppEvent::RoomClosed {
+        room_id: room.id.clone(),
+        standings,
+    });
+
+    let grace = Duration::from_millis(
+        context.config.current().events.flush_interval_ms + ROOM_CLOSE_GRACE_MS,
+    );
+    let shutdown = room.shutdown.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(grace).await;
+        shutdown.cancel();
+    });
+
+    Ok(Some(room))
+}
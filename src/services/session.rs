@@ -1,31 +1,305 @@
-use axum::http::{HeaderMap, StatusCode, header};
+use std::time::Duration;
 
-use crate::managers::session::SessionManager;
+use axum::http::{HeaderMap, HeaderValue, header};
+use tokio::time::interval;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 
-pub async fn get_session_from_cookie(
-    headers: &HeaderMap,
-    query_session_id: Option<&String>,
-    sessions: &SessionManager,
-) -> Result<String, StatusCode> {
-    let session_id_from_cookie = headers
+use crate::{
+    app::room::RoomManager,
+    config::CookieConfig,
+    error::AppError,
+    managers::session::SessionManager,
+    models::{
+        event::AppEvent,
+        recovery::{generate_recovery_code, hash_recovery_code},
+        requests::UpgradeMethod,
+        responses::{GuestUpgraded, PlayerDataDeleted},
+        session::{ACCOUNT_ID_PREFIX, Session},
+        types::Lamports,
+    },
+};
+
+/// The signed session token in the request's cookie named `cookie_name`.
+pub fn session_token_from_cookie(headers: &HeaderMap, cookie_name: &str) -> Option<String> {
+    headers
         .get(header::COOKIE)
         .and_then(|h| h.to_str().ok())
         .and_then(|cookies| {
             cookies
                 .split(';')
-                .find(|c| c.trim().starts_with("raiku_session="))
-                .and_then(|c| c.split('=').nth(1))
-                .map(|s| s.to_string())
-        });
+                .filter_map(|c| c.trim().split_once('='))
+                .find(|(name, _)| *name == cookie_name)
+                .map(|(_, value)| value.to_string())
+        })
+}
+
+/// `Set-Cookie` value storing the signed token for `session_id` in the
+/// session cookie.
+pub fn session_cookie(
+    sessions: &SessionManager,
+    session_id: &str,
+) -> Result<HeaderValue, AppError> {
+    let cookie = sessions.cookie();
+    build_cookie(
+        cookie,
+        &sessions.session_token(session_id),
+        cookie.max_age_secs,
+    )
+}
+
+/// `Set-Cookie` value deleting the session cookie.
+pub fn expired_session_cookie(cookie: &CookieConfig) -> Result<HeaderValue, AppError> {
+    build_cookie(cookie, "", 0)
+}
+
+fn build_cookie(
+    cookie: &CookieConfig,
+    value: &str,
+    max_age_secs: u64,
+) -> Result<HeaderValue, AppError> {
+    let mut header = format!(
+        "{}={}; Path=/; HttpOnly; SameSite={}",
+        cookie.name,
+        value,
+        cookie.same_site.as_str()
+    );
+    if cookie.secure {
+        header.push_str("; Secure");
+    }
+    if let Some(domain) = &cookie.domain {
+        header.push_str(&format!("; Domain={}", domain));
+    }
+    header.push_str(&format!("; Max-Age={}", max_age_secs));
+
+    HeaderValue::from_str(&header)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Failed to set session cookie: {}", e)))
+}
+
+pub fn api_key_from_headers(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// Resolves the caller's session from an `Authorization: Bearer` API key, the
+/// session cookie or, failing both, the `session_id` query parameter. Cookie
+/// and query values are signed session tokens; one whose signature doesn't
+/// match is rejected with `InvalidSessionToken` rather than looked up.
+pub async fn get_session_from_cookie(
+    headers: &HeaderMap,
+    query_session_id: Option<&String>,
+    sessions: &SessionManager,
+) -> Result<String, AppError> {
+    // A presented API key must be valid; don't fall back to other credentials
+    if let Some(api_key) = api_key_from_headers(headers) {
+        return sessions
+            .authenticate_api_key(api_key)
+            .await
+            .ok_or(AppError::Unauthorized);
+    }
 
     // Fall back to query parameter
-    let session_id = session_id_from_cookie
+    let token = session_token_from_cookie(headers, &sessions.cookie().name)
         .or_else(|| query_session_id.cloned())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+        .ok_or(AppError::Unauthorized)?;
+    let session_id = sessions
+        .verify_session_token(&token)
+        .ok_or(AppError::InvalidSessionToken)?;
 
     if sessions.validate_session(&session_id).await {
         Ok(session_id)
     } else {
-        Err(StatusCode::UNAUTHORIZED)
+        Err(AppError::Unauthorized)
+    }
+}
+
+/// Moves a session to a fresh ID in every room, so a leaked or planted ID stops
+/// working while the player keeps their stats, history, rooms and API keys.
+/// Account sessions stay accounts. Refused while any of the player's bids is
+/// pending, since its auction entry is held under the old ID.
+pub async fn refresh_session(rooms: &RoomManager, session_id: &str) -> Result<Session, AppError> {
+    let prefix = if session_id.starts_with(ACCOUNT_ID_PREFIX) {
+        ACCOUNT_ID_PREFIX
+    } else {
+        ""
+    };
+    rekey_session(rooms, session_id, format!("{}{}", prefix, Uuid::new_v4())).await
+}
+
+/// Upgrades a guest session to an account: its stats, transactions and escrow
+/// records move to a new account ID in every room together, under the
+/// player's bid locks, and the profile is tied to `method` so it outlives the
+/// session. Already upgraded sessions keep their ID; a `recovery_code`
+/// upgrade then just issues a new code.
+pub async fn upgrade_guest(
+    rooms: &RoomManager,
+    session_id: &str,
+    method: UpgradeMethod,
+) -> Result<GuestUpgraded, AppError> {
+    let global = rooms.global().state.clone();
+    let session = global
+        .sessions
+        .get_session(session_id)
+        .await
+        .ok_or(AppError::Unauthorized)?;
+    if method == UpgradeMethod::Wallet && session.wallet.is_none() {
+        return Err(AppError::WalletNotLinked);
     }
+
+    let session = if session.is_account() {
+        session
+    } else {
+        let account_id = format!("{}{}", ACCOUNT_ID_PREFIX, Uuid::new_v4());
+        rekey_session(rooms, session_id, account_id).await?
+    };
+
+    let recovery_code = match method {
+        UpgradeMethod::Wallet => None,
+        UpgradeMethod::RecoveryCode => {
+            let recovery_code = generate_recovery_code();
+            let (account, code_hash) = (session.id.clone(), hash_recovery_code(&recovery_code));
+            global
+                .game
+                .call(move |game| game.set_recovery_code(&account, code_hash))
+                .await?;
+            Some(recovery_code)
+        }
+    };
+
+    Ok(GuestUpgraded {
+        transaction_count: global.get_session_transaction_count(&session.id).await,
+        session_token: global.sessions.session_token(&session.id),
+        session_id: session.id,
+        method,
+        recovery_code,
+    })
+}
+
+/// Moves a session and everything keyed by it to `new_id` in every room.
+async fn rekey_session(
+    rooms: &RoomManager,
+    session_id: &str,
+    new_id: String,
+) -> Result<Session, AppError> {
+    let rooms_list = rooms.list();
+    let mut bid_guards = Vec::with_capacity(rooms_list.len());
+    for room in &rooms_list {
+        bid_guards.push(room.state.lock_player_bids(session_id).await?);
+    }
+    for room in &rooms_list {
+        if room.state.has_pending_bids(session_id).await {
+            return Err(AppError::BidsPending);
+        }
+    }
+
+    let global = rooms.global().state.clone();
+    let session = global
+        .sessions
+        .rotate_session(session_id, new_id)
+        .await
+        .ok_or(AppError::Unauthorized)?;
+
+    for room in &rooms_list {
+        room.state.rename_player(session_id, &session.id).await?;
+    }
+    rooms.rename_member(session_id, &session.id);
+    global
+        .moderation
+        .rename_session(session_id, &session.id)
+        .await;
+    global.sessions.move_api_keys(session_id, &session.id).await;
+    global.nonces.rename(session_id, &session.id).await;
+
+    Ok(session)
+}
+
+/// Ends the session and erases the player from every room. Their open bids
+/// are refunded and the transactions and bids they leave behind are kept for
+/// market totals under one anonymous ID.
+pub async fn delete_player_data(
+    rooms: &RoomManager,
+    session_id: &str,
+) -> Result<PlayerDataDeleted, AppError> {
+    const REASON: &str = "Player deleted their data";
+
+    let global = rooms.global().state.clone();
+    if !global.sessions.invalidate_session(session_id).await {
+        return Err(AppError::Unauthorized);
+    }
+
+    let alias = format!("deleted-{}", Uuid::new_v4());
+    let mut refunded = Lamports::ZERO;
+    let mut anonymized = 0;
+    for room in rooms.list() {
+        let (refund, transactions) = room.state.delete_player(session_id, &alias, REASON).await?;
+        refunded += refund;
+        anonymized += transactions;
+        room.state.events.broadcast(AppEvent::PlayerDeleted {
+            player: session_id.to_string(),
+        });
+    }
+    refunded += rooms.remove_from_rooms(session_id, REASON).await?;
+    global.moderation.rename_session(session_id, &alias).await;
+    global.nonces.forget(&[session_id.to_string()]).await;
+
+    Ok(PlayerDataDeleted {
+        refunded,
+        anonymized_transactions: u32::try_from(anonymized).unwrap_or(u32::MAX),
+    })
+}
+
+/// Periodically removes expired sessions and their player stats in every room
+/// until `shutdown` is cancelled.
+pub async fn run_session_cleanup(rooms: RoomManager, shutdown: CancellationToken) {
+    let mut interval = interval(Duration::from_secs(300));
+    let state = rooms.global().state.clone();
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        let removed_sessions = state.sessions.cleanup_expired_sessions().await;
+
+        if !removed_sessions.is_empty() {
+            // Escrow is returned before each player's profile is dropped
+            for session_id in &removed_sessions {
+                let removed = match state.remove_player(session_id, "Session expired").await {
+                    Ok(_) => rooms.remove_from_rooms(session_id, "Session expired").await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = removed {
+                    tracing::error!(
+                        "Failed to remove expired player {}: {}",
+                        session_id.chars().take(8).collect::<String>(),
+                        e
+                    );
+                }
+            }
+
+            state.nonces.forget(&removed_sessions).await;
+
+            tracing::info!(
+                "Cleaned up {} expired sessions and their player stats",
+                removed_sessions.len()
+            );
+        }
+
+        let expired_keys = state.idempotency.cleanup_expired().await;
+        if expired_keys > 0 {
+            tracing::info!("Dropped {} expired idempotency keys", expired_keys);
+        }
+
+        let session_count = state.sessions.get_session_count().await;
+        if session_count > 0 {
+            tracing::info!("Active sessions: {}", session_count);
+        }
+    }
+
+    tracing::info!("Session cleanup stopped");
 }
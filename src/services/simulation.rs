@@ -0,0 +1,493 @@
+use std::{collections::HashMap, fmt, str::FromStr, time::Instant};
+
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::Serialize;
+
+use crate::{
+    INITIAL_PLAYER_BALANCE, JIT_PREMIUM_MULTIPLIER,
+    app::state::AppState,
+    config::GlobalConfig,
+    error::AppError,
+    models::{
+        auction::{AotAuction, JitAuction, ResolvedAuction},
+        marketplace::SlotWindow,
+        transaction::Transaction,
+        types::{Lamports, TransactionType},
+    },
+    services::{
+        bid::{AuctionTerms, submit_bid},
+        slot::process_slot_tick,
+    },
+};
+
+/// Compute units every simulated bid asks for.
+const BOT_COMPUTE_UNITS: u64 = 200_000;
+
+/// How a bot decides whether and how much to bid each slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BotStrategy {
+    /// Now and then bids the minimum for the next slot, unless it has been
+    /// bid past twice the floor
+    Passive,
+    /// Outbids the leader of the next slot's JIT auction every slot, up to ten
+    /// times the base fee
+    Aggressive,
+    /// Reserves slots ahead of time through AOT auctions
+    Planner,
+    /// Picks JIT or AOT at random and bids a random premium
+    Random,
+}
+
+impl BotStrategy {
+    pub const ALL: [BotStrategy; 4] = [
+        BotStrategy::Passive,
+        BotStrategy::Aggressive,
+        BotStrategy::Planner,
+        BotStrategy::Random,
+    ];
+}
+
+impl fmt::Display for BotStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BotStrategy::Passive => "passive",
+            BotStrategy::Aggressive => "aggressive",
+            BotStrategy::Planner => "planner",
+            BotStrategy::Random => "random",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for BotStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BotStrategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.to_string() == s)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown bot strategy '{}'; expected one of passive, aggressive, planner, random",
+                    s
+                )
+            })
+    }
+}
+
+/// A number of bots sharing a strategy, written `strategy=count`.
+#[derive(Clone, Copy, Debug)]
+pub struct BotPopulation {
+    pub strategy: BotStrategy,
+    pub count: usize,
+}
+
+impl FromStr for BotPopulation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (strategy, count) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Bot population '{}' must be strategy=count", s))?;
+        let count = count
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid bot count in '{}': {}", s, e))?;
+
+        Ok(Self {
+            strategy: strategy.trim().parse()?,
+            count,
+        })
+    }
+}
+
+pub struct SimulationSettings {
+    pub slots: u64,
+    pub populations: Vec<BotPopulation>,
+    /// Seeds the bots' decisions; a random seed is picked and reported if unset
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AuctionSummary {
+    pub auctions: u64,
+    pub total_bids: u64,
+    pub avg_clearing_price_sol: f64,
+    pub min_clearing_price_sol: f64,
+    pub max_clearing_price_sol: f64,
+    pub refunded_sol: f64,
+}
+
+impl AuctionSummary {
+    fn record(&mut self, auction: &ResolvedAuction) {
+        let price = auction.clearing_price.as_sol();
+        if self.auctions == 0 {
+            self.min_clearing_price_sol = price;
+            self.max_clearing_price_sol = price;
+        } else {
+            self.min_clearing_price_sol = self.min_clearing_price_sol.min(price);
+            self.max_clearing_price_sol = self.max_clearing_price_sol.max(price);
+        }
+        self.avg_clearing_price_sol = (self.avg_clearing_price_sol * self.auctions as f64 + price)
+            / (self.auctions + 1) as f64;
+        self.auctions += 1;
+        self.total_bids += auction.bid_count as u64;
+        self.refunded_sol += auction.refunded.as_sol();
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StrategySummary {
+    pub strategy: BotStrategy,
+    pub bots: usize,
+    pub bids_submitted: u64,
+    pub bids_rejected: u64,
+    pub auctions_won: u64,
+    /// Share of the run's auctions won by this population
+    pub win_share: f64,
+    pub sol_spent: f64,
+    pub avg_final_balance_sol: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimulationReport {
+    pub seed: u64,
+    pub slots: u64,
+    pub bots: usize,
+    pub elapsed_ms: u64,
+    pub slots_per_second: f64,
+    pub bids_submitted: u64,
+    pub bids_rejected: u64,
+    pub jit: AuctionSummary,
+    pub aot: AuctionSummary,
+    pub strategies: Vec<StrategySummary>,
+}
+
+impl SimulationReport {
+    pub const CSV_HEADER: &str = "strategy,bots,bids_submitted,bids_rejected,auctions_won,\
+        win_share,sol_spent,avg_final_balance_sol\n";
+
+    /// One CSV row per bot strategy.
+    pub fn strategies_csv(&self) -> String {
+        let mut csv = Self::CSV_HEADER.to_string();
+        for summary in &self.strategies {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.4},{:.9},{:.9}\n",
+                summary.strategy,
+                summary.bots,
+                summary.bids_submitted,
+                summary.bids_rejected,
+                summary.auctions_won,
+                summary.win_share,
+                summary.sol_spent,
+                summary.avg_final_balance_sol
+            ));
+        }
+        csv
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Simulated {} slots with {} bots in {} ms ({:.0} slots/s, seed {})",
+            self.slots, self.bots, self.elapsed_ms, self.slots_per_second, self.seed
+        )?;
+        writeln!(
+            f,
+            "Bids: {} submitted, {} rejected",
+            self.bids_submitted, self.bids_rejected
+        )?;
+        for (name, summary) in [("JIT", &self.jit), ("AOT", &self.aot)] {
+            writeln!(
+                f,
+                "{} auctions: {} resolved, {} bids, clearing price avg {:.6} / min {:.6} / max {:.6} SOL, {:.6} SOL refunded",
+                name,
+                summary.auctions,
+                summary.total_bids,
+                summary.avg_clearing_price_sol,
+                summary.min_clearing_price_sol,
+                summary.max_clearing_price_sol,
+                summary.refunded_sol
+            )?;
+        }
+        writeln!(
+            f,
+            "{:<12} {:>5} {:>9} {:>9} {:>7} {:>7} {:>14} {:>16}",
+            "strategy", "bots", "bids", "rejected", "wins", "share", "spent (SOL)", "avg balance"
+        )?;
+        for summary in &self.strategies {
+            writeln!(
+                f,
+                "{:<12} {:>5} {:>9} {:>9} {:>7} {:>6.1}% {:>14.6} {:>16.6}",
+                summary.strategy.to_string(),
+                summary.bots,
+                summary.bids_submitted,
+                summary.bids_rejected,
+                summary.auctions_won,
+                summary.win_share * 100.0,
+                summary.sol_spent,
+                summary.avg_final_balance_sol
+            )?;
+        }
+        Ok(())
+    }
+}
+
+struct Bot {
+    id: String,
+    strategy: BotStrategy,
+}
+
+#[derive(Default)]
+struct BidCounts {
+    submitted: u64,
+    rejected: u64,
+}
+
+/// Runs the marketplace engine for `settings.slots` slots as fast as it can
+/// go, with no HTTP layer and no slot timer. Every bot gets one decision per
+/// slot, in a shuffled order, before the slot advances.
+pub async fn run_simulation(
+    config: &GlobalConfig,
+    settings: SimulationSettings,
+) -> anyhow::Result<SimulationReport> {
+    if settings.slots == 0 {
+        anyhow::bail!("A simulation needs at least one slot");
+    }
+
+    let seed = settings.seed.unwrap_or_else(|| rand::rng().random());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let marketplace = &config.marketplace;
+    let state = AppState::new(
+        marketplace.slot_duration_ms,
+        Lamports::from_sol(marketplace.base_fee_sol),
+        SlotWindow {
+            lookahead: marketplace.slot_lookahead,
+            listing: marketplace.listing_window_slots,
+        },
+        config.abuse.clone(),
+        config.server.cookie.clone(),
+    );
+    let base_fee = Lamports::from_sol(marketplace.base_fee_sol);
+    let terms = AuctionTerms::from_config(config);
+
+    // A strategy listed twice gets one population of the combined size
+    let mut strategies: Vec<(BotStrategy, usize)> = Vec::new();
+    for population in &settings.populations {
+        match strategies
+            .iter_mut()
+            .find(|(strategy, _)| *strategy == population.strategy)
+        {
+            Some((_, count)) => *count += population.count,
+            None => strategies.push((population.strategy, population.count)),
+        }
+    }
+    strategies.retain(|(_, count)| *count > 0);
+
+    let mut bots: Vec<Bot> = Vec::new();
+    for &(strategy, count) in &strategies {
+        for n in 1..=count {
+            bots.push(Bot {
+                id: format!("{}-{}", strategy, n),
+                strategy,
+            });
+        }
+    }
+
+    let mut counts: HashMap<BotStrategy, BidCounts> = HashMap::new();
+    let (mut jit, mut aot) = (AuctionSummary::default(), AuctionSummary::default());
+    let started = Instant::now();
+
+    for _ in 0..settings.slots {
+        let current_slot = state.get_current_slot().await?;
+
+        bots.shuffle(&mut rng);
+        for bot in &bots {
+            let Some(transaction) = decide_bid(
+                &state,
+                bot,
+                current_slot,
+                base_fee,
+                marketplace.aot_max_lookahead_slots,
+                marketplace.transaction_ttl_slots,
+                &mut rng,
+            )
+            .await?
+            else {
+                continue;
+            };
+
+            let bot_counts = counts.entry(bot.strategy).or_default();
+            bot_counts.submitted += 1;
+            if submit_bid(&state, transaction, terms.clone())
+                .await
+                .is_err()
+            {
+                bot_counts.rejected += 1;
+            }
+        }
+
+        let resolved_slot = process_slot_tick(&state).await?;
+        for auction in state
+            .auction_history
+            .call(move |history| history.range(resolved_slot, resolved_slot))
+            .await?
+        {
+            match auction.auction_type {
+                TransactionType::Jit => jit.record(&auction),
+                TransactionType::Aot => aot.record(&auction),
+            }
+        }
+    }
+
+    let elapsed = started.elapsed();
+    let total_wins = jit.auctions + aot.auctions;
+    let players = state.game.call(|game| game.player_stats.clone()).await?;
+
+    let strategies = strategies
+        .into_iter()
+        .map(|(strategy, bot_count)| {
+            let members: Vec<_> = bots
+                .iter()
+                .filter(|bot| bot.strategy == strategy)
+                .filter_map(|bot| players.get(&bot.id))
+                .collect();
+            let auctions_won: u64 = members
+                .iter()
+                .map(|stats| stats.total_auctions_won as u64)
+                .sum();
+            let sol_spent: f64 = members
+                .iter()
+                .map(|stats| stats.total_sol_spent.as_sol())
+                .sum();
+            // Bots that never bid still hold the starting balance
+            let balances: f64 = members
+                .iter()
+                .map(|stats| stats.balance.as_sol())
+                .sum::<f64>()
+                + (bot_count - members.len()) as f64 * INITIAL_PLAYER_BALANCE.as_sol();
+            let bid_counts = counts.get(&strategy);
+
+            StrategySummary {
+                strategy,
+                bots: bot_count,
+                bids_submitted: bid_counts.map_or(0, |counts| counts.submitted),
+                bids_rejected: bid_counts.map_or(0, |counts| counts.rejected),
+                auctions_won,
+                win_share: if total_wins == 0 {
+                    0.0
+                } else {
+                    auctions_won as f64 / total_wins as f64
+                },
+                sol_spent,
+                avg_final_balance_sol: balances / bot_count as f64,
+            }
+        })
+        .collect();
+
+    Ok(SimulationReport {
+        seed,
+        slots: settings.slots,
+        bots: bots.len(),
+        elapsed_ms: elapsed.as_millis() as u64,
+        slots_per_second: settings.slots as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        bids_submitted: counts.values().map(|counts| counts.submitted).sum(),
+        bids_rejected: counts.values().map(|counts| counts.rejected).sum(),
+        jit,
+        aot,
+        strategies,
+    })
+}
+
+/// The bot's bid for this slot, if it bids at all.
+async fn decide_bid(
+    state: &AppState,
+    bot: &Bot,
+    current_slot: u64,
+    base_fee: Lamports,
+    aot_max_lookahead_slots: u64,
+    ttl_slots: u64,
+    rng: &mut StdRng,
+) -> Result<Option<Transaction>, AppError> {
+    let jit_slot = current_slot + 1;
+    let jit_floor = base_fee.scale(JIT_PREMIUM_MULTIPLIER);
+
+    let jit = |amount: Lamports| {
+        Transaction::jit(bot.id.clone(), BOT_COMPUTE_UNITS, amount, String::new())
+            .with_ttl(current_slot, ttl_slots)
+    };
+    let aot = |slot: u64, amount: Lamports| {
+        Transaction::aot(
+            bot.id.clone(),
+            BOT_COMPUTE_UNITS,
+            amount,
+            slot,
+            String::new(),
+        )
+        .with_ttl(current_slot, ttl_slots)
+    };
+
+    Ok(match bot.strategy {
+        BotStrategy::Passive => {
+            if !rng.random_bool(0.3) {
+                return Ok(None);
+            }
+            let amount = jit_auction(state, jit_slot)
+                .await?
+                .map_or(jit_floor, |auction| auction.min_next_bid());
+            (amount <= jit_floor.scale(2.0)).then(|| jit(amount))
+        }
+        BotStrategy::Aggressive => {
+            let auction = jit_auction(state, jit_slot).await?;
+            let leading = auction.as_ref().and_then(|auction| auction.resolve());
+            if leading.is_some_and(|(leader, _)| leader == bot.id) {
+                return Ok(None);
+            }
+            let amount = auction
+                .map_or(jit_floor, |auction| auction.min_next_bid())
+                .scale(rng.random_range(1.1..1.5));
+            (amount <= base_fee.scale(10.0)).then(|| jit(amount))
+        }
+        BotStrategy::Planner => {
+            if aot_max_lookahead_slots < 2 || !rng.random_bool(0.5) {
+                return Ok(None);
+            }
+            let slot = current_slot + rng.random_range(2..=aot_max_lookahead_slots);
+            let amount = aot_auction(state, slot)
+                .await?
+                .map_or(base_fee, |auction| auction.get_min_next_bid());
+            (amount <= base_fee.scale(3.0)).then(|| aot(slot, amount))
+        }
+        BotStrategy::Random => {
+            if !rng.random_bool(0.5) {
+                return Ok(None);
+            }
+            let premium = rng.random_range(1.0..3.0);
+            if aot_max_lookahead_slots >= 2 && rng.random_bool(0.5) {
+                let slot = current_slot + rng.random_range(2..=aot_max_lookahead_slots);
+                Some(aot(slot, base_fee.scale(premium)))
+            } else {
+                Some(jit(jit_floor.scale(premium)))
+            }
+        }
+    })
+}
+
+async fn jit_auction(state: &AppState, slot: u64) -> Result<Option<JitAuction>, AppError> {
+    state
+        .auctions
+        .shard(slot)
+        .call(move |auctions| auctions.jit_auctions.get(&slot).cloned())
+        .await
+}
+
+async fn aot_auction(state: &AppState, slot: u64) -> Result<Option<AotAuction>, AppError> {
+    state
+        .auctions
+        .shard(slot)
+        .call(move |auctions| auctions.aot_auctions.get(&slot).cloned())
+        .await
+}
@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use tokio::time::interval;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    app::state::AppState,
+    error::AppError,
+    models::types::{InclusionType, TransactionType},
+    services::{
+        bid::rebid_lost_jit_bids,
+        invariants::{TickWinner, enforce_invariants},
+        transaction::{
+            announce_achievements, expire_stale_transactions, update_transaction_status_lose,
+            update_transaction_status_win,
+        },
+    },
+    utils::{
+        auction::{distinct_losers, fill_slot, record_losses},
+        rpc::SolanaRpcClient,
+    },
+};
+
+/// Upper bound on simulated slots processed per RPC poll so a long stall
+/// on the cluster side cannot block the task indefinitely.
+const MAX_RPC_CATCH_UP_SLOTS: u64 = 100;
+
+/// Advances the marketplace by one slot and resolves any auctions that are due.
+#[tracing::instrument(skip_all, fields(current_slot = tracing::field::Empty))]
+pub async fn process_slot_tick(state: &AppState) -> Result<u64, AppError> {
+    let current_slot = state.advance_slot().await?;
+    tracing::Span::current().record("current_slot", current_slot);
+    state.health.record_tick();
+    let mut winners = Vec::new();
+
+    if let Some((winner, bid)) = state.resolve_jit_auction(current_slot).await? {
+        tracing::info!(
+            "JIT auction resolved - Slot: {}, Winner: {}, Bid: {} SOL",
+            current_slot,
+            winner.chars().take(8).collect::<String>(),
+            bid
+        );
+
+        let included = update_transaction_status_win(
+            state,
+            &winner,
+            current_slot,
+            bid,
+            InclusionType::Jit,
+            TransactionType::Jit,
+        )
+        .await?;
+
+        let slot_winner = winner.clone();
+        state
+            .slots
+            .shard(current_slot)
+            .call(move |shard| {
+                shard.update(current_slot, |slot_obj| {
+                    fill_slot(slot_obj, slot_winner, bid, TransactionType::Jit, &included)
+                })
+            })
+            .await?;
+
+        // Outbid JIT bidders are settled now rather than waiting for their bids to expire
+        let jit_winner = winner.clone();
+        let losers = state
+            .escrow
+            .shard(current_slot)
+            .call(move |escrow| {
+                escrow.held_bidders(&TransactionType::Jit, current_slot, &jit_winner)
+            })
+            .await?;
+        process_auction_losers(state, current_slot, InclusionType::Jit, losers).await?;
+        rebid_lost_jit_bids(state, current_slot, &winner, bid).await;
+        winners.push(TickWinner {
+            slot_number: current_slot,
+            auction_type: TransactionType::Jit,
+            winner: winner.clone(),
+        });
+        state
+            .record_auction_history(current_slot, TransactionType::Jit, winner, bid)
+            .await?;
+    }
+
+    // Warnings go out before this tick closes any auction they count down to
+    state.announce_closing_aot_auctions().await?;
+    let resolved_aot = state.resolve_ready_aot_auctions(current_slot).await?;
+    for (slot, winner, bid, losers_with_bids) in resolved_aot {
+        tracing::info!(
+            "AOT auction resolved - Slot: {}, Winner: {}, Bid: {} SOL, Refunding {} losers",
+            slot,
+            winner.chars().take(8).collect::<String>(),
+            bid,
+            losers_with_bids.len()
+        );
+
+        let included = update_transaction_status_win(
+            state,
+            &winner,
+            slot,
+            bid,
+            InclusionType::Aot {
+                reserved_slot: slot,
+            },
+            TransactionType::Aot,
+        )
+        .await?;
+
+        let slot_winner = winner.clone();
+        state
+            .slots
+            .shard(slot)
+            .call(move |shard| {
+                shard.update(slot, |slot_obj| {
+                    fill_slot(slot_obj, slot_winner, bid, TransactionType::Aot, &included)
+                })
+            })
+            .await?;
+
+        let losers: Vec<String> = losers_with_bids
+            .into_iter()
+            .map(|(loser_id, _)| loser_id)
+            .collect();
+        process_auction_losers(
+            state,
+            slot,
+            InclusionType::Aot {
+                reserved_slot: slot,
+            },
+            losers,
+        )
+        .await?;
+        winners.push(TickWinner {
+            slot_number: slot,
+            auction_type: TransactionType::Aot,
+            winner: winner.clone(),
+        });
+        state
+            .record_auction_history(slot, TransactionType::Aot, winner, bid)
+            .await?;
+    }
+
+    expire_stale_transactions(state, current_slot).await?;
+
+    if cfg!(debug_assertions) {
+        enforce_invariants(state, current_slot, &winners).await?;
+    }
+
+    if current_slot % 10 == 0 {
+        tracing::info!("Current slot: {}", current_slot);
+    }
+
+    Ok(current_slot)
+}
+
+/// Fails the losers' transactions for `slot`, refunding their escrow, and records
+/// the loss on their stats. Each loser is processed once however many bids they placed.
+async fn process_auction_losers(
+    state: &AppState,
+    slot: u64,
+    inclusion_type: InclusionType,
+    losers: Vec<String>,
+) -> Result<(), AppError> {
+    let losers = distinct_losers(losers);
+
+    for loser_id in &losers {
+        update_transaction_status_lose(state, loser_id, slot, inclusion_type.clone()).await?;
+    }
+
+    let unlocked = state
+        .game
+        .call(move |game| record_losses(game, slot, &losers))
+        .await?;
+
+    for (loser_id, achievements) in unlocked {
+        announce_achievements(state, &loser_id, achievements);
+    }
+    Ok(())
+}
+
+/// Drives slot advancement from a local timer until `shutdown` is cancelled.
+/// A tick that has already started always runs to completion.
+pub async fn run_local_slot_clock(
+    state: AppState,
+    advance_slot_interval_ms: u64,
+    shutdown: CancellationToken,
+) {
+    let mut interval = interval(Duration::from_millis(advance_slot_interval_ms));
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        if let Err(e) = process_slot_tick(&state).await {
+            tracing::error!("Slot tick failed: {}", e);
+        }
+    }
+
+    tracing::info!("Slot clock stopped");
+}
+
+/// Drives slot advancement by following a real Solana cluster's slot height.
+///
+/// The marketplace is re-anchored at the first observed slot, after which one
+/// simulated slot is processed for every slot the cluster produces.
+pub async fn run_rpc_slot_sync(
+    state: AppState,
+    client: SolanaRpcClient,
+    poll_interval_ms: u64,
+    shutdown: CancellationToken,
+) {
+    let mut interval = interval(Duration::from_millis(poll_interval_ms));
+    // A restarted sync keeps the existing window; the marketplace only sits at
+    // slot 0 before the first anchor
+    let mut anchored = state.get_current_slot().await.is_ok_and(|slot| slot != 0);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        let cluster_slot = match client.get_slot().await {
+            Ok(slot) => {
+                // The cluster may not have produced a new slot yet, but polling is healthy
+                state.health.record_tick();
+                slot
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch slot from {}: {}", client.url(), e);
+                continue;
+            }
+        };
+
+        if !anchored {
+            if let Err(e) = state.anchor_to_slot(cluster_slot).await {
+                tracing::error!("Failed to anchor to cluster slot {}: {}", cluster_slot, e);
+                continue;
+            }
+            anchored = true;
+
+            tracing::info!("Anchored marketplace to cluster slot {}", cluster_slot);
+            continue;
+        }
+
+        let current_slot = match state.get_current_slot().await {
+            Ok(slot) => slot,
+            Err(e) => {
+                tracing::error!("Failed to read the current slot: {}", e);
+                continue;
+            }
+        };
+        let behind = cluster_slot.saturating_sub(current_slot);
+
+        if behind > MAX_RPC_CATCH_UP_SLOTS {
+            tracing::warn!(
+                "Marketplace is {} slots behind the cluster, catching up {} this poll",
+                behind,
+                MAX_RPC_CATCH_UP_SLOTS
+            );
+        }
+
+        for _ in 0..behind.min(MAX_RPC_CATCH_UP_SLOTS) {
+            if let Err(e) = process_slot_tick(&state).await {
+                tracing::error!("Slot tick failed: {}", e);
+                break;
+            }
+        }
+    }
+
+    tracing::info!("RPC slot sync stopped");
+}
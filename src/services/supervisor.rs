@@ -0,0 +1,75 @@
+use std::{
+    any::Any,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+use crate::managers::health::HealthMonitor;
+
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Runs a background task, restarting it with exponential backoff whenever it
+/// panics. Returns once the task exits normally or `shutdown` is cancelled.
+pub async fn supervise<F, Fut>(
+    name: &'static str,
+    health: HealthMonitor,
+    shutdown: CancellationToken,
+    mut task: F,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut backoff = INITIAL_RESTART_BACKOFF;
+
+    loop {
+        let started_at = Instant::now();
+
+        let error = match tokio::spawn(task()).await {
+            Ok(()) => break,
+            Err(e) if e.is_panic() => e,
+            Err(e) => {
+                tracing::error!("Background task {} was cancelled: {}", name, e);
+                break;
+            }
+        };
+
+        let message = panic_message(error.into_panic());
+        health.record_task_panic(name, message.clone());
+
+        // A task that stayed up for a while gets a fresh backoff
+        if started_at.elapsed() > MAX_RESTART_BACKOFF {
+            backoff = INITIAL_RESTART_BACKOFF;
+        }
+
+        tracing::error!(
+            alert = true,
+            task = name,
+            restarts = health.task_restarts(name),
+            "Background task {} panicked: {}. Restarting in {:?}",
+            name,
+            message,
+            backoff
+        );
+
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = sleep(backoff) => {}
+        }
+
+        backoff = (backoff * 2).min(MAX_RESTART_BACKOFF);
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
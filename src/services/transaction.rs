@@ -1,95 +1,88 @@
+use std::collections::HashMap;
+
 use crate::{
     app::state::AppState,
+    error::AppError,
+    managers::transaction::TransactionFilter,
     models::{
-        transaction::TransactionStatus,
-        types::{InclusionType, TransactionType},
+        event::AppEvent,
+        metrics::Achievement,
+        transaction::{Transaction, TransactionStatus, TransactionStatusKind},
+        types::{InclusionType, Lamports, TransactionType},
+    },
+    utils::auction::{
+        expire_if_stale, record_win, settle_losing_bid, settle_winning_bids, settles_with,
     },
 };
 
+/// Settles the winning session's transactions for `slot` and returns the ones
+/// included in the slot, in execution order. Dependents whose payload would
+/// overflow the slot's byte limit fail instead.
+#[tracing::instrument(skip(state, winner_session, inclusion_type, transaction_type), fields(winning_bid = %winning_bid))]
 pub async fn update_transaction_status_win(
     state: &AppState,
     winner_session: &str,
     slot: u64,
-    winning_bid: f64,
+    winning_bid: Lamports,
     inclusion_type: InclusionType,
     transaction_type: TransactionType,
-) {
-    let session_transactions = state.get_session_transactions(winner_session).await;
+) -> Result<Vec<Transaction>, AppError> {
+    let slot_transactions: Vec<Transaction> = state
+        .get_session_transactions(winner_session)
+        .await
+        .into_iter()
+        .filter(|transaction| settles_with(transaction, &inclusion_type, slot))
+        .collect();
 
-    let mut refund_total = 0.0;
+    let mut included: Vec<Transaction> = Vec::new();
 
-    // Process all transactions for the winning session
-    for mut transaction in session_transactions {
-        if transaction.inclusion_type == inclusion_type
-            && matches!(transaction.status, TransactionStatus::Pending)
-        {
-            if (transaction.priority_fee - winning_bid).abs() < 0.0001 {
-                transaction.mark_included(slot);
-                transaction.mark_auction_won(slot, winning_bid);
-                
-                state
-                    .update_transaction_by_id(&transaction.id, transaction.clone())
-                    .await;
-                
-                tracing::info!(
-                    "Updated transaction {} status to AuctionWon for slot {} with bid {} SOL",
-                    transaction.id.chars().take(8).collect::<String>(),
-                    slot,
-                    winning_bid
-                );
-        } else {
-                transaction.mark_failed(format!(
-                    "Outbid by higher amount. Refunding {} SOL",
-                    transaction.priority_fee
-                ));
-                
-                state
-                    .update_transaction_by_id(&transaction.id, transaction.clone())
-                    .await;
-                
-                refund_total += transaction.priority_fee;
-                
-                tracing::info!(
-                    "Marked transaction {} as failed and queued {} SOL for refund",
-                    transaction.id.chars().take(8).collect::<String>(),
-                    transaction.priority_fee
-                );
-            }
-        }
-    }
+    for transaction in settle_winning_bids(slot_transactions, slot, winning_bid) {
+        state
+            .update_transaction_by_id(&transaction.id, transaction.clone())
+            .await?;
+
+        if matches!(transaction.status, TransactionStatus::AuctionWon { .. }) {
+            state.release_escrow(&transaction).await?;
 
-    // Refund all outbid amounts at once
-    if refund_total > 0.0 {
-        let mut game = state.game.write().await;
-        if let Some(stats) = game.player_stats.get_mut(winner_session) {
-            stats.increment_balance(refund_total);
             tracing::info!(
-                "Refunded total {} SOL to winner {} for outbid transactions",
-                refund_total,
+                "Updated transaction {} status to AuctionWon for slot {} with bid {} SOL",
+                transaction.id.chars().take(8).collect::<String>(),
+                slot,
+                winning_bid
+            );
+
+            included.push(transaction);
+        } else if let Some(refund) = state.refund_escrow(&transaction).await? {
+            tracing::info!(
+                "Marked transaction {} as failed and refunded {} SOL to winner {}",
+                transaction.id.chars().take(8).collect::<String>(),
+                refund,
                 winner_session.chars().take(8).collect::<String>()
             );
         }
     }
 
-    {
-        let mut game = state.game.write().await;
+    let winner = winner_session.to_string();
+    let (stats, achievements) = state
+        .game
+        .call(move |game| {
+            let achievements = record_win(game, &winner, slot, transaction_type);
+            (game.player_stats.get(&winner).cloned(), achievements)
+        })
+        .await?;
+    announce_achievements(state, winner_session, achievements);
 
-        if let Some(stats) = game.player_stats.get_mut(winner_session) {
-            stats.mark_auction_resolved(slot);
-        }
-
-        game.process_auction_win(winner_session, transaction_type);
-
-        if let Some(stats) = game.player_stats.get(winner_session) {
-            tracing::info!(
-                "Player {} won auction! Level: {}, Wins: {}, Balance: {:.3} SOL",
-                winner_session.chars().take(8).collect::<String>(),
-                stats.level,
-                stats.total_auctions_won,
-                stats.balance
-            );
-        }
+    if let Some(stats) = stats {
+        tracing::info!(
+            "Player {} won auction! Level: {}, Wins: {}, Balance: {:.3} SOL",
+            winner_session.chars().take(8).collect::<String>(),
+            stats.level,
+            stats.total_auctions_won,
+            stats.balance
+        );
     }
+
+    Ok(included)
 }
 
 pub async fn update_transaction_status_lose(
@@ -97,18 +90,18 @@ pub async fn update_transaction_status_lose(
     loser_session: &str,
     slot: u64,
     inclusion_type: InclusionType,
-) {
+) -> Result<(), AppError> {
     let session_transactions = state.get_session_transactions(loser_session).await;
+    let mut refund_total = Lamports::ZERO;
 
     for mut transaction in session_transactions {
-        if transaction.inclusion_type == inclusion_type
-            && matches!(transaction.status, TransactionStatus::Pending)
-        {
-            transaction.mark_failed(format!("Lost auction for slot {}", slot));
+        if settles_with(&transaction, &inclusion_type, slot) {
+            settle_losing_bid(&mut transaction, slot);
 
             state
                 .update_transaction_by_id(&transaction.id, transaction.clone())
-                .await;
+                .await?;
+            refund_total += state.refund_escrow(&transaction).await?.unwrap_or_default();
 
             tracing::info!(
                 "Updated transaction {} status to Failed (auction lost) for slot {}",
@@ -117,4 +110,117 @@ pub async fn update_transaction_status_lose(
             );
         }
     }
+
+    if !refund_total.is_zero() {
+        tracing::info!(
+            "Refunded {} SOL to {}",
+            refund_total,
+            loser_session.chars().take(8).collect::<String>()
+        );
+    }
+    Ok(())
+}
+
+/// Tells the player about achievements they just unlocked, on their private
+/// event stream only.
+pub fn announce_achievements(state: &AppState, session_id: &str, achievements: Vec<Achievement>) {
+    for achievement in achievements {
+        tracing::info!(
+            "Player {} unlocked {}",
+            session_id.chars().take(8).collect::<String>(),
+            achievement.name
+        );
+        state
+            .events
+            .send_private(session_id, AppEvent::AchievementUnlocked { achievement });
+    }
+}
+
+/// Marks pending transactions past their TTL as expired and refunds their escrow.
+pub async fn expire_stale_transactions(
+    state: &AppState,
+    current_slot: u64,
+) -> Result<(), AppError> {
+    let expired: Vec<Transaction> = state
+        .transactions
+        .filtered(TransactionFilter {
+            status: Some(TransactionStatusKind::Pending),
+            ..TransactionFilter::default()
+        })
+        .into_iter()
+        .filter_map(|mut transaction| {
+            expire_if_stale(&mut transaction, current_slot).then_some(transaction)
+        })
+        .collect();
+
+    if expired.is_empty() {
+        return Ok(());
+    }
+
+    let mut refunds: HashMap<String, Lamports> = HashMap::new();
+
+    for transaction in expired {
+        tracing::info!(
+            "Transaction {} expired at slot {} without inclusion",
+            transaction.id.chars().take(8).collect::<String>(),
+            current_slot
+        );
+
+        state
+            .update_transaction_by_id(&transaction.id, transaction.clone())
+            .await?;
+
+        if let Some(refund) = state.refund_escrow(&transaction).await? {
+            *refunds.entry(transaction.sender.clone()).or_default() += refund;
+        }
+    }
+
+    for (session_id, refund) in refunds {
+        tracing::info!(
+            "Refunded {} SOL to {} for expired transactions",
+            refund,
+            session_id.chars().take(8).collect::<String>()
+        );
+    }
+    Ok(())
+}
+
+/// Checks that a dependency refers to a pending transaction from the same session
+/// that targets the same slot, so the dependent can execute right after it.
+pub async fn validate_dependency(
+    state: &AppState,
+    session_id: &str,
+    dependency_id: &str,
+    target_slot: u64,
+) -> Result<(), AppError> {
+    let dependency = state
+        .get_transaction_by_id(dependency_id)
+        .await
+        .ok_or_else(|| {
+            AppError::InvalidDependency(format!("Dependency {} not found", dependency_id))
+        })?;
+
+    if dependency.sender != session_id {
+        return Err(AppError::InvalidDependency(
+            "Dependencies must belong to the same session".to_string(),
+        ));
+    }
+
+    if !matches!(dependency.status, TransactionStatus::Pending) {
+        return Err(AppError::InvalidDependency(format!(
+            "Dependency {} is no longer pending",
+            dependency_id
+        )));
+    }
+
+    if dependency.target_slot() != target_slot {
+        return Err(AppError::InvalidDependency(format!(
+            "Dependency {} targets slot {}, not slot {}",
+            dependency_id,
+            dependency.target_slot(),
+            target_slot
+        )));
+    }
+
+    Ok(())
 }
@@ -0,0 +1,280 @@
+//! Fixtures for exercising the full API in-process: a router with no
+//! listener, a slot tick that runs only when the test says so, and a seeded
+//! RNG for generating bids, so auction lifecycles replay identically run to
+//! run.
+
+use std::net::{Ipv4Addr, SocketAddr};
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    extract::ConnectInfo,
+    http::{HeaderValue, Method, Request, StatusCode, header},
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::json;
+use tower::ServiceExt;
+
+use crate::{
+    app::{api::AppContext, state::AppState},
+    config::{GlobalConfig, RateLimitProfile},
+    engine::SimulationEngine,
+    error::AppError,
+    models::{
+        responses::{ApiResponse, BidReceipt, SessionView},
+        types::Lamports,
+    },
+};
+
+pub const DEFAULT_SEED: u64 = 42;
+/// Address every harness request appears to come from
+pub const TEST_CLIENT_ADDR: SocketAddr =
+    SocketAddr::new(std::net::IpAddr::V4(Ipv4Addr::LOCALHOST), 40000);
+const TEST_COMPUTE_UNITS: u64 = 200_000;
+
+/// The default config with rate limits out of the way, and slots a day long
+/// with AOT auctions open for as long, so slots expire and auctions close by
+/// slot number rather than wall time.
+pub fn test_config() -> GlobalConfig {
+    let mut config = GlobalConfig::default();
+    let unlimited = RateLimitProfile {
+        requests_per_second: 1_000_000,
+    };
+    config.rate_limit.read = unlimited.clone();
+    config.rate_limit.write = unlimited.clone();
+    config.rate_limit.auth = unlimited;
+    config.marketplace.slot_duration_ms = 86_400_000;
+    config.auction.aot_default_duration_sec = 86_400;
+    config
+}
+
+/// A response collected in full.
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    pub fn json<T: DeserializeOwned>(&self) -> anyhow::Result<ApiResponse<T>> {
+        serde_json::from_slice(&self.body).map_err(|e| {
+            anyhow::anyhow!(
+                "Unexpected {} response body: {} ({})",
+                self.status,
+                String::from_utf8_lossy(&self.body),
+                e
+            )
+        })
+    }
+
+    /// The response's `data`, failing unless the request succeeded.
+    pub fn data<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        if !self.status.is_success() {
+            anyhow::bail!(
+                "Request failed with {}: {}",
+                self.status,
+                String::from_utf8_lossy(&self.body)
+            );
+        }
+        self.json::<T>()?
+            .into_data()
+            .ok_or_else(|| anyhow::anyhow!("Response with {} had no data", self.status))
+    }
+
+    pub fn error_code(&self) -> Option<String> {
+        self.json::<serde_json::Value>()
+            .ok()
+            .and_then(|body| body.error_code().map(str::to_string))
+    }
+}
+
+/// One simulator with no background tasks, so slots advance only on
+/// `advance_slots`. Timestamps such as `ends_at` still come from the wall
+/// clock; `test_config` makes slots and AOT auctions long enough that none of
+/// them runs out during a test.
+pub struct TestHarness {
+    engine: SimulationEngine,
+    router: Router,
+    rng: StdRng,
+    seed: u64,
+}
+
+impl TestHarness {
+    pub fn new() -> anyhow::Result<Self> {
+        Self::with_config(test_config(), DEFAULT_SEED)
+    }
+
+    /// Must be called inside a Tokio runtime, like `SimulationEngine::new`.
+    pub fn with_config(config: GlobalConfig, seed: u64) -> anyhow::Result<Self> {
+        let engine = SimulationEngine::new(config)?;
+        let router = engine.router();
+
+        Ok(Self {
+            engine,
+            router,
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+        })
+    }
+
+    pub fn engine(&self) -> &SimulationEngine {
+        &self.engine
+    }
+
+    pub fn context(&self) -> &AppContext {
+        self.engine.context()
+    }
+
+    pub fn state(&self) -> &AppState {
+        self.engine.state()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    pub async fn current_slot(&self) -> Result<u64, AppError> {
+        self.state().get_current_slot().await
+    }
+
+    /// Advances `count` slots, resolving each one's auctions in turn, and
+    /// returns the new current slot.
+    pub async fn advance_slots(&self, count: u64) -> Result<u64, AppError> {
+        for _ in 0..count {
+            self.engine.tick().await?;
+        }
+        self.current_slot().await
+    }
+
+    /// Runs a request through the router as if it came from `TEST_CLIENT_ADDR`.
+    pub async fn request(&self, mut request: Request<Body>) -> anyhow::Result<TestResponse> {
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(TEST_CLIENT_ADDR));
+        let response = match self.router.clone().oneshot(request).await {
+            Ok(response) => response,
+            Err(never) => match never {},
+        };
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await?;
+
+        Ok(TestResponse {
+            status,
+            body: body.to_vec(),
+        })
+    }
+
+    /// Sends `body` as JSON, authenticated as `session_id` if given.
+    pub async fn send<B: Serialize>(
+        &self,
+        method: Method,
+        path: &str,
+        session_id: Option<&str>,
+        body: Option<&B>,
+    ) -> anyhow::Result<TestResponse> {
+        let mut builder = Request::builder().method(method).uri(path);
+        if let Some(session_id) = session_id {
+            builder = builder.header(
+                header::COOKIE,
+                HeaderValue::from_str(&format!(
+                    "{}={}",
+                    self.state().sessions.cookie().name,
+                    self.state().sessions.session_token(session_id)
+                ))?,
+            );
+        }
+        let request = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(body)?))?,
+            None => builder.body(Body::empty())?,
+        };
+        self.request(request).await
+    }
+
+    pub async fn get(&self, path: &str, session_id: Option<&str>) -> anyhow::Result<TestResponse> {
+        self.send::<()>(Method::GET, path, session_id, None).await
+    }
+
+    /// Starts a player session through `POST /sessions` and returns its ID.
+    pub async fn create_session(&self) -> anyhow::Result<String> {
+        let session: SessionView = self
+            .send::<()>(Method::POST, "/sessions", None, None)
+            .await?
+            .data()?;
+        Ok(session.session_id)
+    }
+
+    /// Starts `count` player sessions.
+    pub async fn create_sessions(&self, count: usize) -> anyhow::Result<Vec<String>> {
+        let mut sessions = Vec::with_capacity(count);
+        for _ in 0..count {
+            sessions.push(self.create_session().await?);
+        }
+        Ok(sessions)
+    }
+
+    /// Bids for the next slot; the response is returned as is so tests can
+    /// check rejections too.
+    pub async fn submit_jit_bid(
+        &self,
+        session_id: &str,
+        amount: Lamports,
+    ) -> anyhow::Result<TestResponse> {
+        let body = json!({
+            "bid_amount": amount,
+            "compute_units": TEST_COMPUTE_UNITS,
+            "data": "test",
+        });
+        self.send(
+            Method::POST,
+            "/transactions/jit",
+            Some(session_id),
+            Some(&body),
+        )
+        .await
+    }
+
+    pub async fn submit_aot_bid(
+        &self,
+        session_id: &str,
+        slot_number: u64,
+        amount: Lamports,
+    ) -> anyhow::Result<TestResponse> {
+        let body = json!({
+            "slot_number": slot_number,
+            "bid_amount": amount,
+            "compute_units": TEST_COMPUTE_UNITS,
+            "data": "test",
+        });
+        self.send(
+            Method::POST,
+            "/transactions/aot",
+            Some(session_id),
+            Some(&body),
+        )
+        .await
+    }
+
+    /// A JIT bid of a seeded random amount between `min` and `max`, which must
+    /// succeed.
+    pub async fn random_jit_bid(
+        &mut self,
+        session_id: &str,
+        min: Lamports,
+        max: Lamports,
+    ) -> anyhow::Result<BidReceipt> {
+        let amount = Lamports(self.rng.random_range(min.0..=max.0));
+        self.submit_jit_bid(session_id, amount).await?.data()
+    }
+}
+
+impl Drop for TestHarness {
+    fn drop(&mut self) {
+        self.engine.shutdown_token().cancel();
+    }
+}
@@ -0,0 +1,238 @@
+//! Rules for settling resolved auctions, shared by the server's slot tick and
+//! the practice game. Each function works on plain values; the caller moves
+//! the escrow and stores the results however its state is held.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    MAX_PAYLOAD_BYTES_PER_SLOT,
+    managers::{
+        escrow::{EscrowRecord, EscrowStatus},
+        game::GameManager,
+    },
+    models::{
+        auction::ResolvedAuction,
+        metrics::Achievement,
+        slot::{Slot, SlotState},
+        transaction::{Transaction, TransactionStatus},
+        types::{InclusionType, Lamports, TransactionType},
+    },
+    utils::transaction::order_by_dependencies,
+};
+
+/// Whether `transaction` is a pending bid settled when the `inclusion_type`
+/// auction for `slot` resolves.
+pub fn settles_with(transaction: &Transaction, inclusion_type: &InclusionType, slot: u64) -> bool {
+    transaction.inclusion_type == *inclusion_type
+        && transaction.target_slot() == slot
+        && matches!(transaction.status, TransactionStatus::Pending)
+}
+
+/// Settles the winner's pending bids for `slot` in dependency order. The bid
+/// that set the clearing price executes, then each dependent whose
+/// prerequisite executed; a dependent whose payload would overflow the slot's
+/// byte limit fails. Returns every bid in that order, marked won or failed, so
+/// the caller releases the escrow of the won ones and refunds the rest.
+pub fn settle_winning_bids(
+    transactions: Vec<Transaction>,
+    slot: u64,
+    winning_bid: Lamports,
+) -> Vec<Transaction> {
+    let mut settled = Vec::with_capacity(transactions.len());
+    let mut included_ids: HashSet<String> = HashSet::new();
+    let mut payload_bytes = 0;
+
+    for mut transaction in order_by_dependencies(transactions) {
+        let executes = match &transaction.depends_on {
+            Some(dependency_id) => included_ids.contains(dependency_id),
+            None => transaction.priority_fee == winning_bid,
+        };
+        let fits = payload_bytes + transaction.data.len() <= MAX_PAYLOAD_BYTES_PER_SLOT;
+
+        if executes && fits {
+            payload_bytes += transaction.data.len();
+            transaction.mark_won(slot, winning_bid);
+            included_ids.insert(transaction.id.clone());
+        } else {
+            match &transaction.depends_on {
+                _ if executes => transaction.mark_failed(format!(
+                    "Payload of {} bytes does not fit in the {} bytes left in slot {}. Refunding {} SOL",
+                    transaction.data.len(),
+                    MAX_PAYLOAD_BYTES_PER_SLOT - payload_bytes,
+                    slot,
+                    transaction.priority_fee
+                )),
+                Some(dependency_id) => transaction.mark_failed(format!(
+                    "Dependency {} was not executed in slot {}. Refunding {} SOL",
+                    dependency_id, slot, transaction.priority_fee
+                )),
+                None => transaction.mark_failed(format!(
+                    "Outbid by higher amount. Refunding {} SOL",
+                    transaction.priority_fee
+                )),
+            }
+        }
+        settled.push(transaction);
+    }
+
+    settled
+}
+
+/// Fails a loser's pending bid for `slot`; its escrow is then refunded.
+pub fn settle_losing_bid(transaction: &mut Transaction, slot: u64) {
+    transaction.mark_failed(format!("Lost auction for slot {}", slot));
+}
+
+/// Expires a pending bid past its TTL at `current_slot`, returning whether it
+/// expired; its escrow is then refunded.
+pub fn expire_if_stale(transaction: &mut Transaction, current_slot: u64) -> bool {
+    if !transaction.is_expired(current_slot) {
+        return false;
+    }
+    transaction.mark_expired(current_slot);
+    true
+}
+
+/// Reserves the slot for the auction's winner with the bids that executed, in
+/// execution order. A JIT slot is filled at once.
+pub fn fill_slot(
+    slot: &mut Slot,
+    winner: String,
+    winning_bid: Lamports,
+    transaction_type: TransactionType,
+    included: &[Transaction],
+) {
+    slot.reserve(winner.clone(), winning_bid, transaction_type);
+    if transaction_type == TransactionType::Jit {
+        let transaction_id = included
+            .first()
+            .map(|transaction| transaction.id.clone())
+            .unwrap_or_else(|| format!("transaction_{}", slot.slot_number));
+        let compute_units = included
+            .iter()
+            .map(|transaction| transaction.compute_units)
+            .sum();
+        slot.fill(winner, transaction_id, compute_units);
+    }
+    slot.record_payload_bytes(
+        included
+            .iter()
+            .map(|transaction| transaction.data.len())
+            .sum(),
+    );
+    slot.set_execution_order(
+        included
+            .iter()
+            .map(|transaction| transaction.id.clone())
+            .collect(),
+    );
+}
+
+/// State a slot shows once `transaction` has entered its auction. AOT slots
+/// show when their auction ends, so nothing changes if it has already closed.
+pub fn bid_slot_state(
+    transaction: &Transaction,
+    aot_ends_at: Option<DateTime<Utc>>,
+) -> Option<SlotState> {
+    let (bidder, amount) = (transaction.sender.clone(), transaction.priority_fee);
+    match transaction.transaction_type() {
+        TransactionType::Jit => Some(SlotState::JitAuction {
+            current_bid: amount,
+            bidder,
+        }),
+        TransactionType::Aot => aot_ends_at.map(|ends_at| SlotState::AotAuction {
+            highest_bid: amount,
+            highest_bidder: bidder.clone(),
+            bids: vec![(bidder, amount)],
+            ends_at,
+        }),
+    }
+}
+
+/// Records the win on the winner's stats, returning the achievements it unlocked.
+pub fn record_win(
+    game: &mut GameManager,
+    winner: &str,
+    slot: u64,
+    transaction_type: TransactionType,
+) -> Vec<Achievement> {
+    if let Some(stats) = game.player_stats.get_mut(winner) {
+        stats.mark_auction_resolved(slot);
+    }
+    game.process_auction_win(winner, transaction_type)
+}
+
+/// Records the loss on each loser's stats, once however many bids they
+/// placed, returning the achievements each unlocked.
+pub fn record_losses(
+    game: &mut GameManager,
+    slot: u64,
+    losers: &[String],
+) -> Vec<(String, Vec<Achievement>)> {
+    losers
+        .iter()
+        .map(|loser| {
+            if let Some(stats) = game.player_stats.get_mut(loser) {
+                stats.mark_auction_resolved(slot);
+            }
+            (loser.clone(), game.process_auction_loss(loser))
+        })
+        .collect()
+}
+
+/// Losers of an auction, each once, in a stable order.
+pub fn distinct_losers(mut losers: Vec<String>) -> Vec<String> {
+    losers.sort();
+    losers.dedup();
+    losers
+}
+
+/// The auction's history entry, from the escrow records of its slot once every
+/// bid is settled. `created_slot` looks up the slot a transaction was placed in.
+pub fn resolved_auction(
+    records: &[EscrowRecord],
+    slot_number: u64,
+    auction_type: TransactionType,
+    winner: String,
+    clearing_price: Lamports,
+    created_slot: impl Fn(&str) -> Option<u64>,
+) -> ResolvedAuction {
+    let records: Vec<&EscrowRecord> = records
+        .iter()
+        .filter(|record| record.slot_number == slot_number && record.auction_type == auction_type)
+        .collect();
+
+    // How far ahead of the slot the winning bid was placed
+    let lead_slots = records
+        .iter()
+        .filter(|record| record.status == EscrowStatus::Released && record.bidder == winner)
+        .filter_map(|record| created_slot(&record.transaction_id))
+        .map(|created_slot| slot_number.saturating_sub(created_slot))
+        .max()
+        .unwrap_or(1);
+
+    let mut losers: Vec<String> = Vec::new();
+    let mut refunded = Lamports::ZERO;
+    for record in &records {
+        if record.status == EscrowStatus::Refunded && record.bidder != winner {
+            refunded += record.amount;
+            if !losers.contains(&record.bidder) {
+                losers.push(record.bidder.clone());
+            }
+        }
+    }
+
+    ResolvedAuction {
+        slot_number,
+        auction_type,
+        winner,
+        clearing_price,
+        bid_count: records.len(),
+        lead_slots,
+        losers,
+        refunded,
+        resolved_at: Utc::now(),
+    }
+}
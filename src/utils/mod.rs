@@ -1,2 +1,10 @@
+pub mod auction;
+#[cfg(feature = "server")]
 pub mod rate_limiter;
+pub mod request_id;
+#[cfg(feature = "server")]
+pub mod rpc;
+#[cfg(feature = "server")]
+pub mod telemetry;
 pub mod transaction;
+pub mod validation;
@@ -1,18 +1,36 @@
 use std::{
     net::SocketAddr,
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
     time::{Duration, Instant},
 };
 
+use axum::http::Method;
 use dashmap::DashMap;
 
+use crate::config::{RateLimitConfig, RouteClass};
+
 #[derive(Clone)]
 pub struct RateLimiter {
-    buckets: Arc<DashMap<String, TokenBucket>>,
-    requests_per_window: u32,
+    buckets: Arc<DashMap<(RouteClass, String), TokenBucket>>,
+    read_requests_per_window: Arc<AtomicU32>,
+    write_requests_per_window: Arc<AtomicU32>,
+    auth_requests_per_window: Arc<AtomicU32>,
     window_duration: Duration,
 }
 
+/// Outcome of a rate limit check, reported to clients in `X-RateLimit-*` headers.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitStatus {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the client's window resets
+    pub reset_after: Duration,
+}
+
 #[derive(Debug)]
 struct TokenBucket {
     tokens: u32,
@@ -22,11 +40,33 @@ struct TokenBucket {
 }
 
 impl RateLimiter {
-    pub fn new(requests_per_second: u32) -> Self {
-        Self {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let limiter = Self {
             buckets: Arc::new(DashMap::new()),
-            requests_per_window: requests_per_second * 60,
+            read_requests_per_window: Arc::new(AtomicU32::new(0)),
+            write_requests_per_window: Arc::new(AtomicU32::new(0)),
+            auth_requests_per_window: Arc::new(AtomicU32::new(0)),
             window_duration: Duration::from_secs(60),
+        };
+        limiter.apply_config(config);
+        limiter
+    }
+
+    /// Changes the limits for all clients; windows already in progress keep their count.
+    pub fn apply_config(&self, config: &RateLimitConfig) {
+        for class in RouteClass::ALL {
+            self.requests_per_window(class).store(
+                config.profile(class).requests_per_second.saturating_mul(60),
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    fn requests_per_window(&self, class: RouteClass) -> &AtomicU32 {
+        match class {
+            RouteClass::Read => &self.read_requests_per_window,
+            RouteClass::Write => &self.write_requests_per_window,
+            RouteClass::Auth => &self.auth_requests_per_window,
         }
     }
 
@@ -34,14 +74,32 @@ impl RateLimiter {
         addr.ip().to_string()
     }
 
-    pub fn check_rate_limit(&self, client_key: &str) -> bool {
+    /// Sessions, credentials and admin calls are `Auth`; any other mutating request is
+    /// `Write`.
+    pub fn classify(method: &Method, path: &str) -> RouteClass {
+        // Chain requests are classed like the endpoint they forward to
+        let path = path
+            .strip_prefix("/chains/")
+            .and_then(|rest| rest.find('/').map(|index| &rest[index..]))
+            .unwrap_or(path);
+        if path.starts_with("/sessions") || path.starts_with("/admin") {
+            RouteClass::Auth
+        } else if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            RouteClass::Read
+        } else {
+            RouteClass::Write
+        }
+    }
+
+    pub fn check_rate_limit(&self, class: RouteClass, client_key: &str) -> RateLimitStatus {
         let now = Instant::now();
+        let requests_per_window = self.requests_per_window(class).load(Ordering::Relaxed);
 
         let mut entry = self
             .buckets
-            .entry(client_key.to_string())
+            .entry((class, client_key.to_string()))
             .or_insert(TokenBucket {
-                tokens: self.requests_per_window,
+                tokens: requests_per_window,
                 last_refill: now,
                 window_start: now,
                 request_count: 0,
@@ -51,15 +109,22 @@ impl RateLimiter {
         if now.duration_since(entry.window_start) >= self.window_duration {
             entry.window_start = now;
             entry.request_count = 0;
-            entry.tokens = self.requests_per_window;
+            entry.tokens = requests_per_window;
         }
 
-        if entry.request_count >= self.requests_per_window {
-            return false;
+        let allowed = entry.request_count < requests_per_window;
+        if allowed {
+            entry.request_count += 1;
+            entry.last_refill = now;
         }
 
-        entry.request_count += 1;
-        entry.last_refill = now;
-        true
+        RateLimitStatus {
+            allowed,
+            limit: requests_per_window,
+            remaining: requests_per_window.saturating_sub(entry.request_count),
+            reset_after: self
+                .window_duration
+                .saturating_sub(now.duration_since(entry.window_start)),
+        }
     }
 }
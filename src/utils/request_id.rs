@@ -0,0 +1,34 @@
+#[cfg(feature = "server")]
+use std::future::Future;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+#[cfg(feature = "server")]
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Request ID of the HTTP request currently being handled, if any.
+#[cfg(feature = "server")]
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+}
+
+/// Without the server there are no HTTP requests to correlate with.
+#[cfg(not(feature = "server"))]
+pub fn current_request_id() -> Option<String> {
+    None
+}
+
+/// Runs `future` with `request_id` available through [`current_request_id`].
+#[cfg(feature = "server")]
+pub async fn scope_request_id<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+/// Accepts client-supplied IDs only if they are short, printable ASCII.
+pub fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= 128
+        && request_id.bytes().all(|b| b.is_ascii_graphic())
+}
@@ -0,0 +1,64 @@
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<u64>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// Minimal read-only JSON-RPC client used to follow a real Solana cluster's slot height.
+#[derive(Clone)]
+pub struct SolanaRpcClient {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl SolanaRpcClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub async fn get_slot(&self) -> Result<u64> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getSlot",
+            "params": [{ "commitment": "processed" }]
+        });
+
+        let response: RpcResponse = self
+            .client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        match (response.result, response.error) {
+            (Some(slot), _) => Ok(slot),
+            (None, Some(error)) => Err(anyhow!(
+                "getSlot failed with code {}: {}",
+                error.code,
+                error.message
+            )),
+            (None, None) => Err(anyhow!("getSlot returned an empty response")),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{Result, anyhow};
+use opentelemetry::{KeyValue, trace::TracerProvider};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
+use tracing_subscriber::{
+    Layer, Registry, filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+use crate::config::{LogFormat, TelemetryConfig};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Installs the global tracing subscriber. When an OTLP endpoint is configured, spans
+/// are additionally exported so a bid can be followed from HTTP ingress to resolution.
+///
+/// The returned provider must be shut down on exit to flush buffered spans.
+pub fn init_tracing(config: &TelemetryConfig) -> Result<Option<SdkTracerProvider>> {
+    let mut layers: Vec<BoxedLayer> = Vec::new();
+
+    layers.push(match config.log_format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .with_filter(LevelFilter::INFO)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_filter(LevelFilter::INFO)
+            .boxed(),
+    });
+
+    let provider = match &config.otlp_endpoint {
+        Some(endpoint) => {
+            let exporter = SpanExporter::builder()
+                .with_http()
+                .with_endpoint(endpoint)
+                .build()
+                .map_err(|e| anyhow!("Failed to build OTLP exporter for {}: {}", endpoint, e))?;
+
+            let provider = SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(
+                    Resource::builder()
+                        .with_service_name(config.service_name.clone())
+                        .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+                        .build(),
+                )
+                .build();
+
+            layers.push(
+                tracing_opentelemetry::layer()
+                    .with_tracer(provider.tracer(config.service_name.clone()))
+                    .with_filter(LevelFilter::INFO)
+                    .boxed(),
+            );
+
+            Some(provider)
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .try_init()
+        .map_err(|e| anyhow!("Failed to install tracing subscriber: {}", e))?;
+
+    Ok(provider)
+}
@@ -1,10 +1,50 @@
-use anyhow::{Result, anyhow};
+use std::collections::{HashMap, HashSet, VecDeque};
+
 use rand::Rng;
 
-use crate::config::GlobalConfig;
+use crate::models::{transaction::Transaction, types::Lamports};
+
+pub fn calculate_base_fee(base_fee: Lamports) -> Lamports {
+    base_fee.scale(rand::rng().random_range(1.0..10.0))
+}
+
+/// Topologically orders a slot's transactions so each one follows the transaction it
+/// depends on. Independent transactions keep their submission order.
+pub fn order_by_dependencies(mut transactions: Vec<Transaction>) -> Vec<Transaction> {
+    transactions.sort_by_key(|transaction| transaction.created_at);
+
+    let ids: HashSet<String> = transactions.iter().map(|t| t.id.clone()).collect();
+    let mut dependents: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut queue: VecDeque<usize> = VecDeque::new();
+
+    for (index, transaction) in transactions.iter().enumerate() {
+        match &transaction.depends_on {
+            Some(dependency_id) if ids.contains(dependency_id) => dependents
+                .entry(dependency_id.clone())
+                .or_default()
+                .push(index),
+            _ => queue.push_back(index),
+        }
+    }
+
+    let mut order = Vec::with_capacity(transactions.len());
+    let mut visited = vec![false; transactions.len()];
+
+    while let Some(index) = queue.pop_front() {
+        visited[index] = true;
+        order.push(index);
+
+        if let Some(children) = dependents.get(&transactions[index].id) {
+            queue.extend(children.iter().copied());
+        }
+    }
 
-pub fn calculate_base_fee() -> Result<f64> {
-    let config = GlobalConfig::from_env().map_err(|e| anyhow!("Configuration error: {}", e))?;
+    // Anything left over is part of a cycle and can never satisfy its dependency
+    order.extend((0..transactions.len()).filter(|index| !visited[*index]));
 
-    Ok(config.marketplace.base_fee_sol * rand::rng().random_range(1.0..10.0))
+    let mut pending: Vec<Option<Transaction>> = transactions.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|index| pending[index].take())
+        .collect()
 }
@@ -0,0 +1,68 @@
+#[cfg(feature = "server")]
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+};
+use serde::Serialize;
+#[cfg(feature = "server")]
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "server")]
+use crate::error::AppError;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Request payloads that check their own fields once deserialized.
+pub trait Validate {
+    /// Returns every failing field, or an empty list if the payload is valid.
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+/// JSON body extractor that rejects payloads which fail to deserialize or
+/// validate with a 422 listing the offending fields.
+#[cfg(feature = "server")]
+pub struct ValidatedJson<T>(pub T);
+
+#[cfg(feature = "server")]
+impl<S, T> FromRequest<S> for ValidatedJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = Bytes::from_request(req, state).await.map_err(|rejection| {
+            AppError::Validation(vec![FieldError::new("body", rejection.body_text())])
+        })?;
+
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        let value: T = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let field = match e.path().to_string() {
+                path if path == "." => "body".to_string(),
+                path => path,
+            };
+            AppError::Validation(vec![FieldError::new(field, e.inner().to_string())])
+        })?;
+
+        let errors = value.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+
+        Ok(Self(value))
+    }
+}
@@ -0,0 +1,69 @@
+//! JavaScript bindings for the practice game, built with
+//! `wasm-pack build --no-default-features --features wasm`. Results cross the
+//! boundary as JSON in the same shapes the server's API and event stream use.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    models::types::Lamports,
+    practice::{PracticeGame, PracticeSettings},
+};
+
+#[wasm_bindgen]
+pub struct PracticeSession {
+    game: PracticeGame,
+}
+
+#[wasm_bindgen]
+impl PracticeSession {
+    /// Starts a practice game against `rivals` bidders with the server's
+    /// default marketplace settings.
+    #[wasm_bindgen(constructor)]
+    pub fn new(rivals: usize) -> PracticeSession {
+        Self {
+            game: PracticeGame::new(PracticeSettings {
+                rivals,
+                ..PracticeSettings::default()
+            }),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn current_slot(&self) -> u64 {
+        self.game.current_slot()
+    }
+
+    /// Advances one slot; returns the events it produced as a JSON array.
+    pub fn tick(&mut self) -> Result<String, JsError> {
+        to_json(&self.game.tick())
+    }
+
+    /// Bids for the next slot; returns the transaction as JSON.
+    pub fn submit_jit_bid(&mut self, amount_sol: f64) -> Result<String, JsError> {
+        let transaction = self
+            .game
+            .submit_jit_bid(Lamports::from_sol(amount_sol))
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        to_json(&transaction)
+    }
+
+    /// Bids for `slot`; returns the transaction as JSON.
+    pub fn submit_aot_bid(&mut self, slot: u64, amount_sol: f64) -> Result<String, JsError> {
+        let transaction = self
+            .game
+            .submit_aot_bid(slot, Lamports::from_sol(amount_sol))
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        to_json(&transaction)
+    }
+
+    /// The player's stats, the listed slots, their transactions and the
+    /// auction history, as JSON.
+    pub fn snapshot(&self) -> Result<String, JsError> {
+        to_json(&self.game.snapshot())
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, JsError> {
+    serde_json::to_string(value).map_err(|e| JsError::new(&e.to_string()))
+}
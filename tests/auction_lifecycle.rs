@@ -0,0 +1,370 @@
+//! Full auction lifecycles through the HTTP API, run in-process on the
+//! `testing` harness: bids escrowed, auctions settled, bids cancelled and
+//! bundles rolled back, with the ledger balanced throughout.
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use raiku_simulator::{
+    models::{
+        player::PlayerStats,
+        responses::{
+            BidCancellation, BidReceipt, BundleReceipt, TransactionDetail, TransactionPage,
+        },
+        slot::{Slot, SlotState},
+        transaction::{Transaction, TransactionStatus},
+        types::Lamports,
+    },
+    services::ledger::audit_ledger,
+    testing::TestHarness,
+};
+use serde_json::json;
+use tokio::time::timeout;
+
+/// How far ahead AOT bids in these tests target
+const AOT_LEAD_SLOTS: u64 = 5;
+
+async fn balance(harness: &TestHarness, session_id: &str) -> anyhow::Result<Lamports> {
+    let stats: PlayerStats = harness
+        .get("/game/player_stats", Some(session_id))
+        .await?
+        .data()?;
+    Ok(stats.balance)
+}
+
+async fn transaction(
+    harness: &TestHarness,
+    session_id: &str,
+    transaction_id: &str,
+) -> anyhow::Result<Transaction> {
+    let detail: TransactionDetail = harness
+        .get(
+            &format!("/transactions/{}", transaction_id),
+            Some(session_id),
+        )
+        .await?
+        .data()?;
+    Ok(detail.transaction)
+}
+
+async fn slot(harness: &TestHarness, slot_number: u64) -> anyhow::Result<Slot> {
+    harness
+        .state()
+        .slots
+        .shard(slot_number)
+        .call(move |shard| shard.get(slot_number).cloned())
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Slot {} is not in the window", slot_number))
+}
+
+async fn assert_balanced(harness: &TestHarness) -> anyhow::Result<()> {
+    let report = audit_ledger(harness.state()).await?;
+    assert!(report.balanced, "ledger out of balance: {:?}", report);
+    Ok(())
+}
+
+#[tokio::test]
+async fn jit_auction_charges_the_winner_and_refunds_the_loser() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let players = harness.create_sessions(2).await?;
+    let (loser, winner) = (&players[0], &players[1]);
+    let starting_balance = balance(&harness, loser).await?;
+    let (low, high) = (Lamports::from_sol(0.01), Lamports::from_sol(0.02));
+
+    let lost: BidReceipt = harness.submit_jit_bid(loser, low).await?.data()?;
+    let won: BidReceipt = harness.submit_jit_bid(winner, high).await?.data()?;
+    assert_eq!(lost.slot_number, won.slot_number);
+
+    // Both bids sit in escrow until the auction resolves
+    assert_eq!(balance(&harness, loser).await?, starting_balance - low);
+    assert_eq!(balance(&harness, winner).await?, starting_balance - high);
+    assert_balanced(&harness).await?;
+
+    assert_eq!(harness.advance_slots(1).await?, won.slot_number);
+
+    let won = transaction(&harness, winner, &won.transaction_id).await?;
+    assert!(matches!(
+        won.status,
+        TransactionStatus::AuctionWon { winning_bid, .. } if winning_bid == high
+    ));
+    let lost = transaction(&harness, loser, &lost.transaction_id).await?;
+    assert!(matches!(lost.status, TransactionStatus::Failed { .. }));
+
+    assert_eq!(balance(&harness, loser).await?, starting_balance);
+    assert_eq!(balance(&harness, winner).await?, starting_balance - high);
+    assert!(matches!(
+        slot(&harness, won.target_slot()).await?.state,
+        SlotState::Filled { ref winner, .. } if winner == &players[1]
+    ));
+    assert_balanced(&harness).await
+}
+
+#[tokio::test]
+async fn aot_auction_settles_when_its_slot_arrives() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let players = harness.create_sessions(2).await?;
+    let (loser, winner) = (&players[0], &players[1]);
+    let starting_balance = balance(&harness, loser).await?;
+    let slot_number = harness.current_slot().await? + AOT_LEAD_SLOTS;
+    let (low, high) = (Lamports::from_sol(0.01), Lamports::from_sol(0.02));
+
+    let lost: BidReceipt = harness
+        .submit_aot_bid(loser, slot_number, low)
+        .await?
+        .data()?;
+    let won: BidReceipt = harness
+        .submit_aot_bid(winner, slot_number, high)
+        .await?
+        .data()?;
+
+    // Still open one slot before
+    harness.advance_slots(AOT_LEAD_SLOTS - 1).await?;
+    let pending = transaction(&harness, loser, &lost.transaction_id).await?;
+    assert!(matches!(pending.status, TransactionStatus::Pending));
+    assert_eq!(balance(&harness, loser).await?, starting_balance - low);
+
+    harness.advance_slots(1).await?;
+
+    let won = transaction(&harness, winner, &won.transaction_id).await?;
+    assert!(matches!(won.status, TransactionStatus::AuctionWon { .. }));
+    let lost = transaction(&harness, loser, &lost.transaction_id).await?;
+    assert!(matches!(lost.status, TransactionStatus::Failed { .. }));
+    assert_eq!(balance(&harness, loser).await?, starting_balance);
+    assert_eq!(balance(&harness, winner).await?, starting_balance - high);
+    assert!(matches!(
+        slot(&harness, slot_number).await?.state,
+        SlotState::Reserved { winning_bid, .. } if winning_bid == high
+    ));
+    assert_balanced(&harness).await
+}
+
+#[tokio::test]
+async fn cancelling_a_bid_refunds_it_and_frees_the_slot() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let player = harness.create_session().await?;
+    let starting_balance = balance(&harness, &player).await?;
+    let slot_number = harness.current_slot().await? + AOT_LEAD_SLOTS;
+    let amount = Lamports::from_sol(0.01);
+
+    let bid: BidReceipt = harness
+        .submit_aot_bid(&player, slot_number, amount)
+        .await?
+        .data()?;
+    assert!(matches!(
+        slot(&harness, slot_number).await?.state,
+        SlotState::AotAuction { .. }
+    ));
+
+    let cancellation: BidCancellation = harness
+        .send::<()>(
+            Method::POST,
+            &format!("/transactions/{}/cancel", bid.transaction_id),
+            Some(&player),
+            None,
+        )
+        .await?
+        .data()?;
+    assert_eq!(cancellation.refunded, amount);
+    assert!(matches!(
+        cancellation.transaction.status,
+        TransactionStatus::Cancelled
+    ));
+    assert_eq!(balance(&harness, &player).await?, starting_balance);
+    assert_eq!(
+        slot(&harness, slot_number).await?.state,
+        SlotState::Available
+    );
+    assert_balanced(&harness).await?;
+
+    // A cancelled bid is settled and cannot be withdrawn twice
+    let again = harness
+        .send::<()>(
+            Method::POST,
+            &format!("/transactions/{}/cancel", bid.transaction_id),
+            Some(&player),
+            None,
+        )
+        .await?;
+    assert_eq!(again.status, StatusCode::CONFLICT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancelling_one_of_several_bids_keeps_the_rest_of_the_ladder() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let players = harness.create_sessions(3).await?;
+    let slot_number = harness.current_slot().await? + AOT_LEAD_SLOTS;
+
+    let mut receipts = Vec::new();
+    for (player, sol) in players.iter().zip([0.01, 0.02, 0.03]) {
+        let receipt: BidReceipt = harness
+            .submit_aot_bid(player, slot_number, Lamports::from_sol(sol))
+            .await?
+            .data()?;
+        receipts.push(receipt);
+    }
+
+    harness
+        .send::<()>(
+            Method::POST,
+            &format!("/transactions/{}/cancel", receipts[2].transaction_id),
+            Some(&players[2]),
+            None,
+        )
+        .await?
+        .data::<BidCancellation>()?;
+
+    let SlotState::AotAuction {
+        highest_bidder,
+        highest_bid,
+        bids,
+        ..
+    } = slot(&harness, slot_number).await?.state
+    else {
+        panic!("slot {} should still be in its AOT auction", slot_number);
+    };
+    assert_eq!(highest_bidder, players[1]);
+    assert_eq!(highest_bid, Lamports::from_sol(0.02));
+    assert_eq!(
+        bids,
+        vec![
+            (players[1].clone(), Lamports::from_sol(0.02)),
+            (players[0].clone(), Lamports::from_sol(0.01)),
+        ]
+    );
+    assert_balanced(&harness).await
+}
+
+#[tokio::test]
+async fn rejected_bundle_leg_rolls_back_the_whole_bundle() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let player = harness.create_session().await?;
+    let starting_balance = balance(&harness, &player).await?;
+    let slot_number = harness.current_slot().await? + AOT_LEAD_SLOTS;
+    let mut events = harness.state().events.subscribe();
+
+    // The AOT leg enters its auction first, then the JIT leg is too low
+    let bundle = json!({
+        "legs": [
+            {
+                "type": "aot",
+                "slot_number": slot_number,
+                "bid_amount": Lamports::from_sol(0.01),
+                "compute_units": 200_000,
+                "data": "test",
+            },
+            {
+                "type": "jit",
+                "bid_amount": Lamports(1),
+                "compute_units": 200_000,
+                "data": "test",
+            },
+        ],
+    });
+    let response = harness
+        .send(
+            Method::POST,
+            "/transactions/bundle",
+            Some(&player),
+            Some(&bundle),
+        )
+        .await?;
+    assert!(!response.status.is_success());
+    assert_eq!(response.error_code().as_deref(), Some("bid_too_low"));
+
+    assert_eq!(balance(&harness, &player).await?, starting_balance);
+    let page: TransactionPage = harness.get("/transactions", Some(&player)).await?.data()?;
+    assert!(page.transactions.is_empty());
+    let aot_bids = harness
+        .state()
+        .auctions
+        .shard(slot_number)
+        .call(move |auctions| {
+            auctions
+                .aot_auctions
+                .get(&slot_number)
+                .map_or(0, |auction| auction.bids.len())
+        })
+        .await?;
+    assert_eq!(aot_bids, 0);
+    assert_balanced(&harness).await?;
+
+    // The withdrawn leg was never announced
+    while let Ok(Ok(event)) = timeout(Duration::from_millis(10), events.recv()).await {
+        assert!(
+            !event.payload.contains("BidSubmitted"),
+            "rolled back bundle leaked {}",
+            event.payload
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn bundle_legs_are_all_escrowed_together() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let player = harness.create_session().await?;
+    let starting_balance = balance(&harness, &player).await?;
+    let slot_number = harness.current_slot().await? + AOT_LEAD_SLOTS;
+
+    let bundle = json!({
+        "legs": [
+            {
+                "type": "aot",
+                "slot_number": slot_number,
+                "bid_amount": Lamports::from_sol(0.01),
+                "compute_units": 200_000,
+                "data": "test",
+            },
+            {
+                "type": "jit",
+                "bid_amount": Lamports::from_sol(0.02),
+                "compute_units": 200_000,
+                "data": "test",
+            },
+        ],
+    });
+    let receipt: BundleReceipt = harness
+        .send(
+            Method::POST,
+            "/transactions/bundle",
+            Some(&player),
+            Some(&bundle),
+        )
+        .await?
+        .data()?;
+    assert_eq!(receipt.legs.len(), 2);
+    assert_eq!(receipt.total_bid, Lamports::from_sol(0.03));
+    assert_eq!(
+        balance(&harness, &player).await?,
+        starting_balance - receipt.total_bid
+    );
+    assert_balanced(&harness).await
+}
+
+#[tokio::test]
+async fn ledger_stays_balanced_through_many_contested_auctions() -> anyhow::Result<()> {
+    let mut harness = TestHarness::new()?;
+    let players = harness.create_sessions(4).await?;
+    let (min, max) = (Lamports::from_sol(0.01), Lamports::from_sol(0.05));
+
+    for round in 0..20u64 {
+        for player in &players {
+            // Losing a bid race to an earlier bid in the same slot is fine
+            let _ = harness.random_jit_bid(player, min, max).await;
+        }
+        let player = &players[(round % players.len() as u64) as usize];
+        let slot_number = harness.current_slot().await? + AOT_LEAD_SLOTS;
+        let amount = Lamports::from_sol(0.01 + 0.001 * round as f64);
+        let _ = harness.submit_aot_bid(player, slot_number, amount).await?;
+
+        harness.advance_slots(1).await?;
+        assert_balanced(&harness).await?;
+    }
+
+    harness.advance_slots(AOT_LEAD_SLOTS).await?;
+    let report = audit_ledger(harness.state()).await?;
+    assert!(report.balanced, "ledger out of balance: {:?}", report);
+    assert!(!report.fees_burned.is_zero());
+    Ok(())
+}
@@ -0,0 +1,125 @@
+//! `Idempotency-Key` handling: retries replay the first response, a key
+//! reused for a different bid is rejected, and a request dropped mid-flight
+//! does not lock its key.
+
+use std::{future::pending, time::Duration};
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, Method, Request, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use raiku_simulator::{
+    error::AppError,
+    services::idempotency::{
+        IDEMPOTENCY_KEY_HEADER, IDEMPOTENT_REPLAYED_HEADER, request_fingerprint, run_idempotent,
+    },
+    testing::{TestHarness, TestResponse},
+};
+use serde_json::{Value, json};
+use tokio::time::timeout;
+
+const KEY: &str = "retry-me";
+
+fn ttl() -> chrono::Duration {
+    chrono::Duration::hours(24)
+}
+
+async fn keyed_jit_bid(
+    harness: &TestHarness,
+    session_id: &str,
+    body: &Value,
+) -> anyhow::Result<TestResponse> {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/transactions/jit")
+        .header(
+            header::COOKIE,
+            format!(
+                "{}={}",
+                harness.state().sessions.cookie().name,
+                harness.state().sessions.session_token(session_id)
+            ),
+        )
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(IDEMPOTENCY_KEY_HEADER, KEY)
+        .body(Body::from(serde_json::to_vec(body)?))?;
+    harness.request(request).await
+}
+
+fn jit_bid(bid_amount: f64) -> Value {
+    json!({ "bid_amount": bid_amount, "compute_units": 200_000, "data": "test" })
+}
+
+#[tokio::test]
+async fn a_retry_replays_the_first_response() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let player = harness.create_session().await?;
+
+    let first = keyed_jit_bid(&harness, &player, &jit_bid(0.01)).await?;
+    assert_eq!(first.status, StatusCode::CREATED);
+    let retry = keyed_jit_bid(&harness, &player, &jit_bid(0.01)).await?;
+    assert_eq!(retry.status, StatusCode::CREATED);
+    assert_eq!(retry.body, first.body);
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_key_reused_for_a_different_bid_is_rejected() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let player = harness.create_session().await?;
+
+    let first = keyed_jit_bid(&harness, &player, &jit_bid(0.01)).await?;
+    assert_eq!(first.status, StatusCode::CREATED);
+    let changed = keyed_jit_bid(&harness, &player, &jit_bid(0.02)).await?;
+    assert_eq!(changed.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        changed.error_code().as_deref(),
+        Some("idempotency_key_reused")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_dropped_request_releases_its_key() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let mut headers = HeaderMap::new();
+    headers.insert(IDEMPOTENCY_KEY_HEADER, HeaderValue::from_static(KEY));
+    let fingerprint = request_fingerprint(&jit_bid(0.01))?;
+
+    // The client goes away while the bid is still being placed
+    let abandoned = run_idempotent(
+        harness.state(),
+        "player",
+        &headers,
+        ttl(),
+        fingerprint,
+        pending::<Result<Response, AppError>>(),
+    );
+    assert!(timeout(Duration::from_millis(10), abandoned).await.is_err());
+
+    let retry = run_idempotent(
+        harness.state(),
+        "player",
+        &headers,
+        ttl(),
+        fingerprint,
+        async { Ok(StatusCode::CREATED.into_response()) },
+    )
+    .await?;
+    assert_eq!(retry.status(), StatusCode::CREATED);
+    assert!(!retry.headers().contains_key(IDEMPOTENT_REPLAYED_HEADER));
+
+    let replayed = run_idempotent(
+        harness.state(),
+        "player",
+        &headers,
+        ttl(),
+        fingerprint,
+        async { Ok(StatusCode::INTERNAL_SERVER_ERROR.into_response()) },
+    )
+    .await?;
+    assert_eq!(replayed.status(), StatusCode::CREATED);
+    assert!(replayed.headers().contains_key(IDEMPOTENT_REPLAYED_HEADER));
+    Ok(())
+}
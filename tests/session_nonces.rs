@@ -0,0 +1,99 @@
+//! Bid nonces follow a session through token rotation and are dropped with
+//! the player's data.
+
+use axum::http::{Method, StatusCode};
+use raiku_simulator::{
+    models::responses::{GuestUpgraded, SessionView},
+    testing::{TestHarness, TestResponse},
+};
+use serde_json::json;
+
+const NONCE: u64 = 7;
+
+async fn jit_bid_with_nonce(
+    harness: &TestHarness,
+    session_id: &str,
+    nonce: u64,
+) -> anyhow::Result<TestResponse> {
+    let body = json!({
+        "bid_amount": 0.01,
+        "compute_units": 200_000,
+        "data": "test",
+        "nonce": nonce,
+    });
+    harness
+        .send(
+            Method::POST,
+            "/transactions/jit",
+            Some(session_id),
+            Some(&body),
+        )
+        .await
+}
+
+/// Spends `NONCE` on a bid and settles its auction, since sessions with
+/// pending bids cannot be rotated.
+async fn spend_nonce(harness: &TestHarness, session_id: &str) -> anyhow::Result<()> {
+    let bid = jit_bid_with_nonce(harness, session_id, NONCE).await?;
+    assert_eq!(bid.status, StatusCode::CREATED);
+    harness.advance_slots(1).await?;
+    Ok(())
+}
+
+async fn assert_nonce_is_stale(harness: &TestHarness, session_id: &str) -> anyhow::Result<()> {
+    let replay = jit_bid_with_nonce(harness, session_id, NONCE).await?;
+    assert_eq!(replay.status, StatusCode::CONFLICT);
+    assert_eq!(replay.error_code().as_deref(), Some("stale_nonce"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_refreshed_session_keeps_its_nonces() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let player = harness.create_session().await?;
+    spend_nonce(&harness, &player).await?;
+
+    let refreshed: SessionView = harness
+        .send::<()>(Method::POST, "/sessions/refresh", Some(&player), None)
+        .await?
+        .data()?;
+
+    assert_nonce_is_stale(&harness, &refreshed.session_id).await?;
+    assert_eq!(harness.state().nonces.last_nonce(&player).await, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn an_upgraded_session_keeps_its_nonces() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let player = harness.create_session().await?;
+    spend_nonce(&harness, &player).await?;
+
+    let upgraded: GuestUpgraded = harness
+        .send(
+            Method::POST,
+            "/sessions/upgrade",
+            Some(&player),
+            Some(&json!({ "method": "recovery_code" })),
+        )
+        .await?
+        .data()?;
+
+    assert_nonce_is_stale(&harness, &upgraded.session_id).await?;
+    assert_eq!(harness.state().nonces.last_nonce(&player).await, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn deleting_player_data_forgets_their_nonces() -> anyhow::Result<()> {
+    let harness = TestHarness::new()?;
+    let player = harness.create_session().await?;
+    spend_nonce(&harness, &player).await?;
+
+    let deleted = harness
+        .send::<()>(Method::DELETE, "/game/me", Some(&player), None)
+        .await?;
+    assert_eq!(deleted.status, StatusCode::OK);
+    assert_eq!(harness.state().nonces.last_nonce(&player).await, None);
+    Ok(())
+}